@@ -0,0 +1,176 @@
+//! Command-line tools built on the ray tracer.
+//!
+//! ```text
+//! rtrace diff <a.ppm> <b.ppm> [--threshold <t>] [--out <heatmap.png|ppm>]
+//! ```
+//!
+//! `diff` compares two PPM images channel by channel, in linear light,
+//! exiting with 1 if any channel differs by more than the threshold (by
+//! default `EPSILON`), so it can gate a CI job on golden images.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use rust_ray_tracer::canvas::Canvas;
+use rust_ray_tracer::math::EPSILON;
+
+const USAGE: &str = "usage: rtrace diff <a.ppm> <b.ppm> [--threshold <t>] [--out <heatmap>]";
+
+/// The exit code for bad arguments or unreadable images, as opposed to
+/// images that differ.
+const ERROR: u8 = 2;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.split_first() {
+        Some((command, rest)) if command == "diff" => {
+            DiffArgs::parse(rest).and_then(|args| args.run())
+        }
+        _ => Err(USAGE.to_string()),
+    };
+    result.unwrap_or_else(|message| {
+        eprintln!("rtrace: {message}");
+        ExitCode::from(ERROR)
+    })
+}
+
+/// The arguments of `rtrace diff`.
+#[derive(Debug, PartialEq)]
+struct DiffArgs {
+    a: PathBuf,
+    b: PathBuf,
+    threshold: f32,
+    out: Option<PathBuf>,
+}
+
+impl DiffArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut paths = Vec::new();
+        let mut threshold = EPSILON;
+        let mut out = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{arg} needs a value"));
+            match arg.as_str() {
+                "--threshold" => {
+                    threshold = value()?
+                        .parse()
+                        .ok()
+                        .filter(|t: &f32| *t >= 0.0)
+                        .ok_or("--threshold must be a number from 0")?;
+                }
+                "--out" => out = Some(PathBuf::from(value()?)),
+                flag if flag.starts_with("--") => return Err(format!("unknown option {flag}")),
+                path => paths.push(PathBuf::from(path)),
+            }
+        }
+        let [a, b] = <[PathBuf; 2]>::try_from(paths).map_err(|_| USAGE.to_string())?;
+        Ok(Self {
+            a,
+            b,
+            threshold,
+            out,
+        })
+    }
+
+    fn run(&self) -> Result<ExitCode, String> {
+        let (a, b) = (read_ppm(&self.a)?, read_ppm(&self.b)?);
+        if (a.width(), a.height()) != (b.width(), b.height()) {
+            return Err(format!(
+                "images differ in size: {}x{} and {}x{}",
+                a.width(),
+                a.height(),
+                b.width(),
+                b.height()
+            ));
+        }
+        let report = a.diff(&b);
+        let m = report.max_error;
+        let mean = report.mean_error;
+        println!("max error:   {:.6} {:.6} {:.6}", m.r, m.g, m.b);
+        println!("mean error:  {:.6} {:.6} {:.6}", mean.r, mean.g, mean.b);
+        println!("worst pixel: {:?}", report.worst_pixel);
+        if let Some(out) = &self.out {
+            let heatmap = match out.extension().and_then(|e| e.to_str()) {
+                Some("ppm") => report.heatmap.to_ppm().into_bytes(),
+                _ => report.heatmap.to_png(),
+            };
+            fs::write(out, heatmap).map_err(|e| format!("{}: {e}", out.display()))?;
+        }
+        if report.within(self.threshold) {
+            println!("ok: within {}", self.threshold);
+            Ok(ExitCode::SUCCESS)
+        } else {
+            println!("FAILED: beyond {}", self.threshold);
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+fn read_ppm(path: &Path) -> Result<Canvas, String> {
+    let data = fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    Canvas::from_ppm(&data).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_ray_tracer::color::Color;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn parsing_diff_arguments() {
+        let parsed = DiffArgs::parse(&args(&["a.ppm", "--threshold", "0.01", "b.ppm"])).unwrap();
+        assert_eq!(
+            parsed,
+            DiffArgs {
+                a: "a.ppm".into(),
+                b: "b.ppm".into(),
+                threshold: 0.01,
+                out: None,
+            }
+        );
+        let parsed = DiffArgs::parse(&args(&["a.ppm", "b.ppm", "--out", "d.png"])).unwrap();
+        assert_eq!(
+            (parsed.threshold, parsed.out),
+            (EPSILON, Some("d.png".into()))
+        );
+        assert!(DiffArgs::parse(&args(&["a.ppm"])).is_err());
+        assert!(DiffArgs::parse(&args(&["a.ppm", "b.ppm", "--threshold"])).is_err());
+        assert!(DiffArgs::parse(&args(&["a.ppm", "b.ppm", "--threshold", "-1"])).is_err());
+        assert!(DiffArgs::parse(&args(&["a.ppm", "b.ppm", "--fast"])).is_err());
+    }
+
+    #[test]
+    fn diffing_files() {
+        let dir = env::temp_dir().join(format!("rtrace-diff-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let a = Canvas::new_with(4, 2, Color::new(0.5, 0.5, 0.5));
+        let mut b = a.clone();
+        b.write_pixel(3, 1, Color::new(0.6, 0.5, 0.5));
+        fs::write(dir.join("a.ppm"), a.to_ppm()).unwrap();
+        fs::write(dir.join("b.ppm"), b.to_ppm()).unwrap();
+        let diff = |threshold: f32| DiffArgs {
+            a: dir.join("a.ppm"),
+            b: dir.join("b.ppm"),
+            threshold,
+            out: Some(dir.join("diff.png")),
+        };
+        assert_eq!(diff(0.01).run(), Ok(ExitCode::FAILURE));
+        assert_eq!(diff(0.5).run(), Ok(ExitCode::SUCCESS));
+        assert!(fs::read(dir.join("diff.png"))
+            .unwrap()
+            .starts_with(b"\x89PNG"));
+        let missing = DiffArgs {
+            a: dir.join("missing.ppm"),
+            ..diff(0.5)
+        };
+        assert!(missing.run().is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,244 @@
+//! Extra images describing what each pixel sees, alongside the render.
+
+use super::{Camera, RenderOptions};
+use crate::canvas::{Canvas, Guides};
+use crate::color::Color;
+use crate::light::surface_color;
+use crate::world::World;
+
+/// A render with the arbitrary output variables (AOVs) that go with it, for
+/// denoising, compositing, and debugging. Each describes the first surface
+/// the ray through the centre of a pixel strikes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aovs {
+    /// The rendered image itself.
+    pub beauty: Canvas,
+    /// The unit surface normal, facing the eye, as `(x, y, z)` in the
+    /// channels; black where nothing was hit.
+    pub normal: Canvas,
+    /// The distance from the eye, in every channel; infinite where nothing
+    /// was hit.
+    pub depth: Canvas,
+    /// The surface's color, from its pattern if it has one, without any
+    /// lighting; black where nothing was hit.
+    pub albedo: Canvas,
+    /// Which of [`World::objects`] is seen; `None` where nothing was hit.
+    pub object_id: Canvas<Option<usize>>,
+    /// How opaque the surface is: 1 over objects, 0 where nothing was hit,
+    /// and the [shadow strength](World::shadow_strength) over a
+    /// [shadow catcher](crate::material::Material::shadow_catcher).
+    pub coverage: Canvas<f32>,
+}
+
+impl Aovs {
+    pub fn object_id_at(&self, x: usize, y: usize) -> Option<usize> {
+        self.object_id.pixel_at(x, y)
+    }
+
+    /// The [`coverage`](Self::coverage), to cut the beauty image out of its
+    /// background with [`Canvas::with_alpha`].
+    pub fn alpha(&self) -> Canvas<f32> {
+        self.coverage.clone()
+    }
+
+    /// The depth as a grey image for compositing: black at `near`, white at
+    /// `far` and beyond (and where nothing was hit), and linear in between.
+    /// It holds distances rather than light, so write it out with
+    /// [`Encoding::Linear`](crate::canvas::Encoding::Linear).
+    pub fn depth_image(&self, near: f32, far: f32) -> Canvas {
+        let mut image = self.depth.clone();
+        let range = far - near;
+        for y in 0..image.height() {
+            for x in 0..image.width() {
+                let depth = image.pixel_at(x, y).r;
+                let grey = if range > 0.0 {
+                    ((depth - near) / range).clamp(0.0, 1.0)
+                } else if depth < near {
+                    0.0
+                } else {
+                    1.0
+                };
+                image.write_pixel(x, y, Color::new(grey, grey, grey));
+            }
+        }
+        image
+    }
+
+    /// The normal, depth, and albedo, to steer a
+    /// [`Denoiser`](crate::canvas::Denoiser) on the beauty image.
+    pub fn guides(&self) -> Guides<'_> {
+        Guides {
+            normal: Some(&self.normal),
+            depth: Some(&self.depth),
+            albedo: Some(&self.albedo),
+        }
+    }
+}
+
+/// What the centre ray of a pixel hits.
+struct Surface {
+    normal: Color,
+    depth: f32,
+    albedo: Color,
+    object: usize,
+    coverage: f32,
+}
+
+impl Camera {
+    /// Renders `world` as [`render_with`](Self::render_with) does, along with
+    /// its [`Aovs`]. Only the beauty image reports progress.
+    pub fn render_aovs(&self, world: &World, options: &RenderOptions) -> Aovs {
+        let beauty = self.render_with(world, options);
+        let region = self.region(options);
+        let quiet = RenderOptions {
+            progress: None,
+            ..*options
+        };
+        let tracker = self.tracker(&quiet, &region, 1);
+        let mut aovs = Aovs {
+            beauty,
+            normal: self.blank(),
+            depth: self.blank(),
+            albedo: self.blank(),
+            object_id: Canvas::new_with(self.hsize, self.vsize, None),
+            coverage: Canvas::new_with(self.hsize, self.vsize, 0.0),
+        };
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                aovs.depth.write_pixel(x, y, Color::WHITE * f32::INFINITY);
+            }
+        }
+        self.trace_pixels(
+            &tracker,
+            &region,
+            |x, y| self.surface_at(world, x, y),
+            |x, y, surface| {
+                if let Some(surface) = surface {
+                    aovs.normal.write_pixel(x, y, surface.normal);
+                    aovs.depth.write_pixel(x, y, Color::WHITE * surface.depth);
+                    aovs.albedo.write_pixel(x, y, surface.albedo);
+                    aovs.object_id.write_pixel(x, y, Some(surface.object));
+                    aovs.coverage.write_pixel(x, y, surface.coverage);
+                }
+            },
+        );
+        aovs
+    }
+
+    fn surface_at(&self, world: &World, x: usize, y: usize) -> Option<Surface> {
+        let ray = self.ray_for_pixel(x, y);
+        let kind = self.settings.camera_rays();
+        // Intersecting the objects one at a time tells which top-level object
+        // a hit inside a group belongs to.
+        let (object, xs) = world
+            .objects
+            .iter()
+            .enumerate()
+            .map(|(i, object)| {
+                let mut xs = object.intersect(&ray);
+                xs.retain(|x| x.shading_object().visibility().allows(kind));
+                (i, xs)
+            })
+            .filter_map(|(i, xs)| Some((i, xs.hit()?.t, xs)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _, xs)| (i, xs))?;
+        let hit = xs.hit()?;
+        let comps = hit.prepare_computations_with_offset(&ray, &xs, world.surface_offset);
+        let material = comps.material;
+        Some(Surface {
+            normal: Color::new(comps.normalv.x, comps.normalv.y, comps.normalv.z),
+            depth: hit.t * ray.direction.magnitude(),
+            albedo: surface_color(material, comps.object, comps.point),
+            object,
+            coverage: if material.shadow_catcher {
+                world.shadow_strength(&comps)
+            } else {
+                1.0
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::view_transform;
+    use crate::canvas::Denoiser;
+    use crate::light::Light;
+    use crate::material::Material;
+    use crate::math::{Matrix4, Vert4};
+    use crate::shape::{Cube, Group, Shape, Sphere};
+    use crate::world::default_world;
+    use std::f32::consts::PI;
+
+    fn camera() -> Camera {
+        Camera::new(11, 11, PI / 2.0).with_transform(view_transform(
+            Vert4::point(0.0, 0.0, -5.0),
+            Vert4::point(0.0, 0.0, 0.0),
+            Vert4::vector(0.0, 1.0, 0.0),
+        ))
+    }
+
+    #[test]
+    fn aovs_describe_the_surface_seen() {
+        let w = default_world();
+        let c = camera();
+        let aovs = c.render_aovs(&w, &RenderOptions::default());
+        assert_eq!(aovs.beauty, c.render(&w));
+        assert_approx_eq!(aovs.normal.pixel_at(5, 5), Color::new(0.0, 0.0, -1.0));
+        assert_approx_eq!(aovs.depth.pixel_at(5, 5), Color::new(4.0, 4.0, 4.0));
+        assert_approx_eq!(aovs.albedo.pixel_at(5, 5), Color::new(0.8, 1.0, 0.6));
+        assert_eq!(aovs.object_id_at(5, 5), Some(0));
+
+        assert_eq!(aovs.normal.pixel_at(0, 0), Color::BLACK);
+        assert_eq!(aovs.depth.pixel_at(0, 0).r, f32::INFINITY);
+        assert_eq!(aovs.object_id_at(0, 0), None);
+        assert_eq!(aovs.alpha().pixel_at(5, 5), 1.0);
+        assert_eq!(aovs.alpha().pixel_at(0, 0), 0.0);
+
+        let denoised = Denoiser::default().denoise(&aovs.beauty, &aovs.guides());
+        assert!(denoised.pixels().iter().all(|c| c.r.is_finite()));
+    }
+
+    #[test]
+    fn shadow_catchers_are_only_as_opaque_as_their_shadows() {
+        let wall = Cube::new()
+            .with_transform(
+                Matrix4::translation(0.0, 0.0, 10.0) * Matrix4::scaling(20.0, 20.0, 1.0),
+            )
+            .with_material(Material::builder().shadow_catcher(true).build());
+        let w = World::new()
+            .with_light(Light::point(Vert4::point(0.0, 0.0, -10.0), Color::WHITE))
+            .with_object(Sphere::new())
+            .with_object(wall);
+        let aovs = camera().render_aovs(&w, &RenderOptions::default());
+        assert_eq!(aovs.alpha().pixel_at(5, 5), 1.0);
+        assert_eq!(aovs.object_id_at(0, 0), Some(1));
+        assert_approx_eq!(aovs.alpha().pixel_at(0, 0), 0.0);
+        assert_eq!(aovs.beauty.pixel_at(0, 0), Color::BLACK);
+    }
+
+    #[test]
+    fn depth_image_spans_near_to_far() {
+        let aovs = camera().render_aovs(&default_world(), &RenderOptions::default());
+        let image = aovs.depth_image(3.0, 5.0);
+        assert_approx_eq!(image.pixel_at(5, 5), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(image.pixel_at(0, 0), Color::WHITE);
+        assert_eq!(aovs.depth_image(5.0, 10.0).pixel_at(5, 5), Color::BLACK);
+        assert_eq!(aovs.depth_image(2.0, 2.0).pixel_at(5, 5), Color::WHITE);
+    }
+
+    #[test]
+    fn object_ids_name_top_level_objects() {
+        let red = Material {
+            color: Color::new(1.0, 0.0, 0.0),
+            ..Material::default()
+        };
+        let w = World::new()
+            .with_object(Sphere::new().with_transform(Matrix4::translation(0.0, 0.0, 10.0)))
+            .with_object(Group::new().with_child(Sphere::new().with_material(red)));
+        let aovs = camera().render_aovs(&w, &RenderOptions::default());
+        assert_eq!(aovs.object_id_at(5, 5), Some(1));
+        assert_approx_eq!(aovs.albedo.pixel_at(5, 5), Color::new(1.0, 0.0, 0.0));
+    }
+}
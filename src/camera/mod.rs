@@ -0,0 +1,1313 @@
+//! Turning a [`World`] into an image.
+
+mod aov;
+mod motion;
+mod orbit;
+mod progress;
+
+pub use aov::Aovs;
+pub use motion::PreviousFrame;
+pub use orbit::OrbitController;
+pub use progress::{Progress, ProgressSink};
+
+use std::f32::consts::PI;
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::panic;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+use crate::canvas::{Canvas, HdrBuffer, ToneMap};
+use crate::color::Color;
+use crate::integrator::{Emitter, Integrator, IrradianceCache, RenderSettings};
+use crate::math::random::Rng;
+use crate::math::{Matrix4, Transform, Vert4};
+use crate::ray::Ray;
+use crate::world::World;
+use progress::Tracker;
+
+/// The transform orienting the world relative to an eye at `from` looking at `to`.
+pub fn view_transform(from: Vert4, to: Vert4, up: Vert4) -> Matrix4 {
+    let forward = (to - from).normalize();
+    let left = forward.cross(&up.normalize());
+    let true_up = left.cross(&forward);
+    let orientation = Matrix4::new([
+        [left.x, left.y, left.z, 0.0],
+        [true_up.x, true_up.y, true_up.z, 0.0],
+        [-forward.x, -forward.y, -forward.z, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+    orientation * Matrix4::translation(-from.x, -from.y, -from.z)
+}
+
+/// The edge length of the square tiles a render is split into.
+const TILE_SIZE: usize = 32;
+
+/// The spacing in pixels of the rays that fill a world's irradiance cache
+/// before a render.
+const CACHE_STRIDE: usize = 2;
+
+/// A rectangle of pixels, `width` × `height` with its top left at (`x`, `y`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Region {
+    pub const fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+
+    /// The part of this region inside `other`.
+    pub fn intersection(&self, other: &Region) -> Region {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        Region::new(x, y, right.saturating_sub(x), bottom.saturating_sub(y))
+    }
+
+    fn tiles_across(&self) -> usize {
+        self.width.div_ceil(TILE_SIZE)
+    }
+
+    fn tile_count(&self) -> usize {
+        self.tiles_across() * self.height.div_ceil(TILE_SIZE)
+    }
+
+    /// The pixels of tile `tile`, counting across from the top left.
+    fn tile(&self, tile: usize) -> Region {
+        let x = self.x + tile % self.tiles_across() * TILE_SIZE;
+        let y = self.y + tile / self.tiles_across() * TILE_SIZE;
+        Region::new(x, y, TILE_SIZE, TILE_SIZE).intersection(self)
+    }
+}
+
+/// Optional hooks into a render; see [`Camera::render_with`].
+#[derive(Clone, Copy, Default)]
+pub struct RenderOptions<'a> {
+    /// Only trace the pixels in this region, leaving the rest of the canvas
+    /// black. The canvas is still full size, so pixels keep their
+    /// coordinates.
+    pub region: Option<Region>,
+    /// Told about each finished tile.
+    pub progress: Option<&'a dyn ProgressSink>,
+    /// Once set, from any thread, the render stops starting new tiles and
+    /// returns what it has so far, with the remaining pixels black.
+    pub cancel: Option<&'a AtomicBool>,
+}
+
+impl fmt::Debug for RenderOptions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RenderOptions")
+            .field("region", &self.region)
+            .field("progress", &self.progress.map(|_| ".."))
+            .field("cancel", &self.cancel)
+            .finish()
+    }
+}
+
+/// How a camera maps canvas pixels to directions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Projection {
+    /// A flat image plane, as seen through a pinhole.
+    #[default]
+    Perspective,
+    /// An equidistant fisheye: the angle from the view direction grows
+    /// linearly with the distance from the canvas centre, reaching half the
+    /// field of view at the edge of the longer side. A field of view of 2π
+    /// sees all around.
+    Fisheye,
+    /// A 360° × 180° panorama with longitude across and latitude down the
+    /// canvas, as used for environment maps. Ignores the field of view.
+    Equirectangular,
+}
+
+/// A camera mapping a `hsize` × `vsize` canvas onto the world. By default it's
+/// a pinhole with the canvas one unit in front of the eye; see [`Projection`]
+/// for the alternatives.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Camera {
+    hsize: usize,
+    vsize: usize,
+    field_of_view: f32,
+    transform: Transform,
+    half_width: f32,
+    half_height: f32,
+    pixel_size: f32,
+    aa_samples: usize,
+    adaptive_threshold: Option<f32>,
+    aperture: f32,
+    focal_distance: f32,
+    projection: Projection,
+    threads: Option<usize>,
+    seed: Option<u64>,
+    integrator: Integrator,
+    settings: RenderSettings,
+}
+
+impl Camera {
+    /// `field_of_view` is the horizontal (or vertical, for portrait canvases) angle in radians.
+    pub fn new(hsize: usize, vsize: usize, field_of_view: f32) -> Self {
+        Self {
+            hsize,
+            vsize,
+            field_of_view: 0.0,
+            transform: Transform::IDENTITY,
+            half_width: 0.0,
+            half_height: 0.0,
+            pixel_size: 0.0,
+            aa_samples: 1,
+            adaptive_threshold: None,
+            aperture: 0.0,
+            focal_distance: 1.0,
+            projection: Projection::Perspective,
+            threads: None,
+            seed: None,
+            integrator: Integrator::Whitted,
+            settings: RenderSettings::default(),
+        }
+        .with_field_of_view(field_of_view)
+    }
+
+    /// Changes the field of view, keeping the canvas size.
+    pub fn with_field_of_view(mut self, field_of_view: f32) -> Self {
+        let half_view = (field_of_view / 2.0).tan();
+        let aspect = self.hsize as f32 / self.vsize as f32;
+        let (half_width, half_height) = if aspect >= 1.0 {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
+        };
+        self.field_of_view = field_of_view;
+        self.half_width = half_width;
+        self.half_height = half_height;
+        self.pixel_size = half_width * 2.0 / self.hsize as f32;
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Matrix4) -> Self {
+        self.set_transform(transform);
+        self
+    }
+
+    /// Supersamples each pixel with an `aa_samples` × `aa_samples` grid of
+    /// rays, averaging their colors to smooth jagged edges.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `aa_samples` is zero.
+    pub fn with_aa_samples(mut self, aa_samples: usize) -> Self {
+        assert!(aa_samples > 0, "a pixel needs at least one sample");
+        self.aa_samples = aa_samples;
+        self
+    }
+
+    /// Only supersamples pixels whose single-ray color differs from a
+    /// neighbour's by more than `threshold` in some channel, leaving flat
+    /// regions at one ray per pixel.
+    pub fn with_adaptive_aa(mut self, threshold: f32) -> Self {
+        self.adaptive_threshold = Some(threshold);
+        self
+    }
+
+    /// The diameter of the lens. Anything off the focal plane blurs in
+    /// proportion; zero is a pinhole, with everything sharp.
+    ///
+    /// The lens is sampled once per anti-aliasing sample, so a wide aperture
+    /// needs [`with_aa_samples`](Self::with_aa_samples) to blur smoothly.
+    pub fn with_aperture(mut self, aperture: f32) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Renders on `threads` threads rather than one per available core.
+    ///
+    /// The thread count never changes the image: every sample draws its
+    /// randomness from a stream of its own, keyed by pixel and sample
+    /// index, and each pixel's samples are summed in the same order
+    /// whichever thread traced them. Golden images rendered on one machine
+    /// match those from another bit for bit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threads` is zero.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "rendering needs at least one thread");
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Jitters every sample randomly within its share of the pixel and the
+    /// lens, trading the regular grid's aliasing for noise. The randomness
+    /// comes from `seed` alone, so a seeded render is reproducible however
+    /// its tiles are scheduled.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Renders with `integrator`, Whitted-style ray tracing by default. Path
+    /// tracing draws its randomness from the [`seed`](Self::with_seed), or 0
+    /// if there isn't one.
+    pub fn with_integrator(mut self, integrator: impl Into<Integrator>) -> Self {
+        self.integrator = integrator.into();
+        self
+    }
+
+    /// Renders with `settings`, which the integrator reads whichever it is.
+    pub fn with_render_settings(mut self, settings: RenderSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// The distance in front of the eye at which objects are in focus.
+    pub fn with_focal_distance(mut self, focal_distance: f32) -> Self {
+        self.focal_distance = focal_distance;
+        self
+    }
+
+    pub fn hsize(&self) -> usize {
+        self.hsize
+    }
+
+    pub fn vsize(&self) -> usize {
+        self.vsize
+    }
+
+    pub fn field_of_view(&self) -> f32 {
+        self.field_of_view
+    }
+
+    pub fn pixel_size(&self) -> f32 {
+        self.pixel_size
+    }
+
+    pub fn aa_samples(&self) -> usize {
+        self.aa_samples
+    }
+
+    pub fn adaptive_threshold(&self) -> Option<f32> {
+        self.adaptive_threshold
+    }
+
+    pub fn aperture(&self) -> f32 {
+        self.aperture
+    }
+
+    pub fn focal_distance(&self) -> f32 {
+        self.focal_distance
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn integrator(&self) -> &Integrator {
+        &self.integrator
+    }
+
+    pub fn render_settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    /// How many threads renders are spread over.
+    pub fn threads(&self) -> usize {
+        self.threads
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get))
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `transform` is not invertible.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = Transform::new(transform);
+    }
+
+    /// The ray from the eye through the centre of pixel (`x`, `y`).
+    pub fn ray_for_pixel(&self, x: usize, y: usize) -> Ray {
+        self.ray_for_sample(x as f32 + 0.5, y as f32 + 0.5)
+    }
+
+    /// The ray from the eye through (`x`, `y`) in canvas coordinates, where
+    /// pixel (0, 0) spans `0.0..1.0` on both axes.
+    pub fn ray_for_sample(&self, x: f32, y: f32) -> Ray {
+        self.ray_through_lens(x, y, 0.0, 0.0)
+    }
+
+    /// Like [`ray_for_sample`](Self::ray_for_sample), but leaving from
+    /// (`lens_x`, `lens_y`) on the lens, in units of its radius, and aimed
+    /// through the focal plane.
+    pub fn ray_through_lens(&self, x: f32, y: f32, lens_x: f32, lens_y: f32) -> Ray {
+        let radius = self.aperture / 2.0;
+        let inverse = self.transform.inverse();
+        let focus = *inverse
+            * (Vert4::point(0.0, 0.0, 0.0) + self.view_direction(x, y) * self.focal_distance);
+        let origin = *inverse * Vert4::point(lens_x * radius, lens_y * radius, 0.0);
+        Ray::new(origin, (focus - origin).normalize())
+    }
+
+    /// The camera-space direction through (`x`, `y`) in canvas coordinates.
+    /// For perspective it's scaled to reach the plane `z = -1`, so focusing is
+    /// on a plane; other projections focus on a sphere.
+    fn view_direction(&self, x: f32, y: f32) -> Vert4 {
+        match self.projection {
+            Projection::Perspective => Vert4::vector(
+                self.half_width - x * self.pixel_size,
+                self.half_height - y * self.pixel_size,
+                -1.0,
+            ),
+            Projection::Fisheye => {
+                let half_extent = self.hsize.max(self.vsize) as f32 / 2.0;
+                let u = (self.hsize as f32 / 2.0 - x) / half_extent;
+                let v = (self.vsize as f32 / 2.0 - y) / half_extent;
+                let r = u.hypot(v);
+                let theta = r * self.field_of_view / 2.0;
+                let (sin, cos) = theta.sin_cos();
+                if r == 0.0 {
+                    Vert4::vector(0.0, 0.0, -1.0)
+                } else {
+                    Vert4::vector(sin * u / r, sin * v / r, -cos)
+                }
+            }
+            Projection::Equirectangular => {
+                let longitude = (0.5 - x / self.hsize as f32) * 2.0 * PI;
+                let latitude = (0.5 - y / self.vsize as f32) * PI;
+                Vert4::vector(
+                    latitude.cos() * longitude.sin(),
+                    latitude.sin(),
+                    -latitude.cos() * longitude.cos(),
+                )
+            }
+        }
+    }
+
+    /// Sample `i` of `count` spread evenly over the unit disk, `jitter`
+    /// (each in `0.0..1.0`) placing it within its stratum; (0.5, 0.5) is the
+    /// middle.
+    fn lens_sample(i: usize, count: usize, jitter: (f32, f32)) -> (f32, f32) {
+        let golden_angle = PI * (3.0 - 5f32.sqrt());
+        let r = ((i as f32 + jitter.0) / count as f32).sqrt();
+        let phi = (i as f32 + jitter.1 - 0.5) * golden_angle;
+        (r * phi.cos(), r * phi.sin())
+    }
+
+    /// Renders one sample per pixel per pass, calling `on_pass` with the
+    /// pass number (from 1) and the average of the passes so far. There are
+    /// `aa_samples`² passes, and the last image is also returned; it's the
+    /// same as [`render`](Self::render) without adaptive anti-aliasing.
+    ///
+    /// If `options` cancels the render, the pass in progress is dropped and
+    /// the average of the finished ones is returned.
+    pub fn render_progressive(
+        &self,
+        world: &World,
+        options: &RenderOptions,
+        mut on_pass: impl FnMut(usize, &Canvas),
+    ) -> Canvas {
+        self.accumulate(world, options, |pass, buffer| {
+            on_pass(pass, &buffer.resolve(0.0, ToneMap::Clamp))
+        })
+        .resolve(0.0, ToneMap::Clamp)
+    }
+
+    /// The `aa_samples`² samples of every pixel, summed without clamping or
+    /// tone mapping, to [`resolve`](HdrBuffer::resolve) as needed.
+    /// Cancelling through `options` keeps the passes already finished.
+    pub fn render_hdr(&self, world: &World, options: &RenderOptions) -> HdrBuffer {
+        self.accumulate(world, options, |_, _| {})
+    }
+
+    /// Renders one sample per pixel per pass into an [`HdrBuffer`], calling
+    /// `on_pass` after each.
+    fn accumulate(
+        &self,
+        world: &World,
+        options: &RenderOptions,
+        mut on_pass: impl FnMut(usize, &HdrBuffer),
+    ) -> HdrBuffer {
+        let count = self.aa_samples * self.aa_samples;
+        let region = self.region(options);
+        let _cache = self.fill_irradiance_cache(world, &region);
+        let emitters = self.integrator.emitters(world);
+        let tracker = self.tracker(options, &region, count);
+        let mut buffer = HdrBuffer::new(self.hsize, self.vsize);
+        for i in 0..count {
+            let samples = self.trace_tiles(&tracker, &region, self.blank(), |x, y| {
+                self.sample_color(world, &emitters, x, y, i)
+            });
+            if tracker.is_cancelled() {
+                break;
+            }
+            for y in region.y..region.y + region.height {
+                for x in region.x..region.x + region.width {
+                    buffer.add_sample(x, y, samples.pixel_at(x, y));
+                }
+            }
+            on_pass(i + 1, &buffer);
+        }
+        buffer
+    }
+
+    pub fn render(&self, world: &World) -> Canvas {
+        self.render_with(world, &RenderOptions::default())
+    }
+
+    /// Like [`render`](Self::render), with the hooks in `options`.
+    pub fn render_with(&self, world: &World, options: &RenderOptions) -> Canvas {
+        let region = self.region(options);
+        let _cache = self.fill_irradiance_cache(world, &region);
+        let emitters = self.integrator.emitters(world);
+        let Some(threshold) = self.adaptive_threshold.filter(|_| self.aa_samples > 1) else {
+            let tracker = self.tracker(options, &region, 1);
+            return self.trace_tiles(&tracker, &region, self.blank(), |x, y| {
+                self.color_for_pixel(world, &emitters, x, y)
+            });
+        };
+
+        let tracker = self.tracker(options, &region, 2);
+        // First pass: one sample per pixel, as a render without
+        // anti-aliasing would take it.
+        let preview = self.trace_tiles(&tracker, &region, self.blank(), |x, y| {
+            self.grid_sample(world, &emitters, x, y, 0, 1)
+        });
+        // Second pass: supersample only where the preview has an edge. A
+        // cancelled render keeps the preview for the tiles it didn't reach.
+        self.trace_tiles(&tracker, &region, preview.clone(), |x, y| {
+            if Self::has_contrast(&preview, &region, x, y, threshold) {
+                self.color_for_pixel(world, &emitters, x, y)
+            } else {
+                preview.pixel_at(x, y)
+            }
+        })
+    }
+
+    /// Renders each of `world`'s [`layers`](World::layers) to a canvas of
+    /// its own, in the same order, in one pass over the pixels. Each is what
+    /// [`render_with`](Self::render_with) would give with the layer set in
+    /// the [render settings](RenderSettings::with_layer), except that
+    /// adaptive anti-aliasing is skipped.
+    pub fn render_layers(&self, world: &World, options: &RenderOptions) -> Vec<Canvas> {
+        let region = self.region(options);
+        let _cache = self.fill_irradiance_cache(world, &region);
+        let emitters = self.integrator.emitters(world);
+        let layers: Vec<Camera> = (0..world.layers.len())
+            .map(|layer| {
+                self.clone()
+                    .with_render_settings(self.settings.with_layer(layer))
+            })
+            .collect();
+        let tracker = self.tracker(options, &region, 1);
+        let mut canvases = vec![self.blank(); layers.len()];
+        self.trace_pixels(
+            &tracker,
+            &region,
+            |x, y| {
+                layers
+                    .iter()
+                    .map(|camera| camera.color_for_pixel(world, &emitters, x, y))
+                    .collect::<Vec<_>>()
+            },
+            |x, y, colors| {
+                for (canvas, color) in canvases.iter_mut().zip(colors) {
+                    canvas.write_pixel(x, y, color);
+                }
+            },
+        );
+        canvases
+    }
+
+    /// `image` colored by `shade`, called for each pixel in `region`; see
+    /// [`trace_pixels`](Self::trace_pixels).
+    fn trace_tiles(
+        &self,
+        tracker: &Tracker,
+        region: &Region,
+        mut image: Canvas,
+        shade: impl Fn(usize, usize) -> Color + Sync,
+    ) -> Canvas {
+        self.trace_pixels(tracker, region, shade, |x, y, color| {
+            image.write_pixel(x, y, color)
+        });
+        image
+    }
+
+    /// Calls `shade` for each pixel in `region`, handing each result to
+    /// `store`. The region is split into tiles which the render threads take
+    /// in turn, each keeping only its own results until the tiles are
+    /// gathered at the end.
+    fn trace_pixels<T: Send>(
+        &self,
+        tracker: &Tracker,
+        region: &Region,
+        shade: impl Fn(usize, usize) -> T + Sync,
+        mut store: impl FnMut(usize, usize, T),
+    ) {
+        let tiles = region.tile_count();
+        let next = AtomicUsize::new(0);
+        let (shade, next) = (&shade, &next);
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.threads().min(tiles))
+                .map(|_| {
+                    scope.spawn(move || {
+                        let mut done = Vec::new();
+                        loop {
+                            let tile = next.fetch_add(1, Ordering::Relaxed);
+                            if tile >= tiles || tracker.is_cancelled() {
+                                break done;
+                            }
+                            let tile = region.tile(tile);
+                            let xs = tile.x..tile.x + tile.width;
+                            let ys = tile.y..tile.y + tile.height;
+                            let pixels: Vec<_> = ys
+                                .flat_map(|y| xs.clone().map(move |x| (x, y, shade(x, y))))
+                                .collect();
+                            tracker.tile_done(pixels.len());
+                            done.push(pixels);
+                        }
+                    })
+                })
+                .collect();
+            for worker in workers {
+                let tiles = worker.join().unwrap_or_else(|e| panic::resume_unwind(e));
+                for (x, y, value) in tiles.into_iter().flatten() {
+                    store(x, y, value);
+                }
+            }
+        });
+    }
+
+    /// Fills `world`'s irradiance cache, if it has one and the integrator
+    /// uses it, from rays through a grid of pixels in `region`, traced on
+    /// this thread in a fixed order. The cache stays frozen until the
+    /// returned guard is dropped, so the render threads only read it and a
+    /// seeded render is the same on any number of threads.
+    fn fill_irradiance_cache<'a>(&self, world: &'a World, region: &Region) -> FrozenCache<'a> {
+        let cache = world
+            .irradiance_cache
+            .as_ref()
+            .filter(|_| self.integrator == Integrator::Whitted);
+        if let Some(cache) = cache {
+            cache.set_frozen(false);
+            for y in (region.y..region.y + region.height).step_by(CACHE_STRIDE) {
+                for x in (region.x..region.x + region.width).step_by(CACHE_STRIDE) {
+                    world.color_at(&self.ray_for_pixel(x, y));
+                }
+            }
+            cache.set_frozen(true);
+        }
+        FrozenCache(cache)
+    }
+
+    /// The part of the canvas `options` asks to render.
+    fn region(&self, options: &RenderOptions) -> Region {
+        let canvas = Region::new(0, 0, self.hsize, self.vsize);
+        options
+            .region
+            .map_or(canvas, |region| region.intersection(&canvas))
+    }
+
+    fn blank(&self) -> Canvas {
+        Canvas::new(self.hsize, self.vsize)
+    }
+
+    /// A tracker for a render making `passes` passes over `region`.
+    fn tracker<'a>(
+        &self,
+        options: &RenderOptions<'a>,
+        region: &Region,
+        passes: usize,
+    ) -> Tracker<'a> {
+        Tracker::new(
+            options,
+            region.width * region.height * passes,
+            region.tile_count() * passes,
+        )
+    }
+
+    /// Whether pixel (`x`, `y`) differs from any of its four neighbours in
+    /// `region` by more than `threshold` in some channel.
+    fn has_contrast(image: &Canvas, region: &Region, x: usize, y: usize, threshold: f32) -> bool {
+        let here = image.pixel_at(x, y);
+        let neighbours = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        neighbours
+            .into_iter()
+            .filter(|&(nx, ny)| region.contains(nx, ny))
+            .any(|(nx, ny)| {
+                let d = image.pixel_at(nx, ny) - here;
+                d.r.abs().max(d.g.abs()).max(d.b.abs()) > threshold
+            })
+    }
+
+    /// The average color over the pixel's grid of samples.
+    fn color_for_pixel(&self, world: &World, emitters: &[Emitter], x: usize, y: usize) -> Color {
+        let count = self.aa_samples * self.aa_samples;
+        let total: Color = (0..count)
+            .map(|i| self.sample_color(world, emitters, x, y, i))
+            .sum();
+        total / count as f32
+    }
+
+    /// The color of sample `i` of pixel (`x`, `y`), where the samples cover
+    /// the pixel in an `aa_samples` × `aa_samples` grid.
+    fn sample_color(
+        &self,
+        world: &World,
+        emitters: &[Emitter],
+        x: usize,
+        y: usize,
+        i: usize,
+    ) -> Color {
+        self.grid_sample(world, emitters, x, y, i, self.aa_samples)
+    }
+
+    /// The color of sample `i` of pixel (`x`, `y`) in an `n` × `n` grid,
+    /// traced with the camera's integrator aiming at `emitters`.
+    fn grid_sample(
+        &self,
+        world: &World,
+        emitters: &[Emitter],
+        x: usize,
+        y: usize,
+        i: usize,
+        n: usize,
+    ) -> Color {
+        let stream = (y * self.hsize + x) * n * n + i;
+        let mut rng = Rng::for_stream(self.seed.unwrap_or(0), stream as u64);
+        let ray = match self.seed {
+            None if n == 1 => self.ray_for_pixel(x, y),
+            None => self.sample_ray(x, y, i, n, [0.5; 4]),
+            Some(_) => {
+                let jitter = [(); 4].map(|_| rng.next_f32());
+                self.sample_ray(x, y, i, n, jitter)
+            }
+        };
+        self.integrator
+            .radiance(world, emitters, &ray, &self.settings, &mut rng)
+    }
+
+    /// The ray for sample `i` of an `n` × `n` grid, placed within its cell
+    /// and lens stratum by `jitter`: the cell's x and y, then the lens's
+    /// radius and angle.
+    fn sample_ray(&self, x: usize, y: usize, i: usize, n: usize, jitter: [f32; 4]) -> Ray {
+        let step = 1.0 / n as f32;
+        // Stride through the lens samples so neighbouring grid cells don't
+        // also share a neighbourhood of the lens.
+        let stride = (1..)
+            .step_by(2)
+            .find(|s| gcd(*s, n * n) == 1 && *s > n)
+            .unwrap_or(1);
+        let sx = x as f32 + ((i % n) as f32 + jitter[0]) * step;
+        let sy = y as f32 + ((i / n) as f32 + jitter[1]) * step;
+        let (lx, ly) = Self::lens_sample(i * stride % (n * n), n * n, (jitter[2], jitter[3]));
+        self.ray_through_lens(sx, sy, lx, ly)
+    }
+}
+
+/// Thaws an irradiance cache when dropped; see
+/// [`Camera::fill_irradiance_cache`].
+struct FrozenCache<'a>(Option<&'a IrradianceCache>);
+
+impl Drop for FrozenCache<'_> {
+    fn drop(&mut self) {
+        if let Some(cache) = self.0 {
+            cache.set_frozen(false);
+        }
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+    use super::*;
+    use crate::integrator::PathTracer;
+    use crate::material::Material;
+    use crate::shape::{Cube, Shape, Sphere, Visibility};
+    use crate::world::default_world;
+
+    /// A wide slab whose top is the plane y = -1.
+    fn floor() -> Cube {
+        Cube::new().with_transform(
+            Matrix4::translation(0.0, -2.0, 0.0) * Matrix4::scaling(10.0, 1.0, 10.0),
+        )
+    }
+
+    #[test]
+    fn default_orientation_is_identity() {
+        let t = view_transform(
+            Vert4::point(0.0, 0.0, 0.0),
+            Vert4::point(0.0, 0.0, -1.0),
+            Vert4::vector(0.0, 1.0, 0.0),
+        );
+        assert_eq!(t, Matrix4::IDENTITY);
+    }
+
+    #[test]
+    fn looking_in_positive_z() {
+        let t = view_transform(
+            Vert4::point(0.0, 0.0, 0.0),
+            Vert4::point(0.0, 0.0, 1.0),
+            Vert4::vector(0.0, 1.0, 0.0),
+        );
+        assert_eq!(t, Matrix4::scaling(-1.0, 1.0, -1.0));
+    }
+
+    #[test]
+    fn view_transform_moves_the_world() {
+        let t = view_transform(
+            Vert4::point(0.0, 0.0, 8.0),
+            Vert4::point(0.0, 0.0, 0.0),
+            Vert4::vector(0.0, 1.0, 0.0),
+        );
+        assert_eq!(t, Matrix4::translation(0.0, 0.0, -8.0));
+    }
+
+    #[test]
+    fn arbitrary_view_transform() {
+        let t = view_transform(
+            Vert4::point(1.0, 3.0, 2.0),
+            Vert4::point(4.0, -2.0, 8.0),
+            Vert4::vector(1.0, 1.0, 0.0),
+        );
+        let expected = Matrix4::new([
+            [-0.50709, 0.50709, 0.67612, -2.36643],
+            [0.76772, 0.60609, 0.12122, -2.82843],
+            [-0.35857, 0.59761, -0.71714, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        assert_approx_eq!(t, expected);
+    }
+
+    #[test]
+    fn pixel_size() {
+        assert_approx_eq!(Camera::new(200, 125, PI / 2.0).pixel_size(), 0.01);
+        assert_approx_eq!(Camera::new(125, 200, PI / 2.0).pixel_size(), 0.01);
+    }
+
+    #[test]
+    fn ray_through_center() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        let r = c.ray_for_pixel(100, 50);
+        assert_approx_eq!(r.origin, Vert4::point(0.0, 0.0, 0.0));
+        assert_approx_eq!(r.direction, Vert4::vector(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn ray_through_corner() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        let r = c.ray_for_pixel(0, 0);
+        assert_approx_eq!(r.origin, Vert4::point(0.0, 0.0, 0.0));
+        assert_approx_eq!(r.direction, Vert4::vector(0.66519, 0.33259, -0.66851));
+    }
+
+    #[test]
+    fn ray_with_transformed_camera() {
+        let c = Camera::new(201, 101, PI / 2.0)
+            .with_transform(Matrix4::rotation_y(PI / 4.0) * Matrix4::translation(0.0, -2.0, 5.0));
+        let r = c.ray_for_pixel(100, 50);
+        assert_approx_eq!(r.origin, Vert4::point(0.0, 2.0, -5.0));
+        assert_approx_eq!(
+            r.direction,
+            Vert4::vector(FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2)
+        );
+    }
+
+    #[test]
+    fn rendering_a_world() {
+        let w = default_world();
+        let c = Camera::new(11, 11, PI / 2.0).with_transform(view_transform(
+            Vert4::point(0.0, 0.0, -5.0),
+            Vert4::point(0.0, 0.0, 0.0),
+            Vert4::vector(0.0, 1.0, 0.0),
+        ));
+        let image = c.render(&w);
+        assert_approx_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn layers_render_to_separate_canvases() {
+        let wall = Cube::new()
+            .with_transform(Matrix4::translation(0.0, 0.0, 5.0) * Matrix4::scaling(10.0, 10.0, 1.0))
+            .with_visibility(Visibility::in_layers([1]));
+        let mut w = default_world()
+            .with_layer("foreground")
+            .with_layer("background");
+        for object in &mut w.objects {
+            object.set_visibility(Visibility::in_layers([0]));
+        }
+        w.objects.push(Box::new(wall));
+        let c = Camera::new(11, 11, PI / 2.0).with_transform(view_transform(
+            Vert4::point(0.0, 0.0, -5.0),
+            Vert4::point(0.0, 0.0, 0.0),
+            Vert4::vector(0.0, 1.0, 0.0),
+        ));
+        let layers = c.render_layers(&w, &RenderOptions::default());
+        assert_eq!(layers.len(), 2);
+        for (layer, image) in layers.iter().enumerate() {
+            let settings = RenderSettings::default().with_layer(layer);
+            assert_eq!(*image, c.clone().with_render_settings(settings).render(&w));
+        }
+        let full = c.render(&w);
+        assert_eq!(layers[0].pixel_at(5, 5), full.pixel_at(5, 5));
+        assert_eq!(layers[0].pixel_at(0, 0), Color::BLACK);
+        assert_ne!(layers[1].pixel_at(5, 5), full.pixel_at(5, 5));
+        assert_eq!(layers[1].pixel_at(0, 0), full.pixel_at(0, 0));
+    }
+
+    #[test]
+    fn ray_for_sample_at_pixel_centre_matches_ray_for_pixel() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        assert_eq!(c.ray_for_sample(0.5, 0.5), c.ray_for_pixel(0, 0));
+    }
+
+    #[test]
+    fn one_aa_sample_matches_plain_render() {
+        let w = default_world();
+        let c = Camera::new(11, 11, PI / 2.0).with_transform(view_transform(
+            Vert4::point(0.0, 0.0, -5.0),
+            Vert4::point(0.0, 0.0, 0.0),
+            Vert4::vector(0.0, 1.0, 0.0),
+        ));
+        assert_eq!(c.clone().with_aa_samples(1).render(&w), c.render(&w));
+    }
+
+    #[test]
+    fn supersampling_blends_edges() {
+        // A glowing sphere covering part of a single wide pixel.
+        let glow = Sphere::new()
+            .with_transform(
+                Matrix4::translation(9.0, 0.0, -10.0) * Matrix4::scaling(10.0, 10.0, 10.0),
+            )
+            .with_material(Material {
+                ambient: 0.0,
+                diffuse: 0.0,
+                specular: 0.0,
+                emission: Color::WHITE,
+                ..Material::default()
+            });
+        let w = World::new().with_object(glow);
+        let c = Camera::new(1, 1, PI / 2.0);
+        assert_eq!(c.render(&w).pixel_at(0, 0), Color::WHITE);
+        let blended = c.with_aa_samples(4).render(&w).pixel_at(0, 0);
+        assert!(blended.r > 0.0 && blended.r < 1.0, "{blended:?}");
+        assert_approx_eq!(blended, Color::new(blended.r, blended.r, blended.r));
+    }
+
+    #[test]
+    fn adaptive_aa_only_refines_edges() {
+        let w = default_world();
+        let c = Camera::new(11, 11, PI / 2.0)
+            .with_transform(view_transform(
+                Vert4::point(0.0, 0.0, -5.0),
+                Vert4::point(0.0, 0.0, 0.0),
+                Vert4::vector(0.0, 1.0, 0.0),
+            ))
+            .with_aa_samples(4);
+        let single = c.clone().with_aa_samples(1).render(&w);
+        let full = c.render(&w);
+        let adaptive = c.with_adaptive_aa(0.1).render(&w);
+        // Background far from the sphere is left alone...
+        assert_eq!(adaptive.pixel_at(0, 0), single.pixel_at(0, 0));
+        // ...while pixels around the silhouette match full supersampling.
+        let edge = (0..11)
+            .find(|&x| single.pixel_at(x, 5) != Color::BLACK)
+            .expect("the sphere is in view");
+        assert_eq!(adaptive.pixel_at(edge, 5), full.pixel_at(edge, 5));
+        assert_ne!(adaptive.pixel_at(edge, 5), single.pixel_at(edge, 5));
+    }
+
+    #[test]
+    fn adaptive_aa_previews_with_the_camera_integrator() {
+        let w = default_world();
+        let c = Camera::new(11, 11, PI / 2.0)
+            .with_transform(view_transform(
+                Vert4::point(0.0, 0.0, -5.0),
+                Vert4::point(0.0, 0.0, 0.0),
+                Vert4::vector(0.0, 1.0, 0.0),
+            ))
+            .with_seed(3)
+            .with_integrator(PathTracer::new(2, 2))
+            .with_aa_samples(3);
+        let single = c.clone().with_aa_samples(1).render(&w);
+        let full = c.render(&w);
+        let adaptive = c.with_adaptive_aa(0.5).render(&w);
+        let mut kept = 0;
+        for (x, y, color) in adaptive.enumerate_pixels() {
+            if color == single.pixel_at(x, y) {
+                kept += 1;
+            } else {
+                assert_eq!(color, full.pixel_at(x, y), "({x}, {y})");
+            }
+        }
+        // The background is flat, so it keeps the one-sample preview.
+        assert_eq!(adaptive.pixel_at(0, 0), single.pixel_at(0, 0));
+        assert!(kept > 0);
+    }
+
+    #[test]
+    fn rays_through_the_lens_meet_on_the_focal_plane() {
+        let c = Camera::new(201, 101, PI / 2.0)
+            .with_aperture(0.5)
+            .with_focal_distance(4.0);
+        let centre = c.ray_for_sample(30.5, 70.5);
+        let edge = c.ray_through_lens(30.5, 70.5, 1.0, 0.0);
+        assert_approx_eq!(edge.origin, Vert4::point(0.25, 0.0, 0.0));
+        let t_centre = -4.0 / centre.direction.z;
+        let t_edge = -4.0 / edge.direction.z;
+        assert_approx_eq!(centre.position(t_centre), edge.position(t_edge));
+    }
+
+    #[test]
+    fn aperture_blurs_out_of_focus_objects() {
+        let w = default_world();
+        let c = Camera::new(11, 11, PI / 2.0)
+            .with_transform(view_transform(
+                Vert4::point(0.0, 0.0, -5.0),
+                Vert4::point(0.0, 0.0, 0.0),
+                Vert4::vector(0.0, 1.0, 0.0),
+            ))
+            .with_aa_samples(4);
+        let sharp = c.render(&w);
+        let in_focus = c
+            .clone()
+            .with_aperture(0.5)
+            .with_focal_distance(4.0)
+            .render(&w);
+        let blurred = c.with_aperture(0.5).with_focal_distance(20.0).render(&w);
+        let diff = |a: &Canvas, b: &Canvas| {
+            (0..11)
+                .map(|x| {
+                    let d = a.pixel_at(x, 5) - b.pixel_at(x, 5);
+                    d.r.abs() + d.g.abs() + d.b.abs()
+                })
+                .sum::<f32>()
+        };
+        assert!(diff(&sharp, &in_focus) < diff(&sharp, &blurred));
+    }
+
+    #[test]
+    fn fisheye_angle_grows_linearly_from_centre() {
+        let c = Camera::new(100, 100, PI).with_projection(Projection::Fisheye);
+        assert_approx_eq!(
+            c.ray_for_sample(50.0, 50.0).direction,
+            Vert4::vector(0.0, 0.0, -1.0)
+        );
+        // Halfway to the edge is a quarter of the field of view off axis.
+        assert_approx_eq!(
+            c.ray_for_sample(25.0, 50.0).direction,
+            Vert4::vector(FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2)
+        );
+        // The edge of the canvas looks sideways.
+        assert_approx_eq!(
+            c.ray_for_sample(50.0, 100.0).direction,
+            Vert4::vector(0.0, -1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn equirectangular_covers_every_direction() {
+        let c = Camera::new(360, 180, PI / 2.0).with_projection(Projection::Equirectangular);
+        let dir = |x, y| c.ray_for_sample(x, y).direction;
+        assert_approx_eq!(dir(180.0, 90.0), Vert4::vector(0.0, 0.0, -1.0));
+        assert_approx_eq!(dir(90.0, 90.0), Vert4::vector(1.0, 0.0, 0.0));
+        assert_approx_eq!(dir(0.0, 90.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert_approx_eq!(dir(180.0, 0.0), Vert4::vector(0.0, 1.0, 0.0));
+        assert_approx_eq!(dir(180.0, 180.0), Vert4::vector(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn progressive_render_converges_on_full_render() {
+        let w = default_world();
+        let c = Camera::new(11, 11, PI / 2.0)
+            .with_transform(view_transform(
+                Vert4::point(0.0, 0.0, -5.0),
+                Vert4::point(0.0, 0.0, 0.0),
+                Vert4::vector(0.0, 1.0, 0.0),
+            ))
+            .with_aa_samples(2);
+        let mut passes = Vec::new();
+        let last = c.render_progressive(&w, &RenderOptions::default(), |pass, image| {
+            passes.push((pass, image.clone()))
+        });
+        assert_eq!(
+            passes.iter().map(|(p, _)| *p).collect::<Vec<_>>(),
+            [1, 2, 3, 4]
+        );
+        assert_eq!(passes[3].1, last);
+        let full = c.render(&w);
+        for y in 0..11 {
+            for x in 0..11 {
+                assert_approx_eq!(last.pixel_at(x, y), full.pixel_at(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn hdr_render_keeps_every_sample_unclamped() {
+        let mut w = default_world();
+        w.objects[0].material_mut().emission = Color::WHITE * 4.0;
+        let c = Camera::new(5, 5, PI / 2.0)
+            .with_transform(view_transform(
+                Vert4::point(0.0, 0.0, -5.0),
+                Vert4::point(0.0, 0.0, 0.0),
+                Vert4::vector(0.0, 1.0, 0.0),
+            ))
+            .with_aa_samples(2);
+        let buffer = c.render_hdr(&w, &RenderOptions::default());
+        assert_eq!(buffer.sample_count(2, 2), 4);
+        assert!(buffer.mean_at(2, 2).r > 4.0);
+        assert_eq!(
+            buffer.resolve(0.0, ToneMap::Clamp),
+            c.render_progressive(&w, &RenderOptions::default(), |_, _| {})
+        );
+    }
+
+    #[test]
+    fn threaded_render_matches_single_threaded() {
+        let w = default_world();
+        // Not a multiple of the tile size, so edge tiles are partial.
+        let c = Camera::new(70, 45, PI / 2.0).with_transform(view_transform(
+            Vert4::point(0.0, 0.0, -5.0),
+            Vert4::point(0.0, 0.0, 0.0),
+            Vert4::vector(0.0, 1.0, 0.0),
+        ));
+        let single = c.clone().with_threads(1).render(&w);
+        let image = c.with_threads(4).render(&w);
+        assert_eq!(image, single);
+        assert_ne!(image.pixel_at(35, 22), Color::BLACK);
+    }
+
+    #[test]
+    fn every_render_is_the_same_on_any_number_of_threads() {
+        let w = default_world().with_object(floor());
+        let c = Camera::new(37, 29, PI / 2.0)
+            .with_transform(view_transform(
+                Vert4::point(0.0, 1.0, -5.0),
+                Vert4::point(0.0, 0.0, 0.0),
+                Vert4::vector(0.0, 1.0, 0.0),
+            ))
+            .with_seed(11)
+            .with_aa_samples(2)
+            .with_aperture(0.2)
+            .with_focal_distance(5.0)
+            .with_integrator(PathTracer::new(2, 3));
+        let adaptive = c.clone().with_adaptive_aa(0.1);
+        let options = RenderOptions::default();
+        let expected = (
+            c.clone().with_threads(1).render(&w),
+            adaptive.clone().with_threads(1).render(&w),
+            c.clone().with_threads(1).render_hdr(&w, &options),
+            c.clone().with_threads(1).render_aovs(&w, &options),
+        );
+        for threads in [2, 3, 8] {
+            let c = c.clone().with_threads(threads);
+            assert_eq!(c.render(&w), expected.0, "{threads} threads");
+            let adaptive = adaptive.clone().with_threads(threads);
+            assert_eq!(adaptive.render(&w), expected.1, "{threads} threads");
+            assert_eq!(c.render_hdr(&w, &options), expected.2, "{threads} threads");
+            assert_eq!(c.render_aovs(&w, &options), expected.3, "{threads} threads");
+        }
+    }
+
+    #[test]
+    fn progress_is_reported_per_tile() {
+        use std::sync::Mutex;
+
+        let w = default_world();
+        let c = Camera::new(70, 45, PI / 2.0).with_threads(3);
+        let reports = Mutex::new(Vec::new());
+        let sink = |p: &Progress| reports.lock().unwrap().push(*p);
+        let options = RenderOptions {
+            progress: Some(&sink),
+            ..RenderOptions::default()
+        };
+        c.render_with(&w, &options);
+        let mut reports = reports.into_inner().unwrap();
+        // 3 × 2 tiles.
+        assert_eq!(reports.len(), 6);
+        reports.sort_by_key(|p| p.tiles_done);
+        let last = reports.last().unwrap();
+        assert_eq!((last.tiles_done, last.tiles_total), (6, 6));
+        assert_eq!((last.pixels_done, last.pixels_total), (70 * 45, 70 * 45));
+    }
+
+    #[test]
+    fn cancelled_render_stops_early() {
+        let w = default_world();
+        let c = Camera::new(70, 45, PI / 2.0)
+            .with_transform(view_transform(
+                Vert4::point(0.0, 0.0, -5.0),
+                Vert4::point(0.0, 0.0, 0.0),
+                Vert4::vector(0.0, 1.0, 0.0),
+            ))
+            .with_threads(1);
+        let cancel = AtomicBool::new(false);
+        // Cancel as soon as the first tile is in.
+        let sink = |_: &Progress| cancel.store(true, Ordering::Relaxed);
+        let options = RenderOptions {
+            progress: Some(&sink),
+            cancel: Some(&cancel),
+            ..RenderOptions::default()
+        };
+        let partial = c.render_with(&w, &options);
+        let full = c.render(&w);
+        // The first tile, top left, is kept; the centre, in a later tile, isn't.
+        assert_eq!(partial.pixel_at(0, 0), full.pixel_at(0, 0));
+        assert_eq!(partial.pixel_at(35, 22), Color::BLACK);
+        assert_ne!(full.pixel_at(35, 22), Color::BLACK);
+    }
+
+    #[test]
+    fn rendering_a_region() {
+        let w = default_world();
+        let c = Camera::new(70, 45, PI / 2.0).with_transform(view_transform(
+            Vert4::point(0.0, 0.0, -5.0),
+            Vert4::point(0.0, 0.0, 0.0),
+            Vert4::vector(0.0, 1.0, 0.0),
+        ));
+        let options = RenderOptions {
+            // Overhangs the canvas, which is ignored.
+            region: Some(Region::new(30, 20, 100, 3)),
+            ..RenderOptions::default()
+        };
+        let cropped = c.render_with(&w, &options);
+        let full = c.render(&w);
+        assert_eq!((cropped.width(), cropped.height()), (70, 45));
+        for y in 0..45 {
+            for x in 0..70 {
+                let expected = if (30..70).contains(&x) && (20..23).contains(&y) {
+                    full.pixel_at(x, y)
+                } else {
+                    Color::BLACK
+                };
+                assert_eq!(cropped.pixel_at(x, y), expected, "({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn seeded_renders_are_reproducible() {
+        let w = default_world();
+        let c = Camera::new(70, 45, PI / 2.0)
+            .with_transform(view_transform(
+                Vert4::point(0.0, 0.0, -5.0),
+                Vert4::point(0.0, 0.0, 0.0),
+                Vert4::vector(0.0, 1.0, 0.0),
+            ))
+            .with_aa_samples(2)
+            .with_aperture(0.3)
+            .with_focal_distance(5.0);
+        let a = c.clone().with_seed(7).with_threads(1).render(&w);
+        let b = c.clone().with_seed(7).with_threads(4).render(&w);
+        let other = c.clone().with_seed(8).render(&w);
+        let unseeded = c.render(&w);
+        assert_eq!(a, b);
+        assert_ne!(a, other);
+        assert_ne!(a, unseeded);
+    }
+
+    #[test]
+    fn seeded_renders_with_an_irradiance_cache_are_reproducible() {
+        let c = Camera::new(40, 30, PI / 2.0)
+            .with_transform(view_transform(
+                Vert4::point(0.0, 1.0, -5.0),
+                Vert4::point(0.0, 0.0, 0.0),
+                Vert4::vector(0.0, 1.0, 0.0),
+            ))
+            .with_seed(7);
+        let world = || {
+            default_world()
+                .with_object(floor())
+                .with_irradiance_cache(IrradianceCache::new(8, 0.5))
+        };
+        let (one, many) = (world(), world());
+        let a = c.clone().with_threads(1).render(&one);
+        let b = c.clone().with_threads(4).render(&many);
+        assert_eq!(a, b);
+        let cache = one.irradiance_cache.as_ref().unwrap();
+        assert!(!cache.is_empty() && !cache.is_frozen());
+        assert_eq!(cache.len(), many.irradiance_cache.as_ref().unwrap().len());
+    }
+
+    #[test]
+    fn path_traced_render_lights_from_emissive_objects() {
+        let lamp = Sphere::new().with_material(Material {
+            ambient: 0.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            emission: Color::WHITE,
+            ..Material::default()
+        });
+        let w = World::new().with_object(lamp);
+        let c = Camera::new(5, 5, PI / 2.0)
+            .with_transform(view_transform(
+                Vert4::point(0.0, 0.0, -5.0),
+                Vert4::point(0.0, 0.0, 0.0),
+                Vert4::vector(0.0, 1.0, 0.0),
+            ))
+            .with_integrator(PathTracer::new(2, 2));
+        let image = c.render(&w);
+        assert_approx_eq!(image.pixel_at(2, 2), Color::WHITE);
+        assert_eq!(image.pixel_at(0, 0), Color::BLACK);
+    }
+
+    #[test]
+    fn path_traced_samples_are_clamped_by_the_render_settings() {
+        let lamp = Sphere::new().with_material(Material {
+            ambient: 0.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            emission: Color::new(4.0, 8.0, 2.0),
+            ..Material::default()
+        });
+        let w = World::new().with_object(lamp);
+        let c = Camera::new(5, 5, PI / 2.0)
+            .with_transform(view_transform(
+                Vert4::point(0.0, 0.0, -5.0),
+                Vert4::point(0.0, 0.0, 0.0),
+                Vert4::vector(0.0, 1.0, 0.0),
+            ))
+            .with_integrator(PathTracer::new(2, 2))
+            .with_render_settings(RenderSettings::default().with_max_sample_value(2.0));
+        assert_approx_eq!(c.render(&w).pixel_at(2, 2), Color::new(1.0, 2.0, 0.5));
+    }
+}
@@ -0,0 +1,239 @@
+//! Per-pixel motion between the frames of an animation.
+
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use super::{Camera, Projection, RenderOptions};
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::math::{Matrix4, Vert4};
+use crate::shape::{ObjectId, Shape};
+use crate::world::World;
+
+/// Where the camera and every shape were in the previous frame of an
+/// animation, for [`Camera::render_motion_vectors`].
+///
+/// Shapes are matched to the next frame's by their place in the world, so
+/// the world can be rebuilt from scratch each frame as long as its objects
+/// and their children are added in the same order.
+#[derive(Debug, Clone)]
+pub struct PreviousFrame {
+    camera: Camera,
+    /// Each shape's object-to-world transform, in [`shapes`] order.
+    transforms: Vec<Matrix4>,
+}
+
+impl PreviousFrame {
+    pub fn capture(camera: &Camera, world: &World) -> Self {
+        let mut transforms = Vec::new();
+        shapes(world, &mut |shape| {
+            transforms.push(*shape.transform().matrix())
+        });
+        Self {
+            camera: camera.clone(),
+            transforms,
+        }
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+}
+
+/// Every object in `world` and all their descendants, parents before their
+/// children.
+fn shapes<'a>(world: &'a World, visit: &mut dyn FnMut(&'a dyn Shape)) {
+    fn walk<'a>(shape: &'a dyn Shape, visit: &mut dyn FnMut(&'a dyn Shape)) {
+        visit(shape);
+        shape.for_each_child(&mut |child| walk(child, visit));
+    }
+    for object in &world.objects {
+        walk(object.as_ref(), visit);
+    }
+}
+
+impl Camera {
+    /// Where `point` appears on the canvas, in the coordinates
+    /// [`ray_for_sample`](Self::ray_for_sample) takes, or `None` if this
+    /// camera can't see it, such as behind a perspective camera.
+    pub fn project(&self, point: Vert4) -> Option<(f32, f32)> {
+        let eye = *self.transform.matrix() * point - Vert4::point(0.0, 0.0, 0.0);
+        self.canvas_position(eye)
+    }
+
+    /// The inverse of `view_direction`: where the camera-space `direction`
+    /// from the eye lands on the canvas.
+    fn canvas_position(&self, direction: Vert4) -> Option<(f32, f32)> {
+        match self.projection {
+            Projection::Perspective => {
+                if direction.z >= 0.0 {
+                    return None;
+                }
+                let (x, y) = (direction.x / -direction.z, direction.y / -direction.z);
+                Some((
+                    (self.half_width - x) / self.pixel_size,
+                    (self.half_height - y) / self.pixel_size,
+                ))
+            }
+            Projection::Fisheye => {
+                let d = direction.normalize();
+                let theta = (-d.z).clamp(-1.0, 1.0).acos();
+                let r = theta * 2.0 / self.field_of_view;
+                let sideways = d.x.hypot(d.y);
+                let (u, v) = if sideways == 0.0 {
+                    (0.0, 0.0)
+                } else {
+                    (r * d.x / sideways, r * d.y / sideways)
+                };
+                let half_extent = self.hsize.max(self.vsize) as f32 / 2.0;
+                Some((
+                    self.hsize as f32 / 2.0 - u * half_extent,
+                    self.vsize as f32 / 2.0 - v * half_extent,
+                ))
+            }
+            Projection::Equirectangular => {
+                let d = direction.normalize();
+                let longitude = d.x.atan2(-d.z);
+                let latitude = d.y.clamp(-1.0, 1.0).asin();
+                Some((
+                    (0.5 - longitude / (2.0 * PI)) * self.hsize as f32,
+                    (0.5 - latitude / PI) * self.vsize as f32,
+                ))
+            }
+        }
+    }
+
+    /// How far what's seen through the centre of each pixel has moved on
+    /// the canvas since `previous`, in pixels, as `(x, y)` in the red and
+    /// green channels: positive to the right and down. Surfaces move with
+    /// both their own transform and the camera's; the background moves
+    /// only as the camera turns.
+    ///
+    /// Pixels showing something the previous camera couldn't see, and
+    /// those outside `options`' region, are left black.
+    pub fn render_motion_vectors(
+        &self,
+        world: &World,
+        previous: &PreviousFrame,
+        options: &RenderOptions,
+    ) -> Canvas {
+        let mut order = HashMap::new();
+        shapes(world, &mut |shape| {
+            order.insert(shape.id(), order.len());
+        });
+        let region = self.region(options);
+        let tracker = self.tracker(options, &region, 1);
+        let mut motion = self.blank();
+        self.trace_pixels(
+            &tracker,
+            &region,
+            |x, y| self.motion_at(world, previous, &order, x, y),
+            |x, y, offset| motion.write_pixel(x, y, offset),
+        );
+        motion
+    }
+
+    fn motion_at(
+        &self,
+        world: &World,
+        previous: &PreviousFrame,
+        order: &HashMap<ObjectId, usize>,
+        x: usize,
+        y: usize,
+    ) -> Color {
+        let ray = self.ray_for_pixel(x, y);
+        let xs = world.intersect_visible(&ray, self.settings.camera_rays());
+        let before = match xs.hit() {
+            Some(hit) => {
+                let shape = hit.shading_object();
+                let transform = shape.transform();
+                let then = order
+                    .get(&shape.id())
+                    .and_then(|&i| previous.transforms.get(i))
+                    .unwrap_or(transform.matrix());
+                let point = *then * transform.to_local(ray.position(hit.t));
+                previous.camera.project(point)
+            }
+            None => previous
+                .camera
+                .canvas_position(*previous.camera.transform.matrix() * ray.direction),
+        };
+        match before {
+            Some((bx, by)) => Color::new(x as f32 + 0.5 - bx, y as f32 + 0.5 - by, 0.0),
+            None => Color::BLACK,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::view_transform;
+    use crate::shape::Sphere;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn camera(from_x: f32) -> Camera {
+        Camera::new(21, 11, FRAC_PI_2).with_transform(view_transform(
+            Vert4::point(from_x, 0.0, -5.0),
+            Vert4::point(from_x, 0.0, 0.0),
+            Vert4::vector(0.0, 1.0, 0.0),
+        ))
+    }
+
+    fn world(x: f32) -> World {
+        World::new().with_object(Sphere::new().with_transform(Matrix4::translation(x, 0.0, 0.0)))
+    }
+
+    #[test]
+    fn projecting_undoes_ray_for_sample() {
+        for projection in [
+            Projection::Perspective,
+            Projection::Fisheye,
+            Projection::Equirectangular,
+        ] {
+            let c = camera(1.0).with_projection(projection);
+            for (x, y) in [(10.5, 5.5), (3.25, 8.0), (17.0, 1.5)] {
+                let ray = c.ray_for_sample(x, y);
+                let (px, py) = c.project(ray.position(3.0)).unwrap();
+                assert_approx_eq!(px, x);
+                assert_approx_eq!(py, y);
+            }
+        }
+        assert_eq!(camera(0.0).project(Vert4::point(0.0, 0.0, -6.0)), None);
+    }
+
+    #[test]
+    fn nothing_moving_gives_no_motion() {
+        let (c, w) = (camera(0.0), world(0.0));
+        let previous = PreviousFrame::capture(&c, &w);
+        let motion = c.render_motion_vectors(&w, &previous, &RenderOptions::default());
+        assert!(motion
+            .pixels()
+            .iter()
+            .all(|m| m.r.abs() < 1e-3 && m.g.abs() < 1e-3));
+    }
+
+    #[test]
+    fn moving_objects_are_matched_across_rebuilt_worlds() {
+        let c = camera(0.0);
+        let previous = PreviousFrame::capture(&c, &world(-0.2));
+        let motion = c.render_motion_vectors(&world(0.0), &previous, &RenderOptions::default());
+        // 0.2 units across at the sphere's front, 4 units away, with pixels
+        // 2/21 units across at 1 unit away.
+        let centre = motion.pixel_at(10, 5);
+        assert_approx_eq!(centre.r, 0.2 / 4.0 * 21.0 / 2.0);
+        assert_approx_eq!(centre.g, 0.0);
+        assert_approx_eq!(motion.pixel_at(0, 0), Color::BLACK);
+    }
+
+    #[test]
+    fn a_moving_camera_moves_everything() {
+        let w = world(0.0);
+        let previous = PreviousFrame::capture(&camera(0.5), &w);
+        let motion = camera(0.0).render_motion_vectors(&w, &previous, &RenderOptions::default());
+        assert!(motion.pixel_at(10, 5).r > 0.0);
+        // The background is at infinity, so moving without turning leaves it
+        // still.
+        assert_approx_eq!(motion.pixel_at(0, 0), Color::BLACK);
+    }
+}
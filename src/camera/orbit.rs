@@ -0,0 +1,199 @@
+//! Orbit, pan, dolly, and zoom controls for framing a shot.
+
+use std::f32::consts::{FRAC_PI_2, PI};
+use std::fmt::{self, Display};
+
+use super::{view_transform, Camera};
+use crate::math::{Matrix4, Vert4, EPSILON};
+
+/// How close to straight up or down the eye can orbit; any closer and the
+/// view's up direction would flip over.
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+/// The narrowest and widest field of view zooming reaches.
+const FIELD_OF_VIEW: (f32, f32) = (0.01, PI - 0.01);
+
+/// An eye circling a target, moved the way a viewer's mouse drags move it.
+///
+/// A viewer calls these as the mouse moves, restarting a low-sample
+/// [`render_progressive`](Camera::render_progressive) through
+/// [`camera`](Self::camera) whenever the controller changes. Once the shot
+/// is framed, the controller's [`Display`] form is the builder calls that
+/// set it up again, to paste into the scene's code.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitController {
+    /// The point the eye looks at and orbits.
+    pub target: Vert4,
+    /// How far the eye is from the target.
+    pub distance: f32,
+    /// The eye's angle about the target's vertical axis, in radians, from
+    /// +z towards +x.
+    pub yaw: f32,
+    /// The eye's angle above the target's horizontal plane, in radians.
+    pub pitch: f32,
+    pub field_of_view: f32,
+}
+
+impl OrbitController {
+    /// A controller with the eye at `from` looking at `to`, which must be
+    /// apart. World +y is up.
+    pub fn new(from: Vert4, to: Vert4, field_of_view: f32) -> Self {
+        let offset = from - to;
+        let distance = offset.magnitude();
+        Self {
+            target: to,
+            distance,
+            yaw: offset.x.atan2(offset.z),
+            pitch: (offset.y / distance).asin().clamp(-MAX_PITCH, MAX_PITCH),
+            field_of_view,
+        }
+    }
+
+    /// The controls for what `camera` shows now, orbiting the point
+    /// `distance` in front of it.
+    pub fn from_camera(camera: &Camera, distance: f32) -> Self {
+        let inverse = *camera.transform().inverse();
+        let eye = inverse * Vert4::point(0.0, 0.0, 0.0);
+        let target = inverse * Vert4::point(0.0, 0.0, -distance);
+        Self::new(eye, target, camera.field_of_view())
+    }
+
+    pub fn eye(&self) -> Vert4 {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        self.target
+            + Vert4::vector(cos_pitch * sin_yaw, sin_pitch, cos_pitch * cos_yaw) * self.distance
+    }
+
+    pub fn view_transform(&self) -> Matrix4 {
+        view_transform(self.eye(), self.target, Vert4::vector(0.0, 1.0, 0.0))
+    }
+
+    /// `base` looking through these controls: the same canvas and settings,
+    /// with this field of view and view transform.
+    pub fn camera(&self, base: &Camera) -> Camera {
+        base.clone()
+            .with_field_of_view(self.field_of_view)
+            .with_transform(self.view_transform())
+    }
+
+    /// Swings the eye around the target by `yaw` and `pitch` radians,
+    /// stopping short of straight up or down.
+    pub fn orbit(&mut self, yaw: f32, pitch: f32) {
+        self.yaw = (self.yaw + yaw).rem_euclid(2.0 * PI);
+        self.pitch = (self.pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Slides the eye and target together across the view, `right` and `up`
+    /// in units of the distance between them, so a drag covers about the
+    /// same part of the picture however far away the target is.
+    pub fn pan(&mut self, right: f32, up: f32) {
+        let forward = (self.target - self.eye()).normalize();
+        let right_dir = Vert4::vector(0.0, 1.0, 0.0).cross(&forward).normalize();
+        let up_dir = forward.cross(&right_dir);
+        self.target = self.target + (right_dir * right + up_dir * up) * self.distance;
+    }
+
+    /// Moves the eye towards the target, to `factor` times the distance;
+    /// below 1 it moves closer. The eye never reaches the target.
+    pub fn dolly(&mut self, factor: f32) {
+        self.distance = (self.distance * factor).max(EPSILON);
+    }
+
+    /// Widens the field of view by `radians`, or narrows it if negative.
+    pub fn zoom(&mut self, radians: f32) {
+        self.field_of_view = (self.field_of_view + radians).clamp(FIELD_OF_VIEW.0, FIELD_OF_VIEW.1);
+    }
+}
+
+/// The camera builder calls for this framing, to follow `Camera::new`.
+impl Display for OrbitController {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (eye, target) = (self.eye(), self.target);
+        write!(
+            f,
+            ".with_field_of_view({:?}).with_transform(view_transform(\
+             Vert4::point({:?}, {:?}, {:?}), Vert4::point({:?}, {:?}, {:?}), \
+             Vert4::vector(0.0, 1.0, 0.0)))",
+            self.field_of_view, eye.x, eye.y, eye.z, target.x, target.y, target.z
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller() -> OrbitController {
+        OrbitController::new(
+            Vert4::point(0.0, 0.0, -5.0),
+            Vert4::point(0.0, 0.0, 0.0),
+            FRAC_PI_2,
+        )
+    }
+
+    #[test]
+    fn a_new_controller_keeps_the_view() {
+        let from = Vert4::point(1.0, 2.0, -3.0);
+        let to = Vert4::point(0.0, 1.0, 0.5);
+        let c = OrbitController::new(from, to, 1.0);
+        assert_approx_eq!(c.eye(), from);
+        assert_approx_eq!(
+            c.view_transform(),
+            view_transform(from, to, Vert4::vector(0.0, 1.0, 0.0))
+        );
+        let camera = c.camera(&Camera::new(20, 10, 0.5));
+        assert_eq!((camera.hsize(), camera.field_of_view()), (20, 1.0));
+        assert_approx_eq!(
+            OrbitController::from_camera(&camera, c.distance).eye(),
+            from
+        );
+    }
+
+    #[test]
+    fn orbiting_circles_the_target() {
+        let mut c = controller();
+        c.orbit(FRAC_PI_2, 0.0);
+        assert_approx_eq!(c.eye(), Vert4::point(-5.0, 0.0, 0.0));
+        c.orbit(0.0, PI);
+        assert_eq!(c.pitch, MAX_PITCH);
+        assert_approx_eq!((c.eye() - c.target).magnitude(), 5.0);
+    }
+
+    #[test]
+    fn panning_keeps_the_target_in_the_middle() {
+        let mut c = controller();
+        c.pan(0.2, -0.1);
+        assert_approx_eq!(c.target, Vert4::point(1.0, -0.5, 0.0));
+        assert_approx_eq!(c.eye(), Vert4::point(1.0, -0.5, -5.0));
+        let camera = c.camera(&Camera::new(11, 11, 1.0));
+        let (x, y) = camera.project(c.target).unwrap();
+        assert_approx_eq!(x, 5.5);
+        assert_approx_eq!(y, 5.5);
+    }
+
+    #[test]
+    fn dollying_and_zooming_stay_in_range() {
+        let mut c = controller();
+        c.dolly(0.5);
+        assert_approx_eq!(c.eye(), Vert4::point(0.0, 0.0, -2.5));
+        c.dolly(0.0);
+        assert!(c.distance > 0.0);
+        c.zoom(-0.5);
+        assert_approx_eq!(c.field_of_view, FRAC_PI_2 - 0.5);
+        c.zoom(10.0);
+        assert_eq!(c.field_of_view, FIELD_OF_VIEW.1);
+    }
+
+    #[test]
+    fn the_framing_prints_as_builder_calls() {
+        let mut c = controller();
+        c.yaw = 0.0;
+        assert_eq!(
+            c.to_string(),
+            ".with_field_of_view(1.5707964).with_transform(view_transform(\
+             Vert4::point(0.0, 0.0, 5.0), Vert4::point(0.0, 0.0, 0.0), \
+             Vert4::vector(0.0, 1.0, 0.0)))"
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! Reporting how far a render has got.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use super::RenderOptions;
+
+/// A snapshot of a render's progress, passed to a [`ProgressSink`] each time a
+/// tile finishes.
+///
+/// Renders that make several passes over the canvas, such as adaptive
+/// anti-aliasing, count every pass in the totals.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub pixels_done: usize,
+    pub pixels_total: usize,
+    pub tiles_done: usize,
+    pub tiles_total: usize,
+    /// The time since the render started.
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// How much of the render is done, from 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        if self.pixels_total == 0 {
+            1.0
+        } else {
+            self.pixels_done as f32 / self.pixels_total as f32
+        }
+    }
+
+    /// The time left if the rest of the render goes as fast as it has so far,
+    /// or `None` before any pixels are done.
+    pub fn remaining(&self) -> Option<Duration> {
+        if self.pixels_done == 0 {
+            return None;
+        }
+        let left = (self.pixels_total - self.pixels_done) as f64 / self.pixels_done as f64;
+        Some(self.elapsed.mul_f64(left))
+    }
+}
+
+/// Receives a render's [`Progress`], for instance to draw a progress bar.
+///
+/// It's called from the render threads as they finish tiles, so calls may
+/// overlap and arrive slightly out of order; keep it quick.
+pub trait ProgressSink: Sync {
+    fn progress(&self, progress: &Progress);
+}
+
+impl<F: Fn(&Progress) + Sync> ProgressSink for F {
+    fn progress(&self, progress: &Progress) {
+        self(progress)
+    }
+}
+
+/// Counts finished tiles across the render threads and reports them, and
+/// tells the threads whether to stop.
+pub(super) struct Tracker<'a> {
+    sink: Option<&'a dyn ProgressSink>,
+    cancel: Option<&'a AtomicBool>,
+    pixels_total: usize,
+    tiles_total: usize,
+    pixels_done: AtomicUsize,
+    tiles_done: AtomicUsize,
+    start: Instant,
+}
+
+impl<'a> Tracker<'a> {
+    pub(super) fn new(
+        options: &RenderOptions<'a>,
+        pixels_total: usize,
+        tiles_total: usize,
+    ) -> Self {
+        Self {
+            sink: options.progress,
+            cancel: options.cancel,
+            pixels_total,
+            tiles_total,
+            pixels_done: AtomicUsize::new(0),
+            tiles_done: AtomicUsize::new(0),
+            start: Instant::now(),
+        }
+    }
+
+    pub(super) fn is_cancelled(&self) -> bool {
+        self.cancel
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    pub(super) fn tile_done(&self, pixels: usize) {
+        let Some(sink) = self.sink else {
+            return;
+        };
+        let pixels_done = self.pixels_done.fetch_add(pixels, Ordering::Relaxed) + pixels;
+        let tiles_done = self.tiles_done.fetch_add(1, Ordering::Relaxed) + 1;
+        sink.progress(&Progress {
+            pixels_done,
+            pixels_total: self.pixels_total,
+            tiles_done,
+            tiles_total: self.tiles_total,
+            elapsed: self.start.elapsed(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remaining_time_extrapolates_elapsed() {
+        let p = Progress {
+            pixels_done: 25,
+            pixels_total: 100,
+            tiles_done: 1,
+            tiles_total: 4,
+            elapsed: Duration::from_secs(2),
+        };
+        assert_eq!(p.fraction(), 0.25);
+        assert_eq!(p.remaining(), Some(Duration::from_secs(6)));
+        assert_eq!(
+            Progress {
+                pixels_done: 0,
+                ..p
+            }
+            .remaining(),
+            None
+        );
+    }
+}
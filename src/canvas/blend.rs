@@ -0,0 +1,90 @@
+//! Compositing one canvas onto another.
+
+use super::Canvas;
+use crate::color::Color;
+
+/// How [`Canvas::blit`] combines a pixel being drawn with the one under it.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BlendMode {
+    /// The new pixel replaces the old.
+    #[default]
+    Replace,
+    /// The two are summed, as for light from separate passes.
+    Add,
+    /// The two are multiplied channel by channel, darkening, as for shadows
+    /// or tints.
+    Multiply,
+    /// The new pixel is laid over the old with this opacity, from 0 (not at
+    /// all) to 1 (as [`Replace`](Self::Replace)).
+    Alpha(f32),
+}
+
+impl BlendMode {
+    /// The result of drawing `source` over `destination`.
+    pub fn blend(&self, source: Color, destination: Color) -> Color {
+        match self {
+            BlendMode::Replace => source,
+            BlendMode::Add => destination + source,
+            BlendMode::Multiply => destination * source,
+            BlendMode::Alpha(alpha) => destination + (source - destination) * alpha.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Canvas {
+    /// Draws `other` with its top left at `position`, combining each of its
+    /// pixels with the one under it by `mode`. Whatever falls off this canvas
+    /// is clipped.
+    pub fn blit(&mut self, other: &Canvas, position: (isize, isize), mode: BlendMode) {
+        let (left, top) = position;
+        for (x, y, source) in other.enumerate_pixels() {
+            let (Ok(x), Ok(y)) = (
+                usize::try_from(left + x as isize),
+                usize::try_from(top + y as isize),
+            ) else {
+                continue;
+            };
+            if let Ok(destination) = self.try_pixel_at(x, y) {
+                self.write_pixel(x, y, mode.blend(source, destination));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blend_modes() {
+        let source = Color::new(0.5, 1.0, 0.0);
+        let destination = Color::new(0.5, 0.5, 1.0);
+        assert_eq!(BlendMode::Replace.blend(source, destination), source);
+        assert_approx_eq!(
+            BlendMode::Add.blend(source, destination),
+            Color::new(1.0, 1.5, 1.0)
+        );
+        assert_approx_eq!(
+            BlendMode::Multiply.blend(source, destination),
+            Color::new(0.25, 0.5, 0.0)
+        );
+        assert_approx_eq!(
+            BlendMode::Alpha(0.5).blend(source, destination),
+            Color::new(0.5, 0.75, 0.5)
+        );
+        assert_approx_eq!(
+            BlendMode::Alpha(0.0).blend(source, destination),
+            destination
+        );
+    }
+
+    #[test]
+    fn blitting_places_and_clips_the_overlay() {
+        let mut base = Canvas::new_with(3, 3, Color::new(0.5, 0.5, 0.5));
+        let overlay = Canvas::new_with(2, 2, Color::WHITE);
+        base.blit(&overlay, (2, -1), BlendMode::Add);
+        assert_approx_eq!(base.pixel_at(2, 0), Color::new(1.5, 1.5, 1.5));
+        assert_approx_eq!(base.pixel_at(1, 0), Color::new(0.5, 0.5, 0.5));
+        assert_approx_eq!(base.pixel_at(2, 1), Color::new(0.5, 0.5, 0.5));
+    }
+}
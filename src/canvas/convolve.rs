@@ -0,0 +1,204 @@
+//! Filtering an image with a convolution kernel.
+
+use super::Canvas;
+use crate::color::Color;
+
+/// A grid of weights, each pixel of a [`convolved`](Canvas::convolve) image
+/// being the sum of its neighbours times the weights over them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Kernel {
+    width: usize,
+    height: usize,
+    weights: Vec<f32>,
+}
+
+impl Kernel {
+    /// A `width` × `height` kernel centred on its middle weight, with
+    /// `weights` given row by row from the top.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either side is even, or there isn't one weight for each
+    /// cell.
+    pub fn new(width: usize, height: usize, weights: Vec<f32>) -> Self {
+        assert!(
+            width % 2 == 1 && height % 2 == 1,
+            "kernel sides must be odd to have a centre, not {width}x{height}"
+        );
+        assert_eq!(
+            weights.len(),
+            width * height,
+            "a {width}x{height} kernel needs {} weights",
+            width * height
+        );
+        Self {
+            width,
+            height,
+            weights,
+        }
+    }
+
+    /// A blur weighting neighbours by a normal distribution with standard
+    /// deviation `sigma` pixels, reaching out 3 `sigma`s and summing to 1.
+    /// A `sigma` of 0 or less doesn't blur at all.
+    pub fn gaussian(sigma: f32) -> Self {
+        if sigma <= 0.0 {
+            return Self::new(1, 1, vec![1.0]);
+        }
+        let radius = (3.0 * sigma).ceil().max(0.0) as isize;
+        let side = 2 * radius as usize + 1;
+        let weight = |x: isize, y: isize| (-((x * x + y * y) as f32) / (2.0 * sigma * sigma)).exp();
+        let weights: Vec<f32> = (-radius..=radius)
+            .flat_map(|y| (-radius..=radius).map(move |x| weight(x, y)))
+            .collect();
+        let total: f32 = weights.iter().sum();
+        Self::new(side, side, weights.into_iter().map(|w| w / total).collect())
+    }
+
+    /// Exaggerates the difference between each pixel and its neighbours.
+    pub fn sharpen() -> Self {
+        #[rustfmt::skip]
+        let weights = vec![
+             0.0, -1.0,  0.0,
+            -1.0,  5.0, -1.0,
+             0.0, -1.0,  0.0,
+        ];
+        Self::new(3, 3, weights)
+    }
+
+    /// The Sobel operator's horizontal gradient: positive where the image
+    /// brightens to the right.
+    pub fn sobel_x() -> Self {
+        #[rustfmt::skip]
+        let weights = vec![
+            -1.0, 0.0, 1.0,
+            -2.0, 0.0, 2.0,
+            -1.0, 0.0, 1.0,
+        ];
+        Self::new(3, 3, weights)
+    }
+
+    /// The Sobel operator's vertical gradient: positive where the image
+    /// brightens downwards.
+    pub fn sobel_y() -> Self {
+        #[rustfmt::skip]
+        let weights = vec![
+            -1.0, -2.0, -1.0,
+             0.0,  0.0,  0.0,
+             1.0,  2.0,  1.0,
+        ];
+        Self::new(3, 3, weights)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
+
+impl Canvas {
+    /// The canvas filtered by `kernel`. Neighbours past the edges take the
+    /// edge's color, so flat areas stay flat right up to the border.
+    pub fn convolve(&self, kernel: &Kernel) -> Canvas {
+        let (rx, ry) = ((kernel.width / 2) as isize, (kernel.height / 2) as isize);
+        let max_x = self.width as isize - 1;
+        let max_y = self.height as isize - 1;
+        Canvas::from_fn(self.width, self.height, |x, y| {
+            let mut total = Color::BLACK;
+            for (i, weight) in kernel.weights.iter().enumerate() {
+                let dx = (i % kernel.width) as isize - rx;
+                let dy = (i / kernel.width) as isize - ry;
+                let sx = (x as isize + dx).clamp(0, max_x) as usize;
+                let sy = (y as isize + dy).clamp(0, max_y) as usize;
+                total += self.pixel_at(sx, sy) * *weight;
+            }
+            total
+        })
+    }
+
+    /// The canvas blurred by a [`Kernel::gaussian`] of `sigma` pixels.
+    pub fn gaussian_blur(&self, sigma: f32) -> Canvas {
+        self.convolve(&Kernel::gaussian(sigma))
+    }
+
+    /// The canvas with edges sharpened by [`Kernel::sharpen`].
+    pub fn sharpen(&self) -> Canvas {
+        self.convolve(&Kernel::sharpen())
+    }
+
+    /// How sharply each channel changes at each pixel, by the Sobel
+    /// operator: black where the image is flat, bright along edges.
+    pub fn edges(&self) -> Canvas {
+        let gx = self.convolve(&Kernel::sobel_x());
+        let gy = self.convolve(&Kernel::sobel_y());
+        Canvas::from_fn(self.width, self.height, |x, y| {
+            gx.pixel_at(x, y).zip(gy.pixel_at(x, y), |a, b| a.hypot(b))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step() -> Canvas {
+        Canvas::from_fn(6, 3, |x, _| if x < 3 { Color::BLACK } else { Color::WHITE })
+    }
+
+    #[test]
+    fn identity_kernel_leaves_the_image_alone() {
+        let c = Canvas::from_fn(4, 3, |x, y| Color::new(x as f32, y as f32, 1.0));
+        let identity = Kernel::new(3, 3, vec![0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(c.convolve(&identity), c);
+    }
+
+    #[test]
+    fn kernels_are_anchored_at_their_centre() {
+        let mut c = Canvas::new(3, 1);
+        c.write_pixel(1, 0, Color::WHITE);
+        let shift = Kernel::new(3, 1, vec![1.0, 0.0, 0.0]);
+        let shifted = c.convolve(&shift);
+        assert_eq!(shifted.pixel_at(2, 0), Color::WHITE);
+        assert_eq!(shifted.pixel_at(1, 0), Color::BLACK);
+    }
+
+    #[test]
+    fn gaussian_blur_keeps_flat_areas_and_softens_edges() {
+        let kernel = Kernel::gaussian(1.0);
+        assert_eq!((kernel.width(), kernel.height()), (7, 7));
+        let c = Canvas::from_fn(4, 3, |x, y| Color::new(x as f32, y as f32, 1.0));
+        assert_eq!(c.gaussian_blur(0.0), c);
+        let flat = Canvas::new_with(5, 5, Color::WHITE * 0.5);
+        assert_canvas_eq!(flat.gaussian_blur(1.0), flat);
+        let blurred = step().gaussian_blur(1.0);
+        let (left, right) = (blurred.pixel_at(2, 1).r, blurred.pixel_at(3, 1).r);
+        assert!(0.0 < left && left < 0.5 && 0.5 < right && right < 1.0);
+        assert_approx_eq!(left + right, 1.0);
+    }
+
+    #[test]
+    fn sharpening_overshoots_at_edges() {
+        let sharp = step().sharpen();
+        assert_approx_eq!(sharp.pixel_at(2, 1), Color::WHITE * -1.0);
+        assert_approx_eq!(sharp.pixel_at(3, 1), Color::WHITE * 2.0);
+        assert_eq!(sharp.pixel_at(0, 1), Color::BLACK);
+    }
+
+    #[test]
+    fn edges_light_up_only_where_the_image_changes() {
+        let edges = step().edges();
+        assert_eq!(edges.pixel_at(0, 1), Color::BLACK);
+        assert_eq!(edges.pixel_at(5, 1), Color::BLACK);
+        assert_approx_eq!(edges.pixel_at(2, 1), Color::WHITE * 4.0);
+        assert_approx_eq!(edges.pixel_at(3, 1), Color::WHITE * 4.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be odd")]
+    fn kernels_need_a_centre() {
+        Kernel::new(2, 1, vec![0.5, 0.5]);
+    }
+}
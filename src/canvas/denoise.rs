@@ -0,0 +1,240 @@
+//! Smoothing away the noise of path-traced images.
+
+use super::Canvas;
+use crate::color::Color;
+
+/// Images that show where a noisy image's edges are, pixel for pixel, so
+/// the [`Denoiser`] doesn't blur across them. Any may be left out.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Guides<'a> {
+    /// The surface normal seen at each pixel, as `(x, y, z)` in the channels.
+    pub normal: Option<&'a Canvas>,
+    /// The distance to the surface seen at each pixel, in every channel.
+    pub depth: Option<&'a Canvas>,
+    /// The surface color seen at each pixel, without lighting. Noise is
+    /// filtered in the light alone, and the texture put back afterwards, so
+    /// textures stay sharp.
+    pub albedo: Option<&'a Canvas>,
+}
+
+/// An edge-avoiding À-Trous wavelet filter (Dammertz et al.): repeated
+/// blurs with a 5 × 5 kernel whose taps spread twice as far each time, each
+/// tap weighted down the more it differs from the pixel being filtered in
+/// color, normal, or depth.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Denoiser {
+    /// How many passes to make; the filter reaches `2^(iterations + 1)`
+    /// pixels in each direction.
+    pub iterations: u32,
+    /// How different colors may be and still be blended. Halved every pass,
+    /// so later, wider passes only remove what's left of the noise.
+    pub color_sigma: f32,
+    /// How different normals may be, as the distance between unit vectors.
+    pub normal_sigma: f32,
+    /// How different depths may be.
+    pub depth_sigma: f32,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Self {
+            iterations: 5,
+            color_sigma: 0.5,
+            normal_sigma: 0.3,
+            depth_sigma: 0.5,
+        }
+    }
+}
+
+/// The B3 spline the kernel is built from.
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+impl Denoiser {
+    /// `image` filtered using `guides`, which must be the same size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a guide is a different size from `image`.
+    pub fn denoise(&self, image: &Canvas, guides: &Guides) -> Canvas {
+        for guide in [guides.normal, guides.depth, guides.albedo]
+            .into_iter()
+            .flatten()
+        {
+            assert!(
+                guide.width() == image.width() && guide.height() == image.height(),
+                "guide is {}x{} but the image is {}x{}",
+                guide.width(),
+                guide.height(),
+                image.width(),
+                image.height()
+            );
+        }
+        let mut current = match guides.albedo {
+            Some(albedo) => map_pixels(image, albedo, |color, albedo| {
+                color.zip(albedo, |c, a| if a > 0.0 { c / a } else { c })
+            }),
+            None => image.clone(),
+        };
+        let mut color_sigma = self.color_sigma;
+        for i in 0..self.iterations {
+            current = self.pass(&current, guides, 1 << i, color_sigma);
+            color_sigma /= 2.0;
+        }
+        match guides.albedo {
+            Some(albedo) => map_pixels(&current, albedo, |light, albedo| {
+                light.zip(albedo, |l, a| if a > 0.0 { l * a } else { l })
+            }),
+            None => current,
+        }
+    }
+
+    /// One filtering pass with taps `step` pixels apart.
+    fn pass(&self, image: &Canvas, guides: &Guides, step: usize, color_sigma: f32) -> Canvas {
+        let (width, height) = (image.width(), image.height());
+        let mut out = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let color = image.pixel_at(x, y);
+                let mut total = Color::BLACK;
+                let mut total_weight = 0.0;
+                for (j, ky) in KERNEL.iter().enumerate() {
+                    let Some(qy) = offset(y, j, step, height) else {
+                        continue;
+                    };
+                    for (i, kx) in KERNEL.iter().enumerate() {
+                        let Some(qx) = offset(x, i, step, width) else {
+                            continue;
+                        };
+                        let other = image.pixel_at(qx, qy);
+                        let mut weight = kx * ky * similarity(color, other, color_sigma);
+                        if let Some(normal) = guides.normal {
+                            weight *= similarity(
+                                normal.pixel_at(x, y),
+                                normal.pixel_at(qx, qy),
+                                self.normal_sigma,
+                            );
+                        }
+                        if let Some(depth) = guides.depth {
+                            weight *= similarity(
+                                depth.pixel_at(x, y),
+                                depth.pixel_at(qx, qy),
+                                self.depth_sigma,
+                            );
+                        }
+                        total += other * weight;
+                        total_weight += weight;
+                    }
+                }
+                // The centre tap always has some weight.
+                out.write_pixel(x, y, total / total_weight);
+            }
+        }
+        out
+    }
+}
+
+/// The coordinate of tap `tap` (0 to 4, 2 being the centre) around `at`, if
+/// it's inside `0..len`.
+fn offset(at: usize, tap: usize, step: usize, len: usize) -> Option<usize> {
+    let q = (at + tap * step).checked_sub(2 * step)?;
+    (q < len).then_some(q)
+}
+
+/// A Gaussian falloff in the distance between `a` and `b`, 1 when equal,
+/// even if infinite, as depth is where nothing was hit.
+fn similarity(a: Color, b: Color, sigma: f32) -> f32 {
+    let d = a.zip(b, |a, b| if a == b { 0.0 } else { a - b });
+    let distance2 = d.r * d.r + d.g * d.g + d.b * d.b;
+    if sigma <= 0.0 {
+        return if distance2 == 0.0 { 1.0 } else { 0.0 };
+    }
+    (-distance2 / (sigma * sigma)).exp()
+}
+
+fn map_pixels(a: &Canvas, b: &Canvas, f: impl Fn(Color, Color) -> Color) -> Canvas {
+    Canvas::from_fn(a.width(), a.height(), |x, y| {
+        f(a.pixel_at(x, y), b.pixel_at(x, y))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::random::Rng;
+
+    fn variance(image: &Canvas, xs: std::ops::Range<usize>) -> f32 {
+        let values: Vec<f32> = (0..image.height())
+            .flat_map(|y| xs.clone().map(move |x| (x, y)))
+            .map(|(x, y)| image.pixel_at(x, y).r)
+            .collect();
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32
+    }
+
+    /// Grey noise around 0.25 on the left half and 0.75 on the right.
+    fn noisy_halves() -> Canvas {
+        let mut rng = Rng::new(1);
+        let mut image = Canvas::new(32, 32);
+        for y in 0..32 {
+            for x in 0..32 {
+                let base = if x < 16 { 0.25 } else { 0.75 };
+                let v = base + (rng.next_f32() - 0.5) * 0.2;
+                image.write_pixel(x, y, Color::new(v, v, v));
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn smooths_noise() {
+        let image = noisy_halves();
+        let denoised = Denoiser::default().denoise(&image, &Guides::default());
+        assert!(variance(&denoised, 0..12) < variance(&image, 0..12) / 4.0);
+    }
+
+    #[test]
+    fn normals_keep_edges_sharp() {
+        let image = noisy_halves();
+        let mut normal = Canvas::new(32, 32);
+        for y in 0..32 {
+            for x in 16..32 {
+                normal.write_pixel(x, y, Color::new(1.0, 0.0, 0.0));
+            }
+        }
+        let guides = Guides {
+            normal: Some(&normal),
+            ..Guides::default()
+        };
+        let denoised = Denoiser {
+            color_sigma: 10.0,
+            ..Denoiser::default()
+        }
+        .denoise(&image, &guides);
+        assert!((denoised.pixel_at(15, 16).r - 0.25).abs() < 0.05);
+        assert!((denoised.pixel_at(16, 16).r - 0.75).abs() < 0.05);
+    }
+
+    #[test]
+    fn albedo_is_kept_out_of_the_blur() {
+        // A flat light on a checkered texture: nothing to remove.
+        let mut image = Canvas::new(8, 8);
+        let mut albedo = Canvas::new(8, 8);
+        for y in 0..8 {
+            for x in 0..8 {
+                let a = if (x + y) % 2 == 0 { 0.2 } else { 0.8 };
+                albedo.write_pixel(x, y, Color::new(a, a, a));
+                image.write_pixel(x, y, Color::new(a, a, a) * 0.5);
+            }
+        }
+        let guides = Guides {
+            albedo: Some(&albedo),
+            ..Guides::default()
+        };
+        let denoised = Denoiser::default().denoise(&image, &guides);
+        for y in 0..8 {
+            for x in 0..8 {
+                assert_approx_eq!(denoised.pixel_at(x, y), image.pixel_at(x, y));
+            }
+        }
+    }
+}
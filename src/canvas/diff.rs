@@ -0,0 +1,118 @@
+//! Comparing two images, for regression tests.
+
+use super::Canvas;
+use crate::color::Color;
+
+/// How far one canvas is from another; see [`Canvas::diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffReport {
+    /// The largest difference in each channel over all pixels.
+    pub max_error: Color,
+    /// The average difference in each channel.
+    pub mean_error: Color,
+    /// The pixel with the largest difference in any channel.
+    pub worst_pixel: (usize, usize),
+    /// Grey where the canvases differ, brightest at the worst pixel, and
+    /// black where they match.
+    pub heatmap: Canvas,
+}
+
+impl DiffReport {
+    /// Whether no channel of any pixel differs by more than `tolerance`.
+    pub fn within(&self, tolerance: f32) -> bool {
+        largest_channel(self.max_error) <= tolerance
+    }
+}
+
+impl Canvas {
+    /// How far `other` is from this canvas, pixel by pixel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the canvases aren't the same size.
+    pub fn diff(&self, other: &Canvas) -> DiffReport {
+        assert!(
+            (self.width, self.height) == (other.width, other.height),
+            "can't diff a {}x{} canvas against a {}x{} one",
+            self.width,
+            self.height,
+            other.width,
+            other.height
+        );
+        let errors = Canvas::from_fn(self.width, self.height, |x, y| {
+            self.pixel_at(x, y)
+                .zip(other.pixel_at(x, y), |a, b| (a - b).abs())
+        });
+        let (worst_pixel, worst) = errors
+            .enumerate_pixels()
+            .map(|(x, y, error)| ((x, y), largest_channel(error)))
+            .fold(((0, 0), 0.0), |a, b| if b.1 > a.1 { b } else { a });
+        let pixels = errors.pixels().len().max(1) as f32;
+        DiffReport {
+            max_error: errors
+                .pixels()
+                .iter()
+                .fold(Color::BLACK, |a, b| a.zip(*b, f32::max)),
+            mean_error: errors.pixels().iter().copied().sum::<Color>() / pixels,
+            worst_pixel,
+            heatmap: errors.map(|error| {
+                let grey = if worst > 0.0 {
+                    largest_channel(error) / worst
+                } else {
+                    0.0
+                };
+                Color::new(grey, grey, grey)
+            }),
+        }
+    }
+}
+
+fn largest_channel(c: Color) -> f32 {
+    c.r.max(c.g).max(c.b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_canvases_have_no_error() {
+        let c = Canvas::from_fn(3, 2, |x, y| Color::new(x as f32, y as f32, 0.5));
+        let report = c.diff(&c.clone());
+        assert_eq!(report.max_error, Color::BLACK);
+        assert_eq!(report.mean_error, Color::BLACK);
+        assert_eq!(report.heatmap, Canvas::new(3, 2));
+        assert!(report.within(0.0));
+        assert_canvas_eq!(c, c.clone());
+    }
+
+    #[test]
+    fn errors_are_measured_per_channel() {
+        let a = Canvas::new(2, 2);
+        let mut b = Canvas::new(2, 2);
+        b.write_pixel(1, 0, Color::new(0.4, -0.2, 0.0));
+        b.write_pixel(0, 1, Color::new(0.1, 0.0, 0.0));
+        let report = a.diff(&b);
+        assert_approx_eq!(report.max_error, Color::new(0.4, 0.2, 0.0));
+        assert_approx_eq!(report.mean_error, Color::new(0.125, 0.05, 0.0));
+        assert_eq!(report.worst_pixel, (1, 0));
+        assert_approx_eq!(report.heatmap.pixel_at(1, 0), Color::WHITE);
+        assert_approx_eq!(report.heatmap.pixel_at(0, 1), Color::WHITE * 0.25);
+        assert!(report.within(0.4) && !report.within(0.3));
+        assert_canvas_eq!(a, b, 0.5);
+    }
+
+    #[test]
+    #[should_panic(expected = "worst at pixel (1, 0)")]
+    fn differing_canvases_fail_the_assertion() {
+        let mut b = Canvas::new(2, 1);
+        b.write_pixel(1, 0, Color::WHITE * 0.01);
+        assert_canvas_eq!(Canvas::new(2, 1), b);
+    }
+
+    #[test]
+    #[should_panic(expected = "can't diff a 2x1 canvas against a 1x2 one")]
+    fn canvases_of_different_sizes_cannot_be_diffed() {
+        Canvas::new(2, 1).diff(&Canvas::new(1, 2));
+    }
+}
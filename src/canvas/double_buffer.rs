@@ -0,0 +1,86 @@
+//! Two canvases taking turns, for animation.
+
+use super::Canvas;
+
+/// A pair of buffers: the front one on show, the back one being drawn. Once
+/// a frame is finished, [`swap`](Self::swap) puts it on show and hands back
+/// the old one to draw the next.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DoubleBuffer<T = Canvas> {
+    buffers: [T; 2],
+    front: usize,
+}
+
+impl<T> DoubleBuffer<T> {
+    pub fn new(front: T, back: T) -> Self {
+        Self {
+            buffers: [front, back],
+            front: 0,
+        }
+    }
+
+    /// The finished frame, to show.
+    pub fn front(&self) -> &T {
+        &self.buffers[self.front]
+    }
+
+    /// The frame being drawn.
+    pub fn back(&self) -> &T {
+        &self.buffers[1 - self.front]
+    }
+
+    pub fn back_mut(&mut self) -> &mut T {
+        &mut self.buffers[1 - self.front]
+    }
+
+    /// The front and back together, to keep showing the one while drawing
+    /// the other.
+    pub fn split(&mut self) -> (&T, &mut T) {
+        let [first, second] = &mut self.buffers;
+        if self.front == 0 {
+            (first, second)
+        } else {
+            (second, first)
+        }
+    }
+
+    /// Puts the back buffer on show, and the front one at the back.
+    pub fn swap(&mut self) {
+        self.front = 1 - self.front;
+    }
+}
+
+impl<P: Clone> DoubleBuffer<Canvas<P>> {
+    /// Two `width` × `height` canvases with every pixel `value`.
+    pub fn new_with(width: usize, height: usize, value: P) -> Self {
+        let canvas = Canvas::new_with(width, height, value);
+        Self::new(canvas.clone(), canvas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn swapping_shows_the_back_buffer() {
+        let mut buffers = DoubleBuffer::new_with(2, 2, Color::BLACK);
+        buffers.back_mut().fill(Color::WHITE);
+        assert_eq!(buffers.front(), &Canvas::new(2, 2));
+        buffers.swap();
+        assert_eq!(buffers.front(), &Canvas::new_with(2, 2, Color::WHITE));
+        assert_eq!(buffers.back(), &Canvas::new(2, 2));
+        buffers.swap();
+        assert_eq!(buffers.front(), &Canvas::new(2, 2));
+    }
+
+    #[test]
+    fn drawing_the_back_while_reading_the_front() {
+        let mut buffers = DoubleBuffer::new(1, 2);
+        buffers.swap();
+        let (front, back) = buffers.split();
+        *back += *front;
+        assert_eq!((*buffers.front(), *buffers.back()), (2, 3));
+    }
+}
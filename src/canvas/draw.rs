@@ -0,0 +1,154 @@
+//! Simple shapes drawn straight onto a canvas, for overlays and markers.
+//!
+//! Positions are signed so shapes can hang off the edges; whatever falls
+//! outside the canvas is clipped.
+
+use super::Canvas;
+use crate::color::Color;
+
+impl Canvas {
+    /// Sets pixel (`x`, `y`) if it's on the canvas.
+    pub(super) fn plot(&mut self, x: isize, y: isize, color: Color) {
+        if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
+            let _ = self.try_write_pixel(x, y, color);
+        }
+    }
+
+    /// Colors the pixels from `x0` to `x1` inclusive along row `y`.
+    fn span(&mut self, x0: isize, x1: isize, y: isize, color: Color) {
+        let x0 = x0.max(0);
+        let x1 = x1.min(self.width as isize - 1);
+        for x in x0..=x1 {
+            self.plot(x, y, color);
+        }
+    }
+
+    /// The one-pixel outline of a circle of `radius` around `center`.
+    pub fn draw_circle(&mut self, center: (isize, isize), radius: usize, color: Color) {
+        let (cx, cy) = center;
+        // The midpoint circle algorithm, walking one octant and mirroring it.
+        let (mut x, mut y) = (radius as isize, 0);
+        let mut error = 1 - x;
+        while x >= y {
+            for (dx, dy) in [
+                (x, y),
+                (y, x),
+                (-y, x),
+                (-x, y),
+                (-x, -y),
+                (-y, -x),
+                (y, -x),
+                (x, -y),
+            ] {
+                self.plot(cx + dx, cy + dy, color);
+            }
+            y += 1;
+            if error < 0 {
+                error += 2 * y + 1;
+            } else {
+                x -= 1;
+                error += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    /// A solid disc of `radius` around `center`, covering the pixels
+    /// [`draw_circle`](Self::draw_circle) outlines.
+    pub fn fill_circle(&mut self, center: (isize, isize), radius: usize, color: Color) {
+        let (cx, cy) = center;
+        let r = radius as isize;
+        for dy in -r..=r {
+            let half = ((r * r + r - dy * dy) as f32).sqrt() as isize;
+            self.span(cx - half, cx + half, cy + dy, color);
+        }
+    }
+
+    /// The one-pixel outline of the `width` × `height` rectangle with its top
+    /// left at `top_left`.
+    pub fn draw_rect(&mut self, top_left: (isize, isize), size: (usize, usize), color: Color) {
+        let (x0, y0) = top_left;
+        let (width, height) = (size.0 as isize, size.1 as isize);
+        if width == 0 || height == 0 {
+            return;
+        }
+        let (x1, y1) = (x0 + width - 1, y0 + height - 1);
+        self.span(x0, x1, y0, color);
+        self.span(x0, x1, y1, color);
+        for y in y0..=y1 {
+            self.plot(x0, y, color);
+            self.plot(x1, y, color);
+        }
+    }
+
+    /// The solid `width` × `height` rectangle with its top left at
+    /// `top_left`.
+    pub fn fill_rect(&mut self, top_left: (isize, isize), size: (usize, usize), color: Color) {
+        let (x0, y0) = top_left;
+        let (width, height) = (size.0 as isize, size.1 as isize);
+        for y in y0.max(0)..(y0 + height).min(self.height as isize) {
+            self.span(x0, x0 + width - 1, y, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit(c: &Canvas) -> Vec<(usize, usize)> {
+        c.enumerate_pixels()
+            .filter(|(_, _, color)| *color == Color::WHITE)
+            .map(|(x, y, _)| (x, y))
+            .collect()
+    }
+
+    #[test]
+    fn rectangles() {
+        let mut c = Canvas::new(5, 5);
+        c.draw_rect((1, 1), (3, 3), Color::WHITE);
+        assert_eq!(
+            lit(&c),
+            [
+                (1, 1),
+                (2, 1),
+                (3, 1),
+                (1, 2),
+                (3, 2),
+                (1, 3),
+                (2, 3),
+                (3, 3)
+            ]
+        );
+        c.fill_rect((1, 1), (3, 3), Color::WHITE);
+        assert_eq!(lit(&c).len(), 9);
+    }
+
+    #[test]
+    fn shapes_off_the_edge_are_clipped() {
+        let mut c = Canvas::new(4, 4);
+        c.fill_rect((-2, -2), (4, 4), Color::WHITE);
+        assert_eq!(lit(&c), [(0, 0), (1, 0), (0, 1), (1, 1)]);
+        c.clear();
+        c.fill_circle((10, 10), 3, Color::WHITE);
+        c.draw_circle((-10, 2), 3, Color::WHITE);
+        assert!(lit(&c).is_empty());
+    }
+
+    #[test]
+    fn circles_are_symmetric_and_filled_inside_their_outline() {
+        let mut outline = Canvas::new(11, 11);
+        outline.draw_circle((5, 5), 4, Color::WHITE);
+        let points = lit(&outline);
+        assert!(points.contains(&(9, 5)) && points.contains(&(5, 1)));
+        assert!(!points.contains(&(5, 5)));
+        for &(x, y) in &points {
+            assert!(points.contains(&(10 - x, y)) && points.contains(&(y, x)));
+        }
+
+        let mut disc = Canvas::new(11, 11);
+        disc.fill_circle((5, 5), 4, Color::WHITE);
+        let filled = lit(&disc);
+        assert!(filled.contains(&(5, 5)));
+        assert!(points.iter().all(|p| filled.contains(p)));
+    }
+}
@@ -0,0 +1,91 @@
+//! Accumulating samples in full precision before they're shown.
+
+use super::{Canvas, ToneMap};
+use crate::color::Color;
+
+/// Running totals of the samples taken for each pixel, kept apart from any
+/// [`Canvas`] until [`resolve`](Self::resolve) averages and tone maps them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HdrBuffer {
+    sums: Canvas,
+    counts: Canvas<u32>,
+}
+
+impl HdrBuffer {
+    /// A `width` × `height` buffer with no samples in it.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            sums: Canvas::new(width, height),
+            counts: Canvas::new_with(width, height, 0),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.sums.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.sums.height()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if (`x`, `y`) is off the buffer.
+    pub fn add_sample(&mut self, x: usize, y: usize, color: Color) {
+        self.sums
+            .write_pixel(x, y, self.sums.pixel_at(x, y) + color);
+        self.counts
+            .write_pixel(x, y, self.counts.pixel_at(x, y) + 1);
+    }
+
+    pub fn sample_count(&self, x: usize, y: usize) -> u32 {
+        self.counts.pixel_at(x, y)
+    }
+
+    /// The average of the samples at a pixel; black if it has none.
+    pub fn mean_at(&self, x: usize, y: usize) -> Color {
+        match self.counts.pixel_at(x, y) {
+            0 => Color::BLACK,
+            n => self.sums.pixel_at(x, y) / n as f32,
+        }
+    }
+
+    /// The averaged image, exposed and tone mapped as by
+    /// [`Canvas::tone_map`].
+    pub fn resolve(&self, exposure: f32, tone_map: ToneMap) -> Canvas {
+        let mut image = Canvas::from_fn(self.width(), self.height(), |x, y| self.mean_at(x, y));
+        image.tone_map(exposure, tone_map);
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_are_averaged_per_pixel() {
+        let mut buffer = HdrBuffer::new(2, 1);
+        buffer.add_sample(0, 0, Color::new(4.0, 0.0, 0.0));
+        buffer.add_sample(0, 0, Color::new(2.0, 1.0, 0.0));
+        assert_eq!(buffer.sample_count(0, 0), 2);
+        assert_eq!(buffer.sample_count(1, 0), 0);
+        assert_approx_eq!(buffer.mean_at(0, 0), Color::new(3.0, 0.5, 0.0));
+        assert_eq!(buffer.mean_at(1, 0), Color::BLACK);
+    }
+
+    #[test]
+    fn resolving_exposes_and_tone_maps() {
+        let mut buffer = HdrBuffer::new(1, 1);
+        buffer.add_sample(0, 0, Color::WHITE * 3.0);
+        buffer.add_sample(0, 0, Color::WHITE);
+        assert_approx_eq!(
+            buffer.resolve(0.0, ToneMap::Clamp).pixel_at(0, 0),
+            Color::WHITE * 2.0
+        );
+        assert_approx_eq!(
+            buffer.resolve(-1.0, ToneMap::Reinhard).pixel_at(0, 0),
+            Color::WHITE * 0.5
+        );
+    }
+}
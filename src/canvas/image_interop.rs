@@ -0,0 +1,120 @@
+//! Converting canvases to and from the `image` crate's buffers, to use its
+//! formats and filters.
+
+use image::{Rgb, Rgb32FImage, RgbImage};
+
+use super::ppm::quantize;
+use super::{Canvas, Encoding};
+use crate::color::Color;
+
+impl Canvas {
+    /// The canvas as an 8-bit sRGB image, clamping channels, as
+    /// [`to_png`](Self::to_png) writes it.
+    pub fn to_rgb_image(&self) -> RgbImage {
+        self.to_rgb_image_with(Encoding::Srgb)
+    }
+
+    /// [`to_rgb_image`](Self::to_rgb_image), choosing how channels are
+    /// encoded.
+    pub fn to_rgb_image_with(&self, encoding: Encoding) -> RgbImage {
+        let max = u16::from(u8::MAX);
+        RgbImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let color = self.pixel_at(x as usize, y as usize);
+            Rgb([color.r, color.g, color.b]
+                .map(|channel| quantize(encoding.encode(channel), max, 0.0) as u8))
+        })
+    }
+
+    /// A canvas from an 8-bit image in sRGB.
+    pub fn from_rgb_image(image: &RgbImage) -> Canvas {
+        Self::from_rgb_image_with(image, Encoding::Srgb)
+    }
+
+    /// [`from_rgb_image`](Self::from_rgb_image), choosing how channels were
+    /// encoded.
+    pub fn from_rgb_image_with(image: &RgbImage, encoding: Encoding) -> Canvas {
+        let max = f32::from(u8::MAX);
+        Canvas::from_fn(image.width() as usize, image.height() as usize, |x, y| {
+            let Rgb([r, g, b]) = *image.get_pixel(x as u32, y as u32);
+            let decode = |channel: u8| encoding.decode(f32::from(channel) / max);
+            Color::new(decode(r), decode(g), decode(b))
+        })
+    }
+
+    /// The canvas as a floating-point image, in linear light and without
+    /// clamping.
+    pub fn to_rgb32f_image(&self) -> Rgb32FImage {
+        Rgb32FImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let color = self.pixel_at(x as usize, y as usize);
+            Rgb([color.r, color.g, color.b])
+        })
+    }
+
+    /// A canvas from a floating-point image in linear light.
+    pub fn from_rgb32f_image(image: &Rgb32FImage) -> Canvas {
+        Canvas::from_fn(image.width() as usize, image.height() as usize, |x, y| {
+            let Rgb([r, g, b]) = *image.get_pixel(x as u32, y as u32);
+            Color::new(r, g, b)
+        })
+    }
+}
+
+impl From<&Canvas> for RgbImage {
+    fn from(canvas: &Canvas) -> Self {
+        canvas.to_rgb_image()
+    }
+}
+
+impl From<&RgbImage> for Canvas {
+    fn from(image: &RgbImage) -> Self {
+        Canvas::from_rgb_image(image)
+    }
+}
+
+impl From<&Canvas> for Rgb32FImage {
+    fn from(canvas: &Canvas) -> Self {
+        canvas.to_rgb32f_image()
+    }
+}
+
+impl From<&Rgb32FImage> for Canvas {
+    fn from(image: &Rgb32FImage) -> Self {
+        Canvas::from_rgb32f_image(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canvas() -> Canvas {
+        Canvas::from_fn(3, 2, |x, y| {
+            Color::new(x as f32 / 2.0, y as f32, if x == 2 { 1.5 } else { 0.25 })
+        })
+    }
+
+    #[test]
+    fn float_images_keep_every_value() {
+        let c = canvas();
+        let image = Rgb32FImage::from(&c);
+        assert_eq!((image.width(), image.height()), (3, 2));
+        assert_eq!(*image.get_pixel(2, 1), Rgb([1.0, 1.0, 1.5]));
+        assert_eq!(Canvas::from(&image), c);
+    }
+
+    #[test]
+    fn byte_images_are_encoded_like_pngs() {
+        let c = canvas();
+        let image = RgbImage::from(&c);
+        assert_eq!(*image.get_pixel(2, 1), Rgb([255, 255, 255]));
+        assert_eq!(image.get_pixel(1, 0)[0], 188);
+        let linear = c.to_rgb_image_with(Encoding::Linear);
+        assert_eq!(linear.get_pixel(1, 0)[0], 128);
+
+        let back = Canvas::from(&image);
+        let decoded = back.pixel_at(0, 1);
+        assert_eq!((decoded.r, decoded.g), (0.0, 1.0));
+        assert!((decoded.b - 0.25).abs() < 1e-2);
+        assert_eq!(back.pixel_at(2, 0).b, 1.0);
+    }
+}
@@ -0,0 +1,399 @@
+//! A 2D grid of colors that renders are written into.
+
+mod blend;
+mod convolve;
+mod denoise;
+mod diff;
+mod double_buffer;
+mod draw;
+mod hdr;
+#[cfg(feature = "image-interop")]
+mod image_interop;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod png;
+mod ppm;
+mod resize;
+mod rgba;
+mod stats;
+mod text;
+mod tiles;
+mod tonemap;
+mod view;
+
+pub use blend::BlendMode;
+pub use convolve::Kernel;
+pub use denoise::{Denoiser, Guides};
+pub use diff::DiffReport;
+pub use double_buffer::DoubleBuffer;
+pub use hdr::HdrBuffer;
+pub(crate) use ppm::PpmLayout;
+pub use ppm::{Dither, Encoding, PPMHeader, PpmError};
+pub use resize::FilterMode;
+pub use stats::{CanvasStats, HISTOGRAM_BINS};
+pub use tonemap::ToneMap;
+pub use view::{CanvasView, CanvasViewMut};
+
+use std::error::Error;
+use std::fmt;
+
+use crate::color::Color;
+
+/// Why a canvas operation failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanvasError {
+    /// Pixel (`x`, `y`) isn't on the `width` × `height` canvas.
+    OutOfBounds {
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+}
+
+impl fmt::Display for CanvasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CanvasError::OutOfBounds {
+                x,
+                y,
+                width,
+                height,
+            } => write!(
+                f,
+                "pixel ({x}, {y}) out of bounds for {width}x{height} canvas"
+            ),
+        }
+    }
+}
+
+impl Error for CanvasError {}
+
+/// A `width` × `height` grid of pixels, stored row by row from the top.
+///
+/// Pixels are [`Color`]s unless told otherwise, but any type will do:
+/// `Canvas<f32>` for a depth buffer, say, or `Canvas<Option<usize>>` for
+/// object IDs, sharing the storage, indexing, and iteration.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "SerializedCanvas<P>")
+)]
+pub struct Canvas<P = Color> {
+    width: usize,
+    height: usize,
+    pixels: Vec<P>,
+}
+
+/// A canvas as read back, before checking its pixels fill it.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "Canvas")]
+struct SerializedCanvas<P> {
+    width: usize,
+    height: usize,
+    pixels: Vec<P>,
+}
+
+#[cfg(feature = "serde")]
+impl<P> TryFrom<SerializedCanvas<P>> for Canvas<P> {
+    type Error = String;
+
+    fn try_from(canvas: SerializedCanvas<P>) -> Result<Self, String> {
+        let SerializedCanvas {
+            width,
+            height,
+            pixels,
+        } = canvas;
+        if pixels.len() == width * height {
+            Ok(Self {
+                width,
+                height,
+                pixels,
+            })
+        } else {
+            Err(format!(
+                "{} pixels don't fill a {width}x{height} canvas",
+                pixels.len()
+            ))
+        }
+    }
+}
+
+impl Canvas {
+    /// A `width` × `height` canvas with every pixel black. Canvases of other
+    /// pixels start from [`new_with`](Self::new_with) or
+    /// [`from_fn`](Self::from_fn), so this one needn't be annotated.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self::new_with(width, height, Color::BLACK)
+    }
+}
+
+impl<P: Clone + Default> Canvas<P> {
+    /// Sets every pixel back to the default: black, for colors.
+    pub fn clear(&mut self) {
+        self.fill(P::default());
+    }
+}
+
+impl<P: Clone> Canvas<P> {
+    /// A `width` × `height` canvas with every pixel `value`.
+    pub fn new_with(width: usize, height: usize, value: P) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![value; width * height],
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if (`x`, `y`) is off the canvas; see
+    /// [`try_pixel_at`](Self::try_pixel_at).
+    pub fn pixel_at(&self, x: usize, y: usize) -> P {
+        self.pixels[self.index(x, y)].clone()
+    }
+
+    /// Like [`pixel_at`](Self::pixel_at), but fails instead of panicking if
+    /// (`x`, `y`) is off the canvas.
+    pub fn try_pixel_at(&self, x: usize, y: usize) -> Result<P, CanvasError> {
+        Ok(self.pixels[self.try_index(x, y)?].clone())
+    }
+
+    /// Each pixel with its coordinates, as `(x, y, value)`, in row-major
+    /// order.
+    pub fn enumerate_pixels(&self) -> impl Iterator<Item = (usize, usize, P)> + '_ {
+        let width = self.width.max(1);
+        self.pixels
+            .iter()
+            .enumerate()
+            .map(move |(i, value)| (i % width, i / width, value.clone()))
+    }
+
+    /// Sets every pixel to `value`.
+    pub fn fill(&mut self, value: P) {
+        self.pixels.fill(value);
+    }
+
+    /// A canvas the same size with each pixel replaced by `f(pixel)`.
+    pub fn map<Q>(&self, f: impl FnMut(P) -> Q) -> Canvas<Q> {
+        Canvas {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().cloned().map(f).collect(),
+        }
+    }
+}
+
+impl<P> Canvas<P> {
+    /// A `width` × `height` canvas with each pixel set to `f(x, y)`.
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(usize, usize) -> P) -> Self {
+        Self {
+            width,
+            height,
+            pixels: (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| f(x, y))
+                .collect(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The pixels in row-major order, top row first.
+    pub fn pixels(&self) -> &[P] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [P] {
+        &mut self.pixels
+    }
+
+    /// The rows of pixels, top first.
+    pub fn rows(&self) -> impl Iterator<Item = &[P]> {
+        self.pixels.chunks(self.width.max(1))
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        self.try_index(x, y).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    fn try_index(&self, x: usize, y: usize) -> Result<usize, CanvasError> {
+        if x < self.width && y < self.height {
+            Ok(y * self.width + x)
+        } else {
+            Err(CanvasError::OutOfBounds {
+                x,
+                y,
+                width: self.width,
+                height: self.height,
+            })
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if (`x`, `y`) is off the canvas; see
+    /// [`try_write_pixel`](Self::try_write_pixel).
+    pub fn write_pixel(&mut self, x: usize, y: usize, value: P) {
+        let i = self.index(x, y);
+        self.pixels[i] = value;
+    }
+
+    /// Like [`write_pixel`](Self::write_pixel), but fails instead of
+    /// panicking if (`x`, `y`) is off the canvas, as plotted points often
+    /// are.
+    pub fn try_write_pixel(&mut self, x: usize, y: usize, value: P) -> Result<(), CanvasError> {
+        let i = self.try_index(x, y)?;
+        self.pixels[i] = value;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_canvas_is_black() {
+        let c = Canvas::new(10, 20);
+        assert_eq!(c.width(), 10);
+        assert_eq!(c.height(), 20);
+        assert!(c.pixels().iter().all(|p| *p == Color::BLACK));
+    }
+
+    #[test]
+    fn filling_and_clearing() {
+        let grey = Color::new(0.5, 0.5, 0.5);
+        let mut c = Canvas::new_with(4, 3, grey);
+        assert!(c.pixels().iter().all(|p| *p == grey));
+        c.fill(Color::WHITE);
+        assert!(c.pixels().iter().all(|p| *p == Color::WHITE));
+        c.clear();
+        assert_eq!(c, Canvas::new(4, 3));
+    }
+
+    #[test]
+    fn fallible_access_rejects_pixels_off_the_canvas() {
+        let mut c = Canvas::new(10, 20);
+        let red = Color::new(1.0, 0.0, 0.0);
+        assert_eq!(c.try_write_pixel(9, 19, red), Ok(()));
+        assert_eq!(c.try_pixel_at(9, 19), Ok(red));
+        let err = CanvasError::OutOfBounds {
+            x: 10,
+            y: 0,
+            width: 10,
+            height: 20,
+        };
+        // Past the end of a row, which would otherwise wrap onto the next.
+        assert_eq!(c.try_write_pixel(10, 0, red), Err(err));
+        assert_eq!(c.try_pixel_at(10, 0), Err(err));
+        assert_eq!(c.pixel_at(0, 1), Color::BLACK);
+        assert_eq!(
+            err.to_string(),
+            "pixel (10, 0) out of bounds for 10x20 canvas"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn writing_past_the_end_of_a_row_panics() {
+        Canvas::new(10, 20).write_pixel(10, 0, Color::WHITE);
+    }
+
+    #[test]
+    fn canvas_from_a_function() {
+        let c = Canvas::from_fn(4, 2, |x, y| Color::new(x as f32, y as f32, 0.0));
+        assert_eq!(c.width(), 4);
+        assert_eq!(c.height(), 2);
+        assert_eq!(c.pixel_at(3, 1), Color::new(3.0, 1.0, 0.0));
+        assert!(c
+            .enumerate_pixels()
+            .all(|(x, y, color)| color == Color::new(x as f32, y as f32, 0.0)));
+    }
+
+    #[test]
+    fn iterating_over_pixels() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(2, 1, Color::WHITE);
+        let lit: Vec<_> = c
+            .enumerate_pixels()
+            .filter(|(_, _, color)| *color == Color::WHITE)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        assert_eq!(lit, [(2, 1)]);
+        assert_eq!(c.enumerate_pixels().count(), 6);
+
+        let rows: Vec<_> = c.rows().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1], [Color::BLACK, Color::BLACK, Color::WHITE]);
+
+        for pixel in c.pixels_mut() {
+            *pixel = *pixel * 0.5;
+        }
+        assert_eq!(c.pixel_at(2, 1), Color::WHITE * 0.5);
+    }
+
+    #[test]
+    fn canvases_of_other_pixel_types() {
+        let mut depth = Canvas::new_with(3, 2, f32::INFINITY);
+        depth.write_pixel(1, 1, 2.5);
+        assert_eq!(depth.pixel_at(1, 1), 2.5);
+        assert_eq!(depth.rows().next().unwrap(), [f32::INFINITY; 3]);
+
+        let mut ids = Canvas::new_with(2, 2, None::<u32>);
+        assert_eq!(ids.pixel_at(1, 0), None);
+        ids.fill(Some(7));
+        assert!(ids.enumerate_pixels().all(|(_, _, id)| id == Some(7)));
+        assert_eq!(ids.map(|id| id.is_some()), Canvas::new_with(2, 2, true));
+        ids.clear();
+        assert_eq!(ids, Canvas::from_fn(2, 2, |_, _| None));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn canvases_serialize_with_their_size() {
+        use serde_test::{assert_de_tokens_error, assert_tokens, Token};
+        let canvas_of = |pixels: &[Token]| {
+            let mut tokens = vec![
+                Token::Struct {
+                    name: "Canvas",
+                    len: 3,
+                },
+                Token::Str("width"),
+                Token::U64(2),
+                Token::Str("height"),
+                Token::U64(1),
+                Token::Str("pixels"),
+                Token::Seq {
+                    len: Some(pixels.len()),
+                },
+            ];
+            tokens.extend_from_slice(pixels);
+            tokens.extend([Token::SeqEnd, Token::StructEnd]);
+            tokens
+        };
+        let c = Canvas::from_fn(2, 1, |x, _| x as u8);
+        assert_tokens(&c, &canvas_of(&[Token::U8(0), Token::U8(1)]));
+        assert_de_tokens_error::<Canvas<u8>>(
+            &canvas_of(&[Token::U8(0)]),
+            "1 pixels don't fill a 2x1 canvas",
+        );
+    }
+
+    #[test]
+    fn writing_pixels() {
+        let mut c = Canvas::new(10, 20);
+        let red = Color::new(1.0, 0.0, 0.0);
+        c.write_pixel(2, 3, red);
+        assert_eq!(c.pixel_at(2, 3), red);
+        assert_eq!(c.pixels()[3 * 10 + 2], red);
+    }
+}
@@ -0,0 +1,61 @@
+//! Canvas passes spread over threads with rayon, for images big enough that
+//! running them on one thread shows.
+
+use rayon::prelude::*;
+
+use super::Canvas;
+
+impl<P: Send> Canvas<P> {
+    /// The rows of pixels, top first, to work on in parallel.
+    pub fn par_rows_mut(&mut self) -> impl IndexedParallelIterator<Item = &mut [P]> {
+        self.pixels.par_chunks_mut(self.width.max(1))
+    }
+
+    /// Like [`fill`](Self::fill), but in parallel.
+    pub fn par_fill(&mut self, value: P)
+    where
+        P: Clone + Sync,
+    {
+        self.pixels
+            .par_iter_mut()
+            .for_each(|pixel| *pixel = value.clone());
+    }
+
+    /// Like [`map`](Self::map), but in parallel.
+    pub fn par_map<Q: Send>(&self, f: impl Fn(P) -> Q + Sync) -> Canvas<Q>
+    where
+        P: Clone + Sync,
+    {
+        Canvas {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.par_iter().cloned().map(&f).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn parallel_passes_match_serial_ones() {
+        let c = Canvas::from_fn(7, 5, |x, y| Color::new(x as f32, y as f32, 1.0));
+        let halve = |color: Color| color * 0.5;
+        assert_eq!(c.par_map(halve), c.map(halve));
+
+        let mut rows = c.clone();
+        rows.par_rows_mut().enumerate().for_each(|(y, row)| {
+            assert_eq!(row.len(), 7);
+            row.fill(Color::new(0.0, y as f32, 0.0));
+        });
+        assert_eq!(
+            rows,
+            Canvas::from_fn(7, 5, |_, y| Color::new(0.0, y as f32, 0.0))
+        );
+
+        rows.par_fill(Color::WHITE);
+        assert_eq!(rows, Canvas::new_with(7, 5, Color::WHITE));
+    }
+}
@@ -0,0 +1,139 @@
+//! Writing canvases as PNG images.
+
+use super::ppm::quantize;
+use super::{Canvas, Encoding};
+
+/// The eight bytes every PNG starts with.
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// The most a stored (uncompressed) deflate block can hold.
+const MAX_STORED: usize = 0xffff;
+
+impl Canvas {
+    /// Encodes the canvas as an 8-bit RGB PNG in sRGB, clamping channels.
+    pub fn to_png(&self) -> Vec<u8> {
+        self.to_png_with(Encoding::Srgb)
+    }
+
+    /// [`to_png`](Self::to_png), choosing how channels are encoded.
+    ///
+    /// The image data is stored rather than compressed: PNG is chosen for
+    /// being readable everywhere, not for size.
+    pub fn to_png_with(&self, encoding: Encoding) -> Vec<u8> {
+        let max = u16::from(u8::MAX);
+        // Each row starts with its filter type, 0 for none.
+        let mut raw = Vec::with_capacity(self.height * (1 + self.width * 3));
+        for row in self.rows() {
+            raw.push(0);
+            for color in row {
+                for channel in [color.r, color.g, color.b] {
+                    raw.push(quantize(encoding.encode(channel), max, 0.0) as u8);
+                }
+            }
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // 8 bits per channel, truecolor, deflate, adaptive filtering, no
+        // interlacing.
+        header.extend([8, 2, 0, 0, 0]);
+
+        let mut out = SIGNATURE.to_vec();
+        chunk(&mut out, b"IHDR", &header);
+        chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+        chunk(&mut out, b"IEND", &[]);
+        out
+    }
+}
+
+/// Appends a chunk of `kind` holding `data` to `out`.
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend(kind);
+    out.extend(data);
+    let crc = crc32(&out[start..]);
+    out.extend(crc.to_be_bytes());
+}
+
+/// `data` in a zlib stream of stored deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32K window and no preset dictionary; 0x01 makes the
+    // header a multiple of 31.
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_STORED).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(u8::from(blocks.peek().is_none()));
+        let len = block.len() as u16;
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(block);
+    }
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, byte| {
+        (0..8).fold(crc ^ u32::from(*byte), |crc, _| {
+            if crc & 1 == 1 {
+                crc >> 1 ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + u32::from(*byte)) % MOD;
+        (a, (b + a) % MOD)
+    });
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn checksums_match_known_values() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn png_layout() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(1.0, 0.0, 0.2));
+        let png = c.to_png_with(Encoding::Linear);
+        assert_eq!(png[..8], SIGNATURE);
+        assert_eq!(png[12..16], *b"IHDR");
+        assert_eq!(png[16..24], [0, 0, 0, 2, 0, 0, 0, 1]);
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+        // IDAT: zlib header, one final stored block of the two rows, Adler-32.
+        let idat = &png[33..];
+        assert_eq!(idat[..8], [0, 0, 0, 18, b'I', b'D', b'A', b'T']);
+        assert_eq!(
+            idat[8..26],
+            [0x78, 0x01, 1, 7, 0, 0xf8, 0xff, 0, 255, 0, 51, 0, 0, 0, 0x06, 0xcd, 0x01, 0x33]
+        );
+    }
+
+    #[test]
+    fn large_images_are_split_into_blocks() {
+        let data = vec![7; MAX_STORED + 10];
+        let z = zlib_stored(&data);
+        assert_eq!(z[2..7], [0, 0xff, 0xff, 0, 0]);
+        let second = 7 + MAX_STORED;
+        assert_eq!(z[second..second + 5], [1, 10, 0, 0xf5, 0xff]);
+        assert_eq!(z.len(), 2 + 5 + MAX_STORED + 5 + 10 + 4);
+    }
+}
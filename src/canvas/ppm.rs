@@ -0,0 +1,480 @@
+use std::error::Error;
+use std::fmt::{self, Display, Write};
+
+use super::Canvas;
+use crate::color::Color;
+
+/// The plain-text (`P3`) PPM header for a canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PPMHeader {
+    pub width: usize,
+    pub height: usize,
+    pub max_color: u16,
+}
+
+impl PPMHeader {
+    pub const MAX_COLOR: u16 = 255;
+    /// PPM readers are not required to accept lines longer than this.
+    const MAX_LINE: usize = 70;
+}
+
+impl Display for PPMHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "P3")?;
+        writeln!(f, "{} {}", self.width, self.height)?;
+        writeln!(f, "{}", self.max_color)
+    }
+}
+
+/// How linear channel values are turned into stored ones before they're
+/// quantized. Lighting is worked out in linear light, but image viewers
+/// assume pixels are gamma encoded; stored linearly, mid-tones look too dark.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Encoding {
+    /// The sRGB transfer curve, as nearly every viewer expects.
+    #[default]
+    Srgb,
+    /// Values stored as they are, for data rather than pictures: normals,
+    /// depth, or anything read back as numbers.
+    Linear,
+    /// A plain power curve, `channel^(1/gamma)`.
+    Gamma(f32),
+}
+
+impl Encoding {
+    /// `channel`, clamped to `0.0..=1.0`, as it's stored.
+    pub fn encode(&self, channel: f32) -> f32 {
+        let channel = channel.clamp(0.0, 1.0);
+        match self {
+            Encoding::Srgb => {
+                if channel <= 0.003_130_8 {
+                    12.92 * channel
+                } else {
+                    1.055 * channel.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            Encoding::Linear => channel,
+            Encoding::Gamma(gamma) => channel.powf(1.0 / gamma),
+        }
+    }
+
+    /// The linear value of a `stored` channel, from `0.0..=1.0`; the inverse
+    /// of [`encode`](Self::encode).
+    pub fn decode(&self, stored: f32) -> f32 {
+        let stored = stored.clamp(0.0, 1.0);
+        match self {
+            Encoding::Srgb => {
+                if stored <= 0.040_45 {
+                    stored / 12.92
+                } else {
+                    ((stored + 0.055) / 1.055).powf(2.4)
+                }
+            }
+            Encoding::Linear => stored,
+            Encoding::Gamma(gamma) => stored.powf(*gamma),
+        }
+    }
+}
+
+/// Why a PPM couldn't be read; see [`Canvas::from_ppm`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PpmError {
+    /// The data doesn't start with `P3` (plain) or `P6` (binary).
+    UnknownFormat,
+    /// The width, height or maximum color value is missing or invalid.
+    BadHeader,
+    /// Channel `index`, counting from the first pixel's red, is missing,
+    /// isn't a number, or is above the maximum color value.
+    BadPixel { index: usize },
+}
+
+impl Display for PpmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PpmError::UnknownFormat => write!(f, "not a P3 or P6 PPM"),
+            PpmError::BadHeader => write!(f, "malformed PPM header"),
+            PpmError::BadPixel { index } => write!(f, "bad or missing PPM channel {index}"),
+        }
+    }
+}
+
+impl Error for PpmError {}
+
+/// How the rounding of channels to whole levels is varied from pixel to
+/// pixel. Rounding alone turns a smooth gradient into visible bands a level
+/// wide; nudging the rounding point in a fine pattern trades them for an
+/// even texture the eye averages out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dither {
+    /// Every channel rounded to its nearest level.
+    #[default]
+    None,
+    /// The rounding point moved by an 8×8 Bayer matrix, the classic ordered
+    /// dither.
+    Bayer,
+}
+
+impl Dither {
+    /// How far, in levels, to move the value of pixel (`x`, `y`) before
+    /// rounding it, from -0.5 up to 0.5.
+    pub fn offset(&self, x: usize, y: usize) -> f32 {
+        match self {
+            Dither::None => 0.0,
+            Dither::Bayer => (bayer(x, y) as f32 + 0.5) / 64.0 - 0.5,
+        }
+    }
+}
+
+/// The entry at (`x`, `y`) of the tiled 8×8 Bayer matrix, from 0 to 63:
+/// the bits of `x ^ y` and `y` interleaved in reverse order, so the
+/// lowest bits weigh the most.
+fn bayer(x: usize, y: usize) -> u32 {
+    let (xy, y) = ((x ^ y) as u32, y as u32);
+    (0..3).fold(0, |value, bit| {
+        let pair = ((xy >> bit) & 1) << 1 | ((y >> bit) & 1);
+        value | pair << (2 * (2 - bit))
+    })
+}
+
+/// `channel` as a whole level out of `max_color`, moved `offset` levels
+/// before rounding.
+pub(super) fn quantize(channel: f32, max_color: u16, offset: f32) -> u16 {
+    (channel * f32::from(max_color) + offset)
+        .round()
+        .clamp(0.0, f32::from(max_color)) as u16
+}
+
+impl Canvas {
+    /// Decodes a plain (`P3`) or binary (`P6`) PPM whose channels are sRGB
+    /// encoded, as [`to_ppm`](Self::to_ppm) writes them.
+    pub fn from_ppm(data: &[u8]) -> Result<Canvas, PpmError> {
+        Self::from_ppm_with(data, Encoding::Srgb)
+    }
+
+    /// [`from_ppm`](Self::from_ppm), choosing how the channels were encoded.
+    pub fn from_ppm_with(data: &[u8], encoding: Encoding) -> Result<Canvas, PpmError> {
+        let PpmLayout {
+            binary,
+            width,
+            height,
+            max_color,
+            data_start,
+        } = PpmLayout::read(data)?;
+        let mut reader = PpmReader {
+            data,
+            pos: data_start,
+        };
+        let count = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(3))
+            .ok_or(PpmError::BadHeader)?;
+        let mut channels = Vec::with_capacity(count.min(data.len()));
+        if binary {
+            let size = PpmLayout::channel_size(max_color);
+            for index in 0..count {
+                let bytes = data
+                    .get(reader.pos + index * size..reader.pos + (index + 1) * size)
+                    .ok_or(PpmError::BadPixel { index })?;
+                let value = bytes.iter().fold(0, |v, b| v << 8 | usize::from(*b));
+                channels.push((index, value));
+            }
+        } else {
+            for index in 0..count {
+                channels.push((index, reader.number().ok_or(PpmError::BadPixel { index })?));
+            }
+        }
+        let channels = channels
+            .into_iter()
+            .map(|(index, value)| match value {
+                v if v <= max_color => Ok(encoding.decode(v as f32 / max_color as f32)),
+                _ => Err(PpmError::BadPixel { index }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Canvas::from_fn(width, height, |x, y| {
+            let i = (y * width + x) * 3;
+            Color::new(channels[i], channels[i + 1], channels[i + 2])
+        }))
+    }
+
+    pub fn ppm_header(&self) -> PPMHeader {
+        PPMHeader {
+            width: self.width,
+            height: self.height,
+            max_color: PPMHeader::MAX_COLOR,
+        }
+    }
+
+    /// Encodes the canvas as a plain PPM in sRGB, clamping channels to
+    /// `0..=255`.
+    pub fn to_ppm(&self) -> String {
+        self.to_ppm_with(Encoding::Srgb)
+    }
+
+    /// [`to_ppm`](Self::to_ppm), choosing how channels are encoded.
+    pub fn to_ppm_with(&self, encoding: Encoding) -> String {
+        self.to_ppm_dithered(encoding, Dither::None)
+    }
+
+    /// [`to_ppm_with`](Self::to_ppm_with), dithering as the channels are
+    /// quantized.
+    pub fn to_ppm_dithered(&self, encoding: Encoding, dither: Dither) -> String {
+        let header = self.ppm_header();
+        let mut out = header.to_string();
+        for (y, row) in self.rows().enumerate() {
+            let mut line = String::new();
+            for (x, color) in row.iter().enumerate() {
+                let offset = dither.offset(x, y);
+                for channel in [color.r, color.g, color.b] {
+                    let encoded = encoding.encode(channel);
+                    let value = quantize(encoded, header.max_color, offset).to_string();
+                    if !line.is_empty() && line.len() + 1 + value.len() > PPMHeader::MAX_LINE {
+                        out.push_str(&line);
+                        out.push('\n');
+                        line.clear();
+                    }
+                    if !line.is_empty() {
+                        line.push(' ');
+                    }
+                    let _ = write!(line, "{value}");
+                }
+            }
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// What a PPM's header says about the pixels after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PpmLayout {
+    /// `P6`, whose channels are bytes, rather than `P3`'s numbers in text.
+    pub binary: bool,
+    pub width: usize,
+    pub height: usize,
+    pub max_color: usize,
+    /// Where the first channel starts.
+    pub data_start: usize,
+}
+
+impl PpmLayout {
+    /// Reads the header at the start of `data`, which needn't go on past it.
+    pub fn read(data: &[u8]) -> Result<Self, PpmError> {
+        let mut reader = PpmReader { data, pos: 0 };
+        let binary = match reader.token() {
+            Some(b"P3") => false,
+            Some(b"P6") => true,
+            _ => return Err(PpmError::UnknownFormat),
+        };
+        let mut number = || {
+            reader
+                .number()
+                .filter(|n| *n > 0)
+                .ok_or(PpmError::BadHeader)
+        };
+        let (width, height, max_color) = (number()?, number()?, number()?);
+        if max_color > usize::from(u16::MAX) {
+            return Err(PpmError::BadHeader);
+        }
+        Ok(Self {
+            binary,
+            width,
+            height,
+            max_color,
+            // A single whitespace byte separates a P6 header from the data.
+            data_start: reader.pos + usize::from(binary),
+        })
+    }
+
+    /// Bytes per channel in a `P6` whose channels go up to `max_color`.
+    pub fn channel_size(max_color: usize) -> usize {
+        if max_color < 256 {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+/// Splits the whitespace-separated tokens of a PPM, skipping comments.
+struct PpmReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PpmReader<'a> {
+    fn token(&mut self) -> Option<&'a [u8]> {
+        loop {
+            match self.data.get(self.pos)? {
+                b'#' => {
+                    while self.data.get(self.pos).is_some_and(|b| *b != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                b if b.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+        let start = self.pos;
+        while self
+            .data
+            .get(self.pos)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+        Some(&self.data[start..self.pos])
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        std::str::from_utf8(self.token()?).ok()?.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bayer, Dither, Encoding, PpmError};
+    use crate::canvas::Canvas;
+    use crate::color::Color;
+
+    #[test]
+    fn header() {
+        let ppm = Canvas::new(5, 3).to_ppm();
+        let lines: Vec<&str> = ppm.lines().take(3).collect();
+        assert_eq!(lines, ["P3", "5 3", "255"]);
+    }
+
+    #[test]
+    fn pixel_data_is_clamped() {
+        let mut c = Canvas::new(5, 3);
+        c.write_pixel(0, 0, Color::new(1.5, 0.0, 0.0));
+        c.write_pixel(2, 1, Color::new(0.0, 0.5, 0.0));
+        c.write_pixel(4, 2, Color::new(-0.5, 0.0, 1.0));
+        let ppm = c.to_ppm_with(Encoding::Linear);
+        let lines: Vec<&str> = ppm.lines().skip(3).collect();
+        assert_eq!(
+            lines,
+            [
+                "255 0 0 0 0 0 0 0 0 0 0 0 0 0 0",
+                "0 0 0 0 0 0 0 128 0 0 0 0 0 0 0",
+                "0 0 0 0 0 0 0 0 0 0 0 0 0 0 255",
+            ]
+        );
+    }
+
+    #[test]
+    fn long_lines_are_split() {
+        let mut c = Canvas::new(10, 2);
+        for y in 0..2 {
+            for x in 0..10 {
+                c.write_pixel(x, y, Color::new(1.0, 0.8, 0.6));
+            }
+        }
+        let ppm = c.to_ppm_with(Encoding::Linear);
+        let lines: Vec<&str> = ppm.lines().skip(3).collect();
+        assert_eq!(
+            lines,
+            [
+                "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
+                "153 255 204 153 255 204 153 255 204 153 255 204 153",
+                "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204",
+                "153 255 204 153 255 204 153 255 204 153 255 204 153",
+            ]
+        );
+    }
+
+    #[test]
+    fn pixel_data_is_srgb_encoded_by_default() {
+        let mut c = Canvas::new(3, 1);
+        c.write_pixel(0, 0, Color::new(0.5, 0.22, 1.0));
+        c.write_pixel(1, 0, Color::new(0.001, 0.0, 2.0));
+        c.write_pixel(2, 0, Color::new(0.25, 0.25, 0.25));
+        let ppm = c.to_ppm();
+        let lines: Vec<&str> = ppm.lines().skip(3).collect();
+        assert_eq!(lines, ["188 129 255 3 0 255 137 137 137"]);
+        let gamma = c.to_ppm_with(Encoding::Gamma(2.0));
+        assert!(gamma.lines().nth(3).unwrap().ends_with("128 128 128"));
+    }
+
+    #[test]
+    fn bayer_matrix_spreads_thresholds_evenly() {
+        let row: Vec<_> = (0..8).map(|x| bayer(x, 0)).collect();
+        assert_eq!(row, [0, 32, 8, 40, 2, 34, 10, 42]);
+        let mut all: Vec<_> = (0..64).map(|i| bayer(i % 8, i / 8)).collect();
+        all.sort_unstable();
+        assert!(all.into_iter().eq(0..64));
+        assert_eq!(bayer(9, 10), bayer(1, 2));
+    }
+
+    #[test]
+    fn dithering_mixes_neighbouring_levels() {
+        // A quarter of the way from level 100 to 101.
+        let c = Canvas::new_with(8, 8, Color::WHITE * (100.25 / 255.0));
+        let plain = c.to_ppm_with(Encoding::Linear);
+        assert!(plain
+            .lines()
+            .skip(3)
+            .flat_map(str::split_whitespace)
+            .all(|v| v == "100"));
+        let dithered = c.to_ppm_dithered(Encoding::Linear, Dither::Bayer);
+        let values: Vec<u32> = dithered
+            .lines()
+            .skip(3)
+            .flat_map(str::split_whitespace)
+            .map(|v| v.parse().unwrap())
+            .collect();
+        assert!(values.iter().all(|v| (100..=101).contains(v)));
+        let high = values.iter().filter(|v| **v == 101).count();
+        assert_eq!(high * 4, values.len());
+    }
+
+    #[test]
+    fn ends_with_newline() {
+        assert!(Canvas::new(5, 3).to_ppm().ends_with('\n'));
+    }
+
+    #[test]
+    fn decoding_undoes_encoding() {
+        for encoding in [Encoding::Srgb, Encoding::Linear, Encoding::Gamma(2.2)] {
+            for channel in [0.0, 0.002, 0.2, 0.5, 1.0] {
+                assert_approx_eq!(encoding.decode(encoding.encode(channel)), channel);
+            }
+        }
+    }
+
+    #[test]
+    fn reading_back_a_plain_ppm() {
+        let c = Canvas::from_fn(12, 3, |x, y| {
+            Color::new(x as f32 / 11.0, y as f32 / 2.0, 0.25)
+        });
+        let read = Canvas::from_ppm(c.to_ppm().as_bytes()).unwrap();
+        crate::assert_canvas_eq!(read, c, 0.01);
+    }
+
+    #[test]
+    fn reading_a_binary_ppm_with_comments() {
+        let mut data = b"P6 # binary\n2 1\n# levels\n255\n".to_vec();
+        data.extend([255, 0, 51, 0, 255, 0]);
+        let c = Canvas::from_ppm_with(&data, Encoding::Linear).unwrap();
+        assert_eq!(c.pixel_at(0, 0), Color::new(1.0, 0.0, 0.2));
+        assert_eq!(c.pixel_at(1, 0), Color::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn malformed_ppms_are_rejected() {
+        assert_eq!(
+            Canvas::from_ppm(b"P5 1 1 255 0"),
+            Err(PpmError::UnknownFormat)
+        );
+        assert_eq!(Canvas::from_ppm(b"P3 1 x 255"), Err(PpmError::BadHeader));
+        assert_eq!(
+            Canvas::from_ppm(b"P3 1 1 255 0 300 0"),
+            Err(PpmError::BadPixel { index: 1 })
+        );
+        assert_eq!(
+            Canvas::from_ppm(b"P6 1 1 255 \x00\x00"),
+            Err(PpmError::BadPixel { index: 2 })
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Reading a canvas between its pixels, and scaling it.
+
+use super::Canvas;
+use crate::color::Color;
+
+/// How an image is sampled between pixel centres.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FilterMode {
+    /// The closest pixel: crisp, but blocky when magnified.
+    #[default]
+    Nearest,
+    /// A blend of the four surrounding pixels, weighted by distance.
+    Bilinear,
+}
+
+impl FilterMode {
+    /// [`Canvas::sample`] for a `width` × `height` image read through
+    /// `pixel`, which is only called for pixels inside it.
+    pub(crate) fn sample(
+        self,
+        width: usize,
+        height: usize,
+        x: f32,
+        y: f32,
+        pixel: impl Fn(usize, usize) -> Color,
+    ) -> Color {
+        let max_x = (width - 1) as f32;
+        let max_y = (height - 1) as f32;
+        let x = x.clamp(0.0, max_x);
+        let y = y.clamp(0.0, max_y);
+        match self {
+            FilterMode::Nearest => pixel(x.round() as usize, y.round() as usize),
+            FilterMode::Bilinear => {
+                let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+                let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+                let (fx, fy) = (x.fract(), y.fract());
+                let lerp = |a: Color, b: Color, t: f32| a + (b - a) * t;
+                let top = lerp(pixel(x0, y0), pixel(x1, y0), fx);
+                let bottom = lerp(pixel(x0, y1), pixel(x1, y1), fx);
+                lerp(top, bottom, fy)
+            }
+        }
+    }
+}
+
+impl Canvas {
+    /// The color at (`x`, `y`), where pixel centres sit on whole numbers,
+    /// read with `filter`. Points past the edges take the edge's color.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the canvas is empty.
+    pub fn sample(&self, x: f32, y: f32, filter: FilterMode) -> Color {
+        filter.sample(self.width, self.height, x, y, |x, y| self.pixel_at(x, y))
+    }
+
+    /// The canvas scaled to `width` × `height`, each new pixel read at the
+    /// matching point of this one with `filter`. Shrinking by more than half
+    /// skips pixels, so small thumbnails can alias; for whole-number ratios
+    /// [`downsample`](Self::downsample) averages them all instead.
+    pub fn resized(&self, width: usize, height: usize, filter: FilterMode) -> Canvas {
+        if self.pixels.is_empty() {
+            return Canvas::new(width, height);
+        }
+        let scale_x = self.width as f32 / width as f32;
+        let scale_y = self.height as f32 / height as f32;
+        Canvas::from_fn(width, height, |x, y| {
+            self.sample(
+                (x as f32 + 0.5) * scale_x - 0.5,
+                (y as f32 + 0.5) * scale_y - 0.5,
+                filter,
+            )
+        })
+    }
+
+    /// The canvas shrunk by `factor` each way, each new pixel the average of
+    /// the `factor` × `factor` block it covers, as for resolving a render
+    /// made at `factor` times the size. Blocks cut short by the right and
+    /// bottom edges average the pixels they have.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is zero.
+    pub fn downsample(&self, factor: usize) -> Canvas {
+        assert!(factor > 0, "can't downsample by a factor of 0");
+        Canvas::from_fn(
+            self.width.div_ceil(factor),
+            self.height.div_ceil(factor),
+            |x, y| {
+                let xs = x * factor..((x + 1) * factor).min(self.width);
+                let ys = y * factor..((y + 1) * factor).min(self.height);
+                let count = xs.len() * ys.len();
+                let total: Color = ys
+                    .flat_map(|y| xs.clone().map(move |x| (x, y)))
+                    .map(|(x, y)| self.pixel_at(x, y))
+                    .sum();
+                total / count as f32
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_upscaling_repeats_pixels() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(1, 0, Color::WHITE);
+        let big = c.resized(4, 2, FilterMode::Nearest);
+        for y in 0..2 {
+            assert_eq!(
+                big.rows().nth(y).unwrap(),
+                [Color::BLACK, Color::BLACK, Color::WHITE, Color::WHITE]
+            );
+        }
+    }
+
+    #[test]
+    fn bilinear_upscaling_blends_between_pixels() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(1, 0, Color::WHITE);
+        let big = c.resized(4, 1, FilterMode::Bilinear);
+        let reds: Vec<f32> = big.pixels().iter().map(|p| p.r).collect();
+        assert_eq!(reds, [0.0, 0.25, 0.75, 1.0]);
+    }
+
+    #[test]
+    fn downsampling_averages_blocks() {
+        let c = Canvas::from_fn(4, 2, |x, y| Color::WHITE * (x + 4 * y) as f32);
+        let small = c.downsample(2);
+        assert_eq!((small.width(), small.height()), (2, 1));
+        assert_eq!(small.pixel_at(0, 0), Color::WHITE * 2.5);
+        assert_eq!(small.pixel_at(1, 0), Color::WHITE * 4.5);
+        assert_eq!(c.downsample(1), c);
+    }
+
+    #[test]
+    fn downsampling_keeps_partial_edge_blocks() {
+        let c = Canvas::from_fn(3, 1, |x, _| Color::WHITE * x as f32);
+        let small = c.downsample(2);
+        assert_eq!((small.width(), small.height()), (2, 1));
+        assert_eq!(small.pixel_at(0, 0), Color::WHITE * 0.5);
+        assert_eq!(small.pixel_at(1, 0), Color::WHITE * 2.0);
+    }
+
+    #[test]
+    fn same_size_is_unchanged() {
+        let c = Canvas::from_fn(3, 2, |x, y| Color::new(x as f32, y as f32, 0.0));
+        for filter in [FilterMode::Nearest, FilterMode::Bilinear] {
+            assert_eq!(c.resized(3, 2, filter), c);
+        }
+    }
+}
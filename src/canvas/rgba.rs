@@ -0,0 +1,123 @@
+//! Canvases with an alpha channel, for compositing.
+
+use super::ppm::quantize;
+use super::{Canvas, Dither, Encoding};
+use crate::color::{Color, Rgba};
+
+impl Canvas {
+    /// The canvas with each pixel given the matching alpha from `alpha`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` isn't the same size as the canvas.
+    pub fn with_alpha(&self, alpha: &Canvas<f32>) -> Canvas<Rgba> {
+        assert!(
+            (alpha.width, alpha.height) == (self.width, self.height),
+            "a {}x{} alpha doesn't fit a {}x{} canvas",
+            alpha.width,
+            alpha.height,
+            self.width,
+            self.height
+        );
+        Canvas::from_fn(self.width, self.height, |x, y| {
+            Rgba::new(self.pixel_at(x, y), alpha.pixel_at(x, y))
+        })
+    }
+}
+
+impl Canvas<Rgba> {
+    /// The canvas laid over an opaque `background` of the same size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `background` isn't the same size as the canvas.
+    pub fn over(&self, background: &Canvas) -> Canvas {
+        assert!(
+            (background.width, background.height) == (self.width, self.height),
+            "can't lay a {}x{} canvas over a {}x{} one",
+            self.width,
+            self.height,
+            background.width,
+            background.height
+        );
+        Canvas::from_fn(self.width, self.height, |x, y| {
+            self.pixel_at(x, y)
+                .over(background.pixel_at(x, y).into())
+                .color
+        })
+    }
+
+    /// The color channels alone, as if every pixel were opaque.
+    pub fn colors(&self) -> Canvas {
+        self.map(|pixel| pixel.color)
+    }
+
+    /// Encodes the canvas as a binary PAM (`P7`) image with an `RGB_ALPHA`
+    /// tuple type, the Netpbm format that carries alpha. The color channels
+    /// are encoded with `encoding`; alpha is always stored linearly.
+    pub fn to_pam_with(&self, encoding: Encoding) -> Vec<u8> {
+        self.to_pam_dithered(encoding, Dither::None)
+    }
+
+    /// [`to_pam_with`](Self::to_pam_with), dithering as the channels are
+    /// quantized.
+    pub fn to_pam_dithered(&self, encoding: Encoding, dither: Dither) -> Vec<u8> {
+        let max = u16::from(u8::MAX);
+        let mut out = format!(
+            "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL {max}\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+            self.width, self.height
+        )
+        .into_bytes();
+        for (x, y, Rgba { color, alpha }) in self.enumerate_pixels() {
+            let offset = dither.offset(x, y);
+            let Color { r, g, b } = color;
+            for channel in [r, g, b] {
+                out.push(quantize(encoding.encode(channel), max, offset) as u8);
+            }
+            out.push(quantize(Encoding::Linear.encode(alpha), max, offset) as u8);
+        }
+        out
+    }
+
+    /// [`to_pam_with`](Self::to_pam_with) in sRGB.
+    pub fn to_pam(&self) -> Vec<u8> {
+        self.to_pam_with(Encoding::Srgb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alpha_is_attached_per_pixel() {
+        let c = Canvas::new_with(2, 1, Color::WHITE);
+        let alpha = Canvas::from_fn(2, 1, |x, _| x as f32);
+        let rgba = c.with_alpha(&alpha);
+        assert_eq!(rgba.pixel_at(0, 0), Rgba::new(Color::WHITE, 0.0));
+        assert_eq!(rgba.pixel_at(1, 0), Rgba::opaque(Color::WHITE));
+        assert_eq!(rgba.colors(), c);
+    }
+
+    #[test]
+    fn compositing_onto_a_background() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let mut layer = Canvas::<Rgba>::new_with(3, 1, Rgba::TRANSPARENT);
+        layer.write_pixel(1, 0, Rgba::new(red, 0.5));
+        layer.write_pixel(2, 0, Rgba::opaque(red));
+        let image = layer.over(&Canvas::new_with(3, 1, Color::WHITE));
+        assert_eq!(image.pixel_at(0, 0), Color::WHITE);
+        assert_approx_eq!(image.pixel_at(1, 0), Color::new(1.0, 0.5, 0.5));
+        assert_eq!(image.pixel_at(2, 0), red);
+    }
+
+    #[test]
+    fn pam_carries_the_alpha_channel() {
+        let mut layer = Canvas::<Rgba>::new_with(2, 1, Rgba::TRANSPARENT);
+        layer.write_pixel(1, 0, Rgba::new(Color::new(1.0, 0.5, 0.0), 0.5));
+        let pam = layer.to_pam_with(Encoding::Linear);
+        let header = "P7\nWIDTH 2\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n";
+        assert_eq!(&pam[..header.len()], header.as_bytes());
+        assert_eq!(&pam[header.len()..], [0, 0, 0, 0, 255, 128, 0, 128]);
+    }
+}
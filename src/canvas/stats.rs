@@ -0,0 +1,100 @@
+//! Summaries of an image's brightness.
+
+use super::Canvas;
+
+/// How many bins each of a [`CanvasStats`]'s histograms has.
+pub const HISTOGRAM_BINS: usize = 256;
+
+/// The spread of brightness over a canvas; see [`Canvas::stats`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanvasStats {
+    pub min_luminance: f32,
+    pub max_luminance: f32,
+    pub mean_luminance: f32,
+    /// How many pixels fall in each of [`HISTOGRAM_BINS`] even steps from 0
+    /// to 1, per channel. Values outside that range count towards the end
+    /// bins, so a full last bin means a blown-out image.
+    pub red: [usize; HISTOGRAM_BINS],
+    pub green: [usize; HISTOGRAM_BINS],
+    pub blue: [usize; HISTOGRAM_BINS],
+}
+
+impl Canvas {
+    /// The luminance range and per-channel histograms of the canvas, for
+    /// choosing an exposure or checking a render isn't all black or all
+    /// white. An empty canvas has a luminance of 0 throughout.
+    pub fn stats(&self) -> CanvasStats {
+        let mut stats = CanvasStats {
+            min_luminance: f32::INFINITY,
+            max_luminance: f32::NEG_INFINITY,
+            mean_luminance: 0.0,
+            red: [0; HISTOGRAM_BINS],
+            green: [0; HISTOGRAM_BINS],
+            blue: [0; HISTOGRAM_BINS],
+        };
+        let mut total = 0.0;
+        for color in &self.pixels {
+            let luminance = color.luminance();
+            stats.min_luminance = stats.min_luminance.min(luminance);
+            stats.max_luminance = stats.max_luminance.max(luminance);
+            total += luminance;
+            stats.red[bin(color.r)] += 1;
+            stats.green[bin(color.g)] += 1;
+            stats.blue[bin(color.b)] += 1;
+        }
+        if self.pixels.is_empty() {
+            stats.min_luminance = 0.0;
+            stats.max_luminance = 0.0;
+        } else {
+            stats.mean_luminance = total / self.pixels.len() as f32;
+        }
+        stats
+    }
+}
+
+/// The histogram bin `value` falls in.
+fn bin(value: f32) -> usize {
+    let last = HISTOGRAM_BINS - 1;
+    ((value * HISTOGRAM_BINS as f32) as usize).min(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn luminance_range_and_mean() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(0, 0, Color::WHITE);
+        c.write_pixel(1, 1, Color::WHITE * 0.5);
+        let stats = c.stats();
+        assert_approx_eq!(stats.min_luminance, 0.0);
+        assert_approx_eq!(stats.max_luminance, 1.0);
+        assert_approx_eq!(stats.mean_luminance, 0.375);
+    }
+
+    #[test]
+    fn histograms_count_each_channel() {
+        let c = Canvas::from_fn(4, 1, |x, _| match x {
+            0 => Color::new(-1.0, 0.0, 0.0),
+            1 => Color::new(0.5, 0.0, 0.0),
+            _ => Color::new(3.0, 0.0, 1.0),
+        });
+        let stats = c.stats();
+        assert_eq!(stats.red[0], 1);
+        assert_eq!(stats.red[128], 1);
+        assert_eq!(stats.red[HISTOGRAM_BINS - 1], 2);
+        assert_eq!(stats.green[0], 4);
+        assert_eq!(stats.blue[HISTOGRAM_BINS - 1], 2);
+        assert_eq!(stats.red.iter().sum::<usize>(), 4);
+    }
+
+    #[test]
+    fn empty_canvas_is_dark() {
+        let stats = Canvas::new(0, 0).stats();
+        assert_eq!(stats.min_luminance, 0.0);
+        assert_eq!(stats.max_luminance, 0.0);
+        assert_eq!(stats.mean_luminance, 0.0);
+    }
+}
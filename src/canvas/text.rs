@@ -0,0 +1,101 @@
+//! A tiny built-in bitmap font for stamping labels onto renders.
+
+use super::Canvas;
+use crate::color::Color;
+
+/// Glyph width and height in pixels.
+const GLYPH_WIDTH: isize = 3;
+const GLYPH_HEIGHT: isize = 5;
+
+/// The characters the font has, in the order of [`GLYPHS`]. Lowercase
+/// letters are drawn as capitals.
+const CHARSET: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ .,:-+/%()=!?_";
+
+/// Each glyph's 3 × 5 bitmap, three bits per row from the top, the highest
+/// bit of each row on the left.
+const GLYPHS: [u16; 50] = [
+    0x7B6F, 0x2C97, 0x73E7, 0x72CF, 0x5BC9, 0x79CF, 0x79EF, 0x7292, 0x7BEF, 0x7BCF, // 0-9
+    0x2BED, 0x6BAE, 0x3923, 0x6B6E, 0x79A7, 0x79A4, 0x396B, 0x5BED, 0x7497, 0x126A, // A-J
+    0x5BAD, 0x4927, 0x5FED, 0x6B6D, 0x2B6A, 0x6BA4, 0x2B73, 0x6BAD, 0x388E, 0x7492, // K-T
+    0x5B6F, 0x5B6A, 0x5BFD, 0x5AAD, 0x5A92, 0x72A7, // U-Z
+    0x0000, 0x0002, 0x0014, 0x0410, 0x01C0, 0x05D0, 0x12A4, 0x52A5, 0x2922, 0x224A, 0x0E38, 0x2482,
+    0x6282, 0x0007, // punctuation
+];
+
+/// The bitmap for `c`, with anything the font lacks drawn as `?`.
+fn glyph(c: char) -> u16 {
+    let c = c.to_ascii_uppercase();
+    let index = CHARSET
+        .find(c)
+        .or_else(|| CHARSET.find('?'))
+        .unwrap_or_default();
+    GLYPHS[index]
+}
+
+impl Canvas {
+    /// Writes `text` with its top left at `position`, in a 3 × 5 pixel font
+    /// with a pixel between characters and lines. Newlines start a new line
+    /// under the first; anything off the canvas is clipped.
+    pub fn draw_text(&mut self, position: (isize, isize), text: &str, color: Color) {
+        let (left, mut y) = position;
+        for line in text.lines() {
+            let mut x = left;
+            for c in line.chars() {
+                let bits = glyph(c);
+                for row in 0..GLYPH_HEIGHT {
+                    for col in 0..GLYPH_WIDTH {
+                        let bit = (GLYPH_HEIGHT - 1 - row) * GLYPH_WIDTH + (GLYPH_WIDTH - 1 - col);
+                        if bits >> bit & 1 == 1 {
+                            self.plot(x + col, y + row, color);
+                        }
+                    }
+                }
+                x += GLYPH_WIDTH + 1;
+            }
+            y += GLYPH_HEIGHT + 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The canvas as rows of `#` and `.`.
+    fn picture(c: &Canvas) -> Vec<String> {
+        c.rows()
+            .map(|row| {
+                row.iter()
+                    .map(|p| if *p == Color::WHITE { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_character_has_a_glyph() {
+        assert_eq!(CHARSET.chars().count(), GLYPHS.len());
+        assert_eq!(glyph('a'), glyph('A'));
+        assert_eq!(glyph('~'), glyph('?'));
+    }
+
+    #[test]
+    fn text_is_drawn_left_to_right_and_line_by_line() {
+        let mut c = Canvas::new(7, 11);
+        c.draw_text((0, 0), "T1\nL", Color::WHITE);
+        assert_eq!(
+            picture(&c),
+            [
+                "###..#.", ".#..##.", ".#...#.", ".#...#.", ".#..###", ".......", "#......",
+                "#......", "#......", "#......", "###....",
+            ]
+        );
+    }
+
+    #[test]
+    fn text_off_the_canvas_is_clipped() {
+        let mut c = Canvas::new(2, 2);
+        c.draw_text((-2, -4), "8", Color::WHITE);
+        assert_eq!(picture(&c), ["#.", ".."]);
+    }
+}
@@ -0,0 +1,78 @@
+//! Putting a frame back together from separately rendered tiles.
+
+use super::Canvas;
+use crate::camera::Region;
+
+impl<P: Clone + Default> Canvas<P> {
+    /// A canvas just big enough for every tile, each `(region, tile)` copied
+    /// into `region`. Tiles can come in any order; where they overlap the
+    /// later one wins, and pixels no tile covers are left at the default
+    /// (black, for colors).
+    ///
+    /// # Panics
+    ///
+    /// Panics if a tile isn't the size of its region.
+    pub fn from_tiles(tiles: impl IntoIterator<Item = (Region, Canvas<P>)>) -> Self {
+        let tiles: Vec<_> = tiles.into_iter().collect();
+        for (region, tile) in &tiles {
+            assert!(
+                (tile.width, tile.height) == (region.width, region.height),
+                "a {}x{} tile doesn't fill its {}x{} region at ({}, {})",
+                tile.width,
+                tile.height,
+                region.width,
+                region.height,
+                region.x,
+                region.y
+            );
+        }
+        let width = tiles.iter().map(|(r, _)| r.x + r.width).max().unwrap_or(0);
+        let height = tiles.iter().map(|(r, _)| r.y + r.height).max().unwrap_or(0);
+        let mut canvas = Canvas::new_with(width, height, P::default());
+        for (region, tile) in tiles {
+            let mut view = canvas.view_mut((region.x, region.y), (region.width, region.height));
+            for (to, from) in view.rows_mut().zip(tile.rows()) {
+                to.clone_from_slice(from);
+            }
+        }
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn tiles_are_stitched_in_place() {
+        let frame = Canvas::from_fn(5, 3, |x, y| Color::new(x as f32, y as f32, 1.0));
+        let regions = [
+            Region::new(3, 2, 2, 1),
+            Region::new(0, 0, 3, 2),
+            Region::new(3, 0, 2, 2),
+            Region::new(0, 2, 3, 1),
+        ];
+        let tiles = regions.map(|r| {
+            let tile = frame.view((r.x, r.y), (r.width, r.height)).to_canvas();
+            (r, tile)
+        });
+        assert_eq!(Canvas::from_tiles(tiles), frame);
+    }
+
+    #[test]
+    fn gaps_are_left_blank() {
+        let tile = Canvas::new_with(1, 1, 7u32);
+        let stitched = Canvas::from_tiles([(Region::new(2, 1, 1, 1), tile)]);
+        assert_eq!((stitched.width(), stitched.height()), (3, 2));
+        assert_eq!(stitched.pixel_at(2, 1), 7);
+        assert_eq!(stitched.pixels().iter().sum::<u32>(), 7);
+        assert_eq!(Canvas::<u32>::from_tiles([]), Canvas::new_with(0, 0, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't fill its 2x2 region")]
+    fn tiles_must_fit_their_regions() {
+        Canvas::from_tiles([(Region::new(0, 0, 2, 2), Canvas::new(1, 2))]);
+    }
+}
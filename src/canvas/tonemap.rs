@@ -0,0 +1,83 @@
+//! Fitting unbounded light into the range an image can show.
+
+use super::Canvas;
+use crate::color::Color;
+
+/// How colors brighter than white are brought into `0.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneMap {
+    /// Leaves colors as they are, so anything over 1 is clipped to white
+    /// when the image is written out.
+    #[default]
+    Clamp,
+    /// Reinhard's `L / (1 + L)` on the luminance, which keeps hues and
+    /// compresses highlights smoothly. Greys never quite reach white, though
+    /// strongly saturated highlights can still clip.
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve, per channel: more contrast
+    /// than [`Reinhard`](Self::Reinhard), and highlights wash out to white
+    /// as they do on film.
+    Aces,
+}
+
+impl ToneMap {
+    pub fn apply(&self, color: Color) -> Color {
+        match self {
+            ToneMap::Clamp => color,
+            ToneMap::Reinhard => {
+                let luminance = color.luminance();
+                if luminance <= 0.0 {
+                    return color;
+                }
+                color / (1.0 + luminance)
+            }
+            ToneMap::Aces => color.map(|x| {
+                let x = x.max(0.0);
+                (x * (2.51 * x + 0.03) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0, 1.0)
+            }),
+        }
+    }
+}
+
+impl Canvas {
+    /// Scales every pixel by `2^exposure`, so each stop of exposure doubles
+    /// the brightness, then applies `tone_map`.
+    pub fn tone_map(&mut self, exposure: f32, tone_map: ToneMap) {
+        let scale = exposure.exp2();
+        for pixel in &mut self.pixels {
+            *pixel = tone_map.apply(*pixel * scale);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamp_only_applies_exposure() {
+        let mut c = Canvas::new(1, 1);
+        c.write_pixel(0, 0, Color::new(0.25, 1.0, 4.0));
+        c.tone_map(1.0, ToneMap::Clamp);
+        assert_approx_eq!(c.pixel_at(0, 0), Color::new(0.5, 2.0, 8.0));
+        c.tone_map(-1.0, ToneMap::Clamp);
+        assert_approx_eq!(c.pixel_at(0, 0), Color::new(0.25, 1.0, 4.0));
+    }
+
+    #[test]
+    fn reinhard_compresses_luminance_keeping_hue() {
+        assert_approx_eq!(ToneMap::Reinhard.apply(Color::WHITE), Color::WHITE * 0.5);
+        assert!(ToneMap::Reinhard.apply(Color::WHITE * 100.0).r < 1.0);
+        let orange = ToneMap::Reinhard.apply(Color::new(10.0, 5.0, 0.0));
+        assert_approx_eq!(orange.g / orange.r, 0.5);
+        assert_eq!(ToneMap::Reinhard.apply(Color::BLACK), Color::BLACK);
+    }
+
+    #[test]
+    fn aces_saturates_to_white() {
+        assert_eq!(ToneMap::Aces.apply(Color::BLACK), Color::BLACK);
+        assert_approx_eq!(ToneMap::Aces.apply(Color::WHITE * 1000.0), Color::WHITE);
+        let mid = ToneMap::Aces.apply(Color::new(0.18, 0.18, 0.18));
+        assert!(mid.r > 0.1 && mid.r < 0.3, "{mid:?}");
+    }
+}
@@ -0,0 +1,316 @@
+//! Borrowed rectangles of a canvas.
+
+use std::ops::Range;
+
+use super::{Canvas, CanvasError};
+use crate::color::Color;
+
+/// A rectangle of a [`Canvas`], borrowed to read, with its own coordinates
+/// starting from `(0, 0)` at its top-left corner.
+#[derive(Debug, Clone)]
+pub struct CanvasView<'a, P = Color> {
+    origin: (usize, usize),
+    width: usize,
+    rows: Vec<&'a [P]>,
+}
+
+/// A rectangle of a [`Canvas`], borrowed to write, with its own coordinates
+/// starting from `(0, 0)` at its top-left corner.
+///
+/// Views from [`Canvas::tiles_mut`] don't overlap, so each can be handed to
+/// a different thread.
+#[derive(Debug)]
+pub struct CanvasViewMut<'a, P = Color> {
+    origin: (usize, usize),
+    width: usize,
+    rows: Vec<&'a mut [P]>,
+}
+
+impl<P> Canvas<P> {
+    /// The `size` pixels from `top_left`, cut short at the canvas's edges.
+    pub fn view(&self, top_left: (usize, usize), size: (usize, usize)) -> CanvasView<'_, P> {
+        let (xs, ys) = self.clip(top_left, size);
+        CanvasView {
+            origin: (xs.start, ys.start),
+            width: xs.len(),
+            rows: self
+                .rows()
+                .skip(ys.start)
+                .take(ys.len())
+                .map(|row| &row[xs.clone()])
+                .collect(),
+        }
+    }
+
+    /// Like [`view`](Self::view), but the pixels can be written.
+    pub fn view_mut(
+        &mut self,
+        top_left: (usize, usize),
+        size: (usize, usize),
+    ) -> CanvasViewMut<'_, P> {
+        let (xs, ys) = self.clip(top_left, size);
+        let width = self.width.max(1);
+        CanvasViewMut {
+            origin: (xs.start, ys.start),
+            width: xs.len(),
+            rows: self
+                .pixels
+                .chunks_mut(width)
+                .skip(ys.start)
+                .take(ys.len())
+                .map(|row| &mut row[xs.clone()])
+                .collect(),
+        }
+    }
+
+    /// The whole canvas cut into views of `size`, across each row of tiles
+    /// then down; those on the right and bottom edges may be smaller.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either side of `size` is zero.
+    pub fn tiles_mut(&mut self, size: (usize, usize)) -> Vec<CanvasViewMut<'_, P>> {
+        let (tile_width, tile_height) = size;
+        assert!(tile_width > 0 && tile_height > 0, "tiles must not be empty");
+        let width = self.width.max(1);
+        let mut tiles = Vec::new();
+        for (band, rows) in self.pixels.chunks_mut(width * tile_height).enumerate() {
+            let first = tiles.len();
+            for (row, pixels) in rows.chunks_mut(width).enumerate() {
+                for (column, pixels) in pixels.chunks_mut(tile_width).enumerate() {
+                    if row == 0 {
+                        tiles.push(CanvasViewMut {
+                            origin: (column * tile_width, band * tile_height),
+                            width: pixels.len(),
+                            rows: Vec::new(),
+                        });
+                    }
+                    tiles[first + column].rows.push(pixels);
+                }
+            }
+        }
+        tiles
+    }
+
+    /// The columns and rows of the `size` pixels from `top_left` that are on
+    /// the canvas.
+    fn clip(&self, top_left: (usize, usize), size: (usize, usize)) -> (Range<usize>, Range<usize>) {
+        let clip = |start: usize, length: usize, limit: usize| {
+            let start = start.min(limit);
+            start..start.saturating_add(length).min(limit)
+        };
+        (
+            clip(top_left.0, size.0, self.width),
+            clip(top_left.1, size.1, self.height),
+        )
+    }
+}
+
+/// The shared read-only half of the view API.
+macro_rules! view_reads {
+    () => {
+        /// Where the view's top-left pixel is on the canvas.
+        pub fn origin(&self) -> (usize, usize) {
+            self.origin
+        }
+
+        pub fn width(&self) -> usize {
+            self.width
+        }
+
+        pub fn height(&self) -> usize {
+            self.rows.len()
+        }
+
+        /// # Panics
+        ///
+        /// Panics if (`x`, `y`) is off the view; see
+        /// [`try_pixel_at`](Self::try_pixel_at).
+        pub fn pixel_at(&self, x: usize, y: usize) -> P
+        where
+            P: Clone,
+        {
+            self.try_pixel_at(x, y).unwrap_or_else(|e| panic!("{e}"))
+        }
+
+        /// Like [`pixel_at`](Self::pixel_at), but fails instead of panicking
+        /// if (`x`, `y`) is off the view.
+        pub fn try_pixel_at(&self, x: usize, y: usize) -> Result<P, CanvasError>
+        where
+            P: Clone,
+        {
+            self.check(x, y)?;
+            Ok(self.rows[y][x].clone())
+        }
+
+        /// Each pixel with its coordinates in the view, as `(x, y, value)`,
+        /// in row-major order.
+        pub fn enumerate_pixels(&self) -> impl Iterator<Item = (usize, usize, P)> + '_
+        where
+            P: Clone,
+        {
+            self.rows().enumerate().flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(move |(x, value)| (x, y, value.clone()))
+            })
+        }
+
+        /// The pixels copied into a canvas of their own.
+        pub fn to_canvas(&self) -> Canvas<P>
+        where
+            P: Clone,
+        {
+            Canvas::from_fn(self.width(), self.height(), |x, y| self.rows[y][x].clone())
+        }
+
+        fn check(&self, x: usize, y: usize) -> Result<(), CanvasError> {
+            if x < self.width && y < self.rows.len() {
+                Ok(())
+            } else {
+                Err(CanvasError::OutOfBounds {
+                    x,
+                    y,
+                    width: self.width,
+                    height: self.rows.len(),
+                })
+            }
+        }
+    };
+}
+
+impl<P> CanvasView<'_, P> {
+    view_reads!();
+
+    /// The rows of pixels, top first.
+    pub fn rows(&self) -> impl Iterator<Item = &[P]> {
+        self.rows.iter().copied()
+    }
+}
+
+impl<'a, P> CanvasViewMut<'a, P> {
+    view_reads!();
+
+    /// The rows of pixels, top first.
+    pub fn rows(&self) -> impl Iterator<Item = &[P]> {
+        self.rows.iter().map(|row| &**row)
+    }
+
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [P]> + use<'_, 'a, P> {
+        self.rows.iter_mut().map(|row| &mut **row)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if (`x`, `y`) is off the view; see
+    /// [`try_write_pixel`](Self::try_write_pixel).
+    pub fn write_pixel(&mut self, x: usize, y: usize, value: P) {
+        self.try_write_pixel(x, y, value)
+            .unwrap_or_else(|e| panic!("{e}"));
+    }
+
+    /// Like [`write_pixel`](Self::write_pixel), but fails instead of
+    /// panicking if (`x`, `y`) is off the view.
+    pub fn try_write_pixel(&mut self, x: usize, y: usize, value: P) -> Result<(), CanvasError> {
+        self.check(x, y)?;
+        self.rows[y][x] = value;
+        Ok(())
+    }
+
+    /// Sets every pixel to `value`.
+    pub fn fill(&mut self, value: P)
+    where
+        P: Clone,
+    {
+        for row in self.rows_mut() {
+            row.fill(value.clone());
+        }
+    }
+
+    /// Sets every pixel back to the default: black, for colors.
+    pub fn clear(&mut self)
+    where
+        P: Clone + Default,
+    {
+        self.fill(P::default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn views_use_their_own_coordinates() {
+        let c = Canvas::from_fn(4, 3, |x, y| Color::new(x as f32, y as f32, 0.0));
+        let view = c.view((1, 1), (2, 2));
+        assert_eq!((view.width(), view.height()), (2, 2));
+        assert_eq!(view.origin(), (1, 1));
+        assert_eq!(view.pixel_at(0, 0), Color::new(1.0, 1.0, 0.0));
+        assert_eq!(view.pixel_at(1, 1), Color::new(2.0, 2.0, 0.0));
+        assert_eq!(
+            view.try_pixel_at(2, 0),
+            Err(CanvasError::OutOfBounds {
+                x: 2,
+                y: 0,
+                width: 2,
+                height: 2
+            })
+        );
+        assert_eq!(
+            view.to_canvas(),
+            Canvas::from_fn(2, 2, |x, y| c.pixel_at(x + 1, y + 1))
+        );
+    }
+
+    #[test]
+    fn views_are_clipped_to_the_canvas() {
+        let c = Canvas::new(4, 3);
+        let view = c.view((3, 1), (5, 5));
+        assert_eq!((view.width(), view.height()), (1, 2));
+        let view = c.view((9, 9), (2, 2));
+        assert_eq!((view.width(), view.height()), (0, 0));
+    }
+
+    #[test]
+    fn writing_through_a_view() {
+        let mut c = Canvas::new(4, 3);
+        let mut view = c.view_mut((2, 1), (2, 2));
+        view.fill(Color::WHITE);
+        view.write_pixel(0, 0, Color::BLACK);
+        assert!(view.try_write_pixel(0, 2, Color::WHITE).is_err());
+        let lit: Vec<_> = c
+            .enumerate_pixels()
+            .filter(|(_, _, color)| *color == Color::WHITE)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        assert_eq!(lit, [(3, 1), (2, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn tiles_cover_the_canvas_once() {
+        let mut c = Canvas::new(5, 3);
+        let tiles = c.tiles_mut((2, 2));
+        let origins: Vec<_> = tiles.iter().map(|t| t.origin()).collect();
+        assert_eq!(origins, [(0, 0), (2, 0), (4, 0), (0, 2), (2, 2), (4, 2)]);
+        assert_eq!((tiles[5].width(), tiles[5].height()), (1, 1));
+        thread::scope(|scope| {
+            for mut tile in tiles {
+                scope.spawn(move || {
+                    let (left, top) = tile.origin();
+                    for (y, row) in tile.rows_mut().enumerate() {
+                        for (x, pixel) in row.iter_mut().enumerate() {
+                            *pixel = Color::new((left + x) as f32, (top + y) as f32, 0.0);
+                        }
+                    }
+                });
+            }
+        });
+        assert_eq!(
+            c,
+            Canvas::from_fn(5, 3, |x, y| Color::new(x as f32, y as f32, 0.0))
+        );
+    }
+}
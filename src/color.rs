@@ -0,0 +1,225 @@
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, Sub};
+
+use crate::math::ApproxEq;
+
+/// A linear RGB color with unbounded `f32` channels.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl Color {
+    pub const BLACK: Self = Self::new(0.0, 0.0, 0.0);
+    pub const WHITE: Self = Self::new(1.0, 1.0, 1.0);
+
+    pub const fn new(r: f32, g: f32, b: f32) -> Self {
+        Self { r, g, b }
+    }
+
+    /// The color with `f` applied to each channel.
+    pub fn map(self, f: impl Fn(f32) -> f32) -> Self {
+        Self::new(f(self.r), f(self.g), f(self.b))
+    }
+
+    /// The color made by applying `f` to each channel of this and `other`.
+    pub fn zip(self, other: Self, f: impl Fn(f32, f32) -> f32) -> Self {
+        Self::new(f(self.r, other.r), f(self.g, other.g), f(self.b, other.b))
+    }
+
+    /// How bright the color looks, weighting the channels as the eye does
+    /// (Rec. 709).
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+}
+
+impl ApproxEq for Color {
+    fn approx_eq(&self, other: &Self) -> bool {
+        self.r.approx_eq(&other.r) && self.g.approx_eq(&other.g) && self.b.approx_eq(&other.b)
+    }
+}
+
+impl Add for Color {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.r + rhs.r, self.g + rhs.g, self.b + rhs.b)
+    }
+}
+
+impl AddAssign for Color {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sum for Color {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::BLACK, Add::add)
+    }
+}
+
+impl Sub for Color {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.r - rhs.r, self.g - rhs.g, self.b - rhs.b)
+    }
+}
+
+impl Mul<f32> for Color {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        Self::new(self.r * rhs, self.g * rhs, self.b * rhs)
+    }
+}
+
+/// The Hadamard (component-wise) product, used to blend colors.
+impl Mul for Color {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(self.r * rhs.r, self.g * rhs.g, self.b * rhs.b)
+    }
+}
+
+impl Div<f32> for Color {
+    type Output = Self;
+
+    fn div(self, rhs: f32) -> Self {
+        Self::new(self.r / rhs, self.g / rhs, self.b / rhs)
+    }
+}
+
+/// A [`Color`] with an alpha channel: 1 where it's opaque, 0 where it's
+/// fully transparent. The color isn't premultiplied by the alpha.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rgba {
+    pub color: Color,
+    pub alpha: f32,
+}
+
+impl Rgba {
+    pub const TRANSPARENT: Self = Self::new(Color::BLACK, 0.0);
+
+    pub const fn new(color: Color, alpha: f32) -> Self {
+        Self { color, alpha }
+    }
+
+    pub const fn opaque(color: Color) -> Self {
+        Self::new(color, 1.0)
+    }
+
+    /// This laid over `background`, letting it show through as much as
+    /// this is transparent (Porter-Duff "over").
+    pub fn over(self, background: Rgba) -> Rgba {
+        let alpha = self.alpha + background.alpha * (1.0 - self.alpha);
+        if alpha <= 0.0 {
+            return Self::TRANSPARENT;
+        }
+        let color = (self.color * self.alpha
+            + background.color * background.alpha * (1.0 - self.alpha))
+            / alpha;
+        Self::new(color, alpha)
+    }
+}
+
+impl From<Color> for Rgba {
+    fn from(color: Color) -> Self {
+        Self::opaque(color)
+    }
+}
+
+impl ApproxEq for Rgba {
+    fn approx_eq(&self, other: &Self) -> bool {
+        self.color.approx_eq(&other.color) && self.alpha.approx_eq(&other.alpha)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let c1 = Color::new(0.9, 0.6, 0.75);
+        let c2 = Color::new(0.7, 0.1, 0.25);
+        assert_approx_eq!(c1 + c2, Color::new(1.6, 0.7, 1.0));
+        assert_approx_eq!(c1 - c2, Color::new(0.2, 0.5, 0.5));
+        assert_approx_eq!(Color::new(0.2, 0.3, 0.4) * 2.0, Color::new(0.4, 0.6, 0.8));
+    }
+
+    #[test]
+    fn hadamard_product() {
+        let c1 = Color::new(1.0, 0.2, 0.4);
+        let c2 = Color::new(0.9, 1.0, 0.1);
+        assert_approx_eq!(c1 * c2, Color::new(0.9, 0.2, 0.04));
+    }
+
+    #[test]
+    fn luminance_and_channelwise_helpers() {
+        assert_approx_eq!(Color::WHITE.luminance(), 1.0);
+        assert!(Color::new(0.0, 1.0, 0.0).luminance() > Color::new(1.0, 0.0, 1.0).luminance());
+        assert_eq!(
+            Color::new(0.25, 4.0, 1.0).map(f32::sqrt),
+            Color::new(0.5, 2.0, 1.0)
+        );
+        assert_eq!(
+            Color::new(1.0, 2.0, 3.0).zip(Color::new(3.0, 2.0, 1.0), f32::max),
+            Color::new(3.0, 2.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn compositing_over_a_background() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let blue = Color::new(0.0, 0.0, 1.0);
+        assert_eq!(Rgba::opaque(red).over(blue.into()), Rgba::opaque(red));
+        assert_eq!(Rgba::TRANSPARENT.over(blue.into()), Rgba::opaque(blue));
+        assert_approx_eq!(
+            Rgba::new(red, 0.25).over(blue.into()),
+            Rgba::opaque(Color::new(0.25, 0.0, 0.75))
+        );
+        let layered = Rgba::new(red, 0.5).over(Rgba::new(blue, 0.5));
+        assert_approx_eq!(
+            layered,
+            Rgba::new(Color::new(2.0 / 3.0, 0.0, 1.0 / 3.0), 0.75)
+        );
+        assert_eq!(Rgba::TRANSPARENT.over(Rgba::TRANSPARENT), Rgba::TRANSPARENT);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn colors_serialize_as_their_channels() {
+        use serde_test::{assert_tokens, Token};
+        assert_tokens(
+            &Color::new(0.25, 0.5, 1.0),
+            &[
+                Token::Struct {
+                    name: "Color",
+                    len: 3,
+                },
+                Token::Str("r"),
+                Token::F32(0.25),
+                Token::Str("g"),
+                Token::F32(0.5),
+                Token::Str("b"),
+                Token::F32(1.0),
+                Token::StructEnd,
+            ],
+        );
+    }
+
+    #[test]
+    fn sum_of_colors() {
+        let colors = [Color::new(0.1, 0.2, 0.3), Color::new(0.4, 0.5, 0.6)];
+        assert_approx_eq!(colors.into_iter().sum::<Color>(), Color::new(0.5, 0.7, 0.9));
+        assert_eq!(std::iter::empty().sum::<Color>(), Color::BLACK);
+    }
+}
@@ -0,0 +1,371 @@
+//! A C ABI for embedding the renderer, enabled by the `ffi` feature. The
+//! crate also builds as a `cdylib` for linking from C or C++.
+//!
+//! A scene is built up through calls rather than read from a file: create
+//! one with [`rt_scene_new`], add shapes and lights, aim the camera, then
+//! [`rt_render`] it to an image whose pixels are read with
+//! [`rt_image_pixels`]. Scenes and images are owned by the caller and freed
+//! with [`rt_scene_free`] and [`rt_image_free`].
+//!
+//! Vectors and colors are passed as pointers to three `float`s, and
+//! transforms as pointers to sixteen, row by row.
+
+use std::ptr;
+use std::slice;
+
+use crate::camera::{view_transform, Camera};
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::light::Light;
+use crate::material::Material;
+use crate::math::{Matrix4, Vert4};
+use crate::shape::{Cube, Shape, Sphere};
+use crate::world::World;
+
+/// A world and the camera it's seen through.
+#[derive(Debug)]
+pub struct RtScene {
+    world: World,
+    camera: Camera,
+}
+
+/// A rendered image, as `width` × `height` RGB triples of `float`s.
+#[derive(Debug)]
+pub struct RtImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<f32>,
+}
+
+impl From<&Canvas> for RtImage {
+    fn from(canvas: &Canvas) -> Self {
+        Self {
+            width: canvas.width(),
+            height: canvas.height(),
+            pixels: canvas
+                .pixels()
+                .iter()
+                .flat_map(|c| [c.r, c.g, c.b])
+                .collect(),
+        }
+    }
+}
+
+/// The kinds of shape [`rt_scene_add_shape`] can add, each in its unit
+/// form before the transform.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtShapeKind {
+    Sphere = 0,
+    Cube = 1,
+}
+
+/// The Phong parameters of a [`Material`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RtMaterial {
+    pub color: [f32; 3],
+    pub ambient: f32,
+    pub diffuse: f32,
+    pub specular: f32,
+    pub shininess: f32,
+    pub reflective: f32,
+    pub transparency: f32,
+    pub refractive_index: f32,
+}
+
+impl From<RtMaterial> for Material {
+    fn from(m: RtMaterial) -> Self {
+        let [r, g, b] = m.color;
+        Material {
+            color: Color::new(r, g, b),
+            ambient: m.ambient,
+            diffuse: m.diffuse,
+            specular: m.specular,
+            shininess: m.shininess,
+            reflective: m.reflective,
+            transparency: m.transparency,
+            refractive_index: m.refractive_index,
+            ..Material::default()
+        }
+    }
+}
+
+/// The parameters of [`Material::default`], to start from.
+#[no_mangle]
+pub extern "C" fn rt_material_default() -> RtMaterial {
+    let m = Material::default();
+    RtMaterial {
+        color: [m.color.r, m.color.g, m.color.b],
+        ambient: m.ambient,
+        diffuse: m.diffuse,
+        specular: m.specular,
+        shininess: m.shininess,
+        reflective: m.reflective,
+        transparency: m.transparency,
+        refractive_index: m.refractive_index,
+    }
+}
+
+/// An empty scene seen by a `width` × `height` camera with a
+/// `field_of_view` in radians, at the origin looking down -z. Null if
+/// either side is zero.
+#[no_mangle]
+pub extern "C" fn rt_scene_new(width: u32, height: u32, field_of_view: f32) -> *mut RtScene {
+    if width == 0 || height == 0 {
+        return ptr::null_mut();
+    }
+    let scene = RtScene {
+        world: World::new(),
+        camera: Camera::new(width as usize, height as usize, field_of_view),
+    };
+    Box::into_raw(Box::new(scene))
+}
+
+/// Frees a scene from [`rt_scene_new`]. Null is ignored.
+///
+/// # Safety
+///
+/// `scene` must be null or a scene from [`rt_scene_new`] not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_free(scene: *mut RtScene) {
+    if !scene.is_null() {
+        drop(Box::from_raw(scene));
+    }
+}
+
+/// Aims the camera from the point `from` at the point `to`, with `up`
+/// roughly upwards. False, leaving the camera as it was, if a pointer is
+/// null.
+///
+/// # Safety
+///
+/// `scene` must be null or a live scene, and the others null or pointers
+/// to three `float`s.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_look_at(
+    scene: *mut RtScene,
+    from: *const f32,
+    to: *const f32,
+    up: *const f32,
+) -> bool {
+    let (Some(scene), Some(from), Some(to), Some(up)) =
+        (scene.as_mut(), triple(from), triple(to), triple(up))
+    else {
+        return false;
+    };
+    let transform = view_transform(
+        Vert4::point(from[0], from[1], from[2]),
+        Vert4::point(to[0], to[1], to[2]),
+        Vert4::vector(up[0], up[1], up[2]),
+    );
+    if transform.inverse().is_none() {
+        return false;
+    }
+    scene.camera.set_transform(transform);
+    true
+}
+
+/// Adds a point light at `position` shining `intensity`. False if a
+/// pointer is null.
+///
+/// # Safety
+///
+/// `scene` must be null or a live scene, and the others null or pointers
+/// to three `float`s.
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_add_point_light(
+    scene: *mut RtScene,
+    position: *const f32,
+    intensity: *const f32,
+) -> bool {
+    let (Some(scene), Some(p), Some(i)) = (scene.as_mut(), triple(position), triple(intensity))
+    else {
+        return false;
+    };
+    scene.world.lights.push(Light::point(
+        Vert4::point(p[0], p[1], p[2]),
+        Color::new(i[0], i[1], i[2]),
+    ));
+    true
+}
+
+/// Adds a shape of `kind`, placed by `transform` (null for none) and
+/// painted with `material` (null for the default). False, adding nothing,
+/// if `scene` is null or the transform can't be inverted.
+///
+/// # Safety
+///
+/// `scene` must be null or a live scene, `transform` null or a pointer to
+/// sixteen `float`s, and `material` null or a pointer to an
+/// [`RtMaterial`].
+#[no_mangle]
+pub unsafe extern "C" fn rt_scene_add_shape(
+    scene: *mut RtScene,
+    kind: RtShapeKind,
+    transform: *const f32,
+    material: *const RtMaterial,
+) -> bool {
+    let Some(scene) = scene.as_mut() else {
+        return false;
+    };
+    let transform = match transform.as_ref() {
+        None => Matrix4::IDENTITY,
+        Some(_) => {
+            let m = slice::from_raw_parts(transform, 16);
+            Matrix4::new([0, 1, 2, 3].map(|r| [0, 1, 2, 3].map(|c| m[r * 4 + c])))
+        }
+    };
+    if transform.inverse().is_none() {
+        return false;
+    }
+    let material = material
+        .as_ref()
+        .map_or_else(Material::default, |m| (*m).into());
+    let shape: Box<dyn Shape> = match kind {
+        RtShapeKind::Sphere => Box::new(
+            Sphere::new()
+                .with_transform(transform)
+                .with_material(material),
+        ),
+        RtShapeKind::Cube => Box::new(
+            Cube::new()
+                .with_transform(transform)
+                .with_material(material),
+        ),
+    };
+    scene.world.objects.push(shape);
+    true
+}
+
+/// Renders the scene to a new image, to free with [`rt_image_free`]. Null
+/// if `scene` is.
+///
+/// # Safety
+///
+/// `scene` must be null or a live scene.
+#[no_mangle]
+pub unsafe extern "C" fn rt_render(scene: *const RtScene) -> *mut RtImage {
+    match scene.as_ref() {
+        Some(scene) => {
+            let image = RtImage::from(&scene.camera.render(&scene.world));
+            Box::into_raw(Box::new(image))
+        }
+        None => ptr::null_mut(),
+    }
+}
+
+/// The image's width in pixels, or 0 if it's null.
+///
+/// # Safety
+///
+/// `image` must be null or a live image.
+#[no_mangle]
+pub unsafe extern "C" fn rt_image_width(image: *const RtImage) -> u32 {
+    image.as_ref().map_or(0, |image| image.width as u32)
+}
+
+/// The image's height in pixels, or 0 if it's null.
+///
+/// # Safety
+///
+/// `image` must be null or a live image.
+#[no_mangle]
+pub unsafe extern "C" fn rt_image_height(image: *const RtImage) -> u32 {
+    image.as_ref().map_or(0, |image| image.height as u32)
+}
+
+/// The image's pixels, row by row from the top left, each as red, green
+/// and blue `float`s, unclamped. They belong to the image and are valid
+/// until it's freed. Null if `image` is.
+///
+/// # Safety
+///
+/// `image` must be null or a live image.
+#[no_mangle]
+pub unsafe extern "C" fn rt_image_pixels(image: *const RtImage) -> *const f32 {
+    image
+        .as_ref()
+        .map_or(ptr::null(), |image| image.pixels.as_ptr())
+}
+
+/// Frees an image from [`rt_render`]. Null is ignored.
+///
+/// # Safety
+///
+/// `image` must be null or an image from [`rt_render`] not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn rt_image_free(image: *mut RtImage) {
+    if !image.is_null() {
+        drop(Box::from_raw(image));
+    }
+}
+
+/// The three `float`s at `p`, if it isn't null.
+unsafe fn triple<'a>(p: *const f32) -> Option<&'a [f32]> {
+    (!p.is_null()).then(|| slice::from_raw_parts(p, 3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendering_a_scene_through_the_c_api() {
+        unsafe {
+            let scene = rt_scene_new(11, 11, std::f32::consts::FRAC_PI_2);
+            assert!(rt_scene_look_at(
+                scene,
+                [0.0, 0.0, -5.0].as_ptr(),
+                [0.0; 3].as_ptr(),
+                [0.0, 1.0, 0.0].as_ptr()
+            ));
+            assert!(rt_scene_add_point_light(
+                scene,
+                [-10.0, 10.0, -10.0].as_ptr(),
+                [1.0; 3].as_ptr()
+            ));
+            let material = RtMaterial {
+                color: [0.8, 1.0, 0.6],
+                diffuse: 0.7,
+                specular: 0.2,
+                ..rt_material_default()
+            };
+            assert!(rt_scene_add_shape(
+                scene,
+                RtShapeKind::Sphere,
+                ptr::null(),
+                &material
+            ));
+            let image = rt_render(scene);
+            assert_eq!((rt_image_width(image), rt_image_height(image)), (11, 11));
+            let pixels = slice::from_raw_parts(rt_image_pixels(image), 11 * 11 * 3);
+            assert_approx_eq!(pixels[(5 * 11 + 5) * 3..][..3], [0.38066, 0.47583, 0.2855]);
+            assert_eq!(pixels[..3], [0.0; 3]);
+            rt_image_free(image);
+            rt_scene_free(scene);
+        }
+    }
+
+    #[test]
+    fn bad_arguments_are_refused() {
+        unsafe {
+            assert!(rt_scene_new(0, 10, 1.0).is_null());
+            assert!(rt_render(ptr::null()).is_null());
+            assert!(rt_image_pixels(ptr::null()).is_null());
+            assert_eq!(rt_image_width(ptr::null()), 0);
+            let scene = rt_scene_new(4, 4, 1.0);
+            let singular = [0.0; 16];
+            assert!(!rt_scene_add_shape(
+                scene,
+                RtShapeKind::Cube,
+                singular.as_ptr(),
+                ptr::null()
+            ));
+            assert!(!rt_scene_add_point_light(scene, ptr::null(), ptr::null()));
+            assert!((*scene).world.objects.is_empty());
+            rt_scene_free(scene);
+            rt_scene_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Reading geometry written by other tools.
+
+mod obj;
+
+pub use obj::{ObjError, ObjFile};
+
+use std::f32::consts::FRAC_PI_2;
+
+use crate::math::Matrix4;
+
+/// The axis a file treats as up. The renderer's is +y, as in most game
+/// engines; Blender and CAD packages tend to use +z.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpAxis {
+    #[default]
+    Y,
+    Z,
+}
+
+/// How imported geometry is fitted to the scene: turned so its up axis is
+/// the renderer's and scaled from the file's units into the scene's. Both
+/// are baked into the vertices, so the imported group's own transform is
+/// left free for placing it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImportOptions {
+    pub up_axis: UpAxis,
+    /// Scene units per file unit, such as `0.01` for a file in centimetres
+    /// going into a scene in metres.
+    pub unit_scale: f32,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            up_axis: UpAxis::Y,
+            unit_scale: 1.0,
+        }
+    }
+}
+
+impl ImportOptions {
+    pub fn with_up_axis(mut self, up_axis: UpAxis) -> Self {
+        self.up_axis = up_axis;
+        self
+    }
+
+    pub fn with_unit_scale(mut self, unit_scale: f32) -> Self {
+        self.unit_scale = unit_scale;
+        self
+    }
+
+    /// The conversion from file space into scene space. Z up turns a
+    /// quarter about x, taking +z to +y and +y to -z.
+    pub fn transform(&self) -> Matrix4 {
+        let s = self.unit_scale;
+        let scaling = Matrix4::scaling(s, s, s);
+        match self.up_axis {
+            UpAxis::Y => scaling,
+            UpAxis::Z => scaling * Matrix4::rotation_x(-FRAC_PI_2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vert4;
+
+    #[test]
+    fn the_default_options_leave_geometry_alone() {
+        assert_eq!(ImportOptions::default().transform(), Matrix4::IDENTITY);
+    }
+
+    #[test]
+    fn z_up_files_are_turned_to_y_up() {
+        let m = ImportOptions::default()
+            .with_up_axis(UpAxis::Z)
+            .with_unit_scale(2.0)
+            .transform();
+        assert_approx_eq!(m * Vert4::point(0.0, 0.0, 1.0), Vert4::point(0.0, 2.0, 0.0));
+        assert_approx_eq!(
+            m * Vert4::point(0.0, 1.0, 0.0),
+            Vert4::point(0.0, 0.0, -2.0)
+        );
+        assert_approx_eq!(m * Vert4::point(1.0, 0.0, 0.0), Vert4::point(2.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,269 @@
+//! Wavefront OBJ meshes.
+
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use super::ImportOptions;
+use crate::math::Vert4;
+use crate::shape::{Group, SmoothTriangle, Triangle};
+
+/// Why an OBJ file couldn't be read; see [`ObjFile::parse`]. Lines count
+/// from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjError {
+    /// A `v` or `vn` statement without three numbers.
+    BadVertex { line: usize },
+    /// An `f` statement with fewer than three vertices, or one referring to
+    /// a vertex or normal that doesn't exist.
+    BadFace { line: usize },
+}
+
+impl Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::BadVertex { line } => write!(f, "malformed OBJ vertex on line {line}"),
+            ObjError::BadFace { line } => write!(f, "malformed OBJ face on line {line}"),
+        }
+    }
+}
+
+impl Error for ObjError {}
+
+/// The triangles of an OBJ file, sorted into the groups it names.
+///
+/// Only vertices (`v`), normals (`vn`), faces (`f`) and groups (`g`) are
+/// read; faces with more than three vertices are split into a fan, and
+/// faces whose vertices all have normals become [`SmoothTriangle`]s.
+#[derive(Debug)]
+pub struct ObjFile {
+    /// Lines holding any other statement, such as materials or texture
+    /// coordinates, which were skipped.
+    pub ignored: usize,
+    default_group: Group,
+    groups: Vec<(String, Group)>,
+}
+
+impl ObjFile {
+    /// Reads `source`, fitting it to the scene with `options`.
+    pub fn parse(source: &str, options: &ImportOptions) -> Result<Self, ObjError> {
+        let transform = options.transform();
+        let mut vertices = Vec::new();
+        let mut normals = Vec::new();
+        let mut ignored = 0;
+        let mut default_group = Group::new();
+        let mut groups: Vec<(String, Group)> = Vec::new();
+        let mut current: Option<usize> = None;
+        for (index, text) in source.lines().enumerate() {
+            let line = index + 1;
+            let mut words = text.split_whitespace();
+            match words.next() {
+                Some("v") => {
+                    let [x, y, z] = numbers(words).ok_or(ObjError::BadVertex { line })?;
+                    vertices.push(transform * Vert4::point(x, y, z));
+                }
+                Some("vn") => {
+                    let [x, y, z] = numbers(words).ok_or(ObjError::BadVertex { line })?;
+                    normals.push(transform * Vert4::vector(x, y, z));
+                }
+                Some("f") => {
+                    let face =
+                        face(words, &vertices, &normals).ok_or(ObjError::BadFace { line })?;
+                    let group = match current {
+                        Some(i) => &mut groups[i].1,
+                        None => &mut default_group,
+                    };
+                    add_fan(group, &face);
+                }
+                Some("g") => {
+                    let name = words.collect::<Vec<_>>().join(" ");
+                    current = Some(match groups.iter().position(|(n, _)| *n == name) {
+                        Some(i) => i,
+                        None => {
+                            groups.push((name, Group::new()));
+                            groups.len() - 1
+                        }
+                    });
+                }
+                Some(_) => ignored += 1,
+                None => {}
+            }
+        }
+        Ok(Self {
+            ignored,
+            default_group,
+            groups,
+        })
+    }
+
+    /// The triangles given before any `g` statement.
+    pub fn default_group(&self) -> &Group {
+        &self.default_group
+    }
+
+    /// The triangles under `g name`.
+    pub fn group(&self, name: &str) -> Option<&Group> {
+        self.groups.iter().find(|(n, _)| n == name).map(|(_, g)| g)
+    }
+
+    /// The whole file as one group: the default group's triangles with each
+    /// named group as a child.
+    pub fn into_group(self) -> Group {
+        let mut group = self.default_group;
+        for (_, child) in self.groups {
+            group.add_child(child);
+        }
+        group
+    }
+}
+
+/// Exactly three numbers.
+fn numbers<'a>(mut words: impl Iterator<Item = &'a str>) -> Option<[f32; 3]> {
+    let mut next = || words.next()?.parse().ok();
+    let xyz = [next()?, next()?, next()?];
+    words.next().is_none().then_some(xyz)
+}
+
+/// The positions and, where given, normals of a face's `v`, `v/vt` or
+/// `v/vt/vn` vertices.
+fn face<'a>(
+    words: impl Iterator<Item = &'a str>,
+    vertices: &[Vert4],
+    normals: &[Vert4],
+) -> Option<Vec<(Vert4, Option<Vert4>)>> {
+    let face = words
+        .map(|word| {
+            let mut parts = word.split('/');
+            let vertex = *lookup(vertices, parts.next()?)?;
+            let normal = match parts.nth(1) {
+                Some(index) if !index.is_empty() => Some(*lookup(normals, index)?),
+                _ => None,
+            };
+            Some((vertex, normal))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (face.len() >= 3).then_some(face)
+}
+
+/// The element at a 1-based `index`, or counting back from the end if it's
+/// negative.
+fn lookup<'a>(items: &'a [Vert4], index: &str) -> Option<&'a Vert4> {
+    let index: isize = index.parse().ok()?;
+    let i = match index {
+        1.. => index as usize - 1,
+        ..0 => items.len().checked_sub(index.unsigned_abs())?,
+        0 => return None,
+    };
+    items.get(i)
+}
+
+fn add_fan(group: &mut Group, face: &[(Vert4, Option<Vert4>)]) {
+    let (p1, n1) = face[0];
+    for pair in face[1..].windows(2) {
+        let [(p2, n2), (p3, n3)] = [pair[0], pair[1]];
+        match (n1, n2, n3) {
+            (Some(n1), Some(n2), Some(n3)) => {
+                group.add_child(SmoothTriangle::new(p1, p2, p3, n1, n2, n3))
+            }
+            _ => group.add_child(Triangle::new(p1, p2, p3)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use super::*;
+    use crate::import::UpAxis;
+    use crate::math::Matrix4;
+    use crate::shape::Shape;
+
+    fn parse(source: &str) -> ObjFile {
+        ObjFile::parse(source, &ImportOptions::default()).unwrap()
+    }
+
+    fn triangle(shape: &dyn Shape) -> &Triangle {
+        (shape as &dyn Any).downcast_ref().unwrap()
+    }
+
+    #[test]
+    fn unknown_statements_are_ignored() {
+        let obj = parse("mtllib scene.mtl\nvt 0 1\n\nusemtl red\n");
+        assert_eq!(obj.ignored, 3);
+        assert!(obj.default_group().is_empty());
+    }
+
+    #[test]
+    fn faces_are_split_into_fans() {
+        let obj = parse(
+            "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\n\
+             f 1 2 3\nf 1 2 3 4 5\n",
+        );
+        let children = obj.default_group().children();
+        assert_eq!(children.len(), 4);
+        let last = triangle(children[3].as_ref());
+        assert_eq!(last.p1, Vert4::point(-1.0, 1.0, 0.0));
+        assert_eq!(last.p2, Vert4::point(1.0, 1.0, 0.0));
+        assert_eq!(last.p3, Vert4::point(0.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn named_groups_become_children() {
+        let obj = parse(
+            "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+             g First\nf 1 2 3\ng Second\nf 1 3 4\n",
+        );
+        assert_eq!(obj.group("First").unwrap().children().len(), 1);
+        assert_eq!(obj.group("Second").unwrap().children().len(), 1);
+        assert!(obj.group("Third").is_none());
+        assert_eq!(obj.into_group().children().len(), 2);
+    }
+
+    #[test]
+    fn faces_with_normals_are_smooth() {
+        let obj = parse(
+            "v 0 1 0\nv -1 0 0\nv 1 0 0\nvn -1 0 0\nvn 1 0 0\nvn 0 1 0\n\
+             f 1//3 2//1 3//2\nf -3/0/-1 -2/0/-3 -1/0/-2\nf 1 2 3\n",
+        );
+        let children = obj.default_group().children();
+        for smooth in &children[..2] {
+            let smooth: &SmoothTriangle = (smooth.as_ref() as &dyn Any).downcast_ref().unwrap();
+            assert_eq!(smooth.n1, Vert4::vector(0.0, 1.0, 0.0));
+            assert_eq!(smooth.n3, Vert4::vector(1.0, 0.0, 0.0));
+        }
+        triangle(children[2].as_ref());
+    }
+
+    #[test]
+    fn malformed_lines_are_reported() {
+        let bad = |source| ObjFile::parse(source, &ImportOptions::default()).unwrap_err();
+        assert_eq!(bad("v 1 2\n"), ObjError::BadVertex { line: 1 });
+        assert_eq!(
+            bad("v 1 2 3\n\nvn x 0 0\n"),
+            ObjError::BadVertex { line: 3 }
+        );
+        assert_eq!(
+            bad("v 0 0 0\nv 1 0 0\nf 1 2\n"),
+            ObjError::BadFace { line: 3 }
+        );
+        assert_eq!(
+            bad("v 0 0 0\nv 1 0 0\nf 1 2 3\n"),
+            ObjError::BadFace { line: 3 }
+        );
+        assert_eq!(bad("v 0 0 0\nf 0 1 1\n"), ObjError::BadFace { line: 2 });
+    }
+
+    #[test]
+    fn the_conversion_is_baked_into_the_vertices() {
+        let options = ImportOptions::default()
+            .with_up_axis(UpAxis::Z)
+            .with_unit_scale(0.5);
+        let obj = ObjFile::parse("v 0 0 2\nv 2 0 0\nv 0 2 0\nf 1 2 3\n", &options).unwrap();
+        let group = obj.into_group();
+        assert_eq!(group.local_transform(), &Matrix4::IDENTITY);
+        let t = triangle(group.children()[0].as_ref());
+        assert_approx_eq!(t.p1, Vert4::point(0.0, 1.0, 0.0));
+        assert_approx_eq!(t.p2, Vert4::point(1.0, 0.0, 0.0));
+        assert_approx_eq!(t.p3, Vert4::point(0.0, 0.0, -1.0));
+    }
+}
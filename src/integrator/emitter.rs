@@ -0,0 +1,150 @@
+//! Emissive objects as lights that can be aimed at.
+
+use std::f32::consts::PI;
+use std::ptr;
+
+use super::direction_around;
+use crate::color::Color;
+use crate::math::random::Rng;
+use crate::math::Vert4;
+use crate::shape::Shape;
+use crate::world::World;
+
+/// A top-level emissive object, sampled through the cone of directions
+/// around its bounding sphere. The cone covers the whole object whatever its
+/// shape, so directions that miss it just carry no light.
+#[derive(Debug, Clone, Copy)]
+pub struct Emitter<'a> {
+    pub(super) object: &'a dyn Shape,
+    center: Vert4,
+    radius: f32,
+}
+
+impl<'a> Emitter<'a> {
+    /// Every top-level object in `world` that glows and has finite bounds.
+    /// Emissive objects inside groups are still found by chance, just not
+    /// aimed at.
+    pub(super) fn collect(world: &'a World) -> Vec<Self> {
+        world
+            .objects
+            .iter()
+            .filter(|object| object.material().emission != Color::BLACK)
+            .filter_map(|object| Self::around(object.as_ref()))
+            .collect()
+    }
+
+    /// Aims at any `object`, glowing or not, as the photon pass does at
+    /// glass. `None` if its bounds are empty or infinite.
+    pub(super) fn around(object: &'a dyn Shape) -> Option<Self> {
+        let bounds = object.bounds();
+        if bounds.is_empty() || !bounds.is_finite() {
+            return None;
+        }
+        let center = bounds.min + (bounds.max - bounds.min) / 2.0;
+        Some(Self {
+            object,
+            center,
+            radius: (bounds.max - center).magnitude(),
+        })
+    }
+
+    pub(super) fn is(&self, object: &dyn Shape) -> bool {
+        ptr::addr_eq(self.object, object)
+    }
+
+    /// A random unit direction from `origin` towards the emitter, and its
+    /// probability density over solid angle.
+    pub(super) fn sample(&self, origin: Vert4, rng: &mut Rng) -> (Vert4, f32) {
+        let phi = 2.0 * PI * rng.next_f32();
+        match self.cone(origin) {
+            Some((axis, _, one_minus_cos_max)) => {
+                let cos_theta = 1.0 - rng.next_f32() * one_minus_cos_max;
+                (
+                    direction_around(axis, cos_theta, phi),
+                    1.0 / (2.0 * PI * one_minus_cos_max),
+                )
+            }
+            // Inside the bounding sphere, so aim anywhere.
+            None => {
+                let cos_theta = 1.0 - 2.0 * rng.next_f32();
+                (
+                    direction_around(Vert4::vector(0.0, 1.0, 0.0), cos_theta, phi),
+                    1.0 / (4.0 * PI),
+                )
+            }
+        }
+    }
+
+    /// The density [`sample`](Self::sample) gives the unit vector `direction`
+    /// from `origin`.
+    pub(super) fn pdf(&self, origin: Vert4, direction: Vert4) -> f32 {
+        match self.cone(origin) {
+            Some((axis, cos_max, one_minus_cos_max)) => {
+                if direction.dot(&axis) >= cos_max {
+                    1.0 / (2.0 * PI * one_minus_cos_max)
+                } else {
+                    0.0
+                }
+            }
+            None => 1.0 / (4.0 * PI),
+        }
+    }
+
+    /// The axis of the cone around the bounding sphere seen from `origin`,
+    /// the cosine of its half angle, and one minus that, computed without
+    /// cancellation for distant emitters. `None` from inside the sphere.
+    fn cone(&self, origin: Vert4) -> Option<(Vert4, f32, f32)> {
+        let to_center = self.center - origin;
+        let distance2 = to_center.dot(&to_center);
+        let radius2 = self.radius * self.radius;
+        if distance2 <= radius2 {
+            return None;
+        }
+        let sin2_max = radius2 / distance2;
+        let cos_max = (1.0 - sin2_max).sqrt();
+        Some((
+            to_center / distance2.sqrt(),
+            cos_max,
+            sin2_max / (1.0 + cos_max),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Material;
+    use crate::math::Matrix4;
+    use crate::shape::Sphere;
+
+    fn lamp() -> Sphere {
+        Sphere::new()
+            .with_transform(Matrix4::translation(0.0, 0.0, 10.0))
+            .with_material(Material {
+                emission: Color::WHITE,
+                ..Material::default()
+            })
+    }
+
+    #[test]
+    fn only_glowing_objects_are_emitters() {
+        let w = World::new().with_object(Sphere::new()).with_object(lamp());
+        let emitters = Emitter::collect(&w);
+        assert_eq!(emitters.len(), 1);
+        assert!(emitters[0].is(w.objects[1].as_ref()));
+    }
+
+    #[test]
+    fn samples_fall_in_the_cone_with_matching_density() {
+        let w = World::new().with_object(lamp());
+        let emitter = Emitter::collect(&w)[0];
+        let origin = Vert4::point(0.0, 0.0, 0.0);
+        let mut rng = Rng::new(9);
+        for _ in 0..100 {
+            let (direction, pdf) = emitter.sample(origin, &mut rng);
+            assert_approx_eq!(direction.magnitude(), 1.0);
+            assert_approx_eq!(emitter.pdf(origin, direction), pdf);
+        }
+        assert_eq!(emitter.pdf(origin, Vert4::vector(0.0, 0.0, -1.0)), 0.0);
+    }
+}
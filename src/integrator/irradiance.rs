@@ -0,0 +1,240 @@
+//! Irradiance caching, for cheap indirect diffuse light.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+use crate::color::Color;
+use crate::math::Vert4;
+
+/// Indirect diffuse light sampled at scattered points and interpolated
+/// between them (Ward's irradiance caching), in place of the flat ambient
+/// term; see [`World::with_irradiance_cache`](crate::world::World::with_irradiance_cache).
+///
+/// Indirect light changes slowly across a surface except near other
+/// geometry, so each sample is reused over a distance proportional to how far
+/// away the surfaces it saw were. Samples are only taken where no nearby one
+/// can be reused, so most shading points cost a lookup instead of `samples`
+/// rays.
+///
+/// Left to itself, the cache fills as shading asks for light, so on several
+/// threads which samples get reused would depend on which thread got where
+/// first. A [`Camera`](crate::camera::Camera) avoids that by filling it on
+/// one thread before rendering, then [freezing](Self::set_frozen) it for
+/// the render proper.
+pub struct IrradianceCache {
+    /// Rays cast over the hemisphere for each new sample.
+    pub samples: u32,
+    /// How much error to allow interpolating: the larger, the further
+    /// samples are reused and the fewer are taken.
+    pub accuracy: f32,
+    /// The least a sample is reused over, so corners don't need endless
+    /// samples.
+    pub min_spacing: f32,
+    /// The most a sample is reused over, even in open space.
+    pub max_spacing: f32,
+    records: RwLock<Records>,
+    frozen: AtomicBool,
+}
+
+/// One sample of the light arriving at a surface.
+#[derive(Debug, Clone, Copy)]
+struct Record {
+    point: Vert4,
+    normal: Vert4,
+    /// The average light seen over the hemisphere, cosine weighted.
+    light: Color,
+    /// The harmonic mean distance to the surfaces seen, clamped to the
+    /// spacing limits.
+    radius: f32,
+}
+
+/// The records, bucketed by the grid cell they sit in.
+#[derive(Debug, Default)]
+struct Records {
+    all: Vec<Record>,
+    cells: HashMap<[i32; 3], Vec<usize>>,
+}
+
+impl IrradianceCache {
+    pub fn new(samples: u32, accuracy: f32) -> Self {
+        Self {
+            samples,
+            accuracy,
+            min_spacing: 0.1,
+            max_spacing: 2.0,
+            records: RwLock::default(),
+            frozen: AtomicBool::new(false),
+        }
+    }
+
+    pub fn with_spacing(mut self, min_spacing: f32, max_spacing: f32) -> Self {
+        self.min_spacing = min_spacing;
+        self.max_spacing = max_spacing;
+        self
+    }
+
+    /// How many samples have been taken.
+    pub fn len(&self) -> usize {
+        self.records.read().unwrap().all.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// While frozen, lookups that find no sample to reuse take one as usual
+    /// but don't keep it, so every lookup sees the same samples whatever
+    /// order they're made in.
+    pub fn set_frozen(&self, frozen: bool) {
+        self.frozen.store(frozen, Ordering::Relaxed);
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.load(Ordering::Relaxed)
+    }
+
+    /// Forgets every sample, as needed after changing the scene.
+    pub fn clear(&self) {
+        *self.records.write().unwrap() = Records::default();
+    }
+
+    /// The average light arriving at `point` on a surface facing `normal`,
+    /// interpolated from the samples nearby. Where there aren't any,
+    /// `sample` takes a new one, returning the light and the harmonic mean
+    /// distance to what it saw, which is kept unless the cache is frozen.
+    pub fn light_at(
+        &self,
+        point: Vert4,
+        normal: Vert4,
+        sample: impl FnOnce() -> (Color, f32),
+    ) -> Color {
+        if let Some(light) = self.interpolate(point, normal) {
+            return light;
+        }
+        let (light, distance) = sample();
+        if self.is_frozen() {
+            return light;
+        }
+        let record = Record {
+            point,
+            normal,
+            light,
+            radius: distance.clamp(self.min_spacing, self.max_spacing),
+        };
+        let cell = self.cell(point);
+        let mut records = self.records.write().unwrap();
+        let index = records.all.len();
+        records.all.push(record);
+        records.cells.entry(cell).or_default().push(index);
+        light
+    }
+
+    /// The weighted average of the reusable records, if there are any.
+    fn interpolate(&self, point: Vert4, normal: Vert4) -> Option<Color> {
+        let records = self.records.read().unwrap();
+        let [x, y, z] = self.cell(point);
+        let mut total = Color::BLACK;
+        let mut total_weight = 0.0;
+        for cell in (-1..=1)
+            .flat_map(|i| (-1..=1).flat_map(move |j| (-1..=1).map(move |k| [x + i, y + j, z + k])))
+        {
+            for &index in records.cells.get(&cell).into_iter().flatten() {
+                let record = &records.all[index];
+                if let Some(weight) = self.weight(record, point, normal) {
+                    total += record.light * weight;
+                    total_weight += weight;
+                }
+            }
+        }
+        (total_weight > 0.0).then(|| total / total_weight)
+    }
+
+    /// Ward's weight for reusing `record` at `point`, or `None` if its error
+    /// would be too large, or `point` is in front of it and may see things
+    /// it couldn't.
+    fn weight(&self, record: &Record, point: Vert4, normal: Vert4) -> Option<f32> {
+        let offset = point - record.point;
+        if offset.dot(&(normal + record.normal)) < -0.1 * record.radius {
+            return None;
+        }
+        let error =
+            offset.magnitude() / record.radius + (1.0 - normal.dot(&record.normal)).max(0.0).sqrt();
+        let weight = 1.0 / error.max(1e-6);
+        (weight > 1.0 / self.accuracy).then_some(weight)
+    }
+
+    /// The grid cell holding `point`, sized so every record that can be
+    /// reused at a point is in its cell or a neighbouring one.
+    fn cell(&self, point: Vert4) -> [i32; 3] {
+        let size = self.accuracy * self.max_spacing;
+        [point.x, point.y, point.z].map(|c| (c / size).floor() as i32)
+    }
+}
+
+impl fmt::Debug for IrradianceCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IrradianceCache")
+            .field("samples", &self.samples)
+            .field("accuracy", &self.accuracy)
+            .field("min_spacing", &self.min_spacing)
+            .field("max_spacing", &self.max_spacing)
+            .field("records", &self.len())
+            .field("frozen", &self.is_frozen())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearby_points_reuse_a_sample() {
+        let cache = IrradianceCache::new(16, 0.5);
+        let up = Vert4::vector(0.0, 1.0, 0.0);
+        let light = cache.light_at(Vert4::point(0.0, 0.0, 0.0), up, || (Color::WHITE, 1.0));
+        assert_eq!(light, Color::WHITE);
+        let reused = cache.light_at(Vert4::point(0.1, 0.0, 0.0), up, || unreachable!());
+        assert_eq!(reused, Color::WHITE);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn distant_or_turned_points_take_their_own_sample() {
+        let cache = IrradianceCache::new(16, 0.5);
+        let up = Vert4::vector(0.0, 1.0, 0.0);
+        cache.light_at(Vert4::point(0.0, 0.0, 0.0), up, || (Color::WHITE, 1.0));
+        let far = cache.light_at(Vert4::point(3.0, 0.0, 0.0), up, || (Color::BLACK, 1.0));
+        assert_eq!(far, Color::BLACK);
+        let side = Vert4::vector(1.0, 0.0, 0.0);
+        cache.light_at(Vert4::point(0.0, 0.0, 0.0), side, || (Color::BLACK, 1.0));
+        assert_eq!(cache.len(), 3);
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn frozen_cache_keeps_no_new_samples() {
+        let cache = IrradianceCache::new(16, 0.5);
+        let up = Vert4::vector(0.0, 1.0, 0.0);
+        cache.set_frozen(true);
+        let light = cache.light_at(Vert4::point(0.0, 0.0, 0.0), up, || (Color::WHITE, 1.0));
+        assert_eq!(light, Color::WHITE);
+        assert!(cache.is_empty());
+        cache.set_frozen(false);
+        cache.light_at(Vert4::point(0.0, 0.0, 0.0), up, || (Color::WHITE, 1.0));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn interpolation_blends_samples() {
+        let cache = IrradianceCache::new(16, 1.0);
+        let up = Vert4::vector(0.0, 1.0, 0.0);
+        cache.light_at(Vert4::point(-0.5, 0.0, 0.0), up, || (Color::WHITE, 1.0));
+        cache.light_at(Vert4::point(0.5, 0.0, 0.0), up, || (Color::BLACK, 1.0));
+        let middle = cache.light_at(Vert4::point(0.0, 0.0, 0.0), up, || unreachable!());
+        assert_approx_eq!(middle, Color::WHITE * 0.5);
+    }
+}
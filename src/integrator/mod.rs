@@ -0,0 +1,132 @@
+//! How the light arriving along a camera ray is worked out.
+
+mod emitter;
+mod irradiance;
+mod path;
+mod photon;
+
+pub use emitter::Emitter;
+pub use irradiance::IrradianceCache;
+pub use path::PathTracer;
+pub use photon::{Caustics, PhotonMap};
+
+use crate::color::Color;
+use crate::math::random::Rng;
+use crate::math::Vert4;
+use crate::ray::Ray;
+use crate::shape::RayKind;
+use crate::world::World;
+
+/// The algorithm a [`Camera`](crate::camera::Camera) renders with.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Integrator {
+    /// Whitted-style recursive ray tracing through
+    /// [`World::color_at`]: direct light from each light with an ambient
+    /// stand-in for the rest, plus mirror reflection and refraction.
+    /// Noise-free, and the randomness is unused.
+    #[default]
+    Whitted,
+    /// Monte Carlo path tracing, for global illumination; see [`PathTracer`].
+    PathTracing(PathTracer),
+}
+
+impl Integrator {
+    /// The emissive objects in `world` the integrator aims at, collected
+    /// once before a render and passed to every [`radiance`](Self::radiance)
+    /// call.
+    pub fn emitters<'a>(&self, world: &'a World) -> Vec<Emitter<'a>> {
+        match self {
+            Integrator::Whitted => Vec::new(),
+            Integrator::PathTracing(tracer) => tracer.emitters(world),
+        }
+    }
+
+    /// The light seen along `ray`, aiming at `emitters` from
+    /// [`emitters`](Self::emitters) and drawing any randomness from `rng`.
+    pub fn radiance(
+        &self,
+        world: &World,
+        emitters: &[Emitter],
+        ray: &Ray,
+        settings: &RenderSettings,
+        rng: &mut Rng,
+    ) -> Color {
+        match self {
+            Integrator::Whitted => world.color_seen(ray, settings.camera_rays()),
+            Integrator::PathTracing(tracer) => tracer.radiance(world, emitters, ray, settings, rng),
+        }
+    }
+}
+
+/// Settings for a whole render that the integrators read, whichever is used.
+/// They're kept on the [`Camera`](crate::camera::Camera) with the rest of the
+/// render's setup, so swapping integrators keeps them.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RenderSettings {
+    /// The brightest any one random sample may be, in its brightest channel;
+    /// brighter samples are dimmed to it, keeping their hue. Rare paths that
+    /// stumble on a small, bright light otherwise leave isolated white pixels
+    /// (fireflies) that take many samples to average away. Clamping removes
+    /// them at the cost of some energy, so it's off when unset.
+    ///
+    /// Whitted ray tracing takes no random samples, so only path tracing
+    /// clamps.
+    pub max_sample_value: Option<f32>,
+    /// The render layer, indexing [`World::layers`], whose objects alone
+    /// camera rays see. The rest still cast shadows and show in
+    /// reflections. Every object is seen when unset.
+    pub layer: Option<usize>,
+}
+
+impl RenderSettings {
+    /// Clamps every sample to `max_sample_value`; see
+    /// [`max_sample_value`](Self::max_sample_value).
+    pub const fn with_max_sample_value(mut self, max_sample_value: f32) -> Self {
+        self.max_sample_value = Some(max_sample_value);
+        self
+    }
+
+    /// Renders only the objects in `layer`; see [`layer`](Self::layer).
+    pub const fn with_layer(mut self, layer: usize) -> Self {
+        self.layer = Some(layer);
+        self
+    }
+
+    /// What camera rays may strike.
+    pub(crate) fn camera_rays(&self) -> RayKind {
+        RayKind::Camera(self.layer)
+    }
+
+    /// `sample` dimmed to [`max_sample_value`](Self::max_sample_value).
+    fn clamp(&self, sample: Color) -> Color {
+        match self.max_sample_value {
+            Some(max) if strength(sample) > max => sample * (max / strength(sample)),
+            _ => sample,
+        }
+    }
+}
+
+impl From<PathTracer> for Integrator {
+    fn from(tracer: PathTracer) -> Self {
+        Integrator::PathTracing(tracer)
+    }
+}
+
+/// The unit vector at angle θ from the unit vector `axis`, given `cos_theta`,
+/// and turned `phi` around it.
+fn direction_around(axis: Vert4, cos_theta: f32, phi: f32) -> Vert4 {
+    let helper = if axis.x.abs() > 0.9 {
+        Vert4::vector(0.0, 1.0, 0.0)
+    } else {
+        Vert4::vector(1.0, 0.0, 0.0)
+    };
+    let tangent = helper.cross(&axis).normalize();
+    let bitangent = axis.cross(&tangent);
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + axis * cos_theta
+}
+
+/// How strongly a lobe of this color scatters, for choosing between them.
+fn strength(c: Color) -> f32 {
+    c.r.max(c.g).max(c.b).max(0.0)
+}
@@ -0,0 +1,660 @@
+//! Unbiased Monte Carlo path tracing.
+
+use std::f32::consts::PI;
+
+use crate::color::Color;
+use crate::intersection::{Computations, Intersections};
+use crate::light::{direct_lighting, surface_color};
+use crate::material::{Material, SpecularModel};
+use crate::math::random::Rng;
+use crate::math::Vert4;
+use crate::ray::Ray;
+use crate::shape::RayKind;
+use crate::world::World;
+
+use super::emitter::Emitter;
+use super::{direction_around, strength, RenderSettings};
+
+/// Follows random paths from the camera, bouncing each off surfaces in a
+/// direction sampled from their material, so light reaches surfaces
+/// indirectly: colors bleed onto neighbours, and emissive objects light the
+/// scene.
+///
+/// Point and directional lights can't be hit by chance, so every bounce also
+/// adds their light directly, with the diffuse and specular terms of
+/// [`lighting`](crate::light::lighting). Ambient light only stands in for the
+/// indirect light a path tracer finds for itself, so it's ignored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathTracer {
+    /// Paths traced, and averaged, for every camera ray.
+    pub samples_per_pixel: usize,
+    /// How many times a path may scatter before it's cut off.
+    pub max_bounces: u32,
+    /// After this many bounces, paths are ended at random, more likely the
+    /// less light they still carry, and the survivors are brightened to make
+    /// up for the rest. This stops dim paths early without darkening the
+    /// image, so [`max_bounces`](Self::max_bounces) can be raised to a
+    /// safety net. Off when unset.
+    pub russian_roulette: Option<u32>,
+    /// Whether every bounce also aims a shadow ray at a random emissive
+    /// object, so small bright ones are found in far fewer samples than by
+    /// bouncing into them by chance. Both ways of finding light are kept and
+    /// weighted by how likely each was (multiple importance sampling), so
+    /// neither large nor small emitters are noisy. On by default.
+    ///
+    /// Only top-level objects are aimed at; see [`World::objects`].
+    pub light_sampling: bool,
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        Self::new(16, 8)
+    }
+}
+
+impl PathTracer {
+    pub const fn new(samples_per_pixel: usize, max_bounces: u32) -> Self {
+        Self {
+            samples_per_pixel,
+            max_bounces,
+            russian_roulette: None,
+            light_sampling: true,
+        }
+    }
+
+    /// Plays [`russian_roulette`](Self::russian_roulette) with paths that
+    /// have bounced more than `after` times.
+    pub const fn with_russian_roulette(mut self, after: u32) -> Self {
+        self.russian_roulette = Some(after);
+        self
+    }
+
+    /// Aims a shadow ray at a random emissive object from every bounce, as
+    /// well as bouncing at random; see
+    /// [`light_sampling`](Self::light_sampling).
+    pub const fn with_light_sampling(mut self, light_sampling: bool) -> Self {
+        self.light_sampling = light_sampling;
+        self
+    }
+
+    /// The emissive objects in `world` to aim shadow rays at, if
+    /// [`light_sampling`](Self::light_sampling) is on. They're the same for
+    /// every ray, so they're collected once per render.
+    pub fn emitters<'a>(&self, world: &'a World) -> Vec<Emitter<'a>> {
+        if self.light_sampling {
+            Emitter::collect(world)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// The average light carried back along `ray` by
+    /// [`samples_per_pixel`](Self::samples_per_pixel) paths, aiming at
+    /// `emitters` from [`emitters`](Self::emitters), each clamped to the
+    /// `settings`' [`max_sample_value`](RenderSettings::max_sample_value).
+    pub fn radiance(
+        &self,
+        world: &World,
+        emitters: &[Emitter],
+        ray: &Ray,
+        settings: &RenderSettings,
+        rng: &mut Rng,
+    ) -> Color {
+        if self.samples_per_pixel == 0 {
+            return Color::BLACK;
+        }
+        let total: Color = (0..self.samples_per_pixel)
+            .map(|_| settings.clamp(self.trace(world, emitters, *ray, settings, rng)))
+            .sum();
+        total / self.samples_per_pixel as f32
+    }
+
+    /// The light carried back along one random path starting with `ray`.
+    fn trace(
+        &self,
+        world: &World,
+        emitters: &[Emitter],
+        mut ray: Ray,
+        settings: &RenderSettings,
+        rng: &mut Rng,
+    ) -> Color {
+        let mut radiance = Color::BLACK;
+        // What the ray may strike: the camera's view until the first bounce.
+        let mut kind = settings.camera_rays();
+        // How much of the light arriving at the current hit reaches the eye.
+        let mut throughput = Color::WHITE;
+        // Where the last bounce left from and the density of its direction,
+        // unless it was a mirror or refraction, which emitters can't be aimed
+        // along.
+        let mut last_bounce: Option<(Vert4, f32)> = None;
+        // One buffer serves every bounce of the path.
+        let mut xs = Intersections::default();
+        for bounce in 0..=self.max_bounces {
+            xs.clear();
+            world.intersect_into(&ray, &mut xs);
+            xs.retain(|x| x.shading_object().visibility().allows(kind));
+            let Some(hit) = xs.hit() else {
+                radiance += throughput * world.background_color(&ray);
+                break;
+            };
+            let comps = hit.prepare_computations_with_offset(&ray, &xs, world.surface_offset);
+            let material = comps.material;
+            if material.shadow_catcher {
+                // Carry on through it, as World::shade_hit looks behind it.
+                throughput = throughput * (1.0 - world.shadow_strength(&comps));
+                ray = Ray::new(comps.under_point, ray.direction);
+                continue;
+            }
+            let emission_weight = match last_bounce {
+                Some((origin, bsdf_pdf)) => emitter_weight(emitters, &comps, origin, ray.direction)
+                    .map_or(1.0, |light_pdf| balance(bsdf_pdf, light_pdf)),
+                None => 1.0,
+            };
+            radiance +=
+                throughput * (material.emission * emission_weight + direct_light(world, &comps));
+            if bounce == self.max_bounces {
+                break;
+            }
+            let bsdf = Bsdf::new(&comps);
+            radiance += throughput * sample_emitter(world, emitters, &bsdf, rng);
+            let Some(bounce_sample) = bsdf.sample(rng) else {
+                break;
+            };
+            throughput = throughput * bounce_sample.weight;
+            if self.russian_roulette.is_some_and(|after| bounce >= after) {
+                // Never certain to survive, so even bright loops end.
+                let survival = throughput.r.max(throughput.g).max(throughput.b).min(0.95);
+                if rng.next_f32() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+            last_bounce = bounce_sample.pdf.map(|pdf| (bounce_sample.ray.origin, pdf));
+            ray = bounce_sample.ray;
+            kind = RayKind::Reflection;
+        }
+        radiance
+    }
+}
+
+/// The balance heuristic's weight for a sample drawn with density `pdf`,
+/// which another strategy could have drawn with density `other`.
+fn balance(pdf: f32, other: f32) -> f32 {
+    pdf / (pdf + other)
+}
+
+/// The density with which the hit in `comps`, reached from `origin` along
+/// `direction`, would have been sampled by aiming at an emitter, or `None` if
+/// its object isn't one.
+fn emitter_weight(
+    emitters: &[Emitter],
+    comps: &Computations,
+    origin: Vert4,
+    direction: Vert4,
+) -> Option<f32> {
+    let emitter = emitters.iter().find(|e| e.is(comps.object))?;
+    Some(emitter.pdf(origin, direction.normalize()) / emitters.len() as f32)
+}
+
+/// Light from one randomly chosen emitter reflected towards the eye, weighted
+/// against the chance of the bounce finding it.
+fn sample_emitter(world: &World, emitters: &[Emitter], bsdf: &Bsdf, rng: &mut Rng) -> Color {
+    if emitters.is_empty() || !bsdf.scatters() {
+        return Color::BLACK;
+    }
+    let comps = bsdf.comps;
+    let pick = ((rng.next_f32() * emitters.len() as f32) as usize).min(emitters.len() - 1);
+    let emitter = &emitters[pick];
+    let (direction, pdf) = emitter.sample(comps.over_point, rng);
+    let light_pdf = pdf / emitters.len() as f32;
+    let reflectance = bsdf.eval(direction);
+    if light_pdf <= 0.0 || reflectance == Color::BLACK {
+        return Color::BLACK;
+    }
+    let ray = Ray::new(comps.over_point, direction);
+    let xs = world.intersect_visible(&ray, RayKind::Shadow);
+    match xs.hit() {
+        Some(hit) if emitter.is(hit.shading_object()) => {
+            let emission = hit.material().emission;
+            let weight = balance(light_pdf, bsdf.pdf(direction));
+            reflectance * emission * (weight / light_pdf)
+        }
+        _ => Color::BLACK,
+    }
+}
+
+/// The light from every point and directional light reflected towards the eye.
+fn direct_light(world: &World, comps: &Computations) -> Color {
+    let material = comps.material;
+    world
+        .lights
+        .iter()
+        .map(|light| {
+            let fraction = world.light_fraction(comps.over_point, light);
+            if fraction <= 0.0 {
+                return Color::BLACK;
+            }
+            direct_lighting(
+                material,
+                comps.object,
+                light,
+                comps.over_point,
+                comps.eyev,
+                comps.normalv,
+            ) * fraction
+        })
+        .sum()
+}
+
+/// The ways a path can leave a surface.
+#[derive(Debug, Clone, Copy)]
+enum Lobe {
+    Diffuse,
+    /// A blurred reflection, the counterpart of the specular highlight, with
+    /// the given Phong exponent.
+    Glossy(f32),
+    Mirror,
+    Transmit,
+}
+
+/// A bounce chosen by [`Bsdf::sample`].
+struct BounceSample {
+    ray: Ray,
+    /// The color the light brought back along `ray` is scaled by.
+    weight: Color,
+    /// The density of the direction, or `None` for mirror reflection and
+    /// refraction, which only ever go one way.
+    pdf: Option<f32>,
+}
+
+/// How the surface at a hit scatters light: its lobes, each with the
+/// fraction of each channel it carries, matching the terms
+/// [`World::shade_hit`] adds up.
+struct Bsdf<'c, 'a> {
+    comps: &'c Computations<'a>,
+    lobes: [(Lobe, Color); 4],
+    total: f32,
+}
+
+impl<'c, 'a> Bsdf<'c, 'a> {
+    fn new(comps: &'c Computations<'a>) -> Self {
+        let material = comps.material;
+        let color = surface_color(material, comps.object, comps.point);
+        let (diffuse, glossy) = match material.specular_model {
+            SpecularModel::Phong => {
+                // The energy under the Phong highlight, cos^n integrated over
+                // the hemisphere, relative to the diffuse term.
+                let n = material.shininess;
+                (
+                    color * material.diffuse,
+                    (Color::WHITE * (2.0 * material.specular / (n + 1.0)), n),
+                )
+            }
+            SpecularModel::Ggx {
+                roughness,
+                metalness,
+            } => {
+                // The Phong exponent with roughly the same lobe width.
+                let alpha = roughness.clamp(0.01, 1.0).powi(2);
+                let n = 2.0 / (alpha * alpha) - 2.0;
+                (
+                    color * material.diffuse * (1.0 - metalness),
+                    (
+                        Material::base_reflectance(color, metalness) * material.specular,
+                        n,
+                    ),
+                )
+            }
+        };
+        let cos = comps.eyev.dot(&comps.normalv);
+        let (reflected, refracted) = if material.reflective > 0.0 && material.transparency > 0.0 {
+            let reflectance = comps.schlick();
+            (reflectance, 1.0 - reflectance)
+        } else {
+            (1.0, 1.0)
+        };
+        let lobes = [
+            (Lobe::Diffuse, diffuse),
+            (Lobe::Glossy(glossy.1), glossy.0),
+            (
+                Lobe::Mirror,
+                material.reflection_tint(color, cos) * (material.reflective * reflected),
+            ),
+            (
+                Lobe::Transmit,
+                Color::WHITE * (material.transparency * refracted),
+            ),
+        ];
+        let total = lobes.iter().map(|(_, weight)| strength(*weight)).sum();
+        Self {
+            comps,
+            lobes,
+            total,
+        }
+    }
+
+    /// Whether any light is scattered over a spread of directions, rather
+    /// than only mirrored or refracted.
+    fn scatters(&self) -> bool {
+        self.lobes.iter().any(|(lobe, weight)| {
+            matches!(lobe, Lobe::Diffuse | Lobe::Glossy(_)) && strength(*weight) > 0.0
+        })
+    }
+
+    /// A direction to continue the path in, picking one of the lobes at
+    /// random. `None` if the path is absorbed.
+    fn sample(&self, rng: &mut Rng) -> Option<BounceSample> {
+        if self.total <= 0.0 {
+            return None;
+        }
+        let comps = self.comps;
+        // Pick each lobe in proportion to its strength, dividing by that
+        // probability to keep the estimate unbiased.
+        let mut candidates = self
+            .lobes
+            .iter()
+            .filter(|(_, weight)| strength(*weight) > 0.0);
+        let mut pick = rng.next_f32() * self.total;
+        let &(mut lobe, mut weight) = candidates.next()?;
+        for &(next_lobe, next_weight) in candidates {
+            pick -= strength(weight);
+            if pick < 0.0 {
+                break;
+            }
+            (lobe, weight) = (next_lobe, next_weight);
+        }
+        let weight = weight * (self.total / strength(weight));
+
+        let (origin, direction) = match lobe {
+            Lobe::Diffuse => (
+                comps.over_point,
+                sample_cosine_lobe(comps.normalv, 1.0, rng),
+            ),
+            Lobe::Glossy(exponent) => {
+                let direction = sample_cosine_lobe(comps.reflectv, exponent, rng);
+                if direction.dot(&comps.normalv) <= 0.0 {
+                    return None;
+                }
+                (comps.over_point, direction)
+            }
+            Lobe::Mirror => {
+                return Some(BounceSample {
+                    ray: Ray::new(comps.over_point, comps.reflectv),
+                    weight,
+                    pdf: None,
+                })
+            }
+            Lobe::Transmit => {
+                return Some(BounceSample {
+                    ray: Ray::new(comps.under_point, comps.refracted_direction()?),
+                    weight,
+                    pdf: None,
+                })
+            }
+        };
+        Some(BounceSample {
+            ray: Ray::new(origin, direction),
+            weight,
+            pdf: Some(self.pdf(direction)),
+        })
+    }
+
+    /// The light reflected towards the eye from the unit vector `direction`
+    /// by the diffuse and glossy lobes, per unit of light arriving, with the
+    /// cosine falloff included.
+    fn eval(&self, direction: Vert4) -> Color {
+        let cos_theta = direction.dot(&self.comps.normalv);
+        if cos_theta <= 0.0 {
+            return Color::BLACK;
+        }
+        self.lobes
+            .iter()
+            .map(|&(lobe, weight)| match lobe {
+                Lobe::Diffuse => weight * (cos_theta / PI),
+                Lobe::Glossy(n) => weight * glossy_density(self.comps.reflectv, n, direction),
+                Lobe::Mirror | Lobe::Transmit => Color::BLACK,
+            })
+            .sum()
+    }
+
+    /// The density with which [`sample`](Self::sample) picks the unit vector
+    /// `direction` through the diffuse and glossy lobes.
+    fn pdf(&self, direction: Vert4) -> f32 {
+        let cos_theta = direction.dot(&self.comps.normalv);
+        if cos_theta <= 0.0 || self.total <= 0.0 {
+            return 0.0;
+        }
+        self.lobes
+            .iter()
+            .map(|&(lobe, weight)| {
+                let chance = strength(weight) / self.total;
+                match lobe {
+                    Lobe::Diffuse => chance * cos_theta / PI,
+                    Lobe::Glossy(n) => chance * glossy_density(self.comps.reflectv, n, direction),
+                    Lobe::Mirror | Lobe::Transmit => 0.0,
+                }
+            })
+            .sum()
+    }
+}
+
+/// The density of [`sample_cosine_lobe`] around `axis` with `exponent` at the
+/// unit vector `direction`.
+fn glossy_density(axis: Vert4, exponent: f32, direction: Vert4) -> f32 {
+    let cos_alpha = direction.dot(&axis);
+    if cos_alpha <= 0.0 {
+        return 0.0;
+    }
+    (exponent + 1.0) / (2.0 * PI) * cos_alpha.powf(exponent)
+}
+
+/// A random unit vector around `axis`, distributed as cos^`exponent` of the
+/// angle to it; an exponent of 1 is Lambert's cosine-weighted hemisphere.
+fn sample_cosine_lobe(axis: Vert4, exponent: f32, rng: &mut Rng) -> Vert4 {
+    let cos_theta = rng.next_f32().powf(1.0 / (exponent + 1.0));
+    direction_around(axis, cos_theta, 2.0 * PI * rng.next_f32())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light::Light;
+    use crate::math::Matrix4;
+    use crate::shape::{Shape, Sphere};
+
+    /// `tracer`'s radiance along `ray`, aiming at the emitters it collects
+    /// from `w`.
+    fn radiance(
+        tracer: PathTracer,
+        w: &World,
+        ray: &Ray,
+        settings: &RenderSettings,
+        rng: &mut Rng,
+    ) -> Color {
+        tracer.radiance(w, &tracer.emitters(w), ray, settings, rng)
+    }
+
+    fn emitter(emission: Color) -> Material {
+        Material {
+            ambient: 0.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            emission,
+            ..Material::default()
+        }
+    }
+
+    #[test]
+    fn escaping_rays_see_the_background() {
+        let w = World::new();
+        let ray = Ray::new(Vert4::point(0.0, 0.0, 0.0), Vert4::vector(0.0, 0.0, 1.0));
+        let mut rng = Rng::new(1);
+        assert_eq!(
+            radiance(
+                PathTracer::default(),
+                &w,
+                &ray,
+                &RenderSettings::default(),
+                &mut rng
+            ),
+            Color::BLACK
+        );
+    }
+
+    #[test]
+    fn emissive_surfaces_are_seen_directly() {
+        let glow = Color::new(0.5, 1.0, 2.0);
+        let w = World::new().with_object(Sphere::new().with_material(emitter(glow)));
+        let ray = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let mut rng = Rng::new(1);
+        assert_approx_eq!(
+            radiance(
+                PathTracer::new(4, 3),
+                &w,
+                &ray,
+                &RenderSettings::default(),
+                &mut rng
+            ),
+            glow
+        );
+    }
+
+    #[test]
+    fn diffuse_surface_picks_up_light_from_emissive_surroundings() {
+        // A white diffuse sphere inside a uniformly glowing shell. Every path
+        // off it eventually reaches the shell, so with albedo a the sphere
+        // returns a + a² + … of the glow, cut off at max_bounces.
+        let shell = Sphere::new()
+            .with_transform(Matrix4::scaling(10.0, 10.0, 10.0))
+            .with_material(emitter(Color::WHITE));
+        let ball = Sphere::new().with_material(Material {
+            ambient: 0.0,
+            diffuse: 0.5,
+            specular: 0.0,
+            ..Material::default()
+        });
+        let w = World::new().with_object(shell).with_object(ball);
+        let ray = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let mut rng = Rng::new(3);
+        // Bouncing alone always finds the shell, so it's exact.
+        let tracer = PathTracer::new(256, 1).with_light_sampling(false);
+        let color = radiance(tracer, &w, &ray, &RenderSettings::default(), &mut rng);
+        // One bounce: the ball reflects half of the shell's glow.
+        assert_approx_eq!(color, Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn point_lights_are_added_directly() {
+        let w = World::new()
+            .with_light(Light::point(Vert4::point(0.0, 0.0, -10.0), Color::WHITE))
+            .with_object(Sphere::new().with_material(Material {
+                specular: 0.0,
+                ..Material::default()
+            }));
+        let ray = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let mut rng = Rng::new(1);
+        // Nothing else in the scene to bounce off, so just the diffuse term.
+        assert_approx_eq!(
+            radiance(
+                PathTracer::new(8, 4),
+                &w,
+                &ray,
+                &RenderSettings::default(),
+                &mut rng
+            ),
+            Color::new(0.9, 0.9, 0.9)
+        );
+    }
+
+    #[test]
+    fn cosine_lobe_stays_around_its_axis() {
+        let mut rng = Rng::new(5);
+        let axis = Vert4::vector(0.0, 1.0, 0.0);
+        for _ in 0..100 {
+            let d = sample_cosine_lobe(axis, 1.0, &mut rng);
+            assert_approx_eq!(d.magnitude(), 1.0);
+            assert!(d.dot(&axis) >= 0.0);
+        }
+        // A tight lobe hugs the axis.
+        let mean: f32 = (0..100)
+            .map(|_| sample_cosine_lobe(axis, 1000.0, &mut rng).dot(&axis))
+            .sum::<f32>()
+            / 100.0;
+        assert!(mean > 0.99, "{mean}");
+    }
+
+    #[test]
+    fn russian_roulette_keeps_the_average() {
+        // As in the glowing shell test, one bounce brings back half the
+        // glow. Roulette ends about half the paths there and doubles the
+        // rest, which should leave the average where it was.
+        let shell = Sphere::new()
+            .with_transform(Matrix4::scaling(10.0, 10.0, 10.0))
+            .with_material(emitter(Color::WHITE));
+        let ball = Sphere::new().with_material(Material {
+            ambient: 0.0,
+            diffuse: 0.5,
+            specular: 0.0,
+            ..Material::default()
+        });
+        let w = World::new().with_object(shell).with_object(ball);
+        let ray = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let mut rng = Rng::new(11);
+        let tracer = PathTracer::new(20_000, 1).with_russian_roulette(0);
+        let color = radiance(tracer, &w, &ray, &RenderSettings::default(), &mut rng);
+        assert!((color.r - 0.5).abs() < 0.02, "{color:?}");
+    }
+
+    #[test]
+    fn light_sampling_finds_small_emitters() {
+        // A small lamp straight above a white diffuse ball subtends a cone
+        // with sin²θ = (r / d)², so the ball reflects albedo × glow × (r / d)².
+        let lamp = Sphere::new()
+            .with_transform(Matrix4::translation(0.0, 0.0, -3.0) * Matrix4::scaling(0.1, 0.1, 0.1))
+            .with_material(emitter(Color::new(100.0, 100.0, 100.0)));
+        let ball = Sphere::new().with_material(Material {
+            ambient: 0.0,
+            diffuse: 0.5,
+            specular: 0.0,
+            ..Material::default()
+        });
+        let w = World::new().with_object(lamp).with_object(ball);
+        let ray = Ray::new(Vert4::point(0.0, 0.0, -1.5), Vert4::vector(0.0, 0.0, 1.0));
+        let mut rng = Rng::new(2);
+        let color = radiance(
+            PathTracer::new(500, 1),
+            &w,
+            &ray,
+            &RenderSettings::default(),
+            &mut rng,
+        );
+        assert!((color.r - 0.125).abs() < 0.005, "{color:?}");
+    }
+
+    #[test]
+    fn bright_paths_are_clamped_keeping_their_hue() {
+        let glow = Color::new(5.0, 10.0, 2.0);
+        let w = World::new().with_object(Sphere::new().with_material(emitter(glow)));
+        let ray = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let mut rng = Rng::new(1);
+        let tracer = PathTracer::new(4, 3);
+        let settings = RenderSettings::default().with_max_sample_value(2.0);
+        assert_approx_eq!(
+            radiance(tracer, &w, &ray, &settings, &mut rng),
+            Color::new(1.0, 2.0, 0.4)
+        );
+        let settings = RenderSettings::default().with_max_sample_value(20.0);
+        assert_approx_eq!(radiance(tracer, &w, &ray, &settings, &mut rng), glow);
+    }
+
+    #[test]
+    fn emitters_are_only_collected_for_light_sampling() {
+        let lamp = Sphere::new().with_material(emitter(Color::WHITE));
+        let w = World::new().with_object(lamp).with_object(Sphere::new());
+        let tracer = PathTracer::default();
+        let emitters = tracer.emitters(&w);
+        assert_eq!(emitters.len(), 1);
+        assert!(emitters[0].is(w.objects[0].as_ref()));
+        assert!(tracer.with_light_sampling(false).emitters(&w).is_empty());
+    }
+}
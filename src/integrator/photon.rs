@@ -0,0 +1,448 @@
+//! Photon mapping, for the caustics mirrors and glass focus onto surfaces.
+
+use std::f32::consts::PI;
+use std::fmt;
+
+use super::emitter::Emitter;
+use super::strength;
+use crate::color::Color;
+use crate::intersection::{Computations, Intersections};
+use crate::light::{surface_color, Light, PointLight};
+use crate::material::Material;
+use crate::math::random::Rng;
+use crate::math::Vert4;
+use crate::ray::Ray;
+use crate::world::World;
+
+/// Settings for rendering caustics with a [`PhotonMap`]; see
+/// [`World::with_caustics`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Caustics {
+    /// Photons shot from each point light, shared between the reflective and
+    /// transparent objects they're aimed at.
+    pub photons: usize,
+    /// How many of the nearest photons are averaged at each shading point.
+    /// More blurs the caustics but makes them less blotchy.
+    pub gather: usize,
+    /// The farthest from a shading point a photon counts.
+    pub radius: f32,
+    pub seed: u64,
+}
+
+impl Caustics {
+    pub const fn new(photons: usize, gather: usize, radius: f32) -> Self {
+        Self {
+            photons,
+            gather,
+            radius,
+            seed: 0,
+        }
+    }
+
+    pub const fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// A bundle of light that arrived at a surface.
+#[derive(Debug, Clone, Copy)]
+struct Photon {
+    position: Vert4,
+    /// The unit direction it was travelling in.
+    direction: Vert4,
+    power: Color,
+}
+
+/// Photons left on matte surfaces by light that was mirrored or refracted on
+/// the way, kept in a kd-tree so the ones near a point are found quickly.
+///
+/// Photons are shot from point lights towards every top-level object that
+/// reflects or refracts, and followed through those until they land on a
+/// surface that diffuses light. The book's lights don't fall off with
+/// distance, so each photon is made as bright as the light is at the first
+/// surface it strikes: a caustic is as bright as [`lighting`] would make that
+/// surface, times however much the glass concentrated the light.
+///
+/// Directional lights shoot no photons.
+///
+/// [`lighting`]: crate::light::lighting
+pub struct PhotonMap {
+    /// A balanced kd-tree: the median of each slice splits the rest along
+    /// the axis stored at the same index in `axes`.
+    photons: Vec<Photon>,
+    axes: Vec<u8>,
+    gather: usize,
+    radius: f32,
+}
+
+impl PhotonMap {
+    /// Shoots the photons for `settings` through `world`.
+    pub fn caustics(world: &World, settings: &Caustics) -> Self {
+        let targets: Vec<_> = world
+            .objects
+            .iter()
+            .filter(|object| is_specular(object.material()))
+            .filter_map(|object| Emitter::around(object.as_ref()))
+            .collect();
+        let mut photons = Vec::new();
+        if let Some(per_target) = settings.photons.checked_div(targets.len()) {
+            let mut rng = Rng::new(settings.seed);
+            for light in &world.lights {
+                let Light::Point(light) = light else {
+                    continue;
+                };
+                for target in &targets {
+                    for _ in 0..per_target {
+                        let (direction, pdf) = target.sample(light.position, &mut rng);
+                        let ray = Ray::new(light.position, direction);
+                        let density = pdf * per_target as f32;
+                        trace_photon(world, light, ray, density, &mut rng, &mut photons);
+                    }
+                }
+            }
+        }
+        let mut axes = vec![0; photons.len()];
+        build(&mut photons, &mut axes);
+        Self {
+            photons,
+            axes,
+            gather: settings.gather,
+            radius: settings.radius,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.photons.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.photons.is_empty()
+    }
+
+    /// The light arriving at `point` on a surface facing `normal`, estimated
+    /// from the density of the nearest photons that reached that side.
+    pub fn irradiance(&self, point: Vert4, normal: Vert4) -> Color {
+        if self.gather == 0 {
+            return Color::BLACK;
+        }
+        let mut found = Vec::with_capacity(self.gather + 1);
+        let mut max_distance2 = self.radius * self.radius;
+        search(
+            &self.photons,
+            &self.axes,
+            point,
+            self.gather,
+            &mut max_distance2,
+            &mut found,
+        );
+        let power: Color = found
+            .iter()
+            .filter(|(_, photon)| photon.direction.dot(&normal) < 0.0)
+            .map(|(_, photon)| photon.power)
+            .sum();
+        power / (PI * max_distance2)
+    }
+}
+
+impl fmt::Debug for PhotonMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PhotonMap")
+            .field("photons", &self.photons.len())
+            .field("gather", &self.gather)
+            .field("radius", &self.radius)
+            .finish()
+    }
+}
+
+fn is_specular(material: &Material) -> bool {
+    material.reflective > 0.0 || material.transparency > 0.0
+}
+
+/// Follows one photon from `light` along `ray`, whose direction was chosen
+/// with `density` per unit solid angle over all the photons shot, storing it
+/// wherever it lands on a matte surface after at least one bounce.
+fn trace_photon(
+    world: &World,
+    light: &PointLight,
+    mut ray: Ray,
+    density: f32,
+    rng: &mut Rng,
+    photons: &mut Vec<Photon>,
+) {
+    let mut power = Color::BLACK;
+    let mut xs = Intersections::default();
+    for bounce in 0..=world.max_depth {
+        xs.clear();
+        world.intersect_into(&ray, &mut xs);
+        let Some(hit) = xs.hit() else {
+            return;
+        };
+        let comps = hit.prepare_computations_with_offset(&ray, &xs, world.surface_offset);
+        let direction = ray.direction.normalize();
+        if bounce == 0 {
+            // Surfaces lit straight from the light are left to `lighting`.
+            if !is_specular(comps.material) {
+                return;
+            }
+            let distance = hit.t * ray.direction.magnitude();
+            let falloff = light.attenuation.factor(distance) * distance * distance;
+            power = light.intensity * (falloff / density);
+        } else if comps.material.diffuse > 0.0 {
+            photons.push(Photon {
+                position: comps.point,
+                direction,
+                power,
+            });
+        }
+        let Some((next, weight)) = specular_bounce(&comps, rng) else {
+            return;
+        };
+        ray = next;
+        power = power * weight;
+    }
+}
+
+/// Mirrors or refracts a photon at a hit, picking between the two in
+/// proportion to how much light each carries, as [`World::shade_hit`] splits
+/// it. Returns the new ray and the factor on the photon's power, or `None` if
+/// it's absorbed.
+fn specular_bounce(comps: &Computations, rng: &mut Rng) -> Option<(Ray, Color)> {
+    let material = comps.material;
+    let (reflected, refracted) = if material.reflective > 0.0 && material.transparency > 0.0 {
+        let reflectance = comps.schlick();
+        (reflectance, 1.0 - reflectance)
+    } else {
+        (1.0, 1.0)
+    };
+    let color = surface_color(material, comps.object, comps.point);
+    let mirror = material.reflection_tint(color, comps.eyev.dot(&comps.normalv))
+        * (material.reflective * reflected);
+    let transmit = Color::WHITE * (material.transparency * refracted);
+    let (mirror_strength, transmit_strength) = (strength(mirror), strength(transmit));
+    // Surfaces passing on less than all the light absorb some photons outright
+    // instead of dimming them, so every stored photon stays bright.
+    let scale = (mirror_strength + transmit_strength).max(1.0);
+    let pick = rng.next_f32() * scale;
+    if pick < mirror_strength {
+        Some((
+            Ray::new(comps.over_point, comps.reflectv),
+            mirror * (scale / mirror_strength),
+        ))
+    } else if pick < mirror_strength + transmit_strength {
+        Some((
+            Ray::new(comps.under_point, comps.refracted_direction()?),
+            transmit * (scale / transmit_strength),
+        ))
+    } else {
+        None
+    }
+}
+
+fn coordinate(point: Vert4, axis: u8) -> f32 {
+    match axis {
+        0 => point.x,
+        1 => point.y,
+        _ => point.z,
+    }
+}
+
+/// Arranges `photons` into a balanced kd-tree, splitting each slice along
+/// its widest axis and recording that axis in `axes`.
+fn build(photons: &mut [Photon], axes: &mut [u8]) {
+    if photons.len() <= 1 {
+        return;
+    }
+    let (min, max) = photons.iter().fold(
+        (
+            Vert4::point(f32::MAX, f32::MAX, f32::MAX),
+            Vert4::point(f32::MIN, f32::MIN, f32::MIN),
+        ),
+        |(min, max), photon| {
+            let p = photon.position;
+            (
+                Vert4::point(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                Vert4::point(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+            )
+        },
+    );
+    let extent = max - min;
+    let axis = if extent.x >= extent.y && extent.x >= extent.z {
+        0
+    } else if extent.y >= extent.z {
+        1
+    } else {
+        2
+    };
+    let mid = photons.len() / 2;
+    photons.select_nth_unstable_by(mid, |a, b| {
+        coordinate(a.position, axis).total_cmp(&coordinate(b.position, axis))
+    });
+    axes[mid] = axis;
+    let (left, rest) = photons.split_at_mut(mid);
+    let (left_axes, rest_axes) = axes.split_at_mut(mid);
+    build(left, left_axes);
+    build(&mut rest[1..], &mut rest_axes[1..]);
+}
+
+/// Collects into `found`, nearest first, up to `k` photons within
+/// `max_distance2` (squared) of `point`, shrinking `max_distance2` to the
+/// farthest of them once there are `k`.
+fn search<'p>(
+    photons: &'p [Photon],
+    axes: &[u8],
+    point: Vert4,
+    k: usize,
+    max_distance2: &mut f32,
+    found: &mut Vec<(f32, &'p Photon)>,
+) {
+    if photons.is_empty() {
+        return;
+    }
+    let mid = photons.len() / 2;
+    let photon = &photons[mid];
+    let axis = axes[mid];
+    let delta = coordinate(point, axis) - coordinate(photon.position, axis);
+    let below = (&photons[..mid], &axes[..mid]);
+    let above = (&photons[mid + 1..], &axes[mid + 1..]);
+    let (near, far) = if delta < 0.0 {
+        (below, above)
+    } else {
+        (above, below)
+    };
+    search(near.0, near.1, point, k, max_distance2, found);
+
+    let offset = photon.position - point;
+    let distance2 = offset.dot(&offset);
+    if distance2 < *max_distance2 {
+        let at = found.partition_point(|&(d, _)| d <= distance2);
+        found.insert(at, (distance2, photon));
+        if found.len() > k {
+            found.pop();
+        }
+        if found.len() == k {
+            *max_distance2 = found[k - 1].0;
+        }
+    }
+
+    if delta * delta < *max_distance2 {
+        search(far.0, far.1, point, k, max_distance2, found);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Matrix4;
+    use crate::shape::{Cube, Shape, Sphere};
+
+    /// A slab whose top face is the plane y = 0.
+    fn floor() -> Cube {
+        Cube::new().with_transform(
+            Matrix4::translation(0.0, -1.0, 0.0) * Matrix4::scaling(10.0, 1.0, 10.0),
+        )
+    }
+
+    #[test]
+    fn no_photons_without_mirrors_or_glass() {
+        let w = World::new()
+            .with_object(floor())
+            .with_object(Sphere::new().with_transform(Matrix4::translation(0.0, 2.0, 0.0)))
+            .with_light(Light::point(Vert4::point(0.0, 5.0, 0.0), Color::WHITE));
+        let map = PhotonMap::caustics(&w, &Caustics::new(1000, 10, 1.0));
+        assert!(map.is_empty());
+        assert_eq!(
+            map.irradiance(Vert4::point(0.0, 0.0, 0.0), Vert4::vector(0.0, 1.0, 0.0)),
+            Color::BLACK
+        );
+    }
+
+    #[test]
+    fn nearest_photons_match_a_linear_search() {
+        let mut rng = Rng::new(3);
+        let mut photons: Vec<_> = (0..500)
+            .map(|_| Photon {
+                position: Vert4::point(rng.next_f32(), rng.next_f32(), rng.next_f32()),
+                direction: Vert4::vector(0.0, -1.0, 0.0),
+                power: Color::WHITE,
+            })
+            .collect();
+        let mut expected: Vec<_> = photons
+            .iter()
+            .map(|photon| {
+                let offset = photon.position - Vert4::point(0.5, 0.5, 0.5);
+                offset.dot(&offset)
+            })
+            .collect();
+        expected.sort_by(f32::total_cmp);
+
+        let mut axes = vec![0; photons.len()];
+        build(&mut photons, &mut axes);
+        let mut found = Vec::new();
+        let mut max_distance2 = f32::INFINITY;
+        search(
+            &photons,
+            &axes,
+            Vert4::point(0.5, 0.5, 0.5),
+            20,
+            &mut max_distance2,
+            &mut found,
+        );
+        let distances: Vec<_> = found.iter().map(|(d, _)| *d).collect();
+        assert_eq!(distances, expected[..20]);
+    }
+
+    #[test]
+    fn a_mirror_casts_the_light_it_reflects() {
+        // A mirror ceiling at y = 2 over a light at y = 1 acts like a second
+        // light at y = 3. The photons that strike the mirror first are as
+        // bright as the light is there, one unit away, so under it the floor
+        // receives 1/3² of the light.
+        let mirror = Cube::new()
+            .with_transform(Matrix4::translation(0.0, 3.0, 0.0) * Matrix4::scaling(10.0, 1.0, 10.0))
+            .with_material(Material {
+                diffuse: 0.0,
+                reflective: 1.0,
+                ..Material::default()
+            });
+        let w = World::new()
+            .with_object(floor())
+            .with_object(mirror)
+            .with_light(Light::point(Vert4::point(0.0, 1.0, 0.0), Color::WHITE));
+        let map = PhotonMap::caustics(&w, &Caustics::new(50_000, 1000, 0.5));
+        let up = Vert4::vector(0.0, 1.0, 0.0);
+        let e = map.irradiance(Vert4::point(0.0, 0.0, 0.0), up);
+        assert!((e.r - 1.0 / 9.0).abs() < 0.015, "{e:?}");
+        // Nothing reaches the underside.
+        assert_eq!(
+            map.irradiance(Vert4::point(0.0, 0.0, 0.0), -up),
+            Color::BLACK
+        );
+    }
+
+    #[test]
+    fn glass_spheres_focus_light_beneath_them() {
+        let glass = Sphere::new()
+            .with_transform(Matrix4::translation(0.0, 2.0, 0.0))
+            .with_material(Material {
+                diffuse: 0.0,
+                transparency: 1.0,
+                refractive_index: 1.5,
+                ..Material::default()
+            });
+        let w = World::new()
+            .with_object(floor())
+            .with_object(glass)
+            .with_light(Light::point(Vert4::point(0.0, 10.0, 0.0), Color::WHITE));
+        let map = PhotonMap::caustics(&w, &Caustics::new(20_000, 50, 0.5));
+        let up = Vert4::vector(0.0, 1.0, 0.0);
+        // Brighter than the unobstructed light at the focus, dark in the
+        // sphere's shadow away from it, and untouched outside.
+        assert!(map.irradiance(Vert4::point(0.0, 0.0, 0.0), up).r > 1.0);
+        assert!(map.irradiance(Vert4::point(0.9, 0.0, 0.0), up).r < 0.5);
+        assert_eq!(
+            map.irradiance(Vert4::point(5.0, 0.0, 0.0), up),
+            Color::BLACK
+        );
+    }
+}
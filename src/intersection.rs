@@ -0,0 +1,638 @@
+use std::ops::Deref;
+use std::ptr;
+
+use crate::material::{Material, NormalMap};
+use crate::math::{Vert4, EPSILON};
+use crate::ray::Ray;
+use crate::shape::{Instance, Shape};
+
+/// A ray crossing `object` at distance `t` along the ray.
+#[derive(Debug, Clone, Copy)]
+pub struct Intersection<'a> {
+    pub t: f32,
+    pub object: &'a dyn Shape,
+    /// Surface coordinates of the hit, for shapes that parameterize their
+    /// surface (barycentric weights on triangles); zero otherwise.
+    pub u: f32,
+    pub v: f32,
+    /// The [`Instance`] whose shared geometry contains `object`, if any.
+    /// `object`'s transform is then relative to the instance, not the world.
+    pub instance: Option<&'a Instance>,
+}
+
+impl<'a> Intersection<'a> {
+    pub fn new(t: f32, object: &'a dyn Shape) -> Self {
+        Self::with_uv(t, object, 0.0, 0.0)
+    }
+
+    pub fn with_uv(t: f32, object: &'a dyn Shape, u: f32, v: f32) -> Self {
+        Self {
+            t,
+            object,
+            u,
+            v,
+            instance: None,
+        }
+    }
+
+    /// The shape whose material and identity matter for shading: the instance
+    /// for hits on shared geometry, otherwise the object itself.
+    pub fn shading_object(&self) -> &'a dyn Shape {
+        self.instance.map_or(self.object, |i| i as &dyn Shape)
+    }
+
+    /// The material the hit is shaded with: the instance's, if it has one of
+    /// its own, otherwise the object's.
+    pub fn material(&self) -> &'a Material {
+        match self.instance.and_then(Instance::own_material) {
+            Some(material) => material,
+            None => self.object.material(),
+        }
+    }
+
+    /// Precomputes the shading inputs for this intersection of `ray`.
+    ///
+    /// `xs` is every intersection along the ray, sorted by `t` and including
+    /// this one; it determines which objects the hit lies inside, and so the
+    /// refractive indices on either side of the surface.
+    pub fn prepare_computations(&self, ray: &Ray, xs: &[Intersection<'a>]) -> Computations<'a> {
+        self.prepare_computations_with_offset(ray, xs, EPSILON)
+    }
+
+    /// [`prepare_computations`](Self::prepare_computations), nudging
+    /// `over_point` and `under_point` off the surface by `offset` rather than
+    /// [`EPSILON`]. Scenes modelled at very large or small scales need an
+    /// offset to match.
+    pub fn prepare_computations_with_offset(
+        &self,
+        ray: &Ray,
+        xs: &[Intersection<'a>],
+        offset: f32,
+    ) -> Computations<'a> {
+        let point = ray.position(self.t);
+        let eyev = -ray.direction;
+        let mut normalv = match self.instance {
+            Some(instance) => {
+                let local_point = instance.world_to_object(point);
+                instance.normal_to_world(self.object.normal_at(local_point, self))
+            }
+            None => self.object.normal_at(point, self),
+        };
+        if let Some(normal_map) = &self.material().normal_map {
+            normalv = self.apply_normal_map(normal_map, point, normalv);
+        }
+        let inside = normalv.dot(&eyev) < 0.0;
+        if inside {
+            normalv = -normalv;
+        }
+        let (n1, n2) = self.refractive_indices(xs);
+        Computations {
+            t: self.t,
+            object: self.shading_object(),
+            material: self.material(),
+            point,
+            over_point: point + normalv * offset,
+            under_point: point - normalv * offset,
+            eyev,
+            normalv,
+            reflectv: ray.direction.reflect(&normalv),
+            inside,
+            n1,
+            n2,
+        }
+    }
+
+    /// Tilts the world-space `normal` at `point` by `normal_map`.
+    ///
+    /// The object's tangents are carried into world space and made
+    /// orthonormal with `normal`. Where they degenerate, such as at a
+    /// sphere's poles, the normal is left alone.
+    fn apply_normal_map(&self, normal_map: &NormalMap, point: Vert4, normal: Vert4) -> Vert4 {
+        let (point, to_world) = match self.instance {
+            Some(instance) => (
+                instance.world_to_object(point),
+                *instance.transform().matrix() * *self.object.transform().matrix(),
+            ),
+            None => (point, *self.object.transform().matrix()),
+        };
+        let local_point = self.object.world_to_object(point);
+        let (tangent, bitangent) = self.object.local_tangents(local_point);
+        let tangent = to_world * tangent;
+        let tangent = tangent - normal * normal.dot(&tangent);
+        let bitangent = to_world * bitangent;
+        let bitangent = bitangent - normal * normal.dot(&bitangent);
+        if tangent.magnitude() < EPSILON || bitangent.magnitude() < EPSILON {
+            return normal;
+        }
+        let tangent = tangent.normalize();
+        let bitangent = bitangent - tangent * tangent.dot(&bitangent);
+        if bitangent.magnitude() < EPSILON {
+            return normal;
+        }
+        let bitangent = bitangent.normalize();
+
+        let uv = self.object.local_uv(local_point);
+        normal_map.perturb(uv, tangent, bitangent, normal)
+    }
+
+    /// The refractive indices of the media the ray leaves and enters at this
+    /// hit, found by tracking which objects the ray is inside along `xs`.
+    /// Empty space has an index of 1.
+    fn refractive_indices(&self, xs: &[Intersection<'a>]) -> (f32, f32) {
+        let mut containers: Vec<&Intersection> = Vec::new();
+        let index = |containers: &[&Intersection]| {
+            containers
+                .last()
+                .map_or(1.0, |x| x.material().refractive_index)
+        };
+        let mut n1 = 1.0;
+        for x in xs {
+            let is_hit = x == self;
+            if is_hit {
+                n1 = index(&containers);
+            }
+            let object = x.shading_object();
+            match containers
+                .iter()
+                .position(|o| ptr::addr_eq(o.shading_object(), object))
+            {
+                Some(i) => {
+                    containers.remove(i);
+                }
+                None => containers.push(x),
+            }
+            if is_hit {
+                return (n1, index(&containers));
+            }
+        }
+        (n1, 1.0)
+    }
+}
+
+/// The geometry at an intersection needed to shade it.
+#[derive(Debug, Clone, Copy)]
+pub struct Computations<'a> {
+    pub t: f32,
+    /// The shape struck: the instance, for hits on shared geometry.
+    pub object: &'a dyn Shape,
+    /// The material to shade with; see [`Intersection::material`].
+    pub material: &'a Material,
+    pub point: Vert4,
+    /// `point` nudged along the normal, so rays cast from it don't re-hit the
+    /// surface they start on (shadow acne).
+    pub over_point: Vert4,
+    /// `point` nudged below the surface, where refracted rays start.
+    pub under_point: Vert4,
+    /// Unit vector from `point` towards the eye.
+    pub eyev: Vert4,
+    /// Surface normal at `point`, flipped to face the eye.
+    pub normalv: Vert4,
+    /// The incoming ray direction mirrored about `normalv`.
+    pub reflectv: Vert4,
+    /// Whether the ray originated inside the object.
+    pub inside: bool,
+    /// Refractive index of the medium the ray is leaving.
+    pub n1: f32,
+    /// Refractive index of the medium the ray is entering.
+    pub n2: f32,
+}
+
+impl Computations<'_> {
+    /// Schlick's approximation of the Fresnel reflectance: the fraction of
+    /// light reflected rather than refracted at this hit. It is 1 under total
+    /// internal reflection and grows towards grazing angles.
+    pub fn schlick(&self) -> f32 {
+        let mut cos = self.eyev.dot(&self.normalv);
+        if self.n1 > self.n2 {
+            let n = self.n1 / self.n2;
+            let sin2_t = n * n * (1.0 - cos * cos);
+            if sin2_t > 1.0 {
+                return 1.0;
+            }
+            cos = (1.0 - sin2_t).sqrt();
+        }
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+    }
+
+    /// The direction light from the eye continues in through the surface,
+    /// bent by Snell's law, or `None` under total internal reflection.
+    pub fn refracted_direction(&self) -> Option<Vert4> {
+        let n_ratio = self.n1 / self.n2;
+        let cos_i = self.eyev.dot(&self.normalv);
+        let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+        if sin2_t > 1.0 {
+            return None;
+        }
+        let cos_t = (1.0 - sin2_t).sqrt();
+        Some(self.normalv * (n_ratio * cos_i - cos_t) - self.eyev * n_ratio)
+    }
+}
+
+/// Two intersections are equal when they are at the same `t` on the same object,
+/// reached through the same [`Instance`].
+impl PartialEq for Intersection<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.t == other.t
+            && ptr::addr_eq(self.object, other.object)
+            && self.instance.map(ptr::from_ref) == other.instance.map(ptr::from_ref)
+    }
+}
+
+/// A collection of intersections kept sorted by ascending `t`.
+///
+/// Intersections with equal `t` keep their insertion order, and NaN
+/// distances (degenerate rays) are dropped rather than poisoning the order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Intersections<'a> {
+    items: Vec<Intersection<'a>>,
+}
+
+impl<'a> Intersections<'a> {
+    pub fn new(items: impl IntoIterator<Item = Intersection<'a>>) -> Self {
+        let mut xs = Self::default();
+        xs.extend(items);
+        xs
+    }
+
+    pub fn push(&mut self, x: Intersection<'a>) {
+        if x.t.is_nan() {
+            return;
+        }
+        let at = self.items.partition_point(|y| y.t <= x.t);
+        self.items.insert(at, x);
+    }
+
+    /// The visible intersection: the one with the lowest non-negative `t`.
+    pub fn hit(&self) -> Option<&Intersection<'a>> {
+        self.items.iter().find(|x| x.t >= 0.0)
+    }
+
+    pub fn into_vec(self) -> Vec<Intersection<'a>> {
+        self.items
+    }
+
+    /// Empties the collection, keeping its storage for the next ray.
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// Keeps only the intersections for which `keep` is true, in order.
+    pub fn retain(&mut self, keep: impl FnMut(&Intersection<'a>) -> bool) {
+        self.items.retain(keep);
+    }
+}
+
+impl<'a> Extend<Intersection<'a>> for Intersections<'a> {
+    fn extend<I: IntoIterator<Item = Intersection<'a>>>(&mut self, iter: I) {
+        let before = self.items.len();
+        self.items
+            .extend(iter.into_iter().filter(|x| !x.t.is_nan()));
+        if self.items[before..].is_empty() {
+            return;
+        }
+        // Stable sort keeps equal-t intersections in insertion order.
+        self.items.sort_by(|a, b| a.t.total_cmp(&b.t));
+    }
+}
+
+impl<'a> FromIterator<Intersection<'a>> for Intersections<'a> {
+    fn from_iter<I: IntoIterator<Item = Intersection<'a>>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
+
+impl<'a> IntoIterator for Intersections<'a> {
+    type Item = Intersection<'a>;
+    type IntoIter = std::vec::IntoIter<Intersection<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> Deref for Intersections<'a> {
+    type Target = [Intersection<'a>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Material;
+    use crate::math::Matrix4;
+    use crate::pattern::UvPattern;
+    use crate::shape::{glass_sphere, Cube, Sphere};
+
+    /// A normal map holding the same tangent-space normal everywhere.
+    #[derive(Debug)]
+    struct UniformNormal(Color);
+
+    impl UvPattern for UniformNormal {
+        fn uv_pattern_at(&self, _u: f32, _v: f32) -> Color {
+            self.0
+        }
+    }
+
+    /// A height field rising steadily with `u`.
+    #[derive(Debug)]
+    struct RampInU;
+
+    impl UvPattern for RampInU {
+        fn uv_pattern_at(&self, u: f32, _v: f32) -> Color {
+            Color::new(u, u, u)
+        }
+    }
+
+    fn with_normal_map(color: Color) -> Material {
+        Material {
+            normal_map: Some(NormalMap::Tangent(Arc::new(UniformNormal(color)))),
+            ..Material::default()
+        }
+    }
+
+    #[test]
+    fn aggregating_intersections() {
+        let s = Sphere::new();
+        let xs = Intersections::new([Intersection::new(2.0, &s), Intersection::new(1.0, &s)]);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 1.0);
+        assert_eq!(xs[1].t, 2.0);
+    }
+
+    #[test]
+    fn hit_when_all_positive() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(1.0, &s);
+        let i2 = Intersection::new(2.0, &s);
+        let xs = Intersections::new([i2, i1]);
+        assert_eq!(xs.hit(), Some(&i1));
+    }
+
+    #[test]
+    fn hit_when_some_negative() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(-1.0, &s);
+        let i2 = Intersection::new(1.0, &s);
+        let xs = Intersections::new([i2, i1]);
+        assert_eq!(xs.hit(), Some(&i2));
+    }
+
+    #[test]
+    fn hit_when_all_negative() {
+        let s = Sphere::new();
+        let xs = Intersections::new([Intersection::new(-2.0, &s), Intersection::new(-1.0, &s)]);
+        assert_eq!(xs.hit(), None);
+    }
+
+    #[test]
+    fn hit_is_lowest_non_negative() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(5.0, &s);
+        let i2 = Intersection::new(7.0, &s);
+        let i3 = Intersection::new(-3.0, &s);
+        let i4 = Intersection::new(2.0, &s);
+        let xs = Intersections::new([i1, i2, i3, i4]);
+        assert_eq!(xs.hit(), Some(&i4));
+    }
+
+    #[test]
+    fn hit_at_zero_counts() {
+        let s = Sphere::new();
+        let xs = Intersections::new([Intersection::new(0.0, &s), Intersection::new(-0.5, &s)]);
+        assert_eq!(xs.hit().map(|x| x.t), Some(0.0));
+    }
+
+    #[test]
+    fn push_and_extend_keep_order() {
+        let a = Sphere::new();
+        let b = Sphere::new();
+        let mut xs = Intersections::new([Intersection::new(3.0, &a)]);
+        xs.push(Intersection::new(1.0, &a));
+        xs.extend([Intersection::new(2.0, &b), Intersection::new(1.0, &b)]);
+        let ts: Vec<f32> = xs.iter().map(|x| x.t).collect();
+        assert_eq!(ts, [1.0, 1.0, 2.0, 3.0]);
+        // Ties keep insertion order.
+        assert!(ptr::addr_eq(xs[0].object, &a));
+        assert!(ptr::addr_eq(xs[1].object, &b));
+    }
+
+    #[test]
+    fn nan_intersections_are_dropped() {
+        let s = Sphere::new();
+        let mut xs = Intersections::new([Intersection::new(f32::NAN, &s)]);
+        xs.push(Intersection::new(f32::NAN, &s));
+        assert!(xs.is_empty());
+        assert_eq!(xs.hit(), None);
+    }
+
+    #[test]
+    fn precomputing_outside_hit() {
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+        let i = Intersection::new(4.0, &s);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_eq!(comps.t, 4.0);
+        assert!(ptr::addr_eq(comps.object, &s));
+        assert_eq!(comps.point, Vert4::point(0.0, 0.0, -1.0));
+        assert_eq!(comps.eyev, Vert4::vector(0.0, 0.0, -1.0));
+        assert_eq!(comps.normalv, Vert4::vector(0.0, 0.0, -1.0));
+        assert!(!comps.inside);
+    }
+
+    #[test]
+    fn precomputing_inside_hit() {
+        let r = Ray::new(Vert4::point(0.0, 0.0, 0.0), Vert4::vector(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+        let i = Intersection::new(1.0, &s);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_eq!(comps.point, Vert4::point(0.0, 0.0, 1.0));
+        assert_eq!(comps.eyev, Vert4::vector(0.0, 0.0, -1.0));
+        assert!(comps.inside);
+        assert_eq!(comps.normalv, Vert4::vector(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn hit_offsets_over_point() {
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let s = Sphere::new().with_transform(Matrix4::translation(0.0, 0.0, 1.0));
+        let i = Intersection::new(5.0, &s);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert!(comps.over_point.z < -EPSILON / 2.0);
+        assert!(comps.point.z > comps.over_point.z);
+    }
+
+    #[test]
+    fn hit_offsets_under_point() {
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let s = glass_sphere().with_transform(Matrix4::translation(0.0, 0.0, 1.0));
+        let i = Intersection::new(5.0, &s);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert!(comps.under_point.z > EPSILON / 2.0);
+        assert!(comps.point.z < comps.under_point.z);
+    }
+
+    #[test]
+    fn custom_surface_offset() {
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+        let i = Intersection::new(4.0, &s);
+        let comps = i.prepare_computations_with_offset(&r, &[i], 0.01);
+        assert_approx_eq!(comps.over_point, Vert4::point(0.0, 0.0, -1.01));
+        assert_approx_eq!(comps.under_point, Vert4::point(0.0, 0.0, -0.99));
+    }
+
+    #[test]
+    fn finding_n1_and_n2() {
+        let a = glass_sphere().with_transform(Matrix4::scaling(2.0, 2.0, 2.0));
+        let mut b = glass_sphere().with_transform(Matrix4::translation(0.0, 0.0, -0.25));
+        b.material_mut().refractive_index = 2.0;
+        let mut c = glass_sphere().with_transform(Matrix4::translation(0.0, 0.0, 0.25));
+        c.material_mut().refractive_index = 2.5;
+        let r = Ray::new(Vert4::point(0.0, 0.0, -4.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = [
+            Intersection::new(2.0, &a),
+            Intersection::new(2.75, &b),
+            Intersection::new(3.25, &c),
+            Intersection::new(4.75, &b),
+            Intersection::new(5.25, &c),
+            Intersection::new(6.0, &a),
+        ];
+        let expected = [
+            (1.0, 1.5),
+            (1.5, 2.0),
+            (2.0, 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.0),
+        ];
+        for (x, (n1, n2)) in xs.iter().zip(expected) {
+            let comps = x.prepare_computations(&r, &xs);
+            assert_eq!((comps.n1, comps.n2), (n1, n2), "t = {}", x.t);
+        }
+    }
+
+    #[test]
+    fn equality_compares_object_identity() {
+        let a = Sphere::new();
+        let b = Sphere::new();
+        assert_ne!(Intersection::new(1.0, &a), Intersection::new(1.0, &b));
+        assert_eq!(Intersection::new(1.0, &a), Intersection::new(1.0, &a));
+    }
+
+    #[test]
+    fn precomputing_reflection_vector() {
+        let s = Cube::new();
+        let half = 2f32.sqrt() / 2.0;
+        let r = Ray::new(
+            Vert4::point(0.0, 2.0, -1.0),
+            Vert4::vector(0.0, -half, half),
+        );
+        let i = Intersection::new(2f32.sqrt(), &s);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_approx_eq!(comps.reflectv, Vert4::vector(0.0, half, half));
+    }
+
+    #[test]
+    fn schlick_under_total_internal_reflection() {
+        let s = glass_sphere();
+        let half = 2f32.sqrt() / 2.0;
+        let r = Ray::new(Vert4::point(0.0, 0.0, half), Vert4::vector(0.0, 1.0, 0.0));
+        let xs = [Intersection::new(-half, &s), Intersection::new(half, &s)];
+        let comps = xs[1].prepare_computations(&r, &xs);
+        assert_eq!(comps.schlick(), 1.0);
+    }
+
+    #[test]
+    fn schlick_with_perpendicular_ray() {
+        let s = glass_sphere();
+        let r = Ray::new(Vert4::point(0.0, 0.0, 0.0), Vert4::vector(0.0, 1.0, 0.0));
+        let xs = [Intersection::new(-1.0, &s), Intersection::new(1.0, &s)];
+        let comps = xs[1].prepare_computations(&r, &xs);
+        assert_approx_eq!(comps.schlick(), 0.04);
+    }
+
+    #[test]
+    fn schlick_with_small_angle_and_n2_greater() {
+        let s = glass_sphere();
+        let r = Ray::new(Vert4::point(0.0, 0.99, -2.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = [Intersection::new(1.8589, &s)];
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_approx_eq!(comps.schlick(), 0.48873);
+    }
+
+    #[test]
+    fn flat_normal_map_keeps_normal() {
+        let s = Sphere::new().with_material(with_normal_map(Color::new(0.5, 0.5, 1.0)));
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = s.intersect(&r);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_approx_eq!(comps.normalv, Vert4::vector(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn normal_map_tilts_along_tangents() {
+        // Facing the camera, a sphere's u runs along +x and v along +y.
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let k = 2f32.sqrt() / 2.0;
+        let cases = [
+            (Color::new(1.0, 0.5, 0.5), Vert4::vector(1.0, 0.0, 0.0)),
+            (Color::new(0.5, 0.0, 0.5), Vert4::vector(0.0, -1.0, 0.0)),
+            (Color::new(0.5, 1.0, 1.0), Vert4::vector(0.0, k, -k)),
+        ];
+        for (color, expected) in cases {
+            let s = Sphere::new()
+                .with_transform(Matrix4::translation(0.0, 0.0, 1.0))
+                .with_material(with_normal_map(color));
+            let xs = s.intersect(&r);
+            let comps = xs[0].prepare_computations(&r, &xs);
+            assert_approx_eq!(comps.normalv, expected);
+        }
+    }
+
+    #[test]
+    fn normal_map_follows_cube_faces() {
+        let c = Cube::new().with_material(with_normal_map(Color::new(1.0, 0.5, 0.5)));
+        let r = Ray::new(Vert4::point(0.3, 0.2, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = c.intersect(&r);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_approx_eq!(comps.normalv, Vert4::vector(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn normal_map_is_ignored_at_degenerate_tangents() {
+        let s = Sphere::new().with_material(with_normal_map(Color::new(1.0, 0.5, 0.5)));
+        let r = Ray::new(Vert4::point(0.0, 5.0, 0.0), Vert4::vector(0.0, -1.0, 0.0));
+        let xs = s.intersect(&r);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_approx_eq!(comps.normalv, Vert4::vector(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn bump_map_leans_away_from_uphill() {
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let bumpy = |height: Arc<dyn UvPattern>| {
+            let s = Sphere::new().with_material(Material {
+                normal_map: Some(NormalMap::Bump {
+                    height,
+                    strength: 1.0,
+                }),
+                ..Material::default()
+            });
+            let xs = s.intersect(&r);
+            xs[0].prepare_computations(&r, &xs).normalv
+        };
+        let flat = bumpy(Arc::new(UniformNormal(Color::WHITE)));
+        assert_approx_eq!(flat, Vert4::vector(0.0, 0.0, -1.0));
+        // Height rises along +x (u) at a slope of 1, so the normal tilts to -x.
+        let k = 2f32.sqrt() / 2.0;
+        let sloped = bumpy(Arc::new(RampInU));
+        assert_approx_eq!(sloped, Vert4::vector(-k, 0.0, -k));
+    }
+}
@@ -0,0 +1,86 @@
+//! A ray tracer following "The Ray Tracer Challenge".
+
+/// Asserts that two values are equal within [`math::EPSILON`].
+#[cfg(test)]
+macro_rules! assert_approx_eq {
+    ($left:expr, $right:expr $(,)?) => {{
+        use $crate::math::ApproxEq;
+        let (left, right) = (&$left, &$right);
+        assert!(
+            left.approx_eq(right),
+            "assertion `left ≈ right` failed\n  left: {:?}\n right: {:?}",
+            left,
+            right
+        );
+    }};
+}
+
+/// Asserts that two [`Canvas`]es match, every channel of every pixel within
+/// a tolerance: [`math::EPSILON`] unless given. On failure it reports the
+/// [`DiffReport`](canvas::DiffReport)'s errors and worst pixel.
+#[macro_export]
+macro_rules! assert_canvas_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_canvas_eq!($left, $right, $crate::math::EPSILON)
+    };
+    ($left:expr, $right:expr, $tolerance:expr $(,)?) => {{
+        let tolerance: f32 = $tolerance;
+        let report = $crate::canvas::Canvas::diff(&$left, &$right);
+        assert!(
+            report.within(tolerance),
+            "assertion `left ≈ right` failed: canvases differ by up to {:?} \
+             (mean {:?}), worst at pixel {:?}, beyond tolerance {}",
+            report.max_error,
+            report.mean_error,
+            report.worst_pixel,
+            tolerance
+        );
+    }};
+}
+
+pub mod camera;
+pub mod canvas;
+pub mod color;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod import;
+pub mod integrator;
+pub mod intersection;
+pub mod light;
+pub mod material;
+pub mod math;
+pub mod pattern;
+pub mod ray;
+pub mod scenes;
+pub mod shape;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod world;
+
+pub use camera::{
+    view_transform, Aovs, Camera, OrbitController, PreviousFrame, Progress, ProgressSink,
+    Projection, Region, RenderOptions,
+};
+pub use canvas::{
+    BlendMode, Canvas, CanvasError, CanvasStats, CanvasView, CanvasViewMut, Denoiser, DiffReport,
+    Dither, DoubleBuffer, Encoding, Guides, HdrBuffer, Kernel, ToneMap,
+};
+pub use color::{Color, Rgba};
+pub use import::{ImportOptions, ObjError, ObjFile, UpAxis};
+pub use integrator::{
+    Caustics, Emitter, Integrator, IrradianceCache, PathTracer, PhotonMap, RenderSettings,
+};
+pub use intersection::{Computations, Intersection, Intersections};
+pub use light::{direct_lighting, lighting, Attenuation, DirectionalLight, Light, PointLight};
+pub use material::{Material, MaterialBuilder, MaterialLibrary, NormalMap, SpecularModel};
+pub use math::{Matrix4, Transform, Vert4};
+pub use pattern::{
+    AlignCheck, CubeMap, FilterMode, Gradient, LazyTexture, Pattern, Perturb, PpmFile,
+    TextureCache, TextureMemory, TexturePattern, TextureSource, UvPattern,
+};
+pub use ray::Ray;
+pub use shape::{
+    BoundingBox, ClipPlane, Clipped, Cone, Csg, CsgOperation, Cube, Cylinder, Group, Instance,
+    ObjectId, RayKind, Shape, SmoothTriangle, Sphere, Torus, Triangle, Visibility,
+};
+pub use world::{AmbientOcclusion, Pick, SceneReport, World};
@@ -0,0 +1,501 @@
+//! Light sources and the Phong lighting model.
+
+use std::f32::consts::PI;
+
+use crate::color::Color;
+use crate::material::{Material, SpecularModel};
+use crate::math::Vert4;
+use crate::shape::Shape;
+
+/// How a light fades with distance `d`: its intensity is divided by
+/// `constant + linear * d + quadratic * d²`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl Attenuation {
+    /// No falloff: the same intensity at any distance.
+    pub const NONE: Self = Self::new(1.0, 0.0, 0.0);
+
+    pub const fn new(constant: f32, linear: f32, quadratic: f32) -> Self {
+        Self {
+            constant,
+            linear,
+            quadratic,
+        }
+    }
+
+    /// The fraction of a light's intensity left at `distance`.
+    pub fn factor(&self, distance: f32) -> f32 {
+        1.0 / (self.constant + self.linear * distance + self.quadratic * distance * distance)
+    }
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// A light with no size, radiating equally in every direction from `position`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointLight {
+    pub position: Vert4,
+    pub intensity: Color,
+    pub attenuation: Attenuation,
+}
+
+impl PointLight {
+    /// A light that doesn't fade with distance.
+    pub const fn new(position: Vert4, intensity: Color) -> Self {
+        Self {
+            position,
+            intensity,
+            attenuation: Attenuation::NONE,
+        }
+    }
+
+    pub const fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+}
+
+/// A light infinitely far away, like the sun: every ray from it travels in the
+/// same `direction` and nothing is beyond it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+    /// The unit direction the light travels in.
+    pub direction: Vert4,
+    pub intensity: Color,
+}
+
+impl DirectionalLight {
+    /// A light travelling along `direction`, which needn't be normalized.
+    pub fn new(direction: Vert4, intensity: Color) -> Self {
+        Self {
+            direction: direction.normalize(),
+            intensity,
+        }
+    }
+}
+
+/// Any light source a [`World`](crate::world::World) can hold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Light {
+    Point(PointLight),
+    Directional(DirectionalLight),
+}
+
+impl Light {
+    /// Shorthand for a [`PointLight`].
+    pub const fn point(position: Vert4, intensity: Color) -> Self {
+        Self::Point(PointLight::new(position, intensity))
+    }
+
+    /// Shorthand for a [`DirectionalLight`].
+    pub fn directional(direction: Vert4, intensity: Color) -> Self {
+        Self::Directional(DirectionalLight::new(direction, intensity))
+    }
+
+    pub fn intensity(&self) -> Color {
+        match self {
+            Self::Point(light) => light.intensity,
+            Self::Directional(light) => light.intensity,
+        }
+    }
+
+    /// The intensity reaching `point`, after any falloff with distance.
+    pub fn intensity_at(&self, point: Vert4) -> Color {
+        match self {
+            Self::Point(light) => {
+                let distance = self.distance_from(point);
+                light.intensity * light.attenuation.factor(distance)
+            }
+            Self::Directional(light) => light.intensity,
+        }
+    }
+
+    /// The unit vector from `point` towards the light.
+    pub fn direction_from(&self, point: Vert4) -> Vert4 {
+        match self {
+            Self::Point(light) => (light.position - point).normalize(),
+            Self::Directional(light) => -light.direction,
+        }
+    }
+
+    /// How far the light is from `point`; infinite for directional lights.
+    pub fn distance_from(&self, point: Vert4) -> f32 {
+        match self {
+            Self::Point(light) => (light.position - point).magnitude(),
+            Self::Directional(_) => f32::INFINITY,
+        }
+    }
+}
+
+impl From<PointLight> for Light {
+    fn from(light: PointLight) -> Self {
+        Self::Point(light)
+    }
+}
+
+impl From<DirectionalLight> for Light {
+    fn from(light: DirectionalLight) -> Self {
+        Self::Directional(light)
+    }
+}
+
+/// The material's color at `point` on `object`, from its pattern if it has one.
+pub fn surface_color(material: &Material, object: &dyn Shape, point: Vert4) -> Color {
+    match &material.pattern {
+        Some(pattern) => pattern.pattern_at_shape(object, point),
+        None => material.color,
+    }
+}
+
+/// Schlick's approximation of Fresnel reflectance per channel, for a surface
+/// reflecting `f0` head-on, at an angle whose cosine is `cos`.
+pub fn fresnel_schlick(f0: Color, cos: f32) -> Color {
+    f0 + (Color::WHITE - f0) * (1.0 - cos.clamp(0.0, 1.0)).powi(5)
+}
+
+/// Phong shading of `point` on `object` as seen along `eyev` (pointing towards
+/// the eye). `object` places the material's pattern, if it has one.
+///
+/// `light_fraction` is how much of the light reaches the point, from 0 in
+/// full shadow, where only the ambient term remains, to 1 in full light. It
+/// scales the diffuse and specular terms.
+pub fn lighting(
+    material: &Material,
+    object: &dyn Shape,
+    light: &Light,
+    point: Vert4,
+    eyev: Vert4,
+    normalv: Vert4,
+    light_fraction: f32,
+) -> Color {
+    let color = surface_color(material, object, point);
+    let ambient = color * light.intensity_at(point) * material.ambient;
+    if light_fraction <= 0.0 {
+        return ambient;
+    }
+    ambient + reflect_light(material, color, light, point, eyev, normalv) * light_fraction
+}
+
+/// The diffuse and specular terms of [`lighting`] in full light, without the
+/// ambient term.
+pub fn direct_lighting(
+    material: &Material,
+    object: &dyn Shape,
+    light: &Light,
+    point: Vert4,
+    eyev: Vert4,
+    normalv: Vert4,
+) -> Color {
+    let color = surface_color(material, object, point);
+    reflect_light(material, color, light, point, eyev, normalv)
+}
+
+/// The light from `light` reflected towards `eyev` by a surface of `color`.
+fn reflect_light(
+    material: &Material,
+    color: Color,
+    light: &Light,
+    point: Vert4,
+    eyev: Vert4,
+    normalv: Vert4,
+) -> Color {
+    let intensity = light.intensity_at(point);
+    let lightv = light.direction_from(point);
+    // A negative cosine means the light is on the other side of the surface.
+    let light_dot_normal = lightv.dot(&normalv);
+    if light_dot_normal < 0.0 {
+        return Color::BLACK;
+    }
+    let diffuse = color * intensity * material.diffuse * light_dot_normal;
+
+    match material.specular_model {
+        SpecularModel::Phong => {
+            let reflect_dot_eye = (-lightv).reflect(&normalv).dot(&eyev);
+            let specular = if reflect_dot_eye <= 0.0 {
+                Color::BLACK
+            } else {
+                intensity * material.specular * reflect_dot_eye.powf(material.shininess)
+            };
+            diffuse + specular
+        }
+        SpecularModel::Ggx {
+            roughness,
+            metalness,
+        } => {
+            let f0 = Material::base_reflectance(color, metalness);
+            let reflectance = ggx(normalv, lightv, eyev, roughness, f0);
+            diffuse * (1.0 - metalness) + intensity * reflectance * material.specular
+        }
+    }
+}
+
+/// The GGX microfacet specular reflectance towards `eyev` of light arriving
+/// from `lightv`, for a surface reflecting `f0` head-on, including the cosine
+/// falloff. It's scaled by π to match the diffuse term, which leaves out
+/// Lambert's 1/π.
+fn ggx(normalv: Vert4, lightv: Vert4, eyev: Vert4, roughness: f32, f0: Color) -> Color {
+    let n_dot_l = normalv.dot(&lightv);
+    let n_dot_v = normalv.dot(&eyev);
+    if n_dot_l <= 0.0 || n_dot_v <= 0.0 {
+        return Color::BLACK;
+    }
+    let halfway = (lightv + eyev).normalize();
+    let n_dot_h = normalv.dot(&halfway).max(0.0);
+    let v_dot_h = eyev.dot(&halfway).max(0.0);
+
+    // A perfectly smooth surface would have an infinitely sharp highlight.
+    let alpha = roughness.clamp(0.01, 1.0).powi(2);
+    let alpha2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    let distribution = alpha2 / (PI * d * d);
+
+    let k = alpha / 2.0;
+    let g1 = |n_dot_x: f32| n_dot_x / (n_dot_x * (1.0 - k) + k);
+    let geometry = g1(n_dot_l) * g1(n_dot_v);
+
+    fresnel_schlick(f0, v_dot_h) * (PI * distribution * geometry / (4.0 * n_dot_v))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::pattern::Gradient;
+    use crate::shape::Sphere;
+
+    fn setup() -> (Material, Vert4) {
+        (Material::default(), Vert4::point(0.0, 0.0, 0.0))
+    }
+
+    #[test]
+    fn eye_between_light_and_surface() {
+        let (m, position) = setup();
+        let eyev = Vert4::vector(0.0, 0.0, -1.0);
+        let normalv = Vert4::vector(0.0, 0.0, -1.0);
+        let light = Light::point(Vert4::point(0.0, 0.0, -10.0), Color::WHITE);
+        assert_approx_eq!(
+            lighting(&m, &Sphere::new(), &light, position, eyev, normalv, 1.0),
+            Color::new(1.9, 1.9, 1.9)
+        );
+    }
+
+    #[test]
+    fn eye_offset_45_degrees() {
+        let (m, position) = setup();
+        let half = 2f32.sqrt() / 2.0;
+        let eyev = Vert4::vector(0.0, half, -half);
+        let normalv = Vert4::vector(0.0, 0.0, -1.0);
+        let light = Light::point(Vert4::point(0.0, 0.0, -10.0), Color::WHITE);
+        assert_approx_eq!(
+            lighting(&m, &Sphere::new(), &light, position, eyev, normalv, 1.0),
+            Color::new(1.0, 1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn light_offset_45_degrees() {
+        let (m, position) = setup();
+        let eyev = Vert4::vector(0.0, 0.0, -1.0);
+        let normalv = Vert4::vector(0.0, 0.0, -1.0);
+        let light = Light::point(Vert4::point(0.0, 10.0, -10.0), Color::WHITE);
+        assert_approx_eq!(
+            lighting(&m, &Sphere::new(), &light, position, eyev, normalv, 1.0),
+            Color::new(0.7364, 0.7364, 0.7364)
+        );
+    }
+
+    #[test]
+    fn eye_in_path_of_reflection() {
+        let (m, position) = setup();
+        let half = 2f32.sqrt() / 2.0;
+        let eyev = Vert4::vector(0.0, -half, -half);
+        let normalv = Vert4::vector(0.0, 0.0, -1.0);
+        let light = Light::point(Vert4::point(0.0, 10.0, -10.0), Color::WHITE);
+        assert_approx_eq!(
+            lighting(&m, &Sphere::new(), &light, position, eyev, normalv, 1.0),
+            Color::new(1.6364, 1.6364, 1.6364)
+        );
+    }
+
+    #[test]
+    fn surface_in_shadow() {
+        let (m, position) = setup();
+        let eyev = Vert4::vector(0.0, 0.0, -1.0);
+        let normalv = Vert4::vector(0.0, 0.0, -1.0);
+        let light = Light::point(Vert4::point(0.0, 0.0, -10.0), Color::WHITE);
+        assert_approx_eq!(
+            lighting(&m, &Sphere::new(), &light, position, eyev, normalv, 0.0),
+            Color::new(0.1, 0.1, 0.1)
+        );
+    }
+
+    #[test]
+    fn light_behind_surface() {
+        let (m, position) = setup();
+        let eyev = Vert4::vector(0.0, 0.0, -1.0);
+        let normalv = Vert4::vector(0.0, 0.0, -1.0);
+        let light = Light::point(Vert4::point(0.0, 0.0, 10.0), Color::WHITE);
+        assert_approx_eq!(
+            lighting(&m, &Sphere::new(), &light, position, eyev, normalv, 1.0),
+            Color::new(0.1, 0.1, 0.1)
+        );
+    }
+
+    #[test]
+    fn pattern_replaces_color() {
+        let m = Material {
+            pattern: Some(Arc::new(Gradient::new(Color::WHITE, Color::BLACK))),
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            ..Material::default()
+        };
+        let eyev = Vert4::vector(0.0, 0.0, -1.0);
+        let normalv = Vert4::vector(0.0, 0.0, -1.0);
+        let light = Light::point(Vert4::point(0.0, 0.0, -10.0), Color::WHITE);
+        let object = Sphere::new();
+        let at = |x: f32| {
+            lighting(
+                &m,
+                &object,
+                &light,
+                Vert4::point(x, 0.0, 0.0),
+                eyev,
+                normalv,
+                1.0,
+            )
+        };
+        assert_eq!(at(0.0), Color::WHITE);
+        assert_approx_eq!(at(0.5), Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn directional_light_is_the_same_everywhere() {
+        let light = Light::directional(Vert4::vector(0.0, -2.0, 0.0), Color::WHITE);
+        for point in [Vert4::point(0.0, 0.0, 0.0), Vert4::point(100.0, -3.0, 7.0)] {
+            assert_eq!(light.direction_from(point), Vert4::vector(0.0, 1.0, 0.0));
+            assert_eq!(light.distance_from(point), f32::INFINITY);
+        }
+    }
+
+    #[test]
+    fn directional_light_shades_by_angle() {
+        let (m, position) = setup();
+        let eyev = Vert4::vector(0.0, 0.0, -1.0);
+        let normalv = Vert4::vector(0.0, 0.0, -1.0);
+        let head_on = Light::directional(Vert4::vector(0.0, 0.0, 1.0), Color::WHITE);
+        assert_approx_eq!(
+            lighting(&m, &Sphere::new(), &head_on, position, eyev, normalv, 1.0),
+            Color::new(1.9, 1.9, 1.9)
+        );
+        // Matches a point light far off along the same 45° line.
+        let slanted = Light::directional(Vert4::vector(0.0, -1.0, 1.0), Color::WHITE);
+        assert_approx_eq!(
+            lighting(&m, &Sphere::new(), &slanted, position, eyev, normalv, 1.0),
+            Color::new(0.7364, 0.7364, 0.7364)
+        );
+    }
+
+    #[test]
+    fn attenuation_factor() {
+        assert_eq!(Attenuation::NONE.factor(100.0), 1.0);
+        let a = Attenuation::new(1.0, 0.5, 0.25);
+        assert_eq!(a.factor(0.0), 1.0);
+        assert_eq!(a.factor(2.0), 1.0 / 3.0);
+    }
+
+    #[test]
+    fn attenuated_light_dims_with_distance() {
+        let falloff = Attenuation::new(0.0, 0.0, 1.0);
+        let light: Light = PointLight::new(Vert4::point(0.0, 0.0, -2.0), Color::WHITE)
+            .with_attenuation(falloff)
+            .into();
+        let near = Vert4::point(0.0, 0.0, 0.0);
+        let far = Vert4::point(0.0, 0.0, 2.0);
+        assert_approx_eq!(light.intensity_at(near), Color::new(0.25, 0.25, 0.25));
+        assert_approx_eq!(light.intensity_at(far), Color::new(0.0625, 0.0625, 0.0625));
+
+        let (m, position) = setup();
+        let eyev = Vert4::vector(0.0, 0.0, -1.0);
+        let normalv = Vert4::vector(0.0, 0.0, -1.0);
+        assert_approx_eq!(
+            lighting(&m, &Sphere::new(), &light, position, eyev, normalv, 1.0),
+            Color::new(1.9, 1.9, 1.9) * 0.25
+        );
+    }
+
+    #[test]
+    fn partial_light_scales_diffuse_and_specular() {
+        let (m, position) = setup();
+        let eyev = Vert4::vector(0.0, 0.0, -1.0);
+        let normalv = Vert4::vector(0.0, 0.0, -1.0);
+        let light = Light::point(Vert4::point(0.0, 0.0, -10.0), Color::WHITE);
+        // 0.1 ambient plus half of the 0.9 diffuse and 0.9 specular.
+        assert_approx_eq!(
+            lighting(&m, &Sphere::new(), &light, position, eyev, normalv, 0.5),
+            Color::new(1.0, 1.0, 1.0)
+        );
+    }
+
+    fn ggx_material(roughness: f32, metalness: f32) -> Material {
+        Material {
+            specular_model: SpecularModel::Ggx {
+                roughness,
+                metalness,
+            },
+            ..Material::default()
+        }
+    }
+
+    #[test]
+    fn ggx_highlight_tightens_as_roughness_falls() {
+        let position = Vert4::point(0.0, 0.0, 0.0);
+        let normalv = Vert4::vector(0.0, 0.0, -1.0);
+        let light = Light::point(Vert4::point(0.0, 0.0, -10.0), Color::WHITE);
+        let shade = |m: &Material, eyev: Vert4| {
+            lighting(m, &Sphere::new(), &light, position, eyev, normalv, 1.0).r
+        };
+        let head_on = Vert4::vector(0.0, 0.0, -1.0);
+        let k = 0.5f32.sqrt();
+        let off_axis = Vert4::vector(0.0, k, -k);
+        let (smooth, rough) = (ggx_material(0.2, 0.0), ggx_material(0.8, 0.0));
+        assert!(shade(&smooth, head_on) > shade(&rough, head_on));
+        assert!(shade(&smooth, off_axis) < shade(&rough, off_axis));
+    }
+
+    #[test]
+    fn ggx_has_no_highlight_from_behind() {
+        let normalv = Vert4::vector(0.0, 0.0, -1.0);
+        let eyev = Vert4::vector(0.0, 0.0, -1.0);
+        let behind = Vert4::vector(0.0, 0.0, 1.0).normalize();
+        let f0 = Color::new(0.04, 0.04, 0.04);
+        assert_eq!(ggx(normalv, behind, eyev, 0.5, f0), Color::BLACK);
+        assert_eq!(ggx(normalv, eyev, behind, 0.5, f0), Color::BLACK);
+    }
+
+    #[test]
+    fn metals_have_no_diffuse_term() {
+        let position = Vert4::point(0.0, 0.0, 0.0);
+        let eyev = Vert4::vector(0.0, 1.0, -1.0).normalize();
+        let normalv = Vert4::vector(0.0, 0.0, -1.0);
+        let light = Light::point(Vert4::point(0.0, 0.0, -10.0), Color::WHITE);
+        let metal = Material {
+            specular: 0.0,
+            ..ggx_material(0.5, 1.0)
+        };
+        assert_approx_eq!(
+            lighting(&metal, &Sphere::new(), &light, position, eyev, normalv, 1.0),
+            Color::new(0.1, 0.1, 0.1)
+        );
+    }
+}
@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::color::Color;
+use crate::light::fresnel_schlick;
+use crate::math::Vert4;
+use crate::pattern::{Pattern, TextureMemory, UvPattern};
+
+/// The shape of a material's specular highlights.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SpecularModel {
+    /// Phong highlights, sized by [`Material::shininess`].
+    #[default]
+    Phong,
+    /// The GGX (Trowbridge-Reitz) microfacet model, with Smith shadowing and
+    /// Schlick's Fresnel approximation. `roughness` runs from 0 (polished) to
+    /// 1 (matte); `metalness` from 0 (dielectric) to 1 (metal), which trades
+    /// the diffuse term for a stronger specular one.
+    Ggx { roughness: f32, metalness: f32 },
+}
+
+/// Surface properties used when shading.
+#[derive(Debug, Clone)]
+pub struct Material {
+    pub color: Color,
+    /// Varies the color across the surface, replacing `color` when set.
+    pub pattern: Option<Arc<dyn Pattern>>,
+    pub ambient: f32,
+    pub diffuse: f32,
+    pub specular: f32,
+    /// Sharpness of [`SpecularModel::Phong`] highlights.
+    pub shininess: f32,
+    /// How highlights are shaped.
+    pub specular_model: SpecularModel,
+    /// Fraction of the reflected scene mixed in, from 0 (matte) to 1 (mirror).
+    pub reflective: f32,
+    /// Fraction of light transmitted through the surface, from 0 (opaque) to 1.
+    pub transparency: f32,
+    /// Index of refraction: 1 for vacuum, about 1.5 for glass.
+    pub refractive_index: f32,
+    /// Fine surface detail that tilts the shading normal.
+    pub normal_map: Option<NormalMap>,
+    /// Light given off by the surface itself, seen whether or not it's lit.
+    pub emission: Color,
+    /// Renders the surface as see-through except for the shadows falling on
+    /// it, whose strength becomes its alpha (see
+    /// [`World::shadow_strength`](crate::world::World::shadow_strength)). A
+    /// floor made of it drops rendered objects' shadows onto a photograph.
+    pub shadow_catcher: bool,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            pattern: None,
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.9,
+            shininess: 200.0,
+            specular_model: SpecularModel::Phong,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            normal_map: None,
+            emission: Color::BLACK,
+            shadow_catcher: false,
+        }
+    }
+}
+
+impl Material {
+    /// How much of each channel a surface of `base_color` reflects head-on
+    /// (F0): 4% grey for dielectrics, blending to the base color itself for
+    /// metals.
+    pub fn base_reflectance(base_color: Color, metalness: f32) -> Color {
+        let dielectric = Color::new(0.04, 0.04, 0.04);
+        dielectric + (base_color - dielectric) * metalness
+    }
+
+    /// The tint on mirrored light leaving a surface of `base_color` at an
+    /// angle whose cosine is `cos`. Metallic [`SpecularModel::Ggx`] surfaces
+    /// tint reflections by their Fresnel reflectance, so gold reflects gold;
+    /// anything else reflects white, leaving the strength to
+    /// [`reflective`](Self::reflective).
+    pub fn reflection_tint(&self, base_color: Color, cos: f32) -> Color {
+        match self.specular_model {
+            SpecularModel::Ggx { metalness, .. } if metalness > 0.0 => {
+                let f0 = Self::base_reflectance(base_color, metalness);
+                let metal = fresnel_schlick(f0, cos);
+                Color::WHITE + (metal - Color::WHITE) * metalness
+            }
+            _ => Color::WHITE,
+        }
+    }
+
+    /// A builder starting from the [default](Material::default) material.
+    pub fn builder() -> MaterialBuilder {
+        MaterialBuilder::default()
+    }
+
+    /// Clear glass: fully transparent and fully reflective, with the two
+    /// blended by Fresnel reflectance, a refractive index of 1.5 and a tight
+    /// highlight. Almost none of its color comes from direct lighting.
+    pub fn glass() -> Self {
+        Self::builder()
+            .ambient(0.0)
+            .diffuse(0.1)
+            .specular(1.0)
+            .shininess(300.0)
+            .reflective(1.0)
+            .transparency(1.0)
+            .refractive_index(1.5)
+            .build()
+    }
+
+    /// Calls `report` with the memory of each image in the pattern and
+    /// normal map; see [`Pattern::textures`].
+    pub fn textures(&self, report: &mut dyn FnMut(TextureMemory)) {
+        if let Some(pattern) = &self.pattern {
+            pattern.textures(report);
+        }
+        match &self.normal_map {
+            Some(NormalMap::Tangent(map)) | Some(NormalMap::Bump { height: map, .. }) => {
+                map.textures(report)
+            }
+            None => {}
+        }
+    }
+}
+
+/// Builds a [`Material`] one property at a time; anything left unset keeps
+/// its default.
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct MaterialBuilder {
+    material: Material,
+}
+
+impl MaterialBuilder {
+    pub fn color(mut self, color: Color) -> Self {
+        self.material.color = color;
+        self
+    }
+
+    pub fn pattern(mut self, pattern: Arc<dyn Pattern>) -> Self {
+        self.material.pattern = Some(pattern);
+        self
+    }
+
+    pub fn ambient(mut self, ambient: f32) -> Self {
+        self.material.ambient = ambient;
+        self
+    }
+
+    pub fn diffuse(mut self, diffuse: f32) -> Self {
+        self.material.diffuse = diffuse;
+        self
+    }
+
+    pub fn specular(mut self, specular: f32) -> Self {
+        self.material.specular = specular;
+        self
+    }
+
+    pub fn shininess(mut self, shininess: f32) -> Self {
+        self.material.shininess = shininess;
+        self
+    }
+
+    pub fn specular_model(mut self, specular_model: SpecularModel) -> Self {
+        self.material.specular_model = specular_model;
+        self
+    }
+
+    pub fn reflective(mut self, reflective: f32) -> Self {
+        self.material.reflective = reflective;
+        self
+    }
+
+    pub fn transparency(mut self, transparency: f32) -> Self {
+        self.material.transparency = transparency;
+        self
+    }
+
+    pub fn refractive_index(mut self, refractive_index: f32) -> Self {
+        self.material.refractive_index = refractive_index;
+        self
+    }
+
+    pub fn normal_map(mut self, normal_map: NormalMap) -> Self {
+        self.material.normal_map = Some(normal_map);
+        self
+    }
+
+    pub fn emission(mut self, emission: Color) -> Self {
+        self.material.emission = emission;
+        self
+    }
+
+    pub fn shadow_catcher(mut self, shadow_catcher: bool) -> Self {
+        self.material.shadow_catcher = shadow_catcher;
+        self
+    }
+
+    pub fn build(self) -> Material {
+        self.material
+    }
+}
+
+/// Starts a builder from an existing material, such as a preset.
+impl From<Material> for MaterialBuilder {
+    fn from(material: Material) -> Self {
+        Self { material }
+    }
+}
+
+/// Whether both are unset or both point at the same value.
+fn same_arc<T: ?Sized>(a: &Option<Arc<T>>, b: &Option<Arc<T>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+        (a, b) => a.is_none() && b.is_none(),
+    }
+}
+
+/// Materials are equal when their properties match and they share the same
+/// pattern instances (patterns themselves can't be compared).
+impl PartialEq for Material {
+    fn eq(&self, other: &Self) -> bool {
+        self.color == other.color
+            && same_arc(&self.pattern, &other.pattern)
+            && self.ambient == other.ambient
+            && self.diffuse == other.diffuse
+            && self.specular == other.specular
+            && self.shininess == other.shininess
+            && self.specular_model == other.specular_model
+            && self.reflective == other.reflective
+            && self.transparency == other.transparency
+            && self.refractive_index == other.refractive_index
+            && self.normal_map == other.normal_map
+            && self.emission == other.emission
+            && self.shadow_catcher == other.shadow_catcher
+    }
+}
+
+/// Step in `u` and `v` between the height samples of a [`NormalMap::Bump`].
+const BUMP_STEP: f32 = 1e-3;
+
+/// Surface detail that tilts the shading normal without changing the
+/// geometry, sampled at the shape's UV mapping and oriented by its
+/// [`local_tangents`](crate::shape::Shape::local_tangents).
+#[derive(Debug, Clone)]
+pub enum NormalMap {
+    /// Tangent-space normals: each color's channels, rescaled from `[0, 1]`
+    /// to `[-1, 1]`, weight the surface's `u` and `v` directions and the true
+    /// normal. Flat areas are `(0.5, 0.5, 1)`.
+    Tangent(Arc<dyn UvPattern>),
+    /// A height field whose brightness is the height: the normal leans away
+    /// from uphill by the slope, found by finite differences, times
+    /// `strength`. Cheaper to author than a tangent map, but coarser.
+    Bump {
+        height: Arc<dyn UvPattern>,
+        strength: f32,
+    },
+}
+
+impl NormalMap {
+    /// The shading normal at `(u, v)`, given the unit `normal` and unit
+    /// `tangent` and `bitangent` along which `u` and `v` increase, all
+    /// mutually perpendicular.
+    pub fn perturb(
+        &self,
+        (u, v): (f32, f32),
+        tangent: Vert4,
+        bitangent: Vert4,
+        normal: Vert4,
+    ) -> Vert4 {
+        match self {
+            Self::Tangent(map) => {
+                let c = map.uv_pattern_at(u, v);
+                let tilt = |channel: f32| channel * 2.0 - 1.0;
+                (tangent * tilt(c.r) + bitangent * tilt(c.g) + normal * tilt(c.b)).normalize()
+            }
+            Self::Bump { height, strength } => {
+                let h = |u: f32, v: f32| {
+                    let c = height.uv_pattern_at(u, v);
+                    (c.r + c.g + c.b) / 3.0
+                };
+                let dh_du = (h(u + BUMP_STEP, v) - h(u - BUMP_STEP, v)) / (2.0 * BUMP_STEP);
+                let dh_dv = (h(u, v + BUMP_STEP) - h(u, v - BUMP_STEP)) / (2.0 * BUMP_STEP);
+                (normal - (tangent * dh_du + bitangent * dh_dv) * *strength).normalize()
+            }
+        }
+    }
+}
+
+/// Normal maps are equal when they're the same kind and share the same map.
+impl PartialEq for NormalMap {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Tangent(a), Self::Tangent(b)) => Arc::ptr_eq(a, b),
+            (
+                Self::Bump { height, strength },
+                Self::Bump {
+                    height: other_height,
+                    strength: other_strength,
+                },
+            ) => Arc::ptr_eq(height, other_height) && strength == other_strength,
+            _ => false,
+        }
+    }
+}
+
+/// Materials registered by name, so scenes can refer to them by name and
+/// many shapes can share one.
+#[derive(Debug, Clone, Default)]
+pub struct MaterialLibrary {
+    materials: HashMap<String, Arc<Material>>,
+}
+
+impl MaterialLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `material` under `name`, replacing any material already
+    /// there, and returns the shared handle to give to shapes.
+    pub fn insert(&mut self, name: impl Into<String>, material: Material) -> Arc<Material> {
+        let material = Arc::new(material);
+        self.materials.insert(name.into(), Arc::clone(&material));
+        material
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<Material>> {
+        self.materials.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Arc<Material>> {
+        self.materials.remove(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.materials.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.materials.is_empty()
+    }
+
+    /// The registered names and materials, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Arc<Material>)> {
+        self.materials.iter().map(|(name, m)| (name.as_str(), m))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::Gradient;
+
+    #[test]
+    fn builder_defaults_match_default() {
+        assert_eq!(Material::builder().build(), Material::default());
+    }
+
+    #[test]
+    fn builder_sets_each_property() {
+        let pattern: Arc<dyn Pattern> = Arc::new(Gradient::new(Color::WHITE, Color::BLACK));
+        let m = Material::builder()
+            .color(Color::new(1.0, 0.0, 0.0))
+            .pattern(Arc::clone(&pattern))
+            .ambient(0.2)
+            .diffuse(0.3)
+            .specular(0.4)
+            .shininess(50.0)
+            .reflective(0.5)
+            .transparency(0.6)
+            .refractive_index(1.3)
+            .emission(Color::new(0.0, 0.1, 0.0))
+            .shadow_catcher(true)
+            .build();
+        assert_eq!(
+            m,
+            Material {
+                color: Color::new(1.0, 0.0, 0.0),
+                pattern: Some(pattern),
+                ambient: 0.2,
+                diffuse: 0.3,
+                specular: 0.4,
+                shininess: 50.0,
+                specular_model: SpecularModel::Phong,
+                reflective: 0.5,
+                transparency: 0.6,
+                refractive_index: 1.3,
+                normal_map: None,
+                emission: Color::new(0.0, 0.1, 0.0),
+                shadow_catcher: true,
+            }
+        );
+    }
+
+    #[test]
+    fn glass_preset() {
+        let glass = Material::glass();
+        assert_eq!(glass.transparency, 1.0);
+        assert_eq!(glass.reflective, 1.0);
+        assert_eq!(glass.refractive_index, 1.5);
+    }
+
+    #[test]
+    fn presets_can_be_adjusted() {
+        let tinted = MaterialBuilder::from(Material::glass())
+            .color(Color::new(0.8, 1.0, 0.8))
+            .build();
+        assert_eq!(tinted.refractive_index, 1.5);
+        assert_eq!(tinted.color, Color::new(0.8, 1.0, 0.8));
+    }
+
+    #[test]
+    fn library_shares_materials_by_name() {
+        let mut library = MaterialLibrary::new();
+        let glass = library.insert("glass", Material::glass());
+        assert!(Arc::ptr_eq(library.get("glass").unwrap(), &glass));
+        assert!(library.get("chrome").is_none());
+        assert_eq!(library.len(), 1);
+        assert!(library.remove("glass").is_some());
+        assert!(library.is_empty());
+    }
+
+    #[test]
+    fn base_reflectance_blends_to_base_color() {
+        let gold = Color::new(1.0, 0.78, 0.34);
+        assert_approx_eq!(
+            Material::base_reflectance(gold, 0.0),
+            Color::new(0.04, 0.04, 0.04)
+        );
+        assert_approx_eq!(Material::base_reflectance(gold, 1.0), gold);
+    }
+
+    #[test]
+    fn metals_tint_reflections() {
+        let gold = Color::new(1.0, 0.78, 0.34);
+        let metal = Material::builder()
+            .specular_model(SpecularModel::Ggx {
+                roughness: 0.3,
+                metalness: 1.0,
+            })
+            .build();
+        assert_approx_eq!(metal.reflection_tint(gold, 1.0), gold);
+        // Towards grazing angles every metal reflects white.
+        assert_approx_eq!(metal.reflection_tint(gold, 0.0), Color::WHITE);
+        assert_eq!(Material::default().reflection_tint(gold, 1.0), Color::WHITE);
+    }
+}
@@ -0,0 +1,358 @@
+use std::array;
+use std::ops::{Index, IndexMut, Mul};
+
+use super::{ApproxEq, Vert4};
+
+/// A row-major 4×4 matrix used for affine transforms of [`Vert4`]s.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix4 {
+    rows: [[f32; 4]; 4],
+}
+
+impl Default for Matrix4 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Matrix4 {
+    pub const IDENTITY: Self = Self::new([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    pub const fn new(rows: [[f32; 4]; 4]) -> Self {
+        Self { rows }
+    }
+
+    pub fn rows(&self) -> &[[f32; 4]; 4] {
+        &self.rows
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut out = Self::IDENTITY;
+        for (r, row) in self.rows.iter().enumerate() {
+            for (c, value) in row.iter().enumerate() {
+                out.rows[c][r] = *value;
+            }
+        }
+        out
+    }
+
+    /// The signed determinant of the 3×3 matrix left after removing `row`
+    /// and `col`.
+    pub fn cofactor(&self, row: usize, col: usize) -> f32 {
+        let skip = |n: usize| -> [usize; 3] { array::from_fn(|i| if i < n { i } else { i + 1 }) };
+        let (rows, cols) = (skip(row), skip(col));
+        let m = |r: usize, c: usize| self.rows[rows[r]][cols[c]];
+        let minor = m(0, 0) * (m(1, 1) * m(2, 2) - m(1, 2) * m(2, 1))
+            - m(0, 1) * (m(1, 0) * m(2, 2) - m(1, 2) * m(2, 0))
+            + m(0, 2) * (m(1, 0) * m(2, 1) - m(1, 1) * m(2, 0));
+        if (row + col).is_multiple_of(2) {
+            minor
+        } else {
+            -minor
+        }
+    }
+
+    pub fn determinant(&self) -> f32 {
+        (0..4).map(|c| self.rows[0][c] * self.cofactor(0, c)).sum()
+    }
+
+    /// Returns `None` when the matrix is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det == 0.0 {
+            return None;
+        }
+        let mut out = Self::IDENTITY;
+        for (r, row) in out.rows.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                *value = self.cofactor(c, r) / det;
+            }
+        }
+        Some(out)
+    }
+
+    pub const fn translation(x: f32, y: f32, z: f32) -> Self {
+        Self::new([
+            [1.0, 0.0, 0.0, x],
+            [0.0, 1.0, 0.0, y],
+            [0.0, 0.0, 1.0, z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub const fn scaling(x: f32, y: f32, z: f32) -> Self {
+        Self::new([
+            [x, 0.0, 0.0, 0.0],
+            [0.0, y, 0.0, 0.0],
+            [0.0, 0.0, z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_x(radians: f32) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        Self::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, cos, -sin, 0.0],
+            [0.0, sin, cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_y(radians: f32) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        Self::new([
+            [cos, 0.0, sin, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [-sin, 0.0, cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn rotation_z(radians: f32) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        Self::new([
+            [cos, -sin, 0.0, 0.0],
+            [sin, cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Shears each axis in proportion to the other two, e.g. `xy` moves x in proportion to y.
+    pub const fn shearing(xy: f32, xz: f32, yx: f32, yz: f32, zx: f32, zy: f32) -> Self {
+        Self::new([
+            [1.0, xy, xz, 0.0],
+            [yx, 1.0, yz, 0.0],
+            [zx, zy, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+}
+
+impl ApproxEq for Matrix4 {
+    fn approx_eq(&self, other: &Self) -> bool {
+        self.rows
+            .iter()
+            .flatten()
+            .zip(other.rows.iter().flatten())
+            .all(|(a, b)| a.approx_eq(b))
+    }
+}
+
+impl Index<(usize, usize)> for Matrix4 {
+    type Output = f32;
+
+    fn index(&self, (row, col): (usize, usize)) -> &f32 {
+        &self.rows[row][col]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Matrix4 {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut f32 {
+        &mut self.rows[row][col]
+    }
+}
+
+impl Mul for Matrix4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let mut out = Self::new([[0.0; 4]; 4]);
+        for r in 0..4 {
+            for c in 0..4 {
+                out.rows[r][c] = (0..4).map(|i| self.rows[r][i] * rhs.rows[i][c]).sum();
+            }
+        }
+        out
+    }
+}
+
+impl Mul<Vert4> for Matrix4 {
+    type Output = Vert4;
+
+    fn mul(self, rhs: Vert4) -> Vert4 {
+        let row = |r: [f32; 4]| r[0] * rhs.x + r[1] * rhs.y + r[2] * rhs.z + r[3] * rhs.w;
+        Vert4::new(
+            row(self.rows[0]),
+            row(self.rows[1]),
+            row(self.rows[2]),
+            row(self.rows[3]),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    #[test]
+    fn multiply_matrices() {
+        let a = Matrix4::new([
+            [1.0, 2.0, 3.0, 4.0],
+            [5.0, 6.0, 7.0, 8.0],
+            [9.0, 8.0, 7.0, 6.0],
+            [5.0, 4.0, 3.0, 2.0],
+        ]);
+        let b = Matrix4::new([
+            [-2.0, 1.0, 2.0, 3.0],
+            [3.0, 2.0, 1.0, -1.0],
+            [4.0, 3.0, 6.0, 5.0],
+            [1.0, 2.0, 7.0, 8.0],
+        ]);
+        let expected = Matrix4::new([
+            [20.0, 22.0, 50.0, 48.0],
+            [44.0, 54.0, 114.0, 108.0],
+            [40.0, 58.0, 110.0, 102.0],
+            [16.0, 26.0, 46.0, 42.0],
+        ]);
+        assert_eq!(a * b, expected);
+        assert_eq!(a * Matrix4::IDENTITY, a);
+    }
+
+    #[test]
+    fn multiply_by_vert() {
+        let a = Matrix4::new([
+            [1.0, 2.0, 3.0, 4.0],
+            [2.0, 4.0, 4.0, 2.0],
+            [8.0, 6.0, 4.0, 1.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        assert_eq!(
+            a * Vert4::new(1.0, 2.0, 3.0, 1.0),
+            Vert4::new(18.0, 24.0, 33.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn transpose() {
+        let a = Matrix4::new([
+            [0.0, 9.0, 3.0, 0.0],
+            [9.0, 8.0, 0.0, 8.0],
+            [1.0, 8.0, 5.0, 3.0],
+            [0.0, 0.0, 5.0, 8.0],
+        ]);
+        let expected = Matrix4::new([
+            [0.0, 9.0, 1.0, 0.0],
+            [9.0, 8.0, 8.0, 0.0],
+            [3.0, 0.0, 5.0, 5.0],
+            [0.0, 8.0, 3.0, 8.0],
+        ]);
+        assert_eq!(a.transpose(), expected);
+        assert_eq!(Matrix4::IDENTITY.transpose(), Matrix4::IDENTITY);
+    }
+
+    #[test]
+    fn determinant_and_singular_inverse() {
+        let a = Matrix4::new([
+            [-2.0, -8.0, 3.0, 5.0],
+            [-3.0, 1.0, 7.0, 3.0],
+            [1.0, 2.0, -9.0, 6.0],
+            [-6.0, 7.0, 7.0, -9.0],
+        ]);
+        assert_eq!(a.cofactor(0, 0), 690.0);
+        assert_eq!(a.cofactor(0, 1), 447.0);
+        assert_eq!(a.cofactor(0, 2), 210.0);
+        assert_eq!(a.cofactor(0, 3), 51.0);
+        assert_eq!(a.determinant(), -4071.0);
+        let singular = Matrix4::new([
+            [-4.0, 2.0, -2.0, -3.0],
+            [9.0, 6.0, 2.0, 6.0],
+            [0.0, -5.0, 1.0, -5.0],
+            [0.0, 0.0, 0.0, 0.0],
+        ]);
+        assert_eq!(singular.inverse(), None);
+    }
+
+    #[test]
+    fn inverse() {
+        let a = Matrix4::new([
+            [-5.0, 2.0, 6.0, -8.0],
+            [1.0, -5.0, 1.0, 8.0],
+            [7.0, 7.0, -6.0, -7.0],
+            [1.0, -3.0, 7.0, 4.0],
+        ]);
+        let expected = Matrix4::new([
+            [0.21805, 0.45113, 0.24060, -0.04511],
+            [-0.80827, -1.45677, -0.44361, 0.52068],
+            [-0.07895, -0.22368, -0.05263, 0.19737],
+            [-0.52256, -0.81391, -0.30075, 0.30639],
+        ]);
+        assert_approx_eq!(a.inverse().unwrap(), expected);
+
+        let b = Matrix4::new([
+            [3.0, -9.0, 7.0, 3.0],
+            [3.0, -8.0, 2.0, -9.0],
+            [-4.0, 4.0, 4.0, 1.0],
+            [-6.0, 5.0, -1.0, 1.0],
+        ]);
+        assert_approx_eq!((a * b) * b.inverse().unwrap(), a);
+    }
+
+    #[test]
+    fn translation_and_scaling() {
+        let p = Vert4::point(-3.0, 4.0, 5.0);
+        assert_eq!(
+            Matrix4::translation(5.0, -3.0, 2.0) * p,
+            Vert4::point(2.0, 1.0, 7.0)
+        );
+        let v = Vert4::vector(-3.0, 4.0, 5.0);
+        assert_eq!(Matrix4::translation(5.0, -3.0, 2.0) * v, v);
+        assert_eq!(
+            Matrix4::scaling(2.0, 3.0, 4.0) * v,
+            Vert4::vector(-6.0, 12.0, 20.0)
+        );
+        assert_approx_eq!(
+            Matrix4::scaling(2.0, 3.0, 4.0).inverse().unwrap() * v,
+            Vert4::vector(-1.5, 4.0 / 3.0, 1.25)
+        );
+    }
+
+    #[test]
+    fn rotations() {
+        let half = 2f32.sqrt() / 2.0;
+        assert_approx_eq!(
+            Matrix4::rotation_x(PI / 4.0) * Vert4::point(0.0, 1.0, 0.0),
+            Vert4::point(0.0, half, half)
+        );
+        assert_approx_eq!(
+            Matrix4::rotation_y(PI / 2.0) * Vert4::point(0.0, 0.0, 1.0),
+            Vert4::point(1.0, 0.0, 0.0)
+        );
+        assert_approx_eq!(
+            Matrix4::rotation_z(PI / 2.0) * Vert4::point(0.0, 1.0, 0.0),
+            Vert4::point(-1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn shearing() {
+        let p = Vert4::point(2.0, 3.0, 4.0);
+        assert_eq!(
+            Matrix4::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0) * p,
+            Vert4::point(5.0, 3.0, 4.0)
+        );
+        assert_eq!(
+            Matrix4::shearing(0.0, 0.0, 0.0, 0.0, 0.0, 1.0) * p,
+            Vert4::point(2.0, 3.0, 7.0)
+        );
+    }
+
+    #[test]
+    fn chained_transforms_apply_in_reverse_order() {
+        let t = Matrix4::translation(10.0, 5.0, 7.0)
+            * Matrix4::scaling(5.0, 5.0, 5.0)
+            * Matrix4::rotation_x(PI / 2.0);
+        assert_approx_eq!(
+            t * Vert4::point(1.0, 0.0, 1.0),
+            Vert4::point(15.0, 0.0, 7.0)
+        );
+    }
+}
@@ -0,0 +1,44 @@
+//! Points, vectors, matrices and the numeric helpers shared by the tracer.
+
+mod matrix;
+pub mod noise;
+pub mod random;
+pub mod roots;
+mod transform;
+mod vert;
+
+pub use matrix::Matrix4;
+pub use transform::Transform;
+pub use vert::Vert4;
+
+/// Tolerance used when comparing floating point values.
+pub const EPSILON: f32 = 1e-4;
+
+/// Equality within [`EPSILON`], for types built out of `f32`s.
+pub trait ApproxEq {
+    fn approx_eq(&self, other: &Self) -> bool;
+}
+
+impl ApproxEq for f32 {
+    fn approx_eq(&self, other: &Self) -> bool {
+        (self - other).abs() < EPSILON
+    }
+}
+
+impl<T: ApproxEq> ApproxEq for [T] {
+    fn approx_eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a.approx_eq(b))
+    }
+}
+
+impl<T: ApproxEq, const N: usize> ApproxEq for [T; N] {
+    fn approx_eq(&self, other: &Self) -> bool {
+        self[..].approx_eq(&other[..])
+    }
+}
+
+impl<T: ApproxEq> ApproxEq for Vec<T> {
+    fn approx_eq(&self, other: &Self) -> bool {
+        self[..].approx_eq(&other[..])
+    }
+}
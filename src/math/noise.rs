@@ -0,0 +1,131 @@
+//! Ken Perlin's improved gradient noise.
+//!
+//! The noise is smooth, repeats every 256 units on each axis, is zero at
+//! integer lattice points and stays within roughly `-1..=1`.
+
+use super::Vert4;
+
+/// Perlin's reference permutation of `0..256`.
+const PERMUTATION: [u8; 256] = [
+    151, 160, 137, 91, 90, 15, 131, 13, 201, 95, 96, 53, 194, 233, 7, 225, 140, 36, 103, 30, 69,
+    142, 8, 99, 37, 240, 21, 10, 23, 190, 6, 148, 247, 120, 234, 75, 0, 26, 197, 62, 94, 252, 219,
+    203, 117, 35, 11, 32, 57, 177, 33, 88, 237, 149, 56, 87, 174, 20, 125, 136, 171, 168, 68, 175,
+    74, 165, 71, 134, 139, 48, 27, 166, 77, 146, 158, 231, 83, 111, 229, 122, 60, 211, 133, 230,
+    220, 105, 92, 41, 55, 46, 245, 40, 244, 102, 143, 54, 65, 25, 63, 161, 1, 216, 80, 73, 209, 76,
+    132, 187, 208, 89, 18, 169, 200, 196, 135, 130, 116, 188, 159, 86, 164, 100, 109, 198, 173,
+    186, 3, 64, 52, 217, 226, 250, 124, 123, 5, 202, 38, 147, 118, 126, 255, 82, 85, 212, 207, 206,
+    59, 227, 47, 16, 58, 17, 182, 189, 28, 42, 223, 183, 170, 213, 119, 248, 152, 2, 44, 154, 163,
+    70, 221, 153, 101, 155, 167, 43, 172, 9, 129, 22, 39, 253, 19, 98, 108, 110, 79, 113, 224, 232,
+    178, 185, 112, 104, 218, 246, 97, 228, 251, 34, 242, 193, 238, 210, 144, 12, 191, 179, 162,
+    241, 81, 51, 145, 235, 249, 14, 239, 107, 49, 192, 214, 31, 181, 199, 106, 157, 184, 84, 204,
+    176, 115, 121, 50, 45, 127, 4, 150, 254, 138, 236, 205, 93, 222, 114, 67, 29, 24, 72, 243, 141,
+    128, 195, 78, 66, 215, 61, 156, 180,
+];
+
+fn hash(i: usize) -> usize {
+    usize::from(PERMUTATION[i & 255])
+}
+
+/// Perlin's quintic ease curve, `6t⁵ - 15t⁴ + 10t³`.
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    a + t * (b - a)
+}
+
+/// The dot product of `(x, y, z)` with one of twelve edge gradients picked by `hash`.
+fn grad(hash: usize, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+/// Noise at the xyz components of `point`.
+pub fn perlin(point: Vert4) -> f32 {
+    let (fx, fy, fz) = (point.x.floor(), point.y.floor(), point.z.floor());
+    // Wrap the lattice cell into the table, negative coordinates included.
+    let cell = |f: f32| f.rem_euclid(256.0) as usize;
+    let (xi, yi, zi) = (cell(fx), cell(fy), cell(fz));
+    let (x, y, z) = (point.x - fx, point.y - fy, point.z - fz);
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let a = hash(xi) + yi;
+    let aa = hash(a) + zi;
+    let ab = hash(a + 1) + zi;
+    let b = hash(xi + 1) + yi;
+    let ba = hash(b) + zi;
+    let bb = hash(b + 1) + zi;
+
+    lerp(
+        w,
+        lerp(
+            v,
+            lerp(u, grad(hash(aa), x, y, z), grad(hash(ba), x - 1.0, y, z)),
+            lerp(
+                u,
+                grad(hash(ab), x, y - 1.0, z),
+                grad(hash(bb), x - 1.0, y - 1.0, z),
+            ),
+        ),
+        lerp(
+            v,
+            lerp(
+                u,
+                grad(hash(aa + 1), x, y, z - 1.0),
+                grad(hash(ba + 1), x - 1.0, y, z - 1.0),
+            ),
+            lerp(
+                u,
+                grad(hash(ab + 1), x, y - 1.0, z - 1.0),
+                grad(hash(bb + 1), x - 1.0, y - 1.0, z - 1.0),
+            ),
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_on_lattice_points() {
+        for (x, y, z) in [(0.0, 0.0, 0.0), (3.0, -7.0, 12.0), (255.0, 1.0, -256.0)] {
+            assert_eq!(perlin(Vert4::point(x, y, z)), 0.0);
+        }
+    }
+
+    #[test]
+    fn bounded_and_varying() {
+        let samples: Vec<f32> = (0..500)
+            .map(|i| {
+                let t = i as f32 * 0.37;
+                perlin(Vert4::point(t, t * 0.61 - 40.0, t * 1.3 + 5.0))
+            })
+            .collect();
+        assert!(samples.iter().all(|n| n.abs() <= 1.05));
+        assert!(samples.iter().any(|n| n.abs() > 0.2));
+    }
+
+    #[test]
+    fn continuous() {
+        let p = Vert4::point(1.3, 2.7, -0.4);
+        let q = Vert4::point(1.3001, 2.7, -0.4);
+        assert!((perlin(p) - perlin(q)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn repeats_every_256_units() {
+        let p = Vert4::point(1.3, 2.7, -0.4);
+        let q = Vert4::point(1.3 + 256.0, 2.7, -0.4 - 256.0);
+        assert_approx_eq!(perlin(p), perlin(q));
+    }
+}
@@ -0,0 +1,198 @@
+//! Real roots of low-degree polynomials, in `f64` for numerical headroom.
+//!
+//! Each solver takes coefficients from the highest degree down and returns
+//! the real roots in ascending order. Repeated roots may be reported once
+//! or several times depending on rounding.
+
+const EPS: f64 = 1e-9;
+
+fn sorted(mut roots: Vec<f64>) -> Vec<f64> {
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+/// Roots of `a·x² + b·x + c`.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a.abs() < EPS {
+        return if b.abs() < EPS {
+            Vec::new()
+        } else {
+            vec![-c / b]
+        };
+    }
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 {
+        return Vec::new();
+    }
+    // Avoid cancellation between -b and the root of the discriminant.
+    let q = -0.5 * (b + b.signum() * disc.sqrt());
+    if q == 0.0 {
+        return vec![0.0, 0.0];
+    }
+    sorted(vec![q / a, c / q])
+}
+
+/// Roots of `a·x³ + b·x² + c·x + d`.
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    if a.abs() < EPS {
+        return solve_quadratic(b, c, d);
+    }
+    let (b, c, d) = (b / a, c / a, d / a);
+    // Depress with x = y - b/3: y³ + p·y + q.
+    let shift = b / 3.0;
+    let p = c - b * b / 3.0;
+    let q = 2.0 * b * b * b / 27.0 - b * c / 3.0 + d;
+    let disc = (q / 2.0).powi(2) + (p / 3.0).powi(3);
+    let roots = if disc > EPS {
+        let s = disc.sqrt();
+        vec![(-q / 2.0 + s).cbrt() + (-q / 2.0 - s).cbrt()]
+    } else if disc < -EPS {
+        // Three distinct real roots: trigonometric form.
+        let r = (-p / 3.0).sqrt();
+        let phi = (-q / (2.0 * r * r * r)).clamp(-1.0, 1.0).acos();
+        (0..3)
+            .map(|k| 2.0 * r * ((phi + 2.0 * std::f64::consts::PI * f64::from(k)) / 3.0).cos())
+            .collect()
+    } else {
+        let u = (-q / 2.0).cbrt();
+        vec![2.0 * u, -u]
+    };
+    sorted(roots.into_iter().map(|y| y - shift).collect())
+}
+
+fn eval(coeffs: &[f64], x: f64) -> (f64, f64) {
+    let (mut value, mut derivative) = (0.0, 0.0);
+    for &c in coeffs {
+        derivative = derivative * x + value;
+        value = value * x + c;
+    }
+    (value, derivative)
+}
+
+/// A few Newton steps against the original polynomial to recover precision
+/// lost in the closed-form solution.
+fn polish(coeffs: &[f64], mut x: f64) -> f64 {
+    for _ in 0..4 {
+        let (value, derivative) = eval(coeffs, x);
+        if derivative.abs() < EPS {
+            break;
+        }
+        let next = x - value / derivative;
+        if !next.is_finite() {
+            break;
+        }
+        x = next;
+    }
+    x
+}
+
+/// Roots of `a·x⁴ + b·x³ + c·x² + d·x + e`, via Ferrari's resolvent cubic.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if a.abs() < EPS {
+        return solve_cubic(b, c, d, e);
+    }
+    let coeffs = [a, b, c, d, e];
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+    // Depress with x = y - b/4: y⁴ + p·y² + q·y + r.
+    let shift = b / 4.0;
+    let b2 = b * b;
+    let p = c - 3.0 * b2 / 8.0;
+    let q = d - b * c / 2.0 + b2 * b / 8.0;
+    let r = e - b * d / 4.0 + b2 * c / 16.0 - 3.0 * b2 * b2 / 256.0;
+
+    let ys = if q.abs() < EPS {
+        // Biquadratic: solve for y² first.
+        solve_quadratic(1.0, p, r)
+            .into_iter()
+            .filter(|&z| z >= -EPS)
+            .flat_map(|z| {
+                let y = z.max(0.0).sqrt();
+                [-y, y]
+            })
+            .collect()
+    } else {
+        // Any positive root m of the resolvent splits the quartic into two quadratics.
+        let m = solve_cubic(1.0, 2.0 * p, p * p - 4.0 * r, -q * q)
+            .into_iter()
+            .fold(f64::NAN, f64::max);
+        if m.is_nan() || m <= 0.0 {
+            return Vec::new();
+        }
+        let sm = m.sqrt();
+        let mut ys = solve_quadratic(1.0, sm, (p + m) / 2.0 - q / (2.0 * sm));
+        ys.extend(solve_quadratic(1.0, -sm, (p + m) / 2.0 + q / (2.0 * sm)));
+        ys
+    };
+    sorted(ys.into_iter().map(|y| polish(&coeffs, y - shift)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roots(actual: Vec<f64>, expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn quadratic() {
+        assert_roots(solve_quadratic(1.0, -3.0, 2.0), &[1.0, 2.0]);
+        assert_roots(solve_quadratic(1.0, 0.0, 1.0), &[]);
+        assert_roots(solve_quadratic(0.0, 2.0, -4.0), &[2.0]);
+    }
+
+    #[test]
+    fn cubic() {
+        // (x - 1)(x - 2)(x + 3)
+        assert_roots(solve_cubic(1.0, 0.0, -7.0, 6.0), &[-3.0, 1.0, 2.0]);
+        // (x - 2)(x² + 1)
+        assert_roots(solve_cubic(1.0, -2.0, 1.0, -2.0), &[2.0]);
+        // (x - 1)³
+        let roots = solve_cubic(1.0, -3.0, 3.0, -1.0);
+        assert!(roots.iter().all(|r| (r - 1.0).abs() < 1e-5), "{roots:?}");
+    }
+
+    #[test]
+    fn quartic_four_roots() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert_roots(
+            solve_quartic(1.0, -10.0, 35.0, -50.0, 24.0),
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+    }
+
+    #[test]
+    fn quartic_biquadratic() {
+        // (x² - 1)(x² - 4)
+        assert_roots(
+            solve_quartic(1.0, 0.0, -5.0, 0.0, 4.0),
+            &[-2.0, -1.0, 1.0, 2.0],
+        );
+    }
+
+    #[test]
+    fn quartic_two_roots_and_none() {
+        // (x - 1)(x + 2)(x² + 1)
+        assert_roots(solve_quartic(1.0, 1.0, -1.0, 1.0, -2.0), &[-2.0, 1.0]);
+        // x⁴ + 1
+        assert_roots(solve_quartic(1.0, 0.0, 0.0, 0.0, 1.0), &[]);
+    }
+
+    #[test]
+    fn quartic_scaled_and_widely_spread() {
+        // 3(x - 0.01)(x - 0.02)(x - 100)(x + 50)
+        let roots = [0.01, 0.02, 100.0, -50.0];
+        let (r0, r1, r2, r3) = (roots[0], roots[1], roots[2], roots[3]);
+        let b = -(r0 + r1 + r2 + r3);
+        let c = r0 * r1 + r0 * r2 + r0 * r3 + r1 * r2 + r1 * r3 + r2 * r3;
+        let d = -(r0 * r1 * r2 + r0 * r1 * r3 + r0 * r2 * r3 + r1 * r2 * r3);
+        let e = r0 * r1 * r2 * r3;
+        assert_roots(
+            solve_quartic(3.0, 3.0 * b, 3.0 * c, 3.0 * d, 3.0 * e),
+            &[-50.0, 0.01, 0.02, 100.0],
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use super::{Matrix4, Vert4};
+
+/// An invertible transform with its inverse and inverse-transpose precomputed,
+/// so world/object space conversions never re-invert per ray.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+    inverse_transpose: Matrix4,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        matrix: Matrix4::IDENTITY,
+        inverse: Matrix4::IDENTITY,
+        inverse_transpose: Matrix4::IDENTITY,
+    };
+
+    /// # Panics
+    ///
+    /// Panics if `matrix` is singular; use [`try_new`](Self::try_new) to handle that case.
+    pub fn new(matrix: Matrix4) -> Self {
+        Self::try_new(matrix).expect("transform matrix must be invertible")
+    }
+
+    pub fn try_new(matrix: Matrix4) -> Option<Self> {
+        let inverse = matrix.inverse()?;
+        Some(Self {
+            matrix,
+            inverse,
+            inverse_transpose: inverse.transpose(),
+        })
+    }
+
+    pub fn matrix(&self) -> &Matrix4 {
+        &self.matrix
+    }
+
+    pub fn inverse(&self) -> &Matrix4 {
+        &self.inverse
+    }
+
+    pub fn inverse_transpose(&self) -> &Matrix4 {
+        &self.inverse_transpose
+    }
+
+    /// Maps a world-space point or vector into the transformed (object) space.
+    pub fn to_local(&self, v: Vert4) -> Vert4 {
+        self.inverse * v
+    }
+
+    /// Maps an object-space normal back to world space via the inverse-transpose,
+    /// dropping any translation picked up in `w` and renormalizing.
+    pub fn normal_to_world(&self, normal: Vert4) -> Vert4 {
+        let mut world = self.inverse_transpose * normal;
+        world.w = 0.0;
+        world.normalize()
+    }
+}
+
+impl From<Matrix4> for Transform {
+    fn from(matrix: Matrix4) -> Self {
+        Self::new(matrix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caches_inverse_and_inverse_transpose() {
+        let m = Matrix4::translation(1.0, 2.0, 3.0) * Matrix4::scaling(2.0, 2.0, 2.0);
+        let t = Transform::new(m);
+        assert_eq!(t.matrix(), &m);
+        assert_approx_eq!(*t.matrix() * *t.inverse(), Matrix4::IDENTITY);
+        assert_approx_eq!(*t.inverse_transpose(), m.inverse().unwrap().transpose());
+    }
+
+    #[test]
+    fn singular_matrix_is_rejected() {
+        assert_eq!(Transform::try_new(Matrix4::scaling(0.0, 1.0, 1.0)), None);
+    }
+}
@@ -0,0 +1,178 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use super::ApproxEq;
+
+/// A homogeneous 4-component vertex: a point when `w == 1`, a vector when `w == 0`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Vert4 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Vert4 {
+    pub const fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Self { x, y, z, w }
+    }
+
+    pub const fn point(x: f32, y: f32, z: f32) -> Self {
+        Self::new(x, y, z, 1.0)
+    }
+
+    pub const fn vector(x: f32, y: f32, z: f32) -> Self {
+        Self::new(x, y, z, 0.0)
+    }
+
+    pub fn is_point(&self) -> bool {
+        self.w == 1.0
+    }
+
+    pub fn is_vector(&self) -> bool {
+        self.w == 0.0
+    }
+
+    pub fn magnitude(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Self {
+        *self / self.magnitude()
+    }
+
+    pub fn dot(&self, other: &Self) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    /// Cross product of the xyz components; the result is always a vector.
+    pub fn cross(&self, other: &Self) -> Self {
+        Self::vector(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    /// This vector mirrored about `normal`, which must be of unit length.
+    pub fn reflect(&self, normal: &Self) -> Self {
+        *self - *normal * 2.0 * self.dot(normal)
+    }
+}
+
+impl ApproxEq for Vert4 {
+    fn approx_eq(&self, other: &Self) -> bool {
+        self.x.approx_eq(&other.x)
+            && self.y.approx_eq(&other.y)
+            && self.z.approx_eq(&other.z)
+            && self.w.approx_eq(&other.w)
+    }
+}
+
+impl Add for Vert4 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(
+            self.x + rhs.x,
+            self.y + rhs.y,
+            self.z + rhs.z,
+            self.w + rhs.w,
+        )
+    }
+}
+
+impl Sub for Vert4 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(
+            self.x - rhs.x,
+            self.y - rhs.y,
+            self.z - rhs.z,
+            self.w - rhs.w,
+        )
+    }
+}
+
+impl Neg for Vert4 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y, -self.z, -self.w)
+    }
+}
+
+impl Mul<f32> for Vert4 {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        Self::new(self.x * rhs, self.y * rhs, self.z * rhs, self.w * rhs)
+    }
+}
+
+impl Div<f32> for Vert4 {
+    type Output = Self;
+
+    fn div(self, rhs: f32) -> Self {
+        Self::new(self.x / rhs, self.y / rhs, self.z / rhs, self.w / rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn point_and_vector_constructors() {
+        let p = Vert4::point(4.0, -4.0, 3.0);
+        let v = Vert4::vector(4.0, -4.0, 3.0);
+        assert!(p.is_point() && !p.is_vector());
+        assert!(v.is_vector() && !v.is_point());
+    }
+
+    #[test]
+    fn arithmetic() {
+        let p = Vert4::point(3.0, 2.0, 1.0);
+        let v = Vert4::vector(5.0, 6.0, 7.0);
+        assert_eq!(p - v, Vert4::point(-2.0, -4.0, -6.0));
+        assert_eq!(p + v, Vert4::point(8.0, 8.0, 8.0));
+        assert_eq!(-v, Vert4::vector(-5.0, -6.0, -7.0));
+        assert_eq!(v * 2.0, Vert4::vector(10.0, 12.0, 14.0));
+        assert_eq!(v / 2.0, Vert4::vector(2.5, 3.0, 3.5));
+    }
+
+    #[test]
+    fn magnitude_and_normalize() {
+        let v = Vert4::vector(1.0, 2.0, 3.0);
+        assert_approx_eq!(v.magnitude(), 14f32.sqrt());
+        assert_approx_eq!(v.normalize().magnitude(), 1.0);
+        assert_approx_eq!(
+            Vert4::vector(4.0, 0.0, 0.0).normalize(),
+            Vert4::vector(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn dot_and_cross() {
+        let a = Vert4::vector(1.0, 2.0, 3.0);
+        let b = Vert4::vector(2.0, 3.0, 4.0);
+        assert_eq!(a.dot(&b), 20.0);
+        assert_eq!(a.cross(&b), Vert4::vector(-1.0, 2.0, -1.0));
+        assert_eq!(b.cross(&a), Vert4::vector(1.0, -2.0, 1.0));
+    }
+
+    #[test]
+    fn reflecting_vectors() {
+        let v = Vert4::vector(1.0, -1.0, 0.0);
+        assert_eq!(
+            v.reflect(&Vert4::vector(0.0, 1.0, 0.0)),
+            Vert4::vector(1.0, 1.0, 0.0)
+        );
+        let half = 2f32.sqrt() / 2.0;
+        let v = Vert4::vector(0.0, -1.0, 0.0);
+        assert_approx_eq!(
+            v.reflect(&Vert4::vector(half, half, 0.0)),
+            Vert4::vector(1.0, 0.0, 0.0)
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use super::UvPattern;
+use crate::color::Color;
+
+/// A test card for UV mappings: `main` everywhere except a distinct color in
+/// each corner of the unit square, so a misplaced or mirrored mapping is
+/// obvious at a glance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlignCheck {
+    pub main: Color,
+    pub upper_left: Color,
+    pub upper_right: Color,
+    pub bottom_left: Color,
+    pub bottom_right: Color,
+}
+
+impl AlignCheck {
+    pub const fn new(
+        main: Color,
+        upper_left: Color,
+        upper_right: Color,
+        bottom_left: Color,
+        bottom_right: Color,
+    ) -> Self {
+        Self {
+            main,
+            upper_left,
+            upper_right,
+            bottom_left,
+            bottom_right,
+        }
+    }
+}
+
+impl UvPattern for AlignCheck {
+    fn uv_pattern_at(&self, u: f32, v: f32) -> Color {
+        match (u < 0.2, u > 0.8, v < 0.2, v > 0.8) {
+            (true, _, _, true) => self.upper_left,
+            (_, true, _, true) => self.upper_right,
+            (true, _, true, _) => self.bottom_left,
+            (_, true, true, _) => self.bottom_right,
+            _ => self.main,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vert4;
+    use crate::pattern::uv::CubeFace;
+    use crate::pattern::Pattern;
+    use crate::shape::Cylinder;
+
+    const MAIN: Color = Color::WHITE;
+    const UL: Color = Color::new(1.0, 0.0, 0.0);
+    const UR: Color = Color::new(1.0, 1.0, 0.0);
+    const BL: Color = Color::new(0.0, 1.0, 0.0);
+    const BR: Color = Color::new(0.0, 1.0, 1.0);
+
+    fn check() -> AlignCheck {
+        AlignCheck::new(MAIN, UL, UR, BL, BR)
+    }
+
+    #[test]
+    fn corners_and_centre() {
+        let p = check();
+        assert_eq!(p.uv_pattern_at(0.5, 0.5), MAIN);
+        assert_eq!(p.uv_pattern_at(0.1, 0.9), UL);
+        assert_eq!(p.uv_pattern_at(0.9, 0.9), UR);
+        assert_eq!(p.uv_pattern_at(0.1, 0.1), BL);
+        assert_eq!(p.uv_pattern_at(0.9, 0.1), BR);
+        assert_eq!(p.uv_pattern_at(0.1, 0.5), MAIN);
+    }
+
+    #[test]
+    fn upper_left_of_every_cube_face() {
+        let p = check();
+        let cases = [
+            (CubeFace::Left, Vert4::point(-1.0, 0.9, -0.9)),
+            (CubeFace::Front, Vert4::point(-0.9, 0.9, 1.0)),
+            (CubeFace::Right, Vert4::point(1.0, 0.9, 0.9)),
+            (CubeFace::Back, Vert4::point(0.9, 0.9, -1.0)),
+            (CubeFace::Up, Vert4::point(-0.9, 1.0, -0.9)),
+            (CubeFace::Down, Vert4::point(-0.9, -1.0, 0.9)),
+        ];
+        for (face, point) in cases {
+            let (u, v) = face.uv(point);
+            assert_eq!(p.uv_pattern_at(u, v), UL, "{face:?}");
+        }
+    }
+
+    #[test]
+    fn wraps_around_cylinder() {
+        let p = check();
+        let cyl = Cylinder::new();
+        // Just right of the seam at -z, near the top of a unit band.
+        assert_eq!(p.pattern_at_shape(&cyl, Vert4::point(0.1, 0.9, -1.0)), UL);
+        assert_eq!(p.pattern_at_shape(&cyl, Vert4::point(-0.1, 0.1, -1.0)), BR);
+        assert_eq!(p.pattern_at_shape(&cyl, Vert4::point(0.0, 0.5, 1.0)), MAIN);
+    }
+}
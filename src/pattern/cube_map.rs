@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use super::uv::{self, CubeFace};
+use super::{Pattern, TextureMemory, TexturePattern, UvPattern};
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::math::{Matrix4, Transform, Vert4};
+
+/// Six images surrounding the origin, looked up by direction.
+///
+/// Set as a [`World::background`](crate::world::World::background) it becomes
+/// a skybox, seen wherever a ray escapes the scene. As an ordinary pattern it
+/// projects the faces out from the origin, so it also textures a [`Cube`].
+///
+/// [`Cube`]: crate::shape::Cube
+#[derive(Debug, Clone)]
+pub struct CubeMap {
+    faces: Faces,
+    transform: Transform,
+}
+
+#[derive(Debug, Clone)]
+enum Faces {
+    Separate(Box<[TexturePattern; 6]>),
+    Cross(TexturePattern),
+}
+
+impl CubeMap {
+    /// A cube map from one image per face, in the order left, right, front,
+    /// back, up, down. Each image is seen from inside the cube; see
+    /// [`CubeFace::uv`].
+    pub fn from_faces(faces: [Arc<Canvas>; 6]) -> Self {
+        Self {
+            faces: Faces::Separate(Box::new(faces.map(TexturePattern::new))),
+            transform: Transform::IDENTITY,
+        }
+    }
+
+    /// A cube map from a single image in the horizontal cross layout; see
+    /// [`uv::cube_map`].
+    pub fn from_cross(image: Arc<Canvas>) -> Self {
+        Self {
+            faces: Faces::Cross(TexturePattern::new(image)),
+            transform: Transform::IDENTITY,
+        }
+    }
+
+    /// Orients the map, for instance to turn a skybox's sun to face the
+    /// scene's light.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `transform` is not invertible.
+    pub fn with_transform(mut self, transform: Matrix4) -> Self {
+        self.transform = Transform::new(transform);
+        self
+    }
+}
+
+impl Pattern for CubeMap {
+    /// The color in the direction of `point` from the origin.
+    fn pattern_at(&self, point: Vert4) -> Color {
+        let extent = point.x.abs().max(point.y.abs()).max(point.z.abs());
+        if extent == 0.0 {
+            return Color::BLACK;
+        }
+        let on_cube = Vert4::point(point.x / extent, point.y / extent, point.z / extent);
+        match &self.faces {
+            Faces::Separate(textures) => {
+                let face = CubeFace::of(on_cube);
+                let (u, v) = face.uv(on_cube);
+                textures[face as usize].uv_pattern_at(u, v)
+            }
+            Faces::Cross(texture) => {
+                let (u, v) = uv::cube_map(on_cube);
+                texture.uv_pattern_at(u, v)
+            }
+        }
+    }
+
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn textures(&self, report: &mut dyn FnMut(TextureMemory)) {
+        match &self.faces {
+            Faces::Separate(textures) => {
+                for texture in textures.iter() {
+                    UvPattern::textures(texture, report);
+                }
+            }
+            Faces::Cross(texture) => UvPattern::textures(texture, report),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(color: Color) -> Arc<Canvas> {
+        let mut image = Canvas::new(1, 1);
+        image.write_pixel(0, 0, color);
+        Arc::new(image)
+    }
+
+    const COLORS: [Color; 6] = [
+        Color::new(1.0, 0.0, 0.0),
+        Color::new(0.0, 1.0, 0.0),
+        Color::new(0.0, 0.0, 1.0),
+        Color::new(1.0, 1.0, 0.0),
+        Color::new(0.0, 1.0, 1.0),
+        Color::new(1.0, 0.0, 1.0),
+    ];
+
+    const DIRECTIONS: [Vert4; 6] = [
+        Vert4::vector(-1.0, 0.2, 0.3),
+        Vert4::vector(3.0, -0.5, 0.0),
+        Vert4::vector(0.1, 0.1, 0.5),
+        Vert4::vector(0.0, 0.0, -1.0),
+        Vert4::vector(0.4, 2.0, -0.9),
+        Vert4::vector(0.0, -1.0, 0.0),
+    ];
+
+    #[test]
+    fn each_face_has_its_own_image() {
+        let map = CubeMap::from_faces(COLORS.map(solid));
+        for (direction, color) in DIRECTIONS.into_iter().zip(COLORS) {
+            assert_eq!(map.pattern_at(direction), color, "{direction:?}");
+        }
+    }
+
+    #[test]
+    fn cross_layout_matches_separate_faces() {
+        // A 4×3 cross with one pixel per face.
+        let mut image = Canvas::new(4, 3);
+        let cells = [(0, 1), (2, 1), (1, 1), (3, 1), (1, 0), (1, 2)];
+        for ((x, y), color) in cells.into_iter().zip(COLORS) {
+            image.write_pixel(x, y, color);
+        }
+        let cross = CubeMap::from_cross(Arc::new(image));
+        let faces = CubeMap::from_faces(COLORS.map(solid));
+        for direction in DIRECTIONS {
+            assert_eq!(
+                cross.pattern_at(direction),
+                faces.pattern_at(direction),
+                "{direction:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn faces_are_seen_from_inside() {
+        // Looking along +z, the left half of the front image is towards -x.
+        let mut front = Canvas::new(2, 1);
+        front.write_pixel(0, 0, Color::WHITE);
+        let mut faces: [_; 6] = std::array::from_fn(|_| solid(Color::BLACK));
+        faces[CubeFace::Front as usize] = Arc::new(front);
+        let map = CubeMap::from_faces(faces);
+        assert_eq!(map.pattern_at(Vert4::vector(-0.5, 0.0, 1.0)), Color::WHITE);
+        assert_eq!(map.pattern_at(Vert4::vector(0.5, 0.0, 1.0)), Color::BLACK);
+    }
+}
@@ -0,0 +1,76 @@
+use super::Pattern;
+use crate::color::Color;
+use crate::math::{Matrix4, Transform, Vert4};
+
+/// A linear blend from `a` at `x = 0` to `b` at `x = 1`, repeating every unit
+/// along x.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gradient {
+    pub a: Color,
+    pub b: Color,
+    transform: Transform,
+}
+
+impl Gradient {
+    pub const fn new(a: Color, b: Color) -> Self {
+        Self {
+            a,
+            b,
+            transform: Transform::IDENTITY,
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `transform` is not invertible.
+    pub fn with_transform(mut self, transform: Matrix4) -> Self {
+        self.transform = Transform::new(transform);
+        self
+    }
+}
+
+impl Pattern for Gradient {
+    fn pattern_at(&self, point: Vert4) -> Color {
+        let fraction = point.x - point.x.floor();
+        self.a + (self.b - self.a) * fraction
+    }
+
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_along_x() {
+        let g = Gradient::new(Color::WHITE, Color::BLACK);
+        let at = |x: f32| g.pattern_at(Vert4::point(x, 0.0, 0.0));
+        assert_eq!(at(0.0), Color::WHITE);
+        assert_approx_eq!(at(0.25), Color::new(0.75, 0.75, 0.75));
+        assert_approx_eq!(at(0.5), Color::new(0.5, 0.5, 0.5));
+        assert_approx_eq!(at(0.75), Color::new(0.25, 0.25, 0.25));
+    }
+
+    #[test]
+    fn constant_in_y_and_z() {
+        let g = Gradient::new(Color::WHITE, Color::BLACK);
+        let c = g.pattern_at(Vert4::point(0.5, 0.0, 0.0));
+        assert_eq!(g.pattern_at(Vert4::point(0.5, 7.0, -3.0)), c);
+    }
+
+    #[test]
+    fn repeats_every_unit() {
+        let g = Gradient::new(Color::WHITE, Color::BLACK);
+        assert_approx_eq!(
+            g.pattern_at(Vert4::point(1.25, 0.0, 0.0)),
+            g.pattern_at(Vert4::point(0.25, 0.0, 0.0))
+        );
+        assert_approx_eq!(
+            g.pattern_at(Vert4::point(-0.75, 0.0, 0.0)),
+            g.pattern_at(Vert4::point(0.25, 0.0, 0.0))
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! Procedural colorings that vary a material's color across a surface.
+
+mod align_check;
+mod cube_map;
+mod gradient;
+mod perturb;
+mod texture;
+mod texture_cache;
+pub mod uv;
+
+pub use crate::canvas::FilterMode;
+pub use align_check::AlignCheck;
+pub use cube_map::CubeMap;
+pub use gradient::Gradient;
+pub use perturb::Perturb;
+pub use texture::TexturePattern;
+pub use texture_cache::{LazyTexture, PpmFile, TextureCache, TextureSource, TILE_SIZE};
+pub use uv::UvPattern;
+
+use std::fmt::Debug;
+use std::mem;
+use std::sync::Arc;
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::math::{Transform, Vert4};
+use crate::shape::Shape;
+
+/// A color that varies with position.
+///
+/// Patterns are evaluated in the object space of the shape they're on, so
+/// they move, scale and rotate with it, and then through the pattern's own
+/// [`transform`](Self::transform) on top of that.
+pub trait Pattern: Debug + Send + Sync {
+    /// The color at `point`, given in pattern space.
+    fn pattern_at(&self, point: Vert4) -> Color;
+
+    /// Places the pattern relative to the object it's on. Patterns without
+    /// one of their own use the identity.
+    fn transform(&self) -> &Transform {
+        &Transform::IDENTITY
+    }
+
+    /// The color at `object_point`, given in the space the pattern is placed
+    /// in: the object's, or an enclosing pattern's.
+    fn pattern_at_object(&self, object_point: Vert4) -> Color {
+        self.pattern_at(self.transform().to_local(object_point))
+    }
+
+    /// The color at `world_point` on `object`.
+    fn pattern_at_shape(&self, object: &dyn Shape, world_point: Vert4) -> Color {
+        self.pattern_at_object(object.world_to_object(world_point))
+    }
+
+    /// Calls `report` with the memory held by each image the pattern
+    /// samples. Patterns without images report nothing.
+    fn textures(&self, _report: &mut dyn FnMut(TextureMemory)) {}
+}
+
+/// Memory held by a texture, as reported by [`Pattern::textures`].
+///
+/// Patterns sharing one image, or one [`TextureCache`], report the same
+/// `storage`, so totals can count it once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureMemory {
+    /// The address of the shared storage, identifying it.
+    pub storage: usize,
+    pub bytes: usize,
+}
+
+impl TextureMemory {
+    pub fn of<T: ?Sized>(storage: &Arc<T>, bytes: usize) -> Self {
+        Self {
+            storage: Arc::as_ptr(storage) as *const () as usize,
+            bytes,
+        }
+    }
+
+    /// The memory of an image held in `image`.
+    pub fn image(image: &Arc<Canvas>) -> Self {
+        Self::of(
+            image,
+            image.width() * image.height() * mem::size_of::<Color>(),
+        )
+    }
+}
+
+/// A pattern whose color is the pattern-space point it's sampled at, for
+/// checking which space patterns are evaluated in.
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct TestPattern;
+
+#[cfg(test)]
+impl Pattern for TestPattern {
+    fn pattern_at(&self, point: Vert4) -> Color {
+        Color::new(point.x, point.y, point.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Matrix4;
+    use crate::shape::Sphere;
+
+    #[test]
+    fn default_transform_is_identity() {
+        assert_eq!(TestPattern.transform(), &Transform::IDENTITY);
+    }
+
+    #[test]
+    fn pattern_transform_applies_within_object_space() {
+        let s = Sphere::new().with_transform(Matrix4::scaling(2.0, 2.0, 2.0));
+        let g = Gradient::new(Color::WHITE, Color::BLACK)
+            .with_transform(Matrix4::translation(0.5, 1.0, 1.5));
+        // Object space (0.75, ...) is pattern space (0.25, ...).
+        assert_approx_eq!(
+            g.pattern_at_shape(&s, Vert4::point(1.5, 3.0, 4.0)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+    }
+
+    #[test]
+    fn pattern_follows_object_transform() {
+        let s = Sphere::new().with_transform(Matrix4::scaling(2.0, 2.0, 2.0));
+        assert_eq!(
+            TestPattern.pattern_at_shape(&s, Vert4::point(2.0, 3.0, 4.0)),
+            Color::new(1.0, 1.5, 2.0)
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use super::{Pattern, TextureMemory};
+use crate::color::Color;
+use crate::math::noise::perlin;
+use crate::math::{Matrix4, Transform, Vert4};
+
+/// Jitters the point `pattern` is sampled at by Perlin noise, turning crisp
+/// stripes and gradients into marbled, organic ones.
+///
+/// The wrapped pattern's transform places it within this one's space.
+#[derive(Debug, Clone)]
+pub struct Perturb {
+    pub pattern: Arc<dyn Pattern>,
+    /// The largest distance a point is displaced along each axis.
+    pub scale: f32,
+    transform: Transform,
+}
+
+impl Perturb {
+    pub fn new(pattern: Arc<dyn Pattern>, scale: f32) -> Self {
+        Self {
+            pattern,
+            scale,
+            transform: Transform::IDENTITY,
+        }
+    }
+
+    /// Scales and places the noise, independently of the wrapped pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `transform` is not invertible.
+    pub fn with_transform(mut self, transform: Matrix4) -> Self {
+        self.transform = Transform::new(transform);
+        self
+    }
+}
+
+impl Pattern for Perturb {
+    fn pattern_at(&self, point: Vert4) -> Color {
+        // Offset the samples so each axis gets independent noise.
+        let jitter = |dz: f32| perlin(Vert4::point(point.x, point.y, point.z + dz)) * self.scale;
+        let moved = Vert4::point(
+            point.x + jitter(0.0),
+            point.y + jitter(1.0),
+            point.z + jitter(2.0),
+        );
+        self.pattern.pattern_at_object(moved)
+    }
+
+    fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    fn textures(&self, report: &mut dyn FnMut(TextureMemory)) {
+        self.pattern.textures(report);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::{Gradient, TestPattern};
+
+    #[test]
+    fn zero_scale_is_unchanged() {
+        let g = Arc::new(Gradient::new(Color::WHITE, Color::BLACK));
+        let p = Perturb::new(g.clone(), 0.0);
+        let point = Vert4::point(0.3, 1.7, -2.2);
+        assert_eq!(p.pattern_at(point), g.pattern_at(point));
+    }
+
+    #[test]
+    fn displacement_is_bounded_by_scale() {
+        let p = Perturb::new(Arc::new(TestPattern), 0.2);
+        for i in 0..100 {
+            let t = i as f32 * 0.173;
+            let point = Vert4::point(t, -t * 0.5, t * 2.0 + 0.5);
+            let c = p.pattern_at(point);
+            for (moved, original) in [(c.r, point.x), (c.g, point.y), (c.b, point.z)] {
+                assert!((moved - original).abs() <= 0.2 * 1.05);
+            }
+        }
+    }
+
+    #[test]
+    fn perturbs_off_lattice_points() {
+        let p = Perturb::new(Arc::new(TestPattern), 0.5);
+        let point = Vert4::point(0.4, 0.3, 0.6);
+        assert_ne!(p.pattern_at(point), Color::new(0.4, 0.3, 0.6));
+    }
+}
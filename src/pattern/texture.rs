@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use super::{TextureMemory, UvPattern};
+use crate::canvas::{Canvas, FilterMode};
+use crate::color::Color;
+
+/// An image stretched over the unit square, with `(0, 0)` at its bottom-left
+/// corner and `(1, 1)` at its top-right.
+///
+/// The canvas is shared, so one large texture can back any number of
+/// materials.
+#[derive(Debug, Clone)]
+pub struct TexturePattern {
+    pub image: Arc<Canvas>,
+    pub filter: FilterMode,
+}
+
+impl TexturePattern {
+    /// A texture sampled with [`FilterMode::Nearest`].
+    pub fn new(image: Arc<Canvas>) -> Self {
+        Self {
+            image,
+            filter: FilterMode::default(),
+        }
+    }
+
+    pub fn with_filter(mut self, filter: FilterMode) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+impl UvPattern for TexturePattern {
+    fn uv_pattern_at(&self, u: f32, v: f32) -> Color {
+        let max_x = (self.image.width() - 1) as f32;
+        let max_y = (self.image.height() - 1) as f32;
+        // Canvas rows run top to bottom, but v runs bottom to top.
+        self.image.sample(u * max_x, (1.0 - v) * max_y, self.filter)
+    }
+
+    fn textures(&self, report: &mut dyn FnMut(TextureMemory)) {
+        report(TextureMemory::image(&self.image));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Matrix4, Vert4};
+    use crate::pattern::Pattern;
+    use crate::shape::{Shape, Sphere};
+
+    /// A 2×2 image: red and green along the top row, blue and white below.
+    fn quadrants() -> TexturePattern {
+        let mut image = Canvas::new(2, 2);
+        image.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        image.write_pixel(1, 0, Color::new(0.0, 1.0, 0.0));
+        image.write_pixel(0, 1, Color::new(0.0, 0.0, 1.0));
+        image.write_pixel(1, 1, Color::WHITE);
+        TexturePattern::new(Arc::new(image))
+    }
+
+    #[test]
+    fn corners_map_to_image_corners() {
+        let t = quadrants();
+        assert_eq!(t.uv_pattern_at(0.0, 1.0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(t.uv_pattern_at(1.0, 1.0), Color::new(0.0, 1.0, 0.0));
+        assert_eq!(t.uv_pattern_at(0.0, 0.0), Color::new(0.0, 0.0, 1.0));
+        assert_eq!(t.uv_pattern_at(1.0, 0.0), Color::WHITE);
+    }
+
+    #[test]
+    fn samples_nearest_pixel() {
+        let mut image = Canvas::new(10, 10);
+        image.write_pixel(3, 6, Color::WHITE);
+        let t = TexturePattern::new(Arc::new(image));
+        assert_eq!(t.uv_pattern_at(0.33, 0.36), Color::WHITE);
+        assert_eq!(t.uv_pattern_at(0.45, 0.36), Color::BLACK);
+    }
+
+    #[test]
+    fn wraps_around_sphere() {
+        let t = quadrants();
+        let s = Sphere::new().with_transform(Matrix4::scaling(2.0, 2.0, 2.0));
+        // Just above the equator facing -x lands on u = 0.75, the top right.
+        let c = t.pattern_at_shape(&s, Vert4::point(-2.0, 0.5, 0.0));
+        assert_eq!(c, Color::new(0.0, 1.0, 0.0));
+        // Just below the equator facing +x lands on u = 0.25, the bottom left.
+        let c = t.pattern_at_shape(&s, Vert4::point(2.0, -0.5, 0.0));
+        assert_eq!(c, Color::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn lies_flat_without_a_shape() {
+        let t = quadrants();
+        assert_eq!(t.pattern_at(Vert4::point(2.9, 5.0, 0.1)), Color::WHITE);
+    }
+
+    #[test]
+    fn bilinear_blends_neighbouring_pixels() {
+        let t = quadrants().with_filter(FilterMode::Bilinear);
+        // Pixel centres are exact.
+        assert_eq!(t.uv_pattern_at(0.0, 1.0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(t.uv_pattern_at(1.0, 0.0), Color::WHITE);
+        // Halfway along the top row, and in the middle of all four.
+        assert_approx_eq!(t.uv_pattern_at(0.5, 1.0), Color::new(0.5, 0.5, 0.0));
+        assert_approx_eq!(t.uv_pattern_at(0.5, 0.5), Color::new(0.5, 0.5, 0.5));
+        assert_approx_eq!(t.uv_pattern_at(0.25, 1.0), Color::new(0.75, 0.25, 0.0));
+    }
+
+    #[test]
+    fn out_of_range_uv_clamps_to_edge() {
+        for filter in [FilterMode::Nearest, FilterMode::Bilinear] {
+            let t = quadrants().with_filter(filter);
+            assert_eq!(t.uv_pattern_at(-0.5, 1.5), Color::new(1.0, 0.0, 0.0));
+            assert_eq!(t.uv_pattern_at(1.5, -0.5), Color::WHITE);
+        }
+    }
+}
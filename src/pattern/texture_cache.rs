@@ -0,0 +1,412 @@
+//! Image textures read a tile at a time as they're sampled, and kept under a
+//! memory budget.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::mem;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use super::{TextureMemory, UvPattern};
+use crate::canvas::{Canvas, Encoding, FilterMode, PpmLayout};
+use crate::color::Color;
+
+/// The width and height of the square tiles textures are loaded in; tiles
+/// on the right and bottom edges may be smaller.
+pub const TILE_SIZE: usize = 64;
+
+/// How much of a PPM file is read looking for the end of its header.
+const HEADER_LIMIT: u64 = 4096;
+
+/// What a texture that can't be read samples as, to stand out in the render.
+const MISSING: Color = Color::new(1.0, 0.0, 1.0);
+
+/// Where a [`LazyTexture`]'s pixels come from.
+pub trait TextureSource: Debug + Send + Sync {
+    /// The image's width and height.
+    fn size(&self) -> io::Result<(usize, usize)>;
+
+    /// The `width` × `height` block of pixels with (`x`, `y`) at its top
+    /// left, which lies within the image.
+    fn read(&self, x: usize, y: usize, width: usize, height: usize) -> io::Result<Canvas>;
+}
+
+/// A PPM file, sRGB encoded unless set otherwise.
+///
+/// Binary (`P6`) files are read a block at a time by seeking to each row.
+/// Plain (`P3`) ones can't be, so every read decodes the whole file.
+#[derive(Debug, Clone)]
+pub struct PpmFile {
+    pub path: PathBuf,
+    pub encoding: Encoding,
+}
+
+impl PpmFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            encoding: Encoding::Srgb,
+        }
+    }
+
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    fn layout(file: &mut File) -> io::Result<PpmLayout> {
+        let mut header = Vec::new();
+        file.take(HEADER_LIMIT).read_to_end(&mut header)?;
+        PpmLayout::read(&header).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl TextureSource for PpmFile {
+    fn size(&self) -> io::Result<(usize, usize)> {
+        let layout = Self::layout(&mut File::open(&self.path)?)?;
+        Ok((layout.width, layout.height))
+    }
+
+    fn read(&self, x: usize, y: usize, width: usize, height: usize) -> io::Result<Canvas> {
+        let mut file = File::open(&self.path)?;
+        let layout = Self::layout(&mut file)?;
+        if !layout.binary {
+            let image = Canvas::from_ppm_with(&fs::read(&self.path)?, self.encoding)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            return Ok(Canvas::from_fn(width, height, |dx, dy| {
+                image.pixel_at(x + dx, y + dy)
+            }));
+        }
+        let size = PpmLayout::channel_size(layout.max_color);
+        let mut row = vec![0; width * 3 * size];
+        let mut block = Canvas::new(width, height);
+        for dy in 0..height {
+            let start = layout.data_start + ((y + dy) * layout.width + x) * 3 * size;
+            file.seek(SeekFrom::Start(start as u64))?;
+            file.read_exact(&mut row)?;
+            let mut channels = row.chunks(size).map(|bytes| {
+                let value = bytes.iter().fold(0, |v, b| v << 8 | usize::from(*b));
+                self.encoding
+                    .decode(value.min(layout.max_color) as f32 / layout.max_color as f32)
+            });
+            for dx in 0..width {
+                let mut next = || channels.next().unwrap_or_default();
+                block.write_pixel(dx, dy, Color::new(next(), next(), next()));
+            }
+        }
+        Ok(block)
+    }
+}
+
+/// Tiles of [`LazyTexture`]s, loaded the first time they're sampled and
+/// dropped, least recently used first, to stay within a memory budget.
+///
+/// Many textures can share one cache, and so one budget. A tile that was
+/// dropped is simply read again if it's needed later, so the budget trades
+/// memory for time spent reading.
+#[derive(Debug)]
+pub struct TextureCache {
+    budget: usize,
+    next_texture: AtomicUsize,
+    tiles: Mutex<Tiles>,
+}
+
+/// Which tile: the texture, then its column and row of tiles.
+type TileKey = (usize, usize, usize);
+
+#[derive(Debug, Default)]
+struct Tiles {
+    /// Each resident tile, with when it was last used.
+    resident: HashMap<TileKey, (Arc<Canvas>, u64)>,
+    /// The resident tiles by when they were last used.
+    by_use: BTreeMap<u64, TileKey>,
+    clock: u64,
+    bytes: usize,
+}
+
+impl Tiles {
+    /// The tile at `key` if it's resident, marked as just used.
+    fn touch(&mut self, key: TileKey) -> Option<Arc<Canvas>> {
+        let (tile, used) = self.resident.get_mut(&key)?;
+        self.by_use.remove(used);
+        self.clock += 1;
+        *used = self.clock;
+        self.by_use.insert(self.clock, key);
+        Some(Arc::clone(tile))
+    }
+
+    /// Adds `tile`, first dropping the least recently used tiles until it
+    /// fits in `budget`, or none are left.
+    fn insert(&mut self, key: TileKey, tile: Arc<Canvas>, budget: usize) {
+        let size = tile_bytes(&tile);
+        while self.bytes + size > budget {
+            let Some((_, oldest)) = self.by_use.pop_first() else {
+                break;
+            };
+            if let Some((evicted, _)) = self.resident.remove(&oldest) {
+                self.bytes -= tile_bytes(&evicted);
+            }
+        }
+        self.clock += 1;
+        self.resident.insert(key, (tile, self.clock));
+        self.by_use.insert(self.clock, key);
+        self.bytes += size;
+    }
+}
+
+fn tile_bytes(tile: &Canvas) -> usize {
+    tile.width() * tile.height() * mem::size_of::<Color>()
+}
+
+impl TextureCache {
+    /// A cache keeping at most `budget` bytes of pixels, except that the
+    /// tile being sampled is always kept.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            next_texture: AtomicUsize::new(0),
+            tiles: Mutex::default(),
+        }
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// The bytes of pixels held now.
+    pub fn resident_bytes(&self) -> usize {
+        self.tiles.lock().unwrap().bytes
+    }
+
+    /// How many tiles are held now.
+    pub fn resident_tiles(&self) -> usize {
+        self.tiles.lock().unwrap().resident.len()
+    }
+
+    /// Drops every tile.
+    pub fn clear(&self) {
+        *self.tiles.lock().unwrap() = Tiles::default();
+    }
+
+    /// A texture read from `source` through this cache. Nothing is read
+    /// until it's first sampled.
+    pub fn texture(self: &Arc<Self>, source: impl TextureSource + 'static) -> LazyTexture {
+        LazyTexture {
+            cache: Arc::clone(self),
+            id: self.next_texture.fetch_add(1, Ordering::Relaxed),
+            source: Arc::new(source),
+            size: OnceLock::new(),
+            filter: FilterMode::default(),
+        }
+    }
+
+    /// The tile at `key`, loaded by `load` unless it's resident.
+    fn tile(
+        &self,
+        key: TileKey,
+        load: impl FnOnce() -> io::Result<Canvas>,
+    ) -> io::Result<Arc<Canvas>> {
+        if let Some(tile) = self.tiles.lock().unwrap().touch(key) {
+            return Ok(tile);
+        }
+        // Read without holding the lock, so other threads can go on sampling.
+        let tile = Arc::new(load()?);
+        let mut tiles = self.tiles.lock().unwrap();
+        // Another thread may have loaded it meanwhile.
+        if let Some(tile) = tiles.touch(key) {
+            return Ok(tile);
+        }
+        tiles.insert(key, Arc::clone(&tile), self.budget);
+        Ok(tile)
+    }
+}
+
+/// A texture like [`TexturePattern`](super::TexturePattern) whose pixels
+/// are read from a [`TextureSource`] through a [`TextureCache`] as they're
+/// needed. A texture that can't be read is magenta.
+#[derive(Debug, Clone)]
+pub struct LazyTexture {
+    cache: Arc<TextureCache>,
+    id: usize,
+    source: Arc<dyn TextureSource>,
+    /// The image's size, read on first use; `None` if it couldn't be.
+    size: OnceLock<Option<(usize, usize)>>,
+    pub filter: FilterMode,
+}
+
+impl LazyTexture {
+    pub fn with_filter(mut self, filter: FilterMode) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    fn size(&self) -> Option<(usize, usize)> {
+        *self.size.get_or_init(|| {
+            self.source
+                .size()
+                .ok()
+                .filter(|&(width, height)| width > 0 && height > 0)
+        })
+    }
+
+    /// Pixel (`x`, `y`) of the image, which is `width` × `height`.
+    fn pixel(&self, x: usize, y: usize, (width, height): (usize, usize)) -> Color {
+        let (column, row) = (x / TILE_SIZE, y / TILE_SIZE);
+        let (left, top) = (column * TILE_SIZE, row * TILE_SIZE);
+        let tile = self.cache.tile((self.id, column, row), || {
+            let tile_width = TILE_SIZE.min(width - left);
+            let tile_height = TILE_SIZE.min(height - top);
+            self.source.read(left, top, tile_width, tile_height)
+        });
+        match tile {
+            Ok(tile) => tile.pixel_at(x - left, y - top),
+            Err(_) => MISSING,
+        }
+    }
+}
+
+impl UvPattern for LazyTexture {
+    fn uv_pattern_at(&self, u: f32, v: f32) -> Color {
+        let Some(size @ (width, height)) = self.size() else {
+            return MISSING;
+        };
+        let max_x = (width - 1) as f32;
+        let max_y = (height - 1) as f32;
+        // Canvas rows run top to bottom, but v runs bottom to top.
+        self.filter
+            .sample(width, height, u * max_x, (1.0 - v) * max_y, |x, y| {
+                self.pixel(x, y, size)
+            })
+    }
+
+    /// The tiles of every texture in the cache, since they share its
+    /// budget.
+    fn textures(&self, report: &mut dyn FnMut(TextureMemory)) {
+        report(TextureMemory::of(&self.cache, self.cache.resident_bytes()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::pattern::TexturePattern;
+
+    /// An image in memory that counts how many blocks are read from it.
+    #[derive(Debug)]
+    struct Counting {
+        image: Canvas,
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl TextureSource for Counting {
+        fn size(&self) -> io::Result<(usize, usize)> {
+            Ok((self.image.width(), self.image.height()))
+        }
+
+        fn read(&self, x: usize, y: usize, width: usize, height: usize) -> io::Result<Canvas> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            Ok(Canvas::from_fn(width, height, |dx, dy| {
+                self.image.pixel_at(x + dx, y + dy)
+            }))
+        }
+    }
+
+    fn gradient(width: usize, height: usize) -> Canvas {
+        Canvas::from_fn(width, height, |x, y| {
+            Color::new(x as f32 / width as f32, y as f32 / height as f32, 0.5)
+        })
+    }
+
+    fn counting(image: Canvas) -> (Counting, Arc<AtomicUsize>) {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let source = Counting {
+            image,
+            reads: Arc::clone(&reads),
+        };
+        (source, reads)
+    }
+
+    #[test]
+    fn samples_match_a_resident_texture() {
+        let image = gradient(150, 70);
+        let (source, _) = counting(image.clone());
+        let cache = Arc::new(TextureCache::new(usize::MAX));
+        let lazy = cache.texture(source).with_filter(FilterMode::Bilinear);
+        let eager = TexturePattern::new(Arc::new(image)).with_filter(FilterMode::Bilinear);
+        for (u, v) in [(0.0, 0.0), (0.43, 0.51), (0.999, 0.2), (1.0, 1.0)] {
+            assert_eq!(lazy.uv_pattern_at(u, v), eager.uv_pattern_at(u, v));
+        }
+    }
+
+    #[test]
+    fn tiles_are_loaded_on_first_sample() {
+        let (source, reads) = counting(gradient(200, 200));
+        let cache = Arc::new(TextureCache::new(usize::MAX));
+        let texture = cache.texture(source);
+        assert_eq!(reads.load(Ordering::Relaxed), 0);
+        texture.uv_pattern_at(0.0, 1.0);
+        texture.uv_pattern_at(0.1, 0.9);
+        assert_eq!(reads.load(Ordering::Relaxed), 1);
+        assert_eq!(cache.resident_bytes(), TILE_SIZE * TILE_SIZE * 12);
+        texture.uv_pattern_at(1.0, 0.0);
+        assert_eq!(reads.load(Ordering::Relaxed), 2);
+        assert_eq!(cache.resident_tiles(), 2);
+    }
+
+    #[test]
+    fn least_recently_used_tiles_are_dropped_over_budget() {
+        let (source, reads) = counting(gradient(256, 64));
+        let tile = TILE_SIZE * TILE_SIZE * 12;
+        let cache = Arc::new(TextureCache::new(2 * tile));
+        let texture = cache.texture(source);
+        // The centres of the first three tiles along the top.
+        let sample = |column: usize| texture.uv_pattern_at((column as f32 + 0.5) / 4.0, 0.5);
+        sample(0);
+        sample(1);
+        sample(0);
+        sample(2);
+        assert_eq!(cache.resident_tiles(), 2);
+        assert!(cache.resident_bytes() <= cache.budget());
+        // Tile 1 was the least recently used, so it went.
+        sample(0);
+        assert_eq!(reads.load(Ordering::Relaxed), 3);
+        sample(1);
+        assert_eq!(reads.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn binary_ppm_files_are_read_a_tile_at_a_time() {
+        let image = Canvas::from_fn(70, 3, |x, y| {
+            Color::new(x as f32 / 69.0, y as f32 / 2.0, 1.0)
+        });
+        let mut data = b"P6\n# a comment\n70 3\n255\n".to_vec();
+        for y in 0..3 {
+            for x in 0..70 {
+                let c = image.pixel_at(x, y);
+                data.extend([c.r, c.g, c.b].map(|channel| (channel * 255.0).round() as u8));
+            }
+        }
+        let path = std::env::temp_dir().join(format!("texture-cache-{}.ppm", std::process::id()));
+        fs::write(&path, &data).unwrap();
+        let file = PpmFile::new(&path).with_encoding(Encoding::Linear);
+        assert_eq!(file.size().unwrap(), (70, 3));
+        let block = file.read(64, 1, 6, 2).unwrap();
+        let whole = Canvas::from_ppm_with(&data, Encoding::Linear).unwrap();
+        fs::remove_file(&path).unwrap();
+        for (x, y) in [(0, 0), (5, 1)] {
+            assert_eq!(block.pixel_at(x, y), whole.pixel_at(64 + x, 1 + y));
+        }
+    }
+
+    #[test]
+    fn unreadable_textures_are_magenta() {
+        let cache = Arc::new(TextureCache::new(usize::MAX));
+        let texture = cache.texture(PpmFile::new("/nonexistent/texture.ppm"));
+        assert_eq!(texture.uv_pattern_at(0.5, 0.5), MISSING);
+    }
+}
@@ -0,0 +1,292 @@
+//! Two-dimensional patterns and the mappings that wrap them onto surfaces.
+//!
+//! A [`UvPattern`] is defined over the unit square. Each shape chooses how its
+//! surface unwraps onto that square through [`Shape::local_uv`], so the same
+//! texture can be laid over a plane or wrapped around a sphere.
+
+use std::f32::consts::PI;
+use std::fmt::Debug;
+
+use super::{Pattern, TextureMemory};
+use crate::color::Color;
+use crate::math::Vert4;
+use crate::shape::Shape;
+
+/// A color that varies over the unit square.
+///
+/// Every `UvPattern` is also a [`Pattern`]: on a shape it's sampled at the
+/// shape's own UV mapping, and on its own it's laid flat on the xz plane.
+pub trait UvPattern: Debug + Send + Sync {
+    /// The color at `(u, v)`, each in `[0, 1)`, with `v` increasing upwards.
+    fn uv_pattern_at(&self, u: f32, v: f32) -> Color;
+
+    /// As [`Pattern::textures`].
+    fn textures(&self, _report: &mut dyn FnMut(TextureMemory)) {}
+}
+
+impl<T: UvPattern> Pattern for T {
+    fn pattern_at(&self, point: Vert4) -> Color {
+        let (u, v) = planar_map(point);
+        self.uv_pattern_at(u, v)
+    }
+
+    fn pattern_at_shape(&self, object: &dyn Shape, world_point: Vert4) -> Color {
+        let (u, v) = object.local_uv(object.world_to_object(world_point));
+        self.uv_pattern_at(u, v)
+    }
+
+    fn textures(&self, report: &mut dyn FnMut(TextureMemory)) {
+        UvPattern::textures(self, report);
+    }
+}
+
+/// Tiles the xz plane with unit squares, `u` along x and `v` along z.
+pub fn planar_map(point: Vert4) -> (f32, f32) {
+    (point.x.rem_euclid(1.0), point.z.rem_euclid(1.0))
+}
+
+/// Wraps the unit square around a sphere centred on the origin: `u` is the
+/// longitude, increasing anticlockwise seen from above with the seam at -z, and
+/// `v` the latitude from the south pole to the north.
+pub fn spherical_map(point: Vert4) -> (f32, f32) {
+    let theta = point.x.atan2(point.z);
+    let radius = Vert4::vector(point.x, point.y, point.z).magnitude();
+    let phi = (point.y / radius).acos();
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    let v = 1.0 - phi / PI;
+    (u.rem_euclid(1.0), v)
+}
+
+/// Wraps the unit square around the side of a unit-radius cylinder about the
+/// y axis: `u` runs around it as in [`spherical_map`], and `v` repeats every
+/// unit of height.
+pub fn cylindrical_map(point: Vert4) -> (f32, f32) {
+    let theta = point.x.atan2(point.z);
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    (u.rem_euclid(1.0), point.y.rem_euclid(1.0))
+}
+
+/// A face of the axis-aligned cube spanning -1 to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeFace {
+    Left,
+    Right,
+    Front,
+    Back,
+    Up,
+    Down,
+}
+
+impl CubeFace {
+    /// The face whose axis `point` is furthest along.
+    pub fn of(point: Vert4) -> Self {
+        let (ax, ay, az) = (point.x.abs(), point.y.abs(), point.z.abs());
+        let max = ax.max(ay).max(az);
+        if max == point.x {
+            Self::Right
+        } else if max == -point.x {
+            Self::Left
+        } else if max == point.y {
+            Self::Up
+        } else if max == -point.y {
+            Self::Down
+        } else if max == point.z {
+            Self::Front
+        } else {
+            Self::Back
+        }
+    }
+
+    /// Where `point`, which lies on this face, lands on the face's own unit
+    /// square. Faces are laid out as seen from inside the cube, as for a
+    /// skybox: the sides with +y up, the top with +z down and the bottom with
+    /// +z up, so each one meets the front face along a shared edge.
+    pub fn uv(self, point: Vert4) -> (f32, f32) {
+        let (u, v) = match self {
+            Self::Front => (point.x + 1.0, point.y + 1.0),
+            Self::Back => (1.0 - point.x, point.y + 1.0),
+            Self::Left => (point.z + 1.0, point.y + 1.0),
+            Self::Right => (1.0 - point.z, point.y + 1.0),
+            Self::Up => (point.x + 1.0, 1.0 - point.z),
+            Self::Down => (point.x + 1.0, point.z + 1.0),
+        };
+        (u.rem_euclid(2.0) / 2.0, v.rem_euclid(2.0) / 2.0)
+    }
+
+    /// The directions in which this face's `u` and `v` increase.
+    pub fn tangents(self) -> (Vert4, Vert4) {
+        let (x, y, z) = (
+            Vert4::vector(1.0, 0.0, 0.0),
+            Vert4::vector(0.0, 1.0, 0.0),
+            Vert4::vector(0.0, 0.0, 1.0),
+        );
+        match self {
+            Self::Front => (x, y),
+            Self::Back => (-x, y),
+            Self::Left => (z, y),
+            Self::Right => (-z, y),
+            Self::Up => (x, -z),
+            Self::Down => (x, z),
+        }
+    }
+
+    /// The face's cell in a horizontal cross layout, as `(column, row)` counted
+    /// from the bottom-left of a 4×3 grid.
+    fn cross_cell(self) -> (f32, f32) {
+        match self {
+            Self::Left => (0.0, 1.0),
+            Self::Front => (1.0, 1.0),
+            Self::Right => (2.0, 1.0),
+            Self::Back => (3.0, 1.0),
+            Self::Up => (1.0, 2.0),
+            Self::Down => (1.0, 0.0),
+        }
+    }
+}
+
+/// Unfolds the cube spanning -1 to 1 onto a single image in the horizontal
+/// cross layout: left, front, right and back across the middle row, with the
+/// top above the front face and the bottom below it.
+pub fn cube_map(point: Vert4) -> (f32, f32) {
+    let face = CubeFace::of(point);
+    let (u, v) = face.uv(point);
+    let (column, row) = face.cross_cell();
+    ((column + u) / 4.0, (row + v) / 3.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::ApproxEq;
+
+    fn uv_eq((u1, v1): (f32, f32), (u2, v2): (f32, f32)) -> bool {
+        u1.approx_eq(&u2) && v1.approx_eq(&v2)
+    }
+
+    #[test]
+    fn spherical_mapping() {
+        let k = 2f32.sqrt() / 2.0;
+        let cases = [
+            (Vert4::point(0.0, 0.0, -1.0), (0.0, 0.5)),
+            (Vert4::point(1.0, 0.0, 0.0), (0.25, 0.5)),
+            (Vert4::point(0.0, 0.0, 1.0), (0.5, 0.5)),
+            (Vert4::point(-1.0, 0.0, 0.0), (0.75, 0.5)),
+            (Vert4::point(0.0, 1.0, 0.0), (0.5, 1.0)),
+            (Vert4::point(0.0, -1.0, 0.0), (0.5, 0.0)),
+            (Vert4::point(k, k, 0.0), (0.25, 0.75)),
+        ];
+        for (point, expected) in cases {
+            let uv = spherical_map(point);
+            assert!(uv_eq(uv, expected), "{point:?}: {uv:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn cylindrical_mapping() {
+        let k = 2f32.sqrt() / 2.0;
+        let cases = [
+            (Vert4::point(0.0, 0.0, -1.0), (0.0, 0.0)),
+            (Vert4::point(0.0, 0.5, -1.0), (0.0, 0.5)),
+            (Vert4::point(0.0, 1.0, -1.0), (0.0, 0.0)),
+            (Vert4::point(k, 0.5, -k), (0.125, 0.5)),
+            (Vert4::point(1.0, 0.5, 0.0), (0.25, 0.5)),
+            (Vert4::point(k, 0.5, k), (0.375, 0.5)),
+            (Vert4::point(0.0, -0.25, 1.0), (0.5, 0.75)),
+            (Vert4::point(-k, 0.5, k), (0.625, 0.5)),
+            (Vert4::point(-1.0, 1.25, 0.0), (0.75, 0.25)),
+            (Vert4::point(-k, 0.5, -k), (0.875, 0.5)),
+        ];
+        for (point, expected) in cases {
+            let uv = cylindrical_map(point);
+            assert!(uv_eq(uv, expected), "{point:?}: {uv:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn identifying_cube_faces() {
+        let cases = [
+            (Vert4::point(-1.0, 0.5, -0.25), CubeFace::Left),
+            (Vert4::point(1.1, -0.75, 0.8), CubeFace::Right),
+            (Vert4::point(0.1, 0.6, 0.9), CubeFace::Front),
+            (Vert4::point(-0.7, 0.0, -2.0), CubeFace::Back),
+            (Vert4::point(0.5, 1.0, 0.9), CubeFace::Up),
+            (Vert4::point(-0.2, -1.3, 1.1), CubeFace::Down),
+        ];
+        for (point, face) in cases {
+            assert_eq!(CubeFace::of(point), face, "{point:?}");
+        }
+    }
+
+    #[test]
+    fn cube_face_mappings() {
+        use CubeFace::*;
+        let cases = [
+            (Front, Vert4::point(-0.5, 0.5, 1.0), (0.25, 0.75)),
+            (Front, Vert4::point(0.5, -0.5, 1.0), (0.75, 0.25)),
+            (Back, Vert4::point(0.5, 0.5, -1.0), (0.25, 0.75)),
+            (Back, Vert4::point(-0.5, -0.5, -1.0), (0.75, 0.25)),
+            (Left, Vert4::point(-1.0, 0.5, -0.5), (0.25, 0.75)),
+            (Left, Vert4::point(-1.0, -0.5, 0.5), (0.75, 0.25)),
+            (Right, Vert4::point(1.0, 0.5, 0.5), (0.25, 0.75)),
+            (Right, Vert4::point(1.0, -0.5, -0.5), (0.75, 0.25)),
+            (Up, Vert4::point(-0.5, 1.0, -0.5), (0.25, 0.75)),
+            (Up, Vert4::point(0.5, 1.0, 0.5), (0.75, 0.25)),
+            (Down, Vert4::point(-0.5, -1.0, 0.5), (0.25, 0.75)),
+            (Down, Vert4::point(0.5, -1.0, -0.5), (0.75, 0.25)),
+        ];
+        for (face, point, expected) in cases {
+            let uv = face.uv(point);
+            assert!(
+                uv_eq(uv, expected),
+                "{face:?} {point:?}: {uv:?} != {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn cube_unfolds_into_a_cross() {
+        let cases = [
+            // The centre of each face is the centre of its cell.
+            (Vert4::point(-1.0, 0.0, 0.0), (0.125, 0.5)),
+            (Vert4::point(0.0, 0.0, 1.0), (0.375, 0.5)),
+            (Vert4::point(1.0, 0.0, 0.0), (0.625, 0.5)),
+            (Vert4::point(0.0, 0.0, -1.0), (0.875, 0.5)),
+            (Vert4::point(0.0, 1.0, 0.0), (0.375, 5.0 / 6.0)),
+            (Vert4::point(0.0, -1.0, 0.0), (0.375, 1.0 / 6.0)),
+            // Neighbouring faces meet along shared edges.
+            (Vert4::point(-0.99, 0.0, 1.0), (0.25 + 0.00125, 0.5)),
+            (Vert4::point(-1.0, 0.0, 0.99), (0.25 - 0.00125, 0.5)),
+            (
+                Vert4::point(0.0, 0.99, 1.0),
+                (0.375, 2.0 / 3.0 - 0.005 / 3.0),
+            ),
+            (
+                Vert4::point(0.0, 1.0, 0.99),
+                (0.375, 2.0 / 3.0 + 0.005 / 3.0),
+            ),
+        ];
+        for (point, expected) in cases {
+            let uv = cube_map(point);
+            assert!(uv_eq(uv, expected), "{point:?}: {uv:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn planar_mapping() {
+        let cases = [
+            (Vert4::point(0.25, 0.0, 0.5), (0.25, 0.5)),
+            (Vert4::point(0.25, 0.0, -0.25), (0.25, 0.75)),
+            (Vert4::point(0.25, 0.5, -0.25), (0.25, 0.75)),
+            (Vert4::point(1.25, 0.0, 0.5), (0.25, 0.5)),
+            (Vert4::point(0.25, 0.0, -1.75), (0.25, 0.25)),
+            (Vert4::point(1.0, 0.0, -1.0), (0.0, 0.0)),
+            (Vert4::point(0.0, 0.0, 0.0), (0.0, 0.0)),
+        ];
+        for (point, expected) in cases {
+            let uv = planar_map(point);
+            assert!(uv_eq(uv, expected), "{point:?}: {uv:?} != {expected:?}");
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::math::{Matrix4, Vert4};
+
+/// A half-line starting at `origin` travelling along `direction`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Vert4,
+    pub direction: Vert4,
+}
+
+impl Ray {
+    pub const fn new(origin: Vert4, direction: Vert4) -> Self {
+        Self { origin, direction }
+    }
+
+    /// The point reached after travelling `t` units of `direction` from `origin`.
+    pub fn position(&self, t: f32) -> Vert4 {
+        self.origin + self.direction * t
+    }
+
+    /// Applies `m` to both the origin and the direction.
+    pub fn transform(&self, m: &Matrix4) -> Self {
+        Self::new(*m * self.origin, *m * self.direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_along_ray() {
+        let r = Ray::new(Vert4::point(2.0, 3.0, 4.0), Vert4::vector(1.0, 0.0, 0.0));
+        assert_eq!(r.position(0.0), Vert4::point(2.0, 3.0, 4.0));
+        assert_eq!(r.position(1.0), Vert4::point(3.0, 3.0, 4.0));
+        assert_eq!(r.position(-1.0), Vert4::point(1.0, 3.0, 4.0));
+        assert_eq!(r.position(2.5), Vert4::point(4.5, 3.0, 4.0));
+    }
+
+    #[test]
+    fn translating_a_ray() {
+        let r = Ray::new(Vert4::point(1.0, 2.0, 3.0), Vert4::vector(0.0, 1.0, 0.0));
+        let r2 = r.transform(&Matrix4::translation(3.0, 4.0, 5.0));
+        assert_eq!(r2.origin, Vert4::point(4.0, 6.0, 8.0));
+        assert_eq!(r2.direction, Vert4::vector(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn scaling_a_ray() {
+        let r = Ray::new(Vert4::point(1.0, 2.0, 3.0), Vert4::vector(0.0, 1.0, 0.0));
+        let r2 = r.transform(&Matrix4::scaling(2.0, 3.0, 4.0));
+        assert_eq!(r2.origin, Vert4::point(2.0, 6.0, 12.0));
+        assert_eq!(r2.direction, Vert4::vector(0.0, 3.0, 0.0));
+    }
+}
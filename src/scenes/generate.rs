@@ -0,0 +1,212 @@
+//! Procedural scene content for benchmarks, stress tests and material
+//! reference renders. Each generator returns a [`Group`] to place in a
+//! world; large ones render far faster after [`Shape::divide`].
+
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_6, PI};
+use std::sync::Arc;
+
+use crate::color::Color;
+use crate::material::{Material, SpecularModel};
+use crate::math::random::Rng;
+use crate::math::Matrix4;
+use crate::shape::{Group, Shape, Sphere};
+
+/// `count` spheres resting on the plane y = 0, scattered over a square
+/// centred on the origin with one unit cell per sphere, so none overlap.
+/// Each is randomly matte, metal or glass, with a random color. The same
+/// `seed` always gives the same spheres.
+pub fn random_spheres(count: usize, seed: u64) -> Group {
+    let side = (count as f32).sqrt().ceil() as usize;
+    let offset = side as f32 / 2.0;
+    let mut rng = Rng::new(seed);
+    let mut group = Group::new();
+    for i in 0..count {
+        let radius = 0.2 + 0.2 * rng.next_f32();
+        // Keep the whole sphere within its cell.
+        let mut place =
+            |cell: usize| cell as f32 + radius + (1.0 - 2.0 * radius) * rng.next_f32() - offset;
+        let (x, z) = (place(i % side), place(i / side));
+        let color = Color::new(rng.next_f32(), rng.next_f32(), rng.next_f32());
+        let material = match rng.next_f32() {
+            p if p < 0.6 => Material {
+                color,
+                specular: 0.1,
+                ..Material::default()
+            },
+            p if p < 0.9 => Material {
+                color,
+                specular_model: SpecularModel::Ggx {
+                    roughness: 0.5 * rng.next_f32(),
+                    metalness: 1.0,
+                },
+                reflective: 0.5,
+                ..Material::default()
+            },
+            _ => Material {
+                color,
+                ..Material::glass()
+            },
+        };
+        group.add_child(
+            Sphere::new()
+                .with_transform(
+                    Matrix4::translation(x, radius, z) * Matrix4::scaling(radius, radius, radius),
+                )
+                .with_material(material),
+        );
+    }
+    group
+}
+
+/// Eric Haines' sphere-flake: a unit sphere at the origin ringed by nine
+/// spheres a third its size, six around its equator and three above, each
+/// ringed in turn, `depth` levels deep. Every sphere shares `material`.
+/// There are (9^(depth + 1) - 1) / 8 spheres in all.
+pub fn sphere_flake(depth: u32, material: Material) -> Group {
+    flake(depth, &Arc::new(material))
+}
+
+fn flake(depth: u32, material: &Arc<Material>) -> Group {
+    let mut group = Group::new();
+    let mut sphere = Sphere::new();
+    sphere.set_material(Arc::clone(material));
+    group.add_child(sphere);
+    if depth == 0 {
+        return group;
+    }
+    // Each child's own up, +y, points away from its parent, so its ring
+    // grows outwards.
+    let equator = (0..6).map(|i| (FRAC_PI_2, i as f32 * PI / 3.0));
+    let above = (0..3).map(|i| (FRAC_PI_6, FRAC_PI_6 + i as f32 * 2.0 * PI / 3.0));
+    for (tilt, turn) in equator.chain(above) {
+        let scale = 1.0 / 3.0;
+        let placement = Matrix4::rotation_y(turn)
+            * Matrix4::rotation_x(tilt)
+            * Matrix4::translation(0.0, 1.0 + scale, 0.0)
+            * Matrix4::scaling(scale, scale, scale);
+        group.add_child(flake(depth - 1, material).with_transform(placement));
+    }
+    group
+}
+
+/// A `rows` × `columns` grid of unit-spaced spheres of `color` facing -z,
+/// centred on the origin, with [GGX](SpecularModel::Ggx) roughness rising
+/// from 0 to 1 across the columns and metalness from 0 to 1 down the rows,
+/// the top row being the first.
+pub fn material_chart(rows: usize, columns: usize, color: Color) -> Group {
+    let step = |i: usize, n: usize| {
+        if n > 1 {
+            i as f32 / (n - 1) as f32
+        } else {
+            0.0
+        }
+    };
+    let mut group = Group::new();
+    for row in 0..rows {
+        for column in 0..columns {
+            let x = column as f32 - (columns - 1) as f32 / 2.0;
+            let y = (rows - 1) as f32 / 2.0 - row as f32;
+            let metalness = step(row, rows);
+            group.add_child(
+                Sphere::new()
+                    .with_transform(
+                        Matrix4::translation(x, y, 0.0) * Matrix4::scaling(0.4, 0.4, 0.4),
+                    )
+                    .with_material(Material {
+                        color,
+                        specular_model: SpecularModel::Ggx {
+                            roughness: step(column, columns),
+                            metalness,
+                        },
+                        reflective: metalness,
+                        ..Material::default()
+                    }),
+            );
+        }
+    }
+    group
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use super::*;
+    use crate::math::Vert4;
+
+    fn as_group(shape: &dyn Shape) -> &Group {
+        (shape as &dyn Any).downcast_ref().unwrap()
+    }
+
+    /// The spheres in `group`, however deeply nested, depth first.
+    fn spheres(group: &Group) -> Vec<&dyn Shape> {
+        group
+            .children()
+            .iter()
+            .flat_map(|child| match (child.as_ref() as &dyn Any).downcast_ref() {
+                Some(g) => spheres(g),
+                None => vec![child.as_ref()],
+            })
+            .collect()
+    }
+
+    fn centre(shape: &dyn Shape) -> Vert4 {
+        *shape.transform().matrix() * Vert4::point(0.0, 0.0, 0.0)
+    }
+
+    #[test]
+    fn random_spheres_rest_on_the_ground_without_overlapping() {
+        let group = random_spheres(50, 7);
+        let all = spheres(&group);
+        assert_eq!(all.len(), 50);
+        for (i, a) in all.iter().enumerate() {
+            let bounds = a.bounds();
+            assert_approx_eq!(bounds.min.y, 0.0);
+            for b in &all[i + 1..] {
+                let gap = (centre(*a) - centre(*b)).magnitude();
+                assert!(gap >= (bounds.max.y + b.bounds().max.y) / 2.0);
+            }
+        }
+        let again = random_spheres(50, 7);
+        assert_eq!(group.bounds(), again.bounds());
+        assert_ne!(group.bounds(), random_spheres(50, 8).bounds());
+    }
+
+    #[test]
+    fn sphere_flake_grows_nine_children_a_level() {
+        assert_eq!(spheres(&sphere_flake(0, Material::default())).len(), 1);
+        let flake = sphere_flake(2, Material::default());
+        let all = spheres(&flake);
+        assert_eq!(all.len(), 1 + 9 + 81);
+        // Children touch their parent.
+        let children = &flake.children()[1..];
+        assert_eq!(children.len(), 9);
+        for child in children {
+            let sphere = spheres(as_group(child.as_ref()))[0];
+            let origin = Vert4::point(0.0, 0.0, 0.0);
+            assert_approx_eq!((centre(sphere) - origin).magnitude(), 4.0 / 3.0);
+        }
+        assert!(all
+            .iter()
+            .all(|s| Arc::ptr_eq(s.shared_material(), all[0].shared_material())));
+    }
+
+    #[test]
+    fn material_chart_varies_roughness_and_metalness() {
+        let chart = material_chart(2, 3, Color::WHITE);
+        let all = spheres(&chart);
+        assert_eq!(all.len(), 6);
+        let ggx = |s: &dyn Shape| match s.material().specular_model {
+            SpecularModel::Ggx {
+                roughness,
+                metalness,
+            } => (roughness, metalness),
+            SpecularModel::Phong => panic!("expected GGX"),
+        };
+        assert_eq!(ggx(all[0]), (0.0, 0.0));
+        assert_eq!(ggx(all[2]), (1.0, 0.0));
+        assert_eq!(ggx(all[4]), (0.5, 1.0));
+        assert_approx_eq!(centre(all[0]), Vert4::point(-1.0, 0.5, 0.0));
+        assert_approx_eq!(centre(all[5]), Vert4::point(1.0, -0.5, 0.0));
+    }
+}
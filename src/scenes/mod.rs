@@ -0,0 +1,3 @@
+//! Ready-made scenes and the building blocks for them.
+
+pub mod generate;
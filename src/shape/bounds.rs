@@ -0,0 +1,256 @@
+use super::cube::check_axis;
+use crate::math::{Matrix4, Vert4};
+use crate::ray::Ray;
+
+/// An axis-aligned bounding box. The default box is empty and contains nothing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Vert4,
+    pub max: Vert4,
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+impl BoundingBox {
+    pub const EMPTY: Self = Self {
+        min: Vert4::point(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+        max: Vert4::point(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+    };
+
+    pub const INFINITE: Self = Self {
+        min: Vert4::point(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        max: Vert4::point(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+    };
+
+    pub const fn new(min: Vert4, max: Vert4) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_points(points: impl IntoIterator<Item = Vert4>) -> Self {
+        let mut bounds = Self::EMPTY;
+        for p in points {
+            bounds.add_point(p);
+        }
+        bounds
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn is_finite(&self) -> bool {
+        [self.min, self.max]
+            .iter()
+            .all(|p| p.x.is_finite() && p.y.is_finite() && p.z.is_finite())
+    }
+
+    pub fn add_point(&mut self, p: Vert4) {
+        self.min = Vert4::point(
+            self.min.x.min(p.x),
+            self.min.y.min(p.y),
+            self.min.z.min(p.z),
+        );
+        self.max = Vert4::point(
+            self.max.x.max(p.x),
+            self.max.y.max(p.y),
+            self.max.z.max(p.z),
+        );
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        if !other.is_empty() {
+            self.add_point(other.min);
+            self.add_point(other.max);
+        }
+    }
+
+    pub fn contains_point(&self, p: Vert4) -> bool {
+        (self.min.x..=self.max.x).contains(&p.x)
+            && (self.min.y..=self.max.y).contains(&p.y)
+            && (self.min.z..=self.max.z).contains(&p.z)
+    }
+
+    pub fn contains_box(&self, other: &Self) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    /// The box enclosing this one after `m` is applied to all eight corners.
+    ///
+    /// Unbounded boxes stay unbounded, since their corners cannot be transformed.
+    pub fn transform(&self, m: &Matrix4) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+        if !self.is_finite() {
+            return Self::INFINITE;
+        }
+        let (lo, hi) = (self.min, self.max);
+        Self::from_points(
+            [
+                Vert4::point(lo.x, lo.y, lo.z),
+                Vert4::point(lo.x, lo.y, hi.z),
+                Vert4::point(lo.x, hi.y, lo.z),
+                Vert4::point(lo.x, hi.y, hi.z),
+                Vert4::point(hi.x, lo.y, lo.z),
+                Vert4::point(hi.x, lo.y, hi.z),
+                Vert4::point(hi.x, hi.y, lo.z),
+                Vert4::point(hi.x, hi.y, hi.z),
+            ]
+            .map(|corner| *m * corner),
+        )
+    }
+
+    /// Whether `ray` passes through the box at all (in either direction).
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let (xtmin, xtmax) = check_axis(ray.origin.x, ray.direction.x, self.min.x, self.max.x);
+        let (ytmin, ytmax) = check_axis(ray.origin.y, ray.direction.y, self.min.y, self.max.y);
+        let (ztmin, ztmax) = check_axis(ray.origin.z, ray.direction.z, self.min.z, self.max.z);
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+        tmin <= tmax
+    }
+
+    /// Halves the box across its longest axis.
+    pub fn split(&self) -> (Self, Self) {
+        let dx = self.max.x - self.min.x;
+        let dy = self.max.y - self.min.y;
+        let dz = self.max.z - self.min.z;
+        let greatest = dx.max(dy).max(dz);
+        let (mut x0, mut y0, mut z0) = (self.min.x, self.min.y, self.min.z);
+        let (mut x1, mut y1, mut z1) = (self.max.x, self.max.y, self.max.z);
+        if greatest == dx {
+            x0 += dx / 2.0;
+            x1 = x0;
+        } else if greatest == dy {
+            y0 += dy / 2.0;
+            y1 = y0;
+        } else {
+            z0 += dz / 2.0;
+            z1 = z0;
+        }
+        (
+            Self::new(self.min, Vert4::point(x1, y1, z1)),
+            Self::new(Vert4::point(x0, y0, z0), self.max),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{PI, SQRT_2};
+
+    use super::*;
+    use crate::shape::{Cylinder, Group, Shape, Sphere};
+
+    #[test]
+    fn empty_and_add_points() {
+        let mut b = BoundingBox::default();
+        assert!(b.is_empty());
+        b.add_point(Vert4::point(-5.0, 2.0, 0.0));
+        b.add_point(Vert4::point(7.0, 0.0, -3.0));
+        assert_eq!(b.min, Vert4::point(-5.0, 0.0, -3.0));
+        assert_eq!(b.max, Vert4::point(7.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn merging() {
+        let mut b1 = BoundingBox::new(Vert4::point(-5.0, -2.0, 0.0), Vert4::point(7.0, 4.0, 4.0));
+        let b2 = BoundingBox::new(Vert4::point(8.0, -7.0, -2.0), Vert4::point(14.0, 2.0, 8.0));
+        b1.merge(&b2);
+        assert_eq!(b1.min, Vert4::point(-5.0, -7.0, -2.0));
+        assert_eq!(b1.max, Vert4::point(14.0, 4.0, 8.0));
+    }
+
+    #[test]
+    fn containment() {
+        let b = BoundingBox::new(Vert4::point(5.0, -2.0, 0.0), Vert4::point(11.0, 4.0, 7.0));
+        assert!(b.contains_point(Vert4::point(5.0, -2.0, 0.0)));
+        assert!(b.contains_point(Vert4::point(8.0, 1.0, 3.0)));
+        assert!(!b.contains_point(Vert4::point(3.0, 0.0, 3.0)));
+        assert!(!b.contains_point(Vert4::point(8.0, 1.0, 8.0)));
+        assert!(b.contains_box(&BoundingBox::new(
+            Vert4::point(6.0, -1.0, 1.0),
+            Vert4::point(10.0, 3.0, 6.0)
+        )));
+        assert!(!b.contains_box(&BoundingBox::new(
+            Vert4::point(4.0, -3.0, -1.0),
+            Vert4::point(10.0, 3.0, 6.0)
+        )));
+    }
+
+    #[test]
+    fn transforming() {
+        let b = BoundingBox::new(Vert4::point(-1.0, -1.0, -1.0), Vert4::point(1.0, 1.0, 1.0));
+        let t = b.transform(&(Matrix4::rotation_x(PI / 4.0) * Matrix4::rotation_y(PI / 4.0)));
+        assert_approx_eq!(t.min, Vert4::point(-SQRT_2, -1.70711, -1.70711));
+        assert_approx_eq!(t.max, Vert4::point(SQRT_2, 1.70711, 1.70711));
+    }
+
+    #[test]
+    fn ray_against_box() {
+        let b = BoundingBox::new(Vert4::point(5.0, -2.0, 0.0), Vert4::point(11.0, 4.0, 7.0));
+        let cases = [
+            ((15.0, 1.0, 2.0), (-1.0, 0.0, 0.0), true),
+            ((-5.0, -1.0, 4.0), (1.0, 0.0, 0.0), true),
+            ((7.0, 6.0, 5.0), (0.0, -1.0, 0.0), true),
+            ((9.0, 1.0, 7.0), (0.0, 0.0, -1.0), true),
+            ((8.0, 2.0, 12.0), (0.0, 0.0, -1.0), true),
+            ((6.0, 0.0, 5.0), (0.0, 0.0, 1.0), true),
+            ((12.0, 5.0, 4.0), (-1.0, 0.0, 0.0), false),
+            ((9.0, -1.0, -8.0), (2.0, 4.0, 6.0), false),
+            ((8.0, 3.0, -4.0), (6.0, 2.0, 4.0), false),
+        ];
+        for ((ox, oy, oz), (dx, dy, dz), expected) in cases {
+            let r = Ray::new(
+                Vert4::point(ox, oy, oz),
+                Vert4::vector(dx, dy, dz).normalize(),
+            );
+            assert_eq!(b.intersects(&r), expected, "origin ({ox}, {oy}, {oz})");
+        }
+    }
+
+    #[test]
+    fn splitting() {
+        let b = BoundingBox::new(Vert4::point(-1.0, -2.0, -3.0), Vert4::point(9.0, 5.5, 3.0));
+        let (l, r) = b.split();
+        assert_eq!(l.min, Vert4::point(-1.0, -2.0, -3.0));
+        assert_eq!(l.max, Vert4::point(4.0, 5.5, 3.0));
+        assert_eq!(r.min, Vert4::point(4.0, -2.0, -3.0));
+        assert_eq!(r.max, Vert4::point(9.0, 5.5, 3.0));
+    }
+
+    #[test]
+    fn shape_bounds() {
+        let s = Sphere::new()
+            .with_transform(Matrix4::translation(1.0, -3.0, 5.0) * Matrix4::scaling(0.5, 2.0, 4.0));
+        let b = s.bounds();
+        assert_eq!(b.min, Vert4::point(0.5, -5.0, 1.0));
+        assert_eq!(b.max, Vert4::point(1.5, -1.0, 9.0));
+
+        let cyl = Cylinder::truncated(-2.0, 3.0, true);
+        assert_eq!(cyl.bounds().min, Vert4::point(-1.0, -2.0, -1.0));
+        assert_eq!(cyl.bounds().max, Vert4::point(1.0, 3.0, 1.0));
+        assert!(!Cylinder::new().bounds().is_finite());
+    }
+
+    #[test]
+    fn group_bounds_cover_children() {
+        let g = Group::new()
+            .with_child(Sphere::new().with_transform(
+                Matrix4::translation(2.0, 5.0, -3.0) * Matrix4::scaling(2.0, 2.0, 2.0),
+            ))
+            .with_child(Cylinder::truncated(-2.0, 2.0, false).with_transform(
+                Matrix4::translation(-4.0, -1.0, 4.0) * Matrix4::scaling(0.5, 1.0, 0.5),
+            ));
+        let b = g.bounds();
+        assert_approx_eq!(b.min, Vert4::point(-4.5, -3.0, -5.0));
+        assert_approx_eq!(b.max, Vert4::point(4.0, 7.0, 4.5));
+    }
+}
@@ -0,0 +1,308 @@
+use std::ptr;
+use std::sync::Arc;
+
+use super::{BoundingBox, Shape, ShapeData, Visibility};
+use crate::color::Color;
+use crate::intersection::{Intersection, Intersections};
+use crate::material::Material;
+use crate::math::{Vert4, EPSILON};
+use crate::ray::Ray;
+
+/// A plane cutting away part of a [`Clipped`] shape: in object space it's
+/// the plane `y = 0`, and everything above it is cut away. Place and tilt
+/// it with a transform.
+///
+/// Its material colors the cap where it cuts through a solid.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ClipPlane {
+    data: ShapeData,
+}
+
+impl ClipPlane {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the world-space `point` is on the side cut away.
+    pub fn clips(&self, point: Vert4) -> bool {
+        self.world_to_object(point).y > 0.0
+    }
+}
+
+impl Shape for ClipPlane {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn local_intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        if ray.direction.y.abs() < EPSILON {
+            return;
+        }
+        xs.push(Intersection::new(-ray.origin.y / ray.direction.y, self));
+    }
+
+    /// Facing the side cut away, which is out of the solid it caps.
+    fn local_normal_at(&self, _local_point: Vert4, _hit: &Intersection) -> Vert4 {
+        Vert4::vector(0.0, 1.0, 0.0)
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::INFINITE
+    }
+}
+
+/// A shape, often a whole [`Group`](super::Group), with everything beyond
+/// its [`ClipPlane`]s cut away, for cutaway and section views.
+///
+/// Cut solids are hollow unless capped: then each plane also shows where it
+/// cuts through the shape, as a flat face in the plane's material. Capping
+/// counts the shape's surfaces along each ray to tell inside from out, so it
+/// needs closed surfaces that don't overlap one another.
+///
+/// The shape and planes inherit this shape's transform, like children of a
+/// group.
+#[derive(Debug)]
+pub struct Clipped {
+    shape: Box<dyn Shape>,
+    planes: Vec<ClipPlane>,
+    cap: Option<Arc<Material>>,
+    data: ShapeData,
+}
+
+impl Clipped {
+    pub fn new(shape: impl Shape) -> Self {
+        let mut clipped = Self {
+            shape: Box::new(shape),
+            planes: Vec::new(),
+            cap: None,
+            data: ShapeData::default(),
+        };
+        clipped.update_transform();
+        clipped
+    }
+
+    pub fn with_plane(mut self, plane: ClipPlane) -> Self {
+        self.add_plane(plane);
+        self
+    }
+
+    pub fn add_plane(&mut self, mut plane: ClipPlane) {
+        plane.set_parent_transform(*self.transform().matrix());
+        plane.set_visibility(self.visibility());
+        if let Some(cap) = &self.cap {
+            plane.set_material(Arc::clone(cap));
+        }
+        self.planes.push(plane);
+    }
+
+    /// Caps the cuts in `color`.
+    pub fn with_cap(self, color: Color) -> Self {
+        self.with_cap_material(Material {
+            color,
+            ..Material::default()
+        })
+    }
+
+    /// Caps the cuts in `material`, replacing the planes' own.
+    pub fn with_cap_material(mut self, material: Material) -> Self {
+        let cap = Arc::new(material);
+        for plane in &mut self.planes {
+            plane.set_material(Arc::clone(&cap));
+        }
+        self.cap = Some(cap);
+        self
+    }
+
+    pub fn shape(&self) -> &dyn Shape {
+        self.shape.as_ref()
+    }
+
+    pub fn planes(&self) -> &[ClipPlane] {
+        &self.planes
+    }
+
+    pub fn is_capped(&self) -> bool {
+        self.cap.is_some()
+    }
+
+    fn kept(&self, point: Vert4, except: Option<usize>) -> bool {
+        self.planes
+            .iter()
+            .enumerate()
+            .all(|(i, plane)| Some(i) == except || !plane.clips(point))
+    }
+}
+
+impl Shape for Clipped {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn update_transform(&mut self) {
+        self.data.update_transform();
+        let matrix = *self.data.transform.matrix();
+        self.shape.set_parent_transform(matrix);
+        for plane in &mut self.planes {
+            plane.set_parent_transform(matrix);
+        }
+    }
+
+    /// The shape and planes already carry this shape's transform, so they
+    /// take the world ray. The shape's hits are gathered apart from `xs`,
+    /// since where the caps go depends on them.
+    fn intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        let mut own = Intersections::default();
+        self.shape.intersect_into(ray, &mut own);
+        if self.cap.is_some() {
+            for (i, plane) in self.planes.iter().enumerate() {
+                let mut crossing = Intersections::default();
+                plane.intersect_into(ray, &mut crossing);
+                let Some(&cap) = crossing.first() else {
+                    continue;
+                };
+                // An odd number of surfaces before the plane puts it inside.
+                let inside = own.iter().filter(|x| x.t < cap.t).count() % 2 == 1;
+                if inside && self.kept(ray.position(cap.t), Some(i)) {
+                    xs.push(cap);
+                }
+            }
+        }
+        xs.extend(
+            own.into_iter()
+                .filter(|x| self.kept(ray.position(x.t), None)),
+        );
+    }
+
+    fn local_intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        self.intersect_into(&ray.transform(self.transform().matrix()), xs);
+    }
+
+    /// # Panics
+    ///
+    /// Always: hits record the shape or plane that was struck, never the
+    /// clipped shape.
+    fn local_normal_at(&self, _local_point: Vert4, _hit: &Intersection) -> Vert4 {
+        panic!("local_normal_at called on a clipped shape")
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.bounds().transform(self.transform().inverse())
+    }
+
+    /// The unclipped shape's: cutting only ever shrinks it.
+    fn bounds(&self) -> BoundingBox {
+        self.shape.bounds()
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        ptr::addr_eq(self, other)
+            || self.shape.includes(other)
+            || self.planes.iter().any(|plane| plane.includes(other))
+    }
+
+    fn for_each_child<'a>(&'a self, visit: &mut dyn FnMut(&'a dyn Shape)) {
+        visit(self.shape.as_ref());
+        for plane in &self.planes {
+            visit(plane);
+        }
+    }
+
+    fn contains_instance(&self) -> bool {
+        self.shape.contains_instance()
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.data.visibility = visibility;
+        self.shape.set_visibility(visibility);
+        for plane in &mut self.planes {
+            plane.set_visibility(visibility);
+        }
+    }
+
+    fn replace_material(&mut self, old: &Arc<Material>, new: &Arc<Material>) {
+        if Arc::ptr_eq(self.shared_material(), old) {
+            self.set_material(Arc::clone(new));
+        }
+        self.shape.replace_material(old, new);
+        for plane in &mut self.planes {
+            plane.replace_material(old, new);
+        }
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        self.shape.divide(threshold);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Matrix4;
+    use crate::shape::{Group, Sphere};
+    use std::f32::consts::FRAC_PI_2;
+
+    fn ts(xs: &Intersections) -> Vec<f32> {
+        xs.iter().map(|x| x.t).collect()
+    }
+
+    fn down(x: f32, z: f32) -> Ray {
+        Ray::new(Vert4::point(x, 5.0, z), Vert4::vector(0.0, -1.0, 0.0))
+    }
+
+    #[test]
+    fn hits_beyond_a_plane_are_cut_away() {
+        let s = Clipped::new(Sphere::new()).with_plane(ClipPlane::new());
+        assert_eq!(ts(&s.intersect(&down(0.0, 0.0))), [6.0]);
+        let across = |y| Ray::new(Vert4::point(-5.0, y, 0.0), Vert4::vector(1.0, 0.0, 0.0));
+        assert!(s.intersect(&across(0.5)).is_empty());
+        assert_eq!(s.intersect(&across(-0.5)).len(), 2);
+    }
+
+    #[test]
+    fn caps_close_the_cut() {
+        let s = Clipped::new(Sphere::new())
+            .with_plane(ClipPlane::new())
+            .with_cap(Color::new(1.0, 0.0, 0.0));
+        let ray = down(0.5, 0.0);
+        let xs = s.intersect(&ray);
+        assert_approx_eq!(ts(&xs)[0], 5.0);
+        let cap = xs.hit().unwrap();
+        assert!(ptr::addr_eq(cap.object, &s.planes()[0]));
+        assert_eq!(cap.material().color, Color::new(1.0, 0.0, 0.0));
+        let comps = cap.prepare_computations(&ray, &xs);
+        assert_approx_eq!(comps.normalv, Vert4::vector(0.0, 1.0, 0.0));
+        // Beside the sphere the plane cuts nothing, so there's no cap.
+        assert!(s.intersect(&down(2.0, 0.0)).is_empty());
+    }
+
+    #[test]
+    fn caps_stop_at_the_other_planes() {
+        let s = Clipped::new(Sphere::new())
+            .with_plane(ClipPlane::new())
+            .with_plane(ClipPlane::new().with_transform(Matrix4::rotation_z(-FRAC_PI_2)))
+            .with_cap(Color::WHITE);
+        // The second plane cuts away x > 0, taking that half of the first
+        // cap with it.
+        assert_eq!(s.intersect(&down(0.5, 0.0)).len(), 0);
+        assert_eq!(s.intersect(&down(-0.5, 0.0)).len(), 2);
+    }
+
+    #[test]
+    fn planes_move_with_the_clipped_shape() {
+        let mut s = Clipped::new(Group::new().with_child(Sphere::new()))
+            .with_plane(ClipPlane::new())
+            .with_cap(Color::WHITE);
+        s.set_transform(Matrix4::translation(0.0, 1.0, 0.0));
+        let xs = s.intersect(&down(0.0, 0.0));
+        assert_eq!(ts(&xs), [4.0, 5.0]);
+        assert!(s.bounds().contains_point(Vert4::point(0.0, 2.0, 0.0)));
+    }
+}
@@ -0,0 +1,209 @@
+use super::cylinder::{cap_normal, intersect_caps, truncate};
+use super::{BoundingBox, Shape, ShapeData};
+use crate::intersection::{Intersection, Intersections};
+use crate::math::{Vert4, EPSILON};
+use crate::pattern::uv;
+use crate::ray::Ray;
+
+/// A double-napped cone around the y axis whose radius equals `|y|`, optionally
+/// truncated to `minimum..maximum` (exclusive) and capped at both ends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cone {
+    pub minimum: f32,
+    pub maximum: f32,
+    pub closed: bool,
+    data: ShapeData,
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        Self::truncated(f32::NEG_INFINITY, f32::INFINITY, false)
+    }
+}
+
+impl Cone {
+    /// An infinite, open double-napped cone.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn truncated(minimum: f32, maximum: f32, closed: bool) -> Self {
+        Self {
+            minimum,
+            maximum,
+            closed,
+            data: ShapeData::default(),
+        }
+    }
+}
+
+impl Shape for Cone {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn local_intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        let (o, d) = (ray.origin, ray.direction);
+        let a = d.x.powi(2) - d.y.powi(2) + d.z.powi(2);
+        let b = 2.0 * o.x * d.x - 2.0 * o.y * d.y + 2.0 * o.z * d.z;
+        let c = o.x.powi(2) - o.y.powi(2) + o.z.powi(2);
+
+        let mut hit = |t| xs.push(Intersection::new(t, self));
+        if a.abs() < EPSILON {
+            // Parallel to one of the nappes: at most one wall hit.
+            if b.abs() >= EPSILON {
+                truncate(ray, [-c / (2.0 * b)], self.minimum, self.maximum, &mut hit);
+            }
+        } else {
+            // Rays grazing the surface produce discriminants that round to just
+            // below zero; treat those as tangent hits.
+            let disc = b * b - 4.0 * a * c;
+            if disc < -EPSILON {
+                return;
+            }
+            let root = disc.max(0.0).sqrt();
+            let t0 = (-b - root) / (2.0 * a);
+            let t1 = (-b + root) / (2.0 * a);
+            truncate(
+                ray,
+                [t0.min(t1), t0.max(t1)],
+                self.minimum,
+                self.maximum,
+                &mut hit,
+            );
+        }
+        if self.closed {
+            intersect_caps(ray, self.minimum, self.maximum, f32::abs, hit);
+        }
+    }
+
+    /// The unnormalized object-space normal at `local_point`.
+    fn local_normal_at(&self, local_point: Vert4, _hit: &Intersection) -> Vert4 {
+        let dist = local_point.x.powi(2) + local_point.z.powi(2);
+        if let Some(normal) = self
+            .closed
+            .then(|| {
+                cap_normal(
+                    local_point,
+                    dist,
+                    local_point.y.abs(),
+                    self.minimum,
+                    self.maximum,
+                )
+            })
+            .flatten()
+        {
+            return normal;
+        }
+        let mut y = dist.sqrt();
+        if local_point.y > 0.0 {
+            y = -y;
+        }
+        Vert4::vector(local_point.x, y, local_point.z)
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let limit = self.minimum.abs().max(self.maximum.abs());
+        BoundingBox::new(
+            Vert4::point(-limit, self.minimum, -limit),
+            Vert4::point(limit, self.maximum, limit),
+        )
+    }
+
+    fn local_uv(&self, local_point: Vert4) -> (f32, f32) {
+        uv::cylindrical_map(local_point)
+    }
+
+    fn local_tangents(&self, p: Vert4) -> (Vert4, Vert4) {
+        (Vert4::vector(-p.z, 0.0, p.x), Vert4::vector(0.0, 1.0, 0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(shape: &dyn Shape, origin: Vert4, direction: Vert4) -> Vec<f32> {
+        let r = Ray::new(origin, direction.normalize());
+        shape.intersect(&r).iter().map(|x| x.t).collect()
+    }
+
+    fn local_normal(cone: &Cone, p: Vert4) -> Vert4 {
+        cone.local_normal_at(p, &Intersection::new(0.0, cone))
+    }
+
+    #[test]
+    fn ray_hits_walls() {
+        let cone = Cone::new();
+        let cases = [
+            ((0.0, 0.0, -5.0), (0.0, 0.0, 1.0), [5.0, 5.0]),
+            ((0.0, 0.0, -5.0), (1.0, 1.0, 1.0), [8.66025, 8.66025]),
+            ((1.0, 1.0, -5.0), (-0.5, -1.0, 1.0), [4.55006, 49.44994]),
+        ];
+        for ((ox, oy, oz), (dx, dy, dz), [t0, t1]) in cases {
+            let got = ts(&cone, Vert4::point(ox, oy, oz), Vert4::vector(dx, dy, dz));
+            assert_eq!(got.len(), 2);
+            // Relative tolerance: the far hit is ~50 units out.
+            assert!((got[0] - t0).abs() / t0 < 1e-4, "{got:?}");
+            assert!((got[1] - t1).abs() / t1 < 1e-4, "{got:?}");
+        }
+    }
+
+    #[test]
+    fn ray_parallel_to_one_half() {
+        let cone = Cone::new();
+        let got = ts(
+            &cone,
+            Vert4::point(0.0, 0.0, -1.0),
+            Vert4::vector(0.0, 1.0, 1.0),
+        );
+        assert_approx_eq!(got, vec![0.35355]);
+    }
+
+    #[test]
+    fn caps() {
+        let cone = Cone::truncated(-0.5, 0.5, true);
+        let cases = [
+            ((0.0, 0.0, -5.0), (0.0, 1.0, 0.0), 0),
+            ((0.0, 0.0, -0.25), (0.0, 1.0, 1.0), 2),
+            ((0.0, 0.0, -0.25), (0.0, 1.0, 0.0), 4),
+        ];
+        for ((ox, oy, oz), (dx, dy, dz), count) in cases {
+            let got = ts(&cone, Vert4::point(ox, oy, oz), Vert4::vector(dx, dy, dz));
+            assert_eq!(got.len(), count, "origin ({ox}, {oy}, {oz})");
+        }
+    }
+
+    #[test]
+    fn wall_normals() {
+        let cone = Cone::default();
+        let cases = [
+            ((0.0, 0.0, 0.0), (0.0, 0.0, 0.0)),
+            ((1.0, 1.0, 1.0), (1.0, -2f32.sqrt(), 1.0)),
+            ((-1.0, -1.0, 0.0), (-1.0, 1.0, 0.0)),
+        ];
+        for ((px, py, pz), (nx, ny, nz)) in cases {
+            assert_approx_eq!(
+                local_normal(&cone, Vert4::point(px, py, pz)),
+                Vert4::vector(nx, ny, nz)
+            );
+        }
+    }
+
+    #[test]
+    fn cap_normals() {
+        let cone = Cone::truncated(-1.0, 2.0, true);
+        assert_eq!(
+            local_normal(&cone, Vert4::point(0.5, 2.0, 0.5)),
+            Vert4::vector(0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            local_normal(&cone, Vert4::point(0.2, -1.0, 0.0)),
+            Vert4::vector(0.0, -1.0, 0.0)
+        );
+    }
+}
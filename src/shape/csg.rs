@@ -0,0 +1,266 @@
+use std::ptr;
+use std::sync::Arc;
+
+use super::{BoundingBox, Shape, ShapeData, Visibility};
+use crate::intersection::{Intersection, Intersections};
+use crate::material::Material;
+use crate::math::Vert4;
+use crate::ray::Ray;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOperation {
+    /// Whether a hit survives, given which side it is on and whether the ray is
+    /// currently inside the left and right operands.
+    pub fn intersection_allowed(
+        self,
+        left_hit: bool,
+        inside_left: bool,
+        inside_right: bool,
+    ) -> bool {
+        match self {
+            Self::Union => (left_hit && !inside_right) || (!left_hit && !inside_left),
+            Self::Intersection => (left_hit && inside_right) || (!left_hit && inside_left),
+            Self::Difference => (left_hit && !inside_right) || (!left_hit && inside_left),
+        }
+    }
+}
+
+/// Constructive solid geometry: two operands combined by an operation.
+///
+/// Both operands inherit the CSG shape's transform, like children of a [`Group`](super::Group).
+#[derive(Debug)]
+pub struct Csg {
+    pub operation: CsgOperation,
+    left: Box<dyn Shape>,
+    right: Box<dyn Shape>,
+    data: ShapeData,
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: impl Shape, right: impl Shape) -> Self {
+        let mut csg = Self {
+            operation,
+            left: Box::new(left),
+            right: Box::new(right),
+            data: ShapeData::default(),
+        };
+        csg.update_transform();
+        csg
+    }
+
+    pub fn left(&self) -> &dyn Shape {
+        self.left.as_ref()
+    }
+
+    pub fn right(&self) -> &dyn Shape {
+        self.right.as_ref()
+    }
+
+    /// Keeps the intersections (sorted by `t`) that lie on the combined surface.
+    pub fn filter_intersections<'a>(&self, xs: Intersections<'a>) -> Intersections<'a> {
+        let mut inside_left = false;
+        let mut inside_right = false;
+        let mut result = Intersections::default();
+        for x in xs {
+            let left_hit = self.left.includes(x.object);
+            if self
+                .operation
+                .intersection_allowed(left_hit, inside_left, inside_right)
+            {
+                result.push(x);
+            }
+            if left_hit {
+                inside_left = !inside_left;
+            } else {
+                inside_right = !inside_right;
+            }
+        }
+        result
+    }
+}
+
+impl Shape for Csg {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn update_transform(&mut self) {
+        self.data.update_transform();
+        let matrix = *self.data.transform.matrix();
+        self.left.set_parent_transform(matrix);
+        self.right.set_parent_transform(matrix);
+    }
+
+    /// The operands already carry this shape's transform, so they take the
+    /// world ray. Their hits are gathered apart from `xs`, since which are
+    /// kept depends on the operands' hits alone.
+    fn intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        let mut own = Intersections::default();
+        self.left.intersect_into(ray, &mut own);
+        self.right.intersect_into(ray, &mut own);
+        xs.extend(self.filter_intersections(own));
+    }
+
+    fn local_intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        self.intersect_into(&ray.transform(self.transform().matrix()), xs);
+    }
+
+    /// # Panics
+    ///
+    /// Always: hits record the operand that was struck, never the CSG shape.
+    fn local_normal_at(&self, _local_point: Vert4, _hit: &Intersection) -> Vert4 {
+        panic!("local_normal_at called on a CSG shape")
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.bounds().transform(self.transform().inverse())
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut bounds = self.left.bounds();
+        bounds.merge(&self.right.bounds());
+        bounds
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        ptr::addr_eq(self, other) || self.left.includes(other) || self.right.includes(other)
+    }
+
+    fn for_each_child<'a>(&'a self, visit: &mut dyn FnMut(&'a dyn Shape)) {
+        visit(self.left.as_ref());
+        visit(self.right.as_ref());
+    }
+
+    fn contains_instance(&self) -> bool {
+        self.left.contains_instance() || self.right.contains_instance()
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.data.visibility = visibility;
+        self.left.set_visibility(visibility);
+        self.right.set_visibility(visibility);
+    }
+
+    fn replace_material(&mut self, old: &Arc<Material>, new: &Arc<Material>) {
+        if Arc::ptr_eq(self.shared_material(), old) {
+            self.set_material(Arc::clone(new));
+        }
+        self.left.replace_material(old, new);
+        self.right.replace_material(old, new);
+    }
+
+    fn divide(&mut self, threshold: usize) {
+        self.left.divide(threshold);
+        self.right.divide(threshold);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Matrix4;
+    use crate::shape::{Cube, Sphere};
+
+    #[test]
+    fn operation_rules() {
+        use CsgOperation::*;
+        // (left_hit, inside_left, inside_right) -> (union, intersection, difference)
+        let cases = [
+            ((true, true, true), (false, true, false)),
+            ((true, true, false), (true, false, true)),
+            ((true, false, true), (false, true, false)),
+            ((true, false, false), (true, false, true)),
+            ((false, true, true), (false, true, true)),
+            ((false, true, false), (false, true, true)),
+            ((false, false, true), (true, false, false)),
+            ((false, false, false), (true, false, false)),
+        ];
+        for ((lhit, inl, inr), (u, i, d)) in cases {
+            assert_eq!(Union.intersection_allowed(lhit, inl, inr), u);
+            assert_eq!(Intersection.intersection_allowed(lhit, inl, inr), i);
+            assert_eq!(Difference.intersection_allowed(lhit, inl, inr), d);
+        }
+    }
+
+    #[test]
+    fn filtering_intersections() {
+        let cases = [
+            (CsgOperation::Union, [0, 3]),
+            (CsgOperation::Intersection, [1, 2]),
+            (CsgOperation::Difference, [0, 1]),
+        ];
+        for (op, [x0, x1]) in cases {
+            let c = Csg::new(op, Sphere::new(), Cube::new());
+            let (s1, s2) = (c.left(), c.right());
+            let xs = [
+                Intersection::new(1.0, s1),
+                Intersection::new(2.0, s2),
+                Intersection::new(3.0, s1),
+                Intersection::new(4.0, s2),
+            ];
+            let result = c.filter_intersections(Intersections::new(xs));
+            assert_eq!(result.len(), 2);
+            assert_eq!(result[0], xs[x0]);
+            assert_eq!(result[1], xs[x1]);
+        }
+    }
+
+    #[test]
+    fn ray_misses_csg() {
+        let c = Csg::new(CsgOperation::Union, Sphere::new(), Cube::new());
+        let r = Ray::new(Vert4::point(0.0, 2.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert!(c.intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_hits_csg() {
+        let c = Csg::new(
+            CsgOperation::Union,
+            Sphere::new(),
+            Sphere::new().with_transform(Matrix4::translation(0.0, 0.0, 0.5)),
+        );
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = c.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4.0);
+        assert!(ptr::addr_eq(xs[0].object, c.left()));
+        assert_eq!(xs[1].t, 6.5);
+        assert!(ptr::addr_eq(xs[1].object, c.right()));
+    }
+
+    #[test]
+    fn difference_carves_a_hole() {
+        // A cube with a sphere removed from its centre: the ray enters the cube,
+        // then exits into the carved cavity at the sphere's surface.
+        let c = Csg::new(
+            CsgOperation::Difference,
+            Cube::new(),
+            Sphere::new().with_transform(Matrix4::scaling(0.5, 0.5, 0.5)),
+        );
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let ts: Vec<f32> = c.intersect(&r).iter().map(|x| x.t).collect();
+        assert_eq!(ts, [4.0, 4.5, 5.5, 6.0]);
+    }
+
+    #[test]
+    fn operands_inherit_csg_transform() {
+        let c = Csg::new(CsgOperation::Union, Sphere::new(), Cube::new())
+            .with_transform(Matrix4::translation(10.0, 0.0, 0.0));
+        assert_eq!(
+            c.left().transform().matrix(),
+            &Matrix4::translation(10.0, 0.0, 0.0)
+        );
+        let r = Ray::new(Vert4::point(10.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert_eq!(c.intersect(&r).len(), 2);
+    }
+}
@@ -0,0 +1,164 @@
+use super::{BoundingBox, Shape, ShapeData};
+use crate::intersection::{Intersection, Intersections};
+use crate::math::{Vert4, EPSILON};
+use crate::pattern::uv;
+use crate::ray::Ray;
+
+/// An axis-aligned cube spanning `-1..=1` on every axis.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Cube {
+    data: ShapeData,
+}
+
+impl Cube {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// The entry and exit distances of a ray against the slab `min..=max` on one axis.
+pub(super) fn check_axis(origin: f32, direction: f32, min: f32, max: f32) -> (f32, f32) {
+    let tmin_numerator = min - origin;
+    let tmax_numerator = max - origin;
+    let (tmin, tmax) = if direction.abs() >= EPSILON {
+        (tmin_numerator / direction, tmax_numerator / direction)
+    } else {
+        (
+            tmin_numerator * f32::INFINITY,
+            tmax_numerator * f32::INFINITY,
+        )
+    };
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+impl Shape for Cube {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn local_intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        let (xtmin, xtmax) = check_axis(ray.origin.x, ray.direction.x, -1.0, 1.0);
+        let (ytmin, ytmax) = check_axis(ray.origin.y, ray.direction.y, -1.0, 1.0);
+        let (ztmin, ztmax) = check_axis(ray.origin.z, ray.direction.z, -1.0, 1.0);
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+        if tmin > tmax {
+            return;
+        }
+        xs.push(Intersection::new(tmin, self));
+        xs.push(Intersection::new(tmax, self));
+    }
+
+    /// The normal of the face containing `local_point`: the axis with the largest component.
+    fn local_normal_at(&self, local_point: Vert4, _hit: &Intersection) -> Vert4 {
+        let (x, y, z) = (
+            local_point.x.abs(),
+            local_point.y.abs(),
+            local_point.z.abs(),
+        );
+        let maxc = x.max(y).max(z);
+        if maxc == x {
+            Vert4::vector(local_point.x, 0.0, 0.0)
+        } else if maxc == y {
+            Vert4::vector(0.0, local_point.y, 0.0)
+        } else {
+            Vert4::vector(0.0, 0.0, local_point.z)
+        }
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(Vert4::point(-1.0, -1.0, -1.0), Vert4::point(1.0, 1.0, 1.0))
+    }
+
+    fn local_uv(&self, local_point: Vert4) -> (f32, f32) {
+        uv::cube_map(local_point)
+    }
+
+    fn local_tangents(&self, local_point: Vert4) -> (Vert4, Vert4) {
+        uv::CubeFace::of(local_point).tangents()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::Vert4;
+    use crate::ray::Ray;
+    use crate::shape::{normal_at, Cube, Shape};
+
+    fn ts(origin: Vert4, direction: Vert4) -> Vec<f32> {
+        Cube::new()
+            .intersect(&Ray::new(origin, direction))
+            .iter()
+            .map(|x| x.t)
+            .collect()
+    }
+
+    #[test]
+    fn ray_intersects_each_face() {
+        let cases = [
+            ((5.0, 0.5, 0.0), (-1.0, 0.0, 0.0), [4.0, 6.0]),
+            ((-5.0, 0.5, 0.0), (1.0, 0.0, 0.0), [4.0, 6.0]),
+            ((0.5, 5.0, 0.0), (0.0, -1.0, 0.0), [4.0, 6.0]),
+            ((0.5, -5.0, 0.0), (0.0, 1.0, 0.0), [4.0, 6.0]),
+            ((0.5, 0.0, 5.0), (0.0, 0.0, -1.0), [4.0, 6.0]),
+            ((0.5, 0.0, -5.0), (0.0, 0.0, 1.0), [4.0, 6.0]),
+            ((0.0, 0.5, 0.0), (0.0, 0.0, 1.0), [-1.0, 1.0]),
+        ];
+        for ((ox, oy, oz), (dx, dy, dz), expected) in cases {
+            let got = ts(Vert4::point(ox, oy, oz), Vert4::vector(dx, dy, dz));
+            assert_eq!(got, expected, "origin ({ox}, {oy}, {oz})");
+        }
+    }
+
+    #[test]
+    fn ray_misses() {
+        let cases = [
+            ((-2.0, 0.0, 0.0), (0.2673, 0.5345, 0.8018)),
+            ((0.0, -2.0, 0.0), (0.8018, 0.2673, 0.5345)),
+            ((0.0, 0.0, -2.0), (0.5345, 0.8018, 0.2673)),
+            ((2.0, 0.0, 2.0), (0.0, 0.0, -1.0)),
+            ((0.0, 2.0, 2.0), (0.0, -1.0, 0.0)),
+            ((2.0, 2.0, 0.0), (-1.0, 0.0, 0.0)),
+        ];
+        for ((ox, oy, oz), (dx, dy, dz)) in cases {
+            assert!(ts(Vert4::point(ox, oy, oz), Vert4::vector(dx, dy, dz)).is_empty());
+        }
+    }
+
+    #[test]
+    fn normals() {
+        let cases = [
+            ((1.0, 0.5, -0.8), (1.0, 0.0, 0.0)),
+            ((-1.0, -0.2, 0.9), (-1.0, 0.0, 0.0)),
+            ((-0.4, 1.0, -0.1), (0.0, 1.0, 0.0)),
+            ((0.3, -1.0, -0.7), (0.0, -1.0, 0.0)),
+            ((-0.6, 0.3, 1.0), (0.0, 0.0, 1.0)),
+            ((0.4, 0.4, -1.0), (0.0, 0.0, -1.0)),
+            ((1.0, 1.0, 1.0), (1.0, 0.0, 0.0)),
+            ((-1.0, -1.0, -1.0), (-1.0, 0.0, 0.0)),
+        ];
+        let c = Cube::new();
+        for ((px, py, pz), (nx, ny, nz)) in cases {
+            assert_eq!(
+                normal_at(&c, Vert4::point(px, py, pz)),
+                Vert4::vector(nx, ny, nz)
+            );
+        }
+    }
+
+    #[test]
+    fn uv_unfolds_faces_into_a_cross() {
+        let c = Cube::new();
+        assert_eq!(c.local_uv(Vert4::point(-1.0, 0.0, 0.0)), (0.125, 0.5));
+        assert_eq!(c.local_uv(Vert4::point(0.0, 0.0, 1.0)), (0.375, 0.5));
+        assert_eq!(c.local_uv(Vert4::point(0.0, 1.0, 0.0)).0, 0.375);
+    }
+}
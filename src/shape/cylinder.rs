@@ -0,0 +1,295 @@
+use super::{BoundingBox, Shape, ShapeData};
+use crate::intersection::{Intersection, Intersections};
+use crate::math::{Vert4, EPSILON};
+use crate::pattern::uv;
+use crate::ray::Ray;
+
+/// A radius-1 cylinder around the y axis, optionally truncated to
+/// `minimum..maximum` (exclusive) and capped at both ends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cylinder {
+    pub minimum: f32,
+    pub maximum: f32,
+    pub closed: bool,
+    data: ShapeData,
+}
+
+impl Default for Cylinder {
+    fn default() -> Self {
+        Self::truncated(f32::NEG_INFINITY, f32::INFINITY, false)
+    }
+}
+
+impl Cylinder {
+    /// An infinite, open cylinder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn truncated(minimum: f32, maximum: f32, closed: bool) -> Self {
+        Self {
+            minimum,
+            maximum,
+            closed,
+            data: ShapeData::default(),
+        }
+    }
+}
+
+/// Whether the ray at `t` lies within `radius` of the y axis. The tolerance keeps
+/// rays that exit exactly through a cap's rim from losing that hit to rounding.
+fn check_cap(ray: &Ray, t: f32, radius: f32) -> bool {
+    let x = ray.origin.x + t * ray.direction.x;
+    let z = ray.origin.z + t * ray.direction.z;
+    x * x + z * z <= radius * radius + EPSILON
+}
+
+/// Passes on to `hit` the wall hits in `ts` whose height lies strictly
+/// inside `minimum..maximum`.
+pub(super) fn truncate(
+    ray: &Ray,
+    ts: impl IntoIterator<Item = f32>,
+    minimum: f32,
+    maximum: f32,
+    mut hit: impl FnMut(f32),
+) {
+    for t in ts {
+        let y = ray.origin.y + t * ray.direction.y;
+        if minimum < y && y < maximum {
+            hit(t);
+        }
+    }
+}
+
+/// Passes on to `hit` the hits on the end caps at `minimum` and `maximum`,
+/// whose radius at each height is given by `radius`.
+pub(super) fn intersect_caps(
+    ray: &Ray,
+    minimum: f32,
+    maximum: f32,
+    radius: impl Fn(f32) -> f32,
+    mut hit: impl FnMut(f32),
+) {
+    if ray.direction.y.abs() < EPSILON {
+        return;
+    }
+    for y in [minimum, maximum] {
+        let t = (y - ray.origin.y) / ray.direction.y;
+        if check_cap(ray, t, radius(y)) {
+            hit(t);
+        }
+    }
+}
+
+/// The cap normal at `local_point` if it lies on a cap, given its squared distance
+/// from the y axis and the cap radius at that height.
+pub(super) fn cap_normal(
+    local_point: Vert4,
+    dist: f32,
+    radius: f32,
+    minimum: f32,
+    maximum: f32,
+) -> Option<Vert4> {
+    if dist >= radius * radius {
+        return None;
+    }
+    if local_point.y >= maximum - EPSILON {
+        Some(Vert4::vector(0.0, 1.0, 0.0))
+    } else if local_point.y <= minimum + EPSILON {
+        Some(Vert4::vector(0.0, -1.0, 0.0))
+    } else {
+        None
+    }
+}
+
+impl Shape for Cylinder {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn local_intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        let mut hit = |t| xs.push(Intersection::new(t, self));
+        let a = ray.direction.x.powi(2) + ray.direction.z.powi(2);
+        // Rays parallel to the y axis can only hit the caps.
+        if a.abs() >= EPSILON {
+            let b = 2.0 * ray.origin.x * ray.direction.x + 2.0 * ray.origin.z * ray.direction.z;
+            let c = ray.origin.x.powi(2) + ray.origin.z.powi(2) - 1.0;
+            let disc = b * b - 4.0 * a * c;
+            if disc < 0.0 {
+                return;
+            }
+            let root = disc.sqrt();
+            let t0 = (-b - root) / (2.0 * a);
+            let t1 = (-b + root) / (2.0 * a);
+            truncate(
+                ray,
+                [t0.min(t1), t0.max(t1)],
+                self.minimum,
+                self.maximum,
+                &mut hit,
+            );
+        }
+        if self.closed {
+            intersect_caps(ray, self.minimum, self.maximum, |_| 1.0, hit);
+        }
+    }
+
+    fn local_normal_at(&self, local_point: Vert4, _hit: &Intersection) -> Vert4 {
+        let dist = local_point.x.powi(2) + local_point.z.powi(2);
+        self.closed
+            .then(|| cap_normal(local_point, dist, 1.0, self.minimum, self.maximum))
+            .flatten()
+            .unwrap_or(Vert4::vector(local_point.x, 0.0, local_point.z))
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Vert4::point(-1.0, self.minimum, -1.0),
+            Vert4::point(1.0, self.maximum, 1.0),
+        )
+    }
+
+    fn local_uv(&self, local_point: Vert4) -> (f32, f32) {
+        uv::cylindrical_map(local_point)
+    }
+
+    fn local_tangents(&self, p: Vert4) -> (Vert4, Vert4) {
+        (Vert4::vector(-p.z, 0.0, p.x), Vert4::vector(0.0, 1.0, 0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::normal_at;
+
+    fn ts(shape: &dyn Shape, origin: Vert4, direction: Vert4) -> Vec<f32> {
+        let r = Ray::new(origin, direction.normalize());
+        shape.intersect(&r).iter().map(|x| x.t).collect()
+    }
+
+    fn truncated(closed: bool) -> Cylinder {
+        Cylinder::truncated(1.0, 2.0, closed)
+    }
+
+    #[test]
+    fn ray_misses() {
+        let cyl = Cylinder::new();
+        let cases = [
+            ((1.0, 0.0, 0.0), (0.0, 1.0, 0.0)),
+            ((0.0, 0.0, 0.0), (0.0, 1.0, 0.0)),
+            ((0.0, 0.0, -5.0), (1.0, 1.0, 1.0)),
+        ];
+        for ((ox, oy, oz), (dx, dy, dz)) in cases {
+            assert!(ts(&cyl, Vert4::point(ox, oy, oz), Vert4::vector(dx, dy, dz)).is_empty());
+        }
+    }
+
+    #[test]
+    fn ray_hits() {
+        let cyl = Cylinder::new();
+        let cases = [
+            ((1.0, 0.0, -5.0), (0.0, 0.0, 1.0), [5.0, 5.0]),
+            ((0.0, 0.0, -5.0), (0.0, 0.0, 1.0), [4.0, 6.0]),
+            ((0.5, 0.0, -5.0), (0.1, 1.0, 1.0), [6.80798, 7.08872]),
+        ];
+        for ((ox, oy, oz), (dx, dy, dz), expected) in cases {
+            let got = ts(&cyl, Vert4::point(ox, oy, oz), Vert4::vector(dx, dy, dz));
+            assert_approx_eq!(got, expected.to_vec());
+        }
+    }
+
+    #[test]
+    fn wall_normals() {
+        let cyl = Cylinder::new();
+        let cases = [
+            ((1.0, 0.0, 0.0), (1.0, 0.0, 0.0)),
+            ((0.0, 5.0, -1.0), (0.0, 0.0, -1.0)),
+            ((0.0, -2.0, 1.0), (0.0, 0.0, 1.0)),
+            ((-1.0, 1.0, 0.0), (-1.0, 0.0, 0.0)),
+        ];
+        for ((px, py, pz), (nx, ny, nz)) in cases {
+            assert_eq!(
+                normal_at(&cyl, Vert4::point(px, py, pz)),
+                Vert4::vector(nx, ny, nz)
+            );
+        }
+    }
+
+    #[test]
+    fn default_is_infinite_and_open() {
+        let c = Cylinder::default();
+        assert_eq!(c.minimum, f32::NEG_INFINITY);
+        assert_eq!(c.maximum, f32::INFINITY);
+        assert!(!c.closed);
+    }
+
+    #[test]
+    fn truncated_cylinder() {
+        let cyl = truncated(false);
+        let cases = [
+            ((0.0, 1.5, 0.0), (0.1, 1.0, 0.0), 0),
+            ((0.0, 3.0, -5.0), (0.0, 0.0, 1.0), 0),
+            ((0.0, 0.0, -5.0), (0.0, 0.0, 1.0), 0),
+            ((0.0, 2.0, -5.0), (0.0, 0.0, 1.0), 0),
+            ((0.0, 1.0, -5.0), (0.0, 0.0, 1.0), 0),
+            ((0.0, 1.5, -2.0), (0.0, 0.0, 1.0), 2),
+        ];
+        for ((ox, oy, oz), (dx, dy, dz), count) in cases {
+            let got = ts(&cyl, Vert4::point(ox, oy, oz), Vert4::vector(dx, dy, dz));
+            assert_eq!(got.len(), count, "origin ({ox}, {oy}, {oz})");
+        }
+    }
+
+    #[test]
+    fn capped_cylinder() {
+        let cyl = truncated(true);
+        let cases = [
+            ((0.0, 3.0, 0.0), (0.0, -1.0, 0.0), 2),
+            ((0.0, 3.0, -2.0), (0.0, -1.0, 2.0), 2),
+            // The ray exits exactly through the rim of the bottom cap.
+            ((0.0, 4.0, -2.0), (0.0, -1.0, 1.0), 2),
+            ((0.0, 0.0, -2.0), (0.0, 1.0, 2.0), 2),
+            ((0.0, -1.0, -2.0), (0.0, 1.0, 1.0), 2),
+        ];
+        for ((ox, oy, oz), (dx, dy, dz), count) in cases {
+            let got = ts(&cyl, Vert4::point(ox, oy, oz), Vert4::vector(dx, dy, dz));
+            assert_eq!(got.len(), count, "origin ({ox}, {oy}, {oz})");
+        }
+    }
+
+    #[test]
+    fn grazing_the_cap_edge_from_outside_misses() {
+        let cyl = truncated(true);
+        // Passes just outside the rim, above the top cap's plane crossing.
+        let got = ts(
+            &cyl,
+            Vert4::point(1.01, 3.0, 0.0),
+            Vert4::vector(0.0, -1.0, 0.0),
+        );
+        assert!(got.is_empty());
+    }
+
+    #[test]
+    fn cap_normals() {
+        let cyl = truncated(true);
+        let cases = [
+            ((0.0, 1.0, 0.0), (0.0, -1.0, 0.0)),
+            ((0.5, 1.0, 0.0), (0.0, -1.0, 0.0)),
+            ((0.0, 1.0, 0.5), (0.0, -1.0, 0.0)),
+            ((0.0, 2.0, 0.0), (0.0, 1.0, 0.0)),
+            ((0.5, 2.0, 0.0), (0.0, 1.0, 0.0)),
+            ((0.0, 2.0, 0.5), (0.0, 1.0, 0.0)),
+        ];
+        for ((px, py, pz), (nx, ny, nz)) in cases {
+            assert_eq!(
+                normal_at(&cyl, Vert4::point(px, py, pz)),
+                Vert4::vector(nx, ny, nz)
+            );
+        }
+    }
+}
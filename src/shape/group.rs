@@ -0,0 +1,393 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::{BoundingBox, RayKind, Shape, ShapeData, Visibility};
+use crate::intersection::{Intersection, Intersections};
+use crate::material::Material;
+use crate::math::Vert4;
+use crate::ray::Ray;
+
+type Children = Vec<Box<dyn Shape>>;
+
+/// A node owning child shapes that inherit its transform.
+///
+/// The group caches the world-space bounds of its children, so rays that miss
+/// the box skip every child.
+#[derive(Debug, Default)]
+pub struct Group {
+    children: Children,
+    bounds: BoundingBox,
+    data: ShapeData,
+}
+
+impl Group {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_child(mut self, child: impl Shape) -> Self {
+        self.add_child(child);
+        self
+    }
+
+    /// Adds `child` to this group, composing this group's transform onto it.
+    pub fn add_child(&mut self, child: impl Shape) {
+        self.add_boxed_child(Box::new(child));
+    }
+
+    /// Adds `child`, composing this group's transform onto it and hiding it
+    /// wherever the group is hidden.
+    pub fn add_boxed_child(&mut self, mut child: Box<dyn Shape>) {
+        child.set_parent_transform(*self.transform().matrix());
+        child.set_visibility(child.visibility().within(self.visibility()));
+        self.bounds.merge(&child.bounds());
+        self.children.push(child);
+    }
+
+    fn update_bounds(&mut self) {
+        self.bounds = BoundingBox::EMPTY;
+        for child in &self.children {
+            self.bounds.merge(&child.bounds());
+        }
+    }
+
+    pub fn children(&self) -> &[Box<dyn Shape>] {
+        &self.children
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Removes the children that fit entirely within either half of the bounds,
+    /// leaving children that straddle the split in place.
+    fn partition_children(&mut self) -> (Children, Children) {
+        let (left_box, right_box) = self.bounds.split();
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        let mut remaining = Vec::new();
+        for child in self.children.drain(..) {
+            let bounds = child.bounds();
+            if left_box.contains_box(&bounds) {
+                left.push(child);
+            } else if right_box.contains_box(&bounds) {
+                right.push(child);
+            } else {
+                remaining.push(child);
+            }
+        }
+        self.children = remaining;
+        (left, right)
+    }
+}
+
+impl Shape for Group {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn update_transform(&mut self) {
+        self.data.update_transform();
+        let matrix = *self.data.transform.matrix();
+        for child in &mut self.children {
+            child.set_parent_transform(matrix);
+        }
+        self.update_bounds();
+    }
+
+    /// Children already carry this group's transform, so they take the
+    /// world ray, all adding to the same `xs`.
+    fn intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        if !self.bounds.intersects(ray) {
+            return;
+        }
+        for child in &self.children {
+            child.intersect_into(ray, xs);
+        }
+    }
+
+    fn local_intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        self.intersect_into(&ray.transform(self.transform().matrix()), xs);
+    }
+
+    fn any_hit<'a>(
+        &'a self,
+        ray: &Ray,
+        t_range: &Range<f32>,
+        kind: RayKind,
+    ) -> Option<Intersection<'a>> {
+        if !self.bounds.intersects(ray) {
+            return None;
+        }
+        self.children
+            .iter()
+            .find_map(|child| child.any_hit(ray, t_range, kind))
+    }
+
+    /// # Panics
+    ///
+    /// Always: a group has no surface of its own, and hits record the child.
+    fn local_normal_at(&self, _local_point: Vert4, _hit: &Intersection) -> Vert4 {
+        panic!("local_normal_at called on a group")
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.bounds.transform(self.transform().inverse())
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        std::ptr::addr_eq(self, other) || self.children.iter().any(|c| c.includes(other))
+    }
+
+    fn for_each_child<'a>(&'a self, visit: &mut dyn FnMut(&'a dyn Shape)) {
+        for child in &self.children {
+            visit(child.as_ref());
+        }
+    }
+
+    fn contains_instance(&self) -> bool {
+        self.children.iter().any(|c| c.contains_instance())
+    }
+
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.data.visibility = visibility;
+        for child in &mut self.children {
+            child.set_visibility(visibility);
+        }
+    }
+
+    fn replace_material(&mut self, old: &Arc<Material>, new: &Arc<Material>) {
+        if Arc::ptr_eq(self.shared_material(), old) {
+            self.set_material(Arc::clone(new));
+        }
+        for child in &mut self.children {
+            child.replace_material(old, new);
+        }
+    }
+
+    /// Builds a bounding-volume hierarchy: any group with at least `threshold`
+    /// children has them partitioned into sub-groups by splitting its bounding
+    /// box, recursively.
+    fn divide(&mut self, threshold: usize) {
+        if threshold <= self.children.len() {
+            let (left, right) = self.partition_children();
+            let parent = *self.transform().matrix();
+            for children in [left, right] {
+                if children.is_empty() {
+                    continue;
+                }
+                let mut subgroup = Group::new();
+                subgroup.set_parent_transform(parent);
+                for child in children {
+                    subgroup.add_boxed_child(child);
+                }
+                self.children.push(Box::new(subgroup));
+            }
+        }
+        for child in &mut self.children {
+            child.divide(threshold);
+        }
+        self.update_bounds();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::f32::consts::PI;
+    use std::ptr;
+
+    use super::*;
+    use crate::math::Matrix4;
+    use crate::shape::{normal_at, Sphere};
+
+    fn as_group(shape: &dyn Shape) -> Option<&Group> {
+        (shape as &dyn Any).downcast_ref()
+    }
+
+    fn children(g: &dyn Shape) -> &[Box<dyn Shape>] {
+        as_group(g).unwrap().children()
+    }
+
+    #[test]
+    fn new_group_is_empty() {
+        let g = Group::new();
+        assert!(children(&g).is_empty());
+        assert_eq!(g.transform().matrix(), &Matrix4::IDENTITY);
+    }
+
+    #[test]
+    fn ray_misses_empty_group() {
+        let r = Ray::new(Vert4::point(0.0, 0.0, 0.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert!(Group::new().intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn intersecting_nonempty_group() {
+        let g = Group::new()
+            .with_child(Sphere::new())
+            .with_child(Sphere::new().with_transform(Matrix4::translation(0.0, 0.0, -3.0)))
+            .with_child(Sphere::new().with_transform(Matrix4::translation(5.0, 0.0, 0.0)));
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = g.intersect(&r);
+        let [s1, s2, _] = children(&g) else {
+            unreachable!()
+        };
+        assert_eq!(xs.len(), 4);
+        assert!(ptr::addr_eq(xs[0].object, s2.as_ref()));
+        assert!(ptr::addr_eq(xs[1].object, s2.as_ref()));
+        assert!(ptr::addr_eq(xs[2].object, s1.as_ref()));
+        assert!(ptr::addr_eq(xs[3].object, s1.as_ref()));
+    }
+
+    #[test]
+    fn intersecting_transformed_group() {
+        let g = Group::new()
+            .with_transform(Matrix4::scaling(2.0, 2.0, 2.0))
+            .with_child(Sphere::new().with_transform(Matrix4::translation(5.0, 0.0, 0.0)));
+        let r = Ray::new(Vert4::point(10.0, 0.0, -10.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert_eq!(g.intersect(&r).len(), 2);
+    }
+
+    #[test]
+    fn transform_set_after_adding_children_propagates() {
+        let mut g = Group::new()
+            .with_child(Sphere::new().with_transform(Matrix4::translation(5.0, 0.0, 0.0)));
+        g.set_transform(Matrix4::scaling(2.0, 2.0, 2.0));
+        let r = Ray::new(Vert4::point(10.0, 0.0, -10.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert_eq!(g.intersect(&r).len(), 2);
+    }
+
+    fn nested() -> Group {
+        Group::new()
+            .with_transform(Matrix4::rotation_y(PI / 2.0))
+            .with_child(
+                Group::new()
+                    .with_transform(Matrix4::scaling(1.0, 2.0, 3.0))
+                    .with_child(Sphere::new().with_transform(Matrix4::translation(5.0, 0.0, 0.0))),
+            )
+    }
+
+    fn leaf(g: &Group) -> &dyn Shape {
+        children(children(g)[0].as_ref())[0].as_ref()
+    }
+
+    #[test]
+    fn world_to_object_through_parents() {
+        let g1 = Group::new()
+            .with_transform(Matrix4::rotation_y(PI / 2.0))
+            .with_child(
+                Group::new()
+                    .with_transform(Matrix4::scaling(2.0, 2.0, 2.0))
+                    .with_child(Sphere::new().with_transform(Matrix4::translation(5.0, 0.0, 0.0))),
+            );
+        let p = leaf(&g1).world_to_object(Vert4::point(-2.0, 0.0, -10.0));
+        assert_approx_eq!(p, Vert4::point(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn normal_to_world_through_parents() {
+        let g1 = nested();
+        let k = 3f32.sqrt() / 3.0;
+        let n = leaf(&g1).normal_to_world(Vert4::vector(k, k, k));
+        assert_approx_eq!(n, Vert4::vector(0.28571, 0.42857, -0.85714));
+    }
+
+    #[test]
+    fn normal_on_child_object() {
+        let g1 = nested();
+        let n = normal_at(leaf(&g1), Vert4::point(1.7321, 1.1547, -5.5774));
+        assert_approx_eq!(n, Vert4::vector(0.28570, 0.42854, -0.85716));
+    }
+
+    fn sphere_at(x: f32, y: f32, z: f32) -> Sphere {
+        Sphere::new().with_transform(Matrix4::translation(x, y, z))
+    }
+
+    #[test]
+    fn partitioning_children() {
+        let mut g = Group::new()
+            .with_child(sphere_at(-2.0, 0.0, 0.0))
+            .with_child(sphere_at(2.0, 0.0, 0.0))
+            .with_child(Sphere::new());
+        let (left, right) = g.partition_children();
+        assert_eq!(g.children.len(), 1);
+        assert_eq!(left.len(), 1);
+        assert_eq!(right.len(), 1);
+        assert_eq!(
+            left[0].transform().matrix(),
+            &Matrix4::translation(-2.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            right[0].transform().matrix(),
+            &Matrix4::translation(2.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn dividing_a_group() {
+        let mut g = Group::new()
+            .with_child(sphere_at(-2.0, -2.0, 0.0))
+            .with_child(sphere_at(-2.0, 2.0, 0.0))
+            .with_child(Sphere::new().with_transform(Matrix4::scaling(4.0, 4.0, 4.0)));
+        g.divide(1);
+        let top = children(&g);
+        assert_eq!(top.len(), 2);
+        assert!(as_group(top[0].as_ref()).is_none());
+        let sub = children(top[1].as_ref());
+        assert_eq!(sub.len(), 2);
+        assert_eq!(children(sub[0].as_ref()).len(), 1);
+        assert_eq!(children(sub[1].as_ref()).len(), 1);
+    }
+
+    #[test]
+    fn dividing_below_threshold_is_noop() {
+        let mut g = Group::new()
+            .with_child(sphere_at(-2.0, 0.0, 0.0))
+            .with_child(sphere_at(2.0, 0.0, 0.0));
+        g.divide(3);
+        assert_eq!(g.children().len(), 2);
+        assert!(g.children().iter().all(|c| as_group(c.as_ref()).is_none()));
+    }
+
+    #[test]
+    fn divided_group_intersects_the_same() {
+        let mut g = Group::new().with_transform(Matrix4::translation(0.0, 0.0, 1.0));
+        for i in 0..20 {
+            let x = i as f32 * 1.5 - 15.0;
+            g.add_child(sphere_at(x, (i % 3) as f32, 0.0));
+        }
+        let rays: Vec<Ray> = (0..20)
+            .map(|i| {
+                let x = i as f32 * 1.5 - 15.0 + 0.3;
+                Ray::new(Vert4::point(x, 0.2, -5.0), Vert4::vector(0.0, 0.0, 1.0))
+            })
+            .collect();
+        let before: Vec<Vec<f32>> = rays
+            .iter()
+            .map(|r| g.intersect(r).iter().map(|x| x.t).collect())
+            .collect();
+        g.divide(4);
+        let after: Vec<Vec<f32>> = rays
+            .iter()
+            .map(|r| g.intersect(r).iter().map(|x| x.t).collect())
+            .collect();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn ray_missing_group_bounds_skips_children() {
+        let g = Group::new().with_child(sphere_at(0.0, 0.0, 0.0));
+        let r = Ray::new(Vert4::point(5.0, 5.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert!(!g.bounds().intersects(&r));
+        assert!(g.intersect(&r).is_empty());
+    }
+}
@@ -0,0 +1,250 @@
+use std::ptr;
+use std::sync::Arc;
+
+use super::{BoundingBox, Shape, ShapeData};
+use crate::intersection::{Intersection, Intersections};
+use crate::material::Material;
+use crate::math::Vert4;
+use crate::ray::Ray;
+
+/// A placement of shared geometry with its own transform and material.
+///
+/// Many instances can reference one `Arc`'d shape (typically a [`Group`](super::Group)
+/// of triangles), so repeating a model costs one transform per copy rather than
+/// a copy of its geometry. The geometry's own transforms are relative to the
+/// instance. Given a material, the instance paints all of its geometry with
+/// it; otherwise each hit keeps the material of the part of the geometry it
+/// struck.
+///
+/// Instances don't nest: [`Instance::new`] rejects geometry that contains an
+/// `Instance`.
+/// Build any bounding-volume hierarchy with [`Shape::divide`] before sharing the
+/// geometry, since it can't be mutated once it's behind an `Arc`.
+#[derive(Debug, Clone)]
+pub struct Instance {
+    geometry: Arc<dyn Shape>,
+    data: ShapeData,
+    material: Option<Arc<Material>>,
+}
+
+impl Instance {
+    /// # Panics
+    ///
+    /// Panics if `geometry` is or contains an `Instance`.
+    pub fn new(geometry: Arc<dyn Shape>) -> Self {
+        assert!(!geometry.contains_instance(), "instances cannot be nested");
+        Self {
+            geometry,
+            data: ShapeData::default(),
+            material: None,
+        }
+    }
+
+    pub fn geometry(&self) -> &Arc<dyn Shape> {
+        &self.geometry
+    }
+
+    /// The material given to the instance, which replaces the geometry's
+    /// own, if any.
+    pub fn own_material(&self) -> Option<&Arc<Material>> {
+        self.material.as_ref()
+    }
+}
+
+impl Shape for Instance {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    /// The instance's [own material](Instance::own_material), or the default
+    /// if it has none; hits then shade with the geometry's materials.
+    fn material(&self) -> &Material {
+        self.shared_material()
+    }
+
+    /// The instance's own material, giving it the default one first if it
+    /// has none.
+    fn material_mut(&mut self) -> &mut Material {
+        Arc::make_mut(self.material.get_or_insert_with(Arc::default))
+    }
+
+    fn shared_material(&self) -> &Arc<Material> {
+        self.material.as_ref().unwrap_or(&self.data.material)
+    }
+
+    fn set_material(&mut self, material: Arc<Material>) {
+        self.material = Some(material);
+    }
+
+    /// The geometry's hits are gathered apart from `xs` so that each can be
+    /// marked as seen through this instance.
+    fn local_intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        xs.extend(
+            self.geometry
+                .intersect(ray)
+                .into_iter()
+                .map(|x| Intersection {
+                    instance: Some(self),
+                    ..x
+                }),
+        );
+    }
+
+    /// # Panics
+    ///
+    /// Always: hits record the geometry that was struck, never the instance.
+    fn local_normal_at(&self, _local_point: Vert4, _hit: &Intersection) -> Vert4 {
+        panic!("local_normal_at called on an instance")
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.geometry.bounds()
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        ptr::addr_eq(self, other) || self.geometry.includes(other)
+    }
+
+    fn for_each_child<'a>(&'a self, visit: &mut dyn FnMut(&'a dyn Shape)) {
+        visit(self.geometry.as_ref());
+    }
+
+    fn contains_instance(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::math::Matrix4;
+    use crate::shape::{Group, Sphere};
+
+    fn shared_sphere() -> Arc<dyn Shape> {
+        Arc::new(Sphere::new().with_material(Material {
+            color: Color::new(1.0, 0.0, 0.0),
+            ..Material::default()
+        }))
+    }
+
+    #[test]
+    fn instances_share_geometry() {
+        let geometry = shared_sphere();
+        let a = Instance::new(Arc::clone(&geometry));
+        let b = Instance::new(Arc::clone(&geometry));
+        assert_eq!(Arc::strong_count(&geometry), 3);
+        assert!(Arc::ptr_eq(a.geometry(), b.geometry()));
+    }
+
+    #[test]
+    fn ray_is_transformed_by_instance_then_geometry() {
+        let geometry: Arc<dyn Shape> =
+            Arc::new(Sphere::new().with_transform(Matrix4::scaling(2.0, 2.0, 2.0)));
+        let inst = Instance::new(geometry).with_transform(Matrix4::translation(0.0, 0.0, 10.0));
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = inst.intersect(&r);
+        let ts: Vec<f32> = xs.iter().map(|x| x.t).collect();
+        assert_eq!(ts, [13.0, 17.0]);
+        assert!(xs.iter().all(|x| ptr::eq(x.instance.unwrap(), &inst)));
+        assert!(ptr::addr_eq(xs[0].object, inst.geometry().as_ref()));
+    }
+
+    #[test]
+    fn normals_pass_through_instance_transform() {
+        let inst = Instance::new(shared_sphere())
+            .with_transform(Matrix4::translation(0.0, 0.0, 10.0) * Matrix4::scaling(1.0, 2.0, 1.0));
+        let r = Ray::new(Vert4::point(0.0, 0.0, 0.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = inst.intersect(&r);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_approx_eq!(comps.point, Vert4::point(0.0, 0.0, 9.0));
+        assert_approx_eq!(comps.normalv, Vert4::vector(0.0, 0.0, -1.0));
+
+        let r = Ray::new(Vert4::point(5.0, 1.0, 10.0), Vert4::vector(-1.0, 0.0, 0.0));
+        let xs = inst.intersect(&r);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        let k = 3f32.sqrt() / 2.0;
+        let expected = Vert4::vector(k, 0.5 / 2.0, 0.0).normalize();
+        assert_approx_eq!(comps.normalv, expected);
+    }
+
+    #[test]
+    fn instance_material_is_used_for_shading() {
+        let inst = Instance::new(shared_sphere()).with_material(Material {
+            color: Color::new(0.0, 0.0, 1.0),
+            ..Material::default()
+        });
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = inst.intersect(&r);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_eq!(comps.material.color, Color::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn instances_without_a_material_keep_the_geometry_materials() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let green = Color::new(0.0, 1.0, 0.0);
+        let paint = |color| Material {
+            color,
+            ..Material::default()
+        };
+        let geometry: Arc<dyn Shape> = Arc::new(
+            Group::new()
+                .with_child(
+                    Sphere::new()
+                        .with_transform(Matrix4::translation(-2.0, 0.0, 0.0))
+                        .with_material(paint(red)),
+                )
+                .with_child(
+                    Sphere::new()
+                        .with_transform(Matrix4::translation(2.0, 0.0, 0.0))
+                        .with_material(paint(green)),
+                ),
+        );
+        let inst = Instance::new(geometry).with_transform(Matrix4::translation(0.0, 0.0, 10.0));
+        assert!(inst.own_material().is_none());
+        let color_at = |x| {
+            let r = Ray::new(Vert4::point(x, 0.0, 0.0), Vert4::vector(0.0, 0.0, 1.0));
+            let xs = inst.intersect(&r);
+            let comps = xs[0].prepare_computations(&r, &xs);
+            assert!(ptr::addr_eq(comps.object, &inst));
+            comps.material.color
+        };
+        assert_eq!(color_at(-2.0), red);
+        assert_eq!(color_at(2.0), green);
+    }
+
+    #[test]
+    fn hits_on_different_instances_are_distinct() {
+        let geometry = shared_sphere();
+        let a = Instance::new(Arc::clone(&geometry));
+        let b = Instance::new(geometry);
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert_ne!(a.intersect(&r)[0], b.intersect(&r)[0]);
+        assert_eq!(a.intersect(&r)[0], a.intersect(&r)[0]);
+    }
+
+    #[test]
+    fn bounds_cover_transformed_geometry() {
+        let geometry: Arc<dyn Shape> = Arc::new(
+            Group::new()
+                .with_child(Sphere::new().with_transform(Matrix4::translation(2.0, 0.0, 0.0))),
+        );
+        let inst = Instance::new(geometry).with_transform(Matrix4::translation(0.0, 5.0, 0.0));
+        let b = inst.bounds();
+        assert_approx_eq!(b.min, Vert4::point(1.0, 4.0, -1.0));
+        assert_approx_eq!(b.max, Vert4::point(3.0, 6.0, 1.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "instances cannot be nested")]
+    fn nested_instances_are_rejected() {
+        let inner = Instance::new(shared_sphere());
+        let geometry: Arc<dyn Shape> = Arc::new(Group::new().with_child(inner));
+        Instance::new(geometry);
+    }
+}
@@ -0,0 +1,535 @@
+//! Geometric primitives that rays can be intersected with.
+
+mod bounds;
+mod clip;
+mod cone;
+mod csg;
+mod cube;
+mod cylinder;
+mod group;
+mod instance;
+mod sphere;
+mod torus;
+mod triangle;
+
+pub use bounds::BoundingBox;
+pub use clip::{ClipPlane, Clipped};
+pub use cone::Cone;
+pub use csg::{Csg, CsgOperation};
+pub use cube::Cube;
+pub use cylinder::Cylinder;
+pub use group::Group;
+pub use instance::Instance;
+pub use sphere::Sphere;
+pub use torus::Torus;
+pub use triangle::{SmoothTriangle, Triangle};
+
+use std::any::Any;
+use std::fmt::Debug;
+use std::ops::Range;
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::intersection::{Intersection, Intersections};
+use crate::material::Material;
+use crate::math::{Matrix4, Transform, Vert4};
+use crate::pattern::uv;
+use crate::ray::Ray;
+
+/// Identifies one shape for as long as it lives, as reported by
+/// [`World::pick`](crate::world::World::pick).
+///
+/// Every shape gets a fresh id when it's created, clones included, so no two
+/// shapes share one. Shared geometry inside [`Instance`]s is one shape, so
+/// hits on it are reported with the instance's id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObjectId(u64);
+
+impl ObjectId {
+    fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// What a ray is traced for, which decides the shapes it can strike; see
+/// [`Visibility`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RayKind {
+    /// Straight from the camera, seeing only the shapes in the given render
+    /// layer (see [`World::layers`](crate::world::World::layers)) if any.
+    Camera(Option<usize>),
+    /// Towards a light, checking for shadows.
+    Shadow,
+    /// Reflected, refracted, or scattered on from a surface.
+    Reflection,
+}
+
+/// Which rays can strike a shape. Hits on shared [`Instance`] geometry go
+/// by the instance's visibility.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Visibility {
+    /// Seen by camera rays.
+    pub camera: bool,
+    /// Blocks light from reaching other surfaces.
+    pub shadows: bool,
+    /// Seen in reflections and refractions, and by scattered paths.
+    pub reflections: bool,
+    /// The render layers the shape is in, one bit per layer, from the
+    /// lowest. Layers only filter camera rays; every layer is set by
+    /// default.
+    pub layers: u64,
+}
+
+impl Default for Visibility {
+    fn default() -> Self {
+        Self {
+            camera: true,
+            shadows: true,
+            reflections: true,
+            layers: u64::MAX,
+        }
+    }
+}
+
+impl Visibility {
+    /// Visible to every ray, but only in the given render layers.
+    pub fn in_layers(layers: impl IntoIterator<Item = usize>) -> Self {
+        Self {
+            layers: layers.into_iter().fold(0, |mask, layer| mask | 1 << layer),
+            ..Self::default()
+        }
+    }
+
+    /// Whether a ray of `kind` can strike the shape.
+    pub fn allows(&self, kind: RayKind) -> bool {
+        match kind {
+            RayKind::Camera(layer) => {
+                self.camera && layer.is_none_or(|layer| self.layers & 1 << layer != 0)
+            }
+            RayKind::Shadow => self.shadows,
+            RayKind::Reflection => self.reflections,
+        }
+    }
+
+    /// Visible only where both this and `parent` are, as for a shape inside
+    /// a group.
+    pub(crate) fn within(self, parent: Visibility) -> Self {
+        Self {
+            camera: self.camera && parent.camera,
+            shadows: self.shadows && parent.shadows,
+            reflections: self.reflections && parent.reflections,
+            layers: self.layers & parent.layers,
+        }
+    }
+}
+
+/// The transforms and material every shape carries, and its [`ObjectId`].
+///
+/// A shape inside a [`Group`] stores the composition of its ancestors'
+/// transforms, so [`Shape::transform`] always maps object space straight to
+/// world space and intersection never walks the parent chain. The material is
+/// reference counted so that many shapes can share one.
+#[derive(Debug)]
+pub struct ShapeData {
+    id: ObjectId,
+    visibility: Visibility,
+    /// The transform relative to the parent group.
+    local_transform: Matrix4,
+    /// The accumulated transform of every enclosing group.
+    parent_transform: Matrix4,
+    /// `parent_transform * local_transform`, with cached inverses.
+    transform: Transform,
+    pub material: Arc<Material>,
+}
+
+impl Default for ShapeData {
+    fn default() -> Self {
+        Self {
+            id: ObjectId::next(),
+            visibility: Visibility::default(),
+            local_transform: Matrix4::default(),
+            parent_transform: Matrix4::default(),
+            transform: Transform::default(),
+            material: Arc::default(),
+        }
+    }
+}
+
+/// A clone is a new shape, so it gets its own id.
+impl Clone for ShapeData {
+    fn clone(&self) -> Self {
+        Self {
+            id: ObjectId::next(),
+            visibility: self.visibility,
+            local_transform: self.local_transform,
+            parent_transform: self.parent_transform,
+            transform: self.transform,
+            material: Arc::clone(&self.material),
+        }
+    }
+}
+
+/// Shapes compare by their geometry and material, whatever their ids.
+impl PartialEq for ShapeData {
+    fn eq(&self, other: &Self) -> bool {
+        self.visibility == other.visibility
+            && self.local_transform == other.local_transform
+            && self.parent_transform == other.parent_transform
+            && self.transform == other.transform
+            && self.material == other.material
+    }
+}
+
+impl ShapeData {
+    fn update_transform(&mut self) {
+        self.transform = Transform::new(self.parent_transform * self.local_transform);
+    }
+}
+
+/// A primitive or aggregate placed in the scene.
+///
+/// Implementors describe their geometry in object space through the `local_*`
+/// methods and expose their [`ShapeData`]; the provided methods handle moving
+/// rays, points and normals between world and object space. The trait is
+/// object safe, so scenes hold shapes as `Box<dyn Shape>`.
+pub trait Shape: Any + Debug + Send + Sync {
+    fn data(&self) -> &ShapeData;
+
+    fn data_mut(&mut self) -> &mut ShapeData;
+
+    fn id(&self) -> ObjectId {
+        self.data().id
+    }
+
+    /// Adds the intersections with `ray`, which is already in object space,
+    /// to `xs`.
+    fn local_intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>);
+
+    /// The object-space normal at `local_point`, not necessarily of unit length.
+    fn local_normal_at(&self, local_point: Vert4, hit: &Intersection) -> Vert4;
+
+    /// The box enclosing the shape in object space.
+    fn local_bounds(&self) -> BoundingBox;
+
+    fn with_transform(mut self, transform: Matrix4) -> Self
+    where
+        Self: Sized,
+    {
+        self.set_transform(transform);
+        self
+    }
+
+    fn with_visibility(mut self, visibility: Visibility) -> Self
+    where
+        Self: Sized,
+    {
+        self.set_visibility(visibility);
+        self
+    }
+
+    fn with_material(self, material: Material) -> Self
+    where
+        Self: Sized,
+    {
+        self.with_shared_material(Arc::new(material))
+    }
+
+    /// Uses `material` as is, so edits made through
+    /// [`World::edit_material`](crate::world::World::edit_material) reach this
+    /// shape too.
+    fn with_shared_material(mut self, material: Arc<Material>) -> Self
+    where
+        Self: Sized,
+    {
+        self.set_material(material);
+        self
+    }
+
+    /// The object-to-world transform, including every enclosing group.
+    fn transform(&self) -> &Transform {
+        &self.data().transform
+    }
+
+    /// The transform relative to the enclosing group, as passed to
+    /// [`set_transform`](Self::set_transform).
+    fn local_transform(&self) -> &Matrix4 {
+        &self.data().local_transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.data().material
+    }
+
+    fn visibility(&self) -> Visibility {
+        self.data().visibility
+    }
+
+    /// Shapes with children override this to hide their descendants too.
+    fn set_visibility(&mut self, visibility: Visibility) {
+        self.data_mut().visibility = visibility;
+    }
+
+    /// The material, copied first if other shapes share it so that changes
+    /// only affect this shape.
+    fn material_mut(&mut self) -> &mut Material {
+        Arc::make_mut(&mut self.data_mut().material)
+    }
+
+    fn shared_material(&self) -> &Arc<Material> {
+        &self.data().material
+    }
+
+    fn set_material(&mut self, material: Arc<Material>) {
+        self.data_mut().material = material;
+    }
+
+    /// Swaps `new` in for `old` wherever this shape or its descendants share
+    /// `old`. Shapes with children override this to pass it down to them.
+    fn replace_material(&mut self, old: &Arc<Material>, new: &Arc<Material>) {
+        if Arc::ptr_eq(self.shared_material(), old) {
+            self.set_material(Arc::clone(new));
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `transform` is not invertible.
+    fn set_transform(&mut self, transform: Matrix4) {
+        self.data_mut().local_transform = transform;
+        self.update_transform();
+    }
+
+    /// Sets the accumulated transform of the enclosing groups.
+    fn set_parent_transform(&mut self, parent: Matrix4) {
+        self.data_mut().parent_transform = parent;
+        self.update_transform();
+    }
+
+    /// Recomputes the cached world transform. Shapes with children override
+    /// this to pass the new transform down to them.
+    fn update_transform(&mut self) {
+        self.data_mut().update_transform();
+    }
+
+    /// Intersects a world-space ray, transforming it into object space first.
+    fn intersect<'a>(&'a self, ray: &Ray) -> Intersections<'a> {
+        let mut xs = Intersections::default();
+        self.intersect_into(ray, &mut xs);
+        xs
+    }
+
+    /// Like [`intersect`](Self::intersect), but adds the intersections to
+    /// `xs`, so one buffer can gather a whole scene's hits and be
+    /// [cleared](Intersections::clear) and reused for the next ray.
+    fn intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        self.local_intersect_into(&ray.transform(self.transform().inverse()), xs);
+    }
+
+    /// Some intersection with `ray` with `t` in `t_range` that a ray of
+    /// `kind` can strike. It's whichever is found first, not necessarily
+    /// the nearest, so shapes with children can stop at the first that's
+    /// hit.
+    fn any_hit<'a>(
+        &'a self,
+        ray: &Ray,
+        t_range: &Range<f32>,
+        kind: RayKind,
+    ) -> Option<Intersection<'a>> {
+        let mut xs = Intersections::default();
+        self.intersect_into(ray, &mut xs);
+        xs.iter()
+            .find(|x| t_range.contains(&x.t) && x.shading_object().visibility().allows(kind))
+            .copied()
+    }
+
+    /// The unit world-space surface normal where `hit` struck this shape.
+    fn normal_at(&self, world_point: Vert4, hit: &Intersection) -> Vert4 {
+        let local_point = self.world_to_object(world_point);
+        self.normal_to_world(self.local_normal_at(local_point, hit))
+    }
+
+    /// The world-space box enclosing the shape, including any descendants.
+    fn bounds(&self) -> BoundingBox {
+        self.local_bounds().transform(self.transform().matrix())
+    }
+
+    /// Whether `other` is this shape or one of its descendants.
+    fn includes(&self, other: &dyn Shape) -> bool {
+        ptr::addr_eq(self, other)
+    }
+
+    /// The shape's type name, such as `"Sphere"`, for reports.
+    fn kind(&self) -> &'static str {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name)
+    }
+
+    /// Calls `visit` with each of this shape's direct children. Shapes with
+    /// children override this; [`Instance`]s visit their shared geometry.
+    fn for_each_child<'a>(&'a self, _visit: &mut dyn FnMut(&'a dyn Shape)) {}
+
+    /// Whether this shape is an [`Instance`] or has one among its
+    /// descendants.
+    fn contains_instance(&self) -> bool {
+        false
+    }
+
+    /// Where `local_point` lands when the surface is unwrapped onto the unit
+    /// square, for sampling a [`UvPattern`](crate::pattern::UvPattern).
+    /// Defaults to tiling the xz plane.
+    fn local_uv(&self, local_point: Vert4) -> (f32, f32) {
+        uv::planar_map(local_point)
+    }
+
+    /// The object-space directions in which the `u` and `v` of
+    /// [`local_uv`](Self::local_uv) increase at `local_point`, which orient
+    /// normal maps. Neither needs to be of unit length.
+    fn local_tangents(&self, _local_point: Vert4) -> (Vert4, Vert4) {
+        (Vert4::vector(1.0, 0.0, 0.0), Vert4::vector(0.0, 0.0, 1.0))
+    }
+
+    /// Builds a bounding-volume hierarchy below this shape; see
+    /// [`Group`]. Shapes without children ignore it.
+    fn divide(&mut self, _threshold: usize) {}
+
+    /// Converts a world-space point into this shape's object space.
+    fn world_to_object(&self, point: Vert4) -> Vert4 {
+        self.transform().to_local(point)
+    }
+
+    /// Converts an object-space normal into a unit world-space normal.
+    fn normal_to_world(&self, normal: Vert4) -> Vert4 {
+        self.transform().normal_to_world(normal)
+    }
+}
+
+/// The normal at `world_point` on a shape whose normal doesn't depend on the hit.
+#[cfg(test)]
+pub(crate) fn normal_at(shape: &dyn Shape, world_point: Vert4) -> Vert4 {
+    shape.normal_at(world_point, &Intersection::new(0.0, shape))
+}
+
+/// A unit sphere of fully transparent glass.
+#[cfg(test)]
+pub(crate) fn glass_sphere() -> Sphere {
+    Sphere::new().with_material(Material {
+        transparency: 1.0,
+        refractive_index: 1.5,
+        ..Material::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+    use super::*;
+
+    #[test]
+    fn default_transform_is_identity() {
+        assert_eq!(Sphere::new().transform(), &Transform::IDENTITY);
+    }
+
+    #[test]
+    fn default_material() {
+        assert_eq!(Sphere::new().material(), &Material::default());
+    }
+
+    #[test]
+    fn changing_transform_updates_inverse() {
+        let mut s = Sphere::new();
+        s.set_transform(Matrix4::translation(2.0, 3.0, 4.0));
+        assert_eq!(s.transform().matrix(), &Matrix4::translation(2.0, 3.0, 4.0));
+        assert_approx_eq!(
+            *s.transform().inverse(),
+            Matrix4::translation(-2.0, -3.0, -4.0)
+        );
+    }
+
+    #[test]
+    fn intersecting_scaled_sphere() {
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let s = Sphere::new().with_transform(Matrix4::scaling(2.0, 2.0, 2.0));
+        let ts: Vec<f32> = s.intersect(&r).iter().map(|x| x.t).collect();
+        assert_eq!(ts, [3.0, 7.0]);
+    }
+
+    #[test]
+    fn intersecting_translated_sphere() {
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let s = Sphere::new().with_transform(Matrix4::translation(5.0, 0.0, 0.0));
+        assert!(s.intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn normal_on_translated_sphere() {
+        let s = Sphere::new().with_transform(Matrix4::translation(0.0, 1.0, 0.0));
+        let n = normal_at(&s, Vert4::point(0.0, 1.0 + FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+        assert_approx_eq!(n, Vert4::vector(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+    }
+
+    #[test]
+    fn normal_on_transformed_sphere() {
+        let s = Sphere::new()
+            .with_transform(Matrix4::scaling(1.0, 0.5, 1.0) * Matrix4::rotation_z(PI / 5.0));
+        let half = 2f32.sqrt() / 2.0;
+        let n = normal_at(&s, Vert4::point(0.0, half, -half));
+        assert_approx_eq!(n, Vert4::vector(0.0, 0.97014, -0.24254));
+    }
+
+    #[test]
+    fn shapes_stored_as_trait_objects() {
+        let shapes: Vec<Box<dyn Shape>> = vec![
+            Box::new(Sphere::new()),
+            Box::new(Cube::new().with_transform(Matrix4::translation(0.0, 0.0, 5.0))),
+            Box::new(Cylinder::new().with_transform(Matrix4::translation(5.0, 0.0, 0.0))),
+        ];
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let counts: Vec<usize> = shapes.iter().map(|s| s.intersect(&r).len()).collect();
+        assert_eq!(counts, [2, 2, 0]);
+    }
+
+    #[test]
+    fn includes_checks_descendants() {
+        let g = Group::new().with_child(Sphere::new());
+        assert!(g.includes(g.children()[0].as_ref()));
+        assert!(g.includes(&g));
+        assert!(!g.includes(&Sphere::new()));
+    }
+
+    #[test]
+    fn every_shape_has_its_own_id() {
+        let s = Sphere::new();
+        let copy = s.clone();
+        assert_ne!(s.id(), Sphere::new().id());
+        assert_ne!(s.id(), copy.id());
+        assert_eq!(s, copy);
+    }
+
+    #[test]
+    fn groups_hide_their_children() {
+        let hidden = Visibility {
+            reflections: false,
+            ..Visibility::in_layers([0, 2])
+        };
+        let mut g = Group::new()
+            .with_visibility(hidden)
+            .with_child(Sphere::new().with_visibility(Visibility::in_layers([2, 3])));
+        let child = g.children()[0].visibility();
+        assert!(!child.allows(RayKind::Reflection));
+        assert!(child.allows(RayKind::Camera(Some(2))));
+        assert!(!child.allows(RayKind::Camera(Some(0))));
+        assert!(!child.allows(RayKind::Camera(Some(3))));
+
+        g.set_visibility(Visibility::default());
+        assert_eq!(g.children()[0].visibility(), Visibility::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "invertible")]
+    fn singular_transform_panics() {
+        Sphere::new().set_transform(Matrix4::scaling(0.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,134 @@
+use super::{BoundingBox, Shape, ShapeData};
+use crate::intersection::{Intersection, Intersections};
+use crate::math::Vert4;
+use crate::pattern::uv;
+use crate::ray::Ray;
+
+/// A unit sphere centred on the origin.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sphere {
+    data: ShapeData,
+}
+
+impl Sphere {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Shape for Sphere {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn local_intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        let sphere_to_ray = ray.origin - Vert4::point(0.0, 0.0, 0.0);
+        let a = ray.direction.dot(&ray.direction);
+        let b = 2.0 * ray.direction.dot(&sphere_to_ray);
+        let c = sphere_to_ray.dot(&sphere_to_ray) - 1.0;
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return;
+        }
+        let root = discriminant.sqrt();
+        xs.push(Intersection::new((-b - root) / (2.0 * a), self));
+        xs.push(Intersection::new((-b + root) / (2.0 * a), self));
+    }
+
+    fn local_normal_at(&self, local_point: Vert4, _hit: &Intersection) -> Vert4 {
+        local_point - Vert4::point(0.0, 0.0, 0.0)
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(Vert4::point(-1.0, -1.0, -1.0), Vert4::point(1.0, 1.0, 1.0))
+    }
+
+    fn local_uv(&self, local_point: Vert4) -> (f32, f32) {
+        uv::spherical_map(local_point)
+    }
+
+    fn local_tangents(&self, p: Vert4) -> (Vert4, Vert4) {
+        // Eastwards around the y axis, and northwards along the meridian.
+        (
+            Vert4::vector(-p.z, 0.0, p.x),
+            Vert4::vector(-p.x * p.y, 1.0 - p.y * p.y, -p.z * p.y),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use crate::math::Vert4;
+    use crate::ray::Ray;
+    use crate::shape::{normal_at, Shape, Sphere};
+
+    fn ts(origin: Vert4) -> Vec<f32> {
+        let s = Sphere::new();
+        let r = Ray::new(origin, Vert4::vector(0.0, 0.0, 1.0));
+        s.intersect(&r).iter().map(|x| x.t).collect()
+    }
+
+    #[test]
+    fn ray_through_center() {
+        assert_eq!(ts(Vert4::point(0.0, 0.0, -5.0)), [4.0, 6.0]);
+    }
+
+    #[test]
+    fn ray_tangent() {
+        assert_eq!(ts(Vert4::point(0.0, 1.0, -5.0)), [5.0, 5.0]);
+    }
+
+    #[test]
+    fn ray_misses() {
+        assert!(ts(Vert4::point(0.0, 2.0, -5.0)).is_empty());
+    }
+
+    #[test]
+    fn ray_inside() {
+        assert_eq!(ts(Vert4::point(0.0, 0.0, 0.0)), [-1.0, 1.0]);
+    }
+
+    #[test]
+    fn sphere_behind_ray() {
+        assert_eq!(ts(Vert4::point(0.0, 0.0, 5.0)), [-6.0, -4.0]);
+    }
+
+    #[test]
+    fn intersect_sets_object() {
+        let s = Sphere::new();
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = s.intersect(&r);
+        assert!(xs.iter().all(|x| ptr::addr_eq(x.object, &s)));
+    }
+
+    #[test]
+    fn normals_on_axes() {
+        let s = Sphere::new();
+        assert_eq!(
+            normal_at(&s, Vert4::point(1.0, 0.0, 0.0)),
+            Vert4::vector(1.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            normal_at(&s, Vert4::point(0.0, 1.0, 0.0)),
+            Vert4::vector(0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            normal_at(&s, Vert4::point(0.0, 0.0, 1.0)),
+            Vert4::vector(0.0, 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn normal_at_nonaxial_point_is_normalized() {
+        let k = 3f32.sqrt() / 3.0;
+        let n = normal_at(&Sphere::new(), Vert4::point(k, k, k));
+        assert_approx_eq!(n, Vert4::vector(k, k, k));
+        assert_approx_eq!(n, n.normalize());
+    }
+}
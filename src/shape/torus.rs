@@ -0,0 +1,189 @@
+use super::cube::check_axis;
+use super::{BoundingBox, Shape, ShapeData};
+use crate::intersection::{Intersection, Intersections};
+use crate::math::roots::solve_quartic;
+use crate::math::Vert4;
+use crate::ray::Ray;
+
+/// A ring around the y axis: a tube of `minor_radius` swept along a circle of
+/// `major_radius` in the xz plane.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Torus {
+    pub major_radius: f32,
+    pub minor_radius: f32,
+    data: ShapeData,
+}
+
+impl Default for Torus {
+    fn default() -> Self {
+        Self::new(1.0, 0.25)
+    }
+}
+
+impl Torus {
+    pub fn new(major_radius: f32, minor_radius: f32) -> Self {
+        Self {
+            major_radius,
+            minor_radius,
+            data: ShapeData::default(),
+        }
+    }
+
+    /// Half-extents of the torus' bounding box.
+    fn extent(&self) -> (f32, f32) {
+        (self.major_radius + self.minor_radius, self.minor_radius)
+    }
+}
+
+impl Shape for Torus {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn local_intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        // Start the quartic at the bounding box entry: coefficients grow with the
+        // fourth power of the origin's distance, which wrecks precision for far rays.
+        let (xz, y) = self.extent();
+        let (xtmin, xtmax) = check_axis(ray.origin.x, ray.direction.x, -xz, xz);
+        let (ytmin, ytmax) = check_axis(ray.origin.y, ray.direction.y, -y, y);
+        let (ztmin, ztmax) = check_axis(ray.origin.z, ray.direction.z, -xz, xz);
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+        if tmin > tmax {
+            return;
+        }
+        let start = f64::from(tmin.max(0.0).min(tmax));
+        let o = ray.position(start as f32);
+        let (ox, oy, oz) = (f64::from(o.x), f64::from(o.y), f64::from(o.z));
+        let d = ray.direction;
+        let (dx, dy, dz) = (f64::from(d.x), f64::from(d.y), f64::from(d.z));
+        let big = f64::from(self.major_radius).powi(2);
+        let small = f64::from(self.minor_radius).powi(2);
+
+        let sum_d_sqrd = dx * dx + dy * dy + dz * dz;
+        let e = ox * ox + oy * oy + oz * oz - big - small;
+        let f = ox * dx + oy * dy + oz * dz;
+        let four_big = 4.0 * big;
+        let roots = solve_quartic(
+            sum_d_sqrd * sum_d_sqrd,
+            4.0 * sum_d_sqrd * f,
+            2.0 * sum_d_sqrd * e + 4.0 * f * f + four_big * dy * dy,
+            4.0 * f * e + 2.0 * four_big * oy * dy,
+            e * e - four_big * (small - oy * oy),
+        );
+        xs.extend(
+            roots
+                .into_iter()
+                .map(|t| Intersection::new((t + start) as f32, self)),
+        );
+    }
+
+    fn local_normal_at(&self, p: Vert4, _hit: &Intersection) -> Vert4 {
+        let big = self.major_radius.powi(2);
+        let param = p.x * p.x + p.y * p.y + p.z * p.z - big - self.minor_radius.powi(2);
+        Vert4::vector(p.x * param, p.y * (param + 2.0 * big), p.z * param)
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let (xz, y) = self.extent();
+        BoundingBox::new(Vert4::point(-xz, -y, -xz), Vert4::point(xz, y, xz))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Matrix4;
+    use crate::shape::normal_at;
+
+    fn ts(shape: &dyn Shape, origin: Vert4, direction: Vert4) -> Vec<f32> {
+        shape
+            .intersect(&Ray::new(origin, direction))
+            .iter()
+            .map(|x| x.t)
+            .collect()
+    }
+
+    #[test]
+    fn ray_through_both_sides_of_ring() {
+        let got = ts(
+            &Torus::default(),
+            Vert4::point(-5.0, 0.0, 0.0),
+            Vert4::vector(1.0, 0.0, 0.0),
+        );
+        assert_approx_eq!(got, vec![3.75, 4.25, 5.75, 6.25]);
+    }
+
+    #[test]
+    fn ray_down_through_tube() {
+        let got = ts(
+            &Torus::default(),
+            Vert4::point(1.0, 5.0, 0.0),
+            Vert4::vector(0.0, -1.0, 0.0),
+        );
+        assert_approx_eq!(got, vec![4.75, 5.25]);
+    }
+
+    #[test]
+    fn ray_through_hole_misses() {
+        let t = Torus::default();
+        assert!(ts(
+            &t,
+            Vert4::point(0.0, 5.0, 0.0),
+            Vert4::vector(0.0, -1.0, 0.0)
+        )
+        .is_empty());
+        assert!(ts(
+            &t,
+            Vert4::point(-5.0, 1.0, 0.0),
+            Vert4::vector(1.0, 0.0, 0.0)
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn far_away_ray_keeps_precision() {
+        let got = ts(
+            &Torus::default(),
+            Vert4::point(-1000.0, 0.0, 0.0),
+            Vert4::vector(1.0, 0.0, 0.0),
+        );
+        let expected = [998.75, 999.25, 1000.75, 1001.25];
+        assert_eq!(got.len(), 4);
+        for (g, e) in got.iter().zip(expected) {
+            assert!((g - e).abs() < 1e-3, "{got:?}");
+        }
+    }
+
+    #[test]
+    fn transformed_torus() {
+        let t = Torus::default().with_transform(Matrix4::scaling(2.0, 2.0, 2.0));
+        let got = ts(
+            &t,
+            Vert4::point(2.0, 5.0, 0.0),
+            Vert4::vector(0.0, -1.0, 0.0),
+        );
+        assert_approx_eq!(got, vec![4.5, 5.5]);
+    }
+
+    #[test]
+    fn normals() {
+        let t = Torus::default();
+        assert_approx_eq!(
+            normal_at(&t, Vert4::point(1.25, 0.0, 0.0)),
+            Vert4::vector(1.0, 0.0, 0.0)
+        );
+        assert_approx_eq!(
+            normal_at(&t, Vert4::point(0.75, 0.0, 0.0)),
+            Vert4::vector(-1.0, 0.0, 0.0)
+        );
+        assert_approx_eq!(
+            normal_at(&t, Vert4::point(0.0, 0.25, 1.0)),
+            Vert4::vector(0.0, 1.0, 0.0)
+        );
+    }
+}
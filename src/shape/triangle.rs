@@ -0,0 +1,289 @@
+use super::{BoundingBox, Shape, ShapeData};
+use crate::intersection::{Intersection, Intersections};
+use crate::math::{Vert4, EPSILON};
+use crate::ray::Ray;
+
+/// Möller–Trumbore intersection against the triangle at `p1` spanned by `e1`
+/// and `e2`, returning `(t, u, v)` where `u`/`v` are the barycentric weights of
+/// the second and third vertices.
+fn intersect_face(p1: Vert4, e1: Vert4, e2: Vert4, ray: &Ray) -> Option<(f32, f32, f32)> {
+    let dir_cross_e2 = ray.direction.cross(&e2);
+    let det = e1.dot(&dir_cross_e2);
+    if det.abs() < EPSILON {
+        return None;
+    }
+    let f = 1.0 / det;
+    let p1_to_origin = ray.origin - p1;
+    let u = f * p1_to_origin.dot(&dir_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let origin_cross_e1 = p1_to_origin.cross(&e1);
+    let v = f * ray.direction.dot(&origin_cross_e1);
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    Some((f * e2.dot(&origin_cross_e1), u, v))
+}
+
+/// The barycentric weights of the second and third vertices at `point`, which
+/// lies in the plane of the triangle at `p1` spanned by `e1` and `e2`.
+fn barycentric(p1: Vert4, e1: Vert4, e2: Vert4, point: Vert4) -> (f32, f32) {
+    let to_point = point - p1;
+    let (d11, d12, d22) = (e1.dot(&e1), e1.dot(&e2), e2.dot(&e2));
+    let (dp1, dp2) = (to_point.dot(&e1), to_point.dot(&e2));
+    let denom = d11 * d22 - d12 * d12;
+    (
+        (d22 * dp1 - d12 * dp2) / denom,
+        (d11 * dp2 - d12 * dp1) / denom,
+    )
+}
+
+/// A flat triangle with precomputed edge vectors and face normal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triangle {
+    pub p1: Vert4,
+    pub p2: Vert4,
+    pub p3: Vert4,
+    e1: Vert4,
+    e2: Vert4,
+    normal: Vert4,
+    data: ShapeData,
+}
+
+impl Triangle {
+    pub fn new(p1: Vert4, p2: Vert4, p3: Vert4) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Self {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: e2.cross(&e1).normalize(),
+            data: ShapeData::default(),
+        }
+    }
+
+    pub fn e1(&self) -> Vert4 {
+        self.e1
+    }
+
+    pub fn e2(&self) -> Vert4 {
+        self.e2
+    }
+
+    pub fn normal(&self) -> Vert4 {
+        self.normal
+    }
+}
+
+impl Shape for Triangle {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn local_intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        if let Some((t, u, v)) = intersect_face(self.p1, self.e1, self.e2, ray) {
+            xs.push(Intersection::with_uv(t, self, u, v));
+        }
+    }
+
+    fn local_normal_at(&self, _local_point: Vert4, _hit: &Intersection) -> Vert4 {
+        self.normal
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::from_points([self.p1, self.p2, self.p3])
+    }
+
+    /// The barycentric weights of `p2` and `p3`.
+    fn local_uv(&self, local_point: Vert4) -> (f32, f32) {
+        barycentric(self.p1, self.e1, self.e2, local_point)
+    }
+
+    fn local_tangents(&self, _local_point: Vert4) -> (Vert4, Vert4) {
+        (self.e1, self.e2)
+    }
+}
+
+/// A triangle whose normal is interpolated from per-vertex normals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmoothTriangle {
+    pub p1: Vert4,
+    pub p2: Vert4,
+    pub p3: Vert4,
+    pub n1: Vert4,
+    pub n2: Vert4,
+    pub n3: Vert4,
+    e1: Vert4,
+    e2: Vert4,
+    data: ShapeData,
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Vert4, p2: Vert4, p3: Vert4, n1: Vert4, n2: Vert4, n3: Vert4) -> Self {
+        Self {
+            p1,
+            p2,
+            p3,
+            n1,
+            n2,
+            n3,
+            e1: p2 - p1,
+            e2: p3 - p1,
+            data: ShapeData::default(),
+        }
+    }
+}
+
+impl Shape for SmoothTriangle {
+    fn data(&self) -> &ShapeData {
+        &self.data
+    }
+
+    fn data_mut(&mut self) -> &mut ShapeData {
+        &mut self.data
+    }
+
+    fn local_intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        if let Some((t, u, v)) = intersect_face(self.p1, self.e1, self.e2, ray) {
+            xs.push(Intersection::with_uv(t, self, u, v));
+        }
+    }
+
+    /// The vertex normals blended by the barycentric `u`/`v` of the hit.
+    fn local_normal_at(&self, _local_point: Vert4, hit: &Intersection) -> Vert4 {
+        self.n2 * hit.u + self.n3 * hit.v + self.n1 * (1.0 - hit.u - hit.v)
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::from_points([self.p1, self.p2, self.p3])
+    }
+
+    /// The barycentric weights of `p2` and `p3`.
+    fn local_uv(&self, local_point: Vert4) -> (f32, f32) {
+        barycentric(self.p1, self.e1, self.e2, local_point)
+    }
+
+    fn local_tangents(&self, _local_point: Vert4) -> (Vert4, Vert4) {
+        (self.e1, self.e2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::normal_at;
+
+    fn tri() -> Triangle {
+        Triangle::new(
+            Vert4::point(0.0, 1.0, 0.0),
+            Vert4::point(-1.0, 0.0, 0.0),
+            Vert4::point(1.0, 0.0, 0.0),
+        )
+    }
+
+    fn smooth() -> SmoothTriangle {
+        SmoothTriangle::new(
+            Vert4::point(0.0, 1.0, 0.0),
+            Vert4::point(-1.0, 0.0, 0.0),
+            Vert4::point(1.0, 0.0, 0.0),
+            Vert4::vector(0.0, 1.0, 0.0),
+            Vert4::vector(-1.0, 0.0, 0.0),
+            Vert4::vector(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn precomputed_edges_and_normal() {
+        let t = Triangle::new(
+            Vert4::point(0.0, 1.0, 0.0),
+            Vert4::point(-1.0, 0.0, 0.0),
+            Vert4::point(1.0, 0.0, 0.0),
+        );
+        assert_eq!(t.e1(), Vert4::vector(-1.0, -1.0, 0.0));
+        assert_eq!(t.e2(), Vert4::vector(1.0, -1.0, 0.0));
+        assert_eq!(t.normal(), Vert4::vector(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn flat_normal_is_constant() {
+        let t = tri();
+        for p in [
+            Vert4::point(0.0, 0.5, 0.0),
+            Vert4::point(-0.5, 0.75, 0.0),
+            Vert4::point(0.5, 0.25, 0.0),
+        ] {
+            assert_eq!(normal_at(&t, p), Vert4::vector(0.0, 0.0, -1.0));
+        }
+    }
+
+    #[test]
+    fn misses() {
+        let t = tri();
+        let cases = [
+            // Parallel to the triangle.
+            ((0.0, -1.0, -2.0), (0.0, 1.0, 0.0)),
+            // Past each edge.
+            ((1.0, 1.0, -2.0), (0.0, 0.0, 1.0)),
+            ((-1.0, 1.0, -2.0), (0.0, 0.0, 1.0)),
+            ((0.0, -1.0, -2.0), (0.0, 0.0, 1.0)),
+        ];
+        for ((ox, oy, oz), (dx, dy, dz)) in cases {
+            let r = Ray::new(Vert4::point(ox, oy, oz), Vert4::vector(dx, dy, dz));
+            assert!(t.intersect(&r).is_empty());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_triangle() {
+        let t = tri();
+        let r = Ray::new(Vert4::point(0.0, 0.5, -2.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = t.intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 2.0);
+    }
+
+    #[test]
+    fn smooth_intersection_stores_uv() {
+        let t = smooth();
+        let r = Ray::new(Vert4::point(-0.2, 0.3, -2.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = t.intersect(&r);
+        assert_approx_eq!(xs[0].u, 0.45);
+        assert_approx_eq!(xs[0].v, 0.25);
+    }
+
+    #[test]
+    fn uv_is_barycentric() {
+        let t = tri();
+        let uv = |p: Vert4| t.local_uv(p);
+        assert_eq!(uv(t.p1), (0.0, 0.0));
+        assert_eq!(uv(t.p2), (1.0, 0.0));
+        assert_eq!(uv(t.p3), (0.0, 1.0));
+        let (u, v) = uv(Vert4::point(0.0, 1.0 / 3.0, 0.0));
+        assert_approx_eq!([u, v], [1.0 / 3.0, 1.0 / 3.0]);
+    }
+
+    #[test]
+    fn smooth_normal_interpolates() {
+        let t = smooth();
+        let i = Intersection::with_uv(1.0, &t, 0.45, 0.25);
+        let n = t.normal_at(Vert4::point(0.0, 0.0, 0.0), &i);
+        assert_approx_eq!(n, Vert4::vector(-0.5547, 0.83205, 0.0));
+    }
+
+    #[test]
+    fn prepare_computations_uses_smooth_normal() {
+        let t = smooth();
+        let i = Intersection::with_uv(1.0, &t, 0.45, 0.25);
+        let r = Ray::new(Vert4::point(-0.2, 0.3, -2.0), Vert4::vector(0.0, 0.0, 1.0));
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_approx_eq!(comps.normalv, Vert4::vector(-0.5547, 0.83205, 0.0));
+    }
+}
@@ -0,0 +1,119 @@
+//! The assertions and fixtures the crate's own tests use, for downstream
+//! crates testing their own shapes, patterns and canvas passes. Enabled by
+//! the `testing` feature.
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::world::World;
+
+/// Asserts that two [`Vert4`](crate::math::Vert4)s are equal, every
+/// component within [`EPSILON`](crate::math::EPSILON).
+#[macro_export]
+macro_rules! assert_vert_eq {
+    ($left:expr, $right:expr $(,)?) => {{
+        let (left, right): (&$crate::math::Vert4, &$crate::math::Vert4) = (&$left, &$right);
+        assert!(
+            $crate::math::ApproxEq::approx_eq(left, right),
+            "assertion `left ≈ right` failed\n  left: {:?}\n right: {:?}",
+            left,
+            right
+        );
+    }};
+}
+
+/// Asserts that two [`Color`](crate::color::Color)s are equal, every channel
+/// within [`EPSILON`](crate::math::EPSILON).
+#[macro_export]
+macro_rules! assert_color_eq {
+    ($left:expr, $right:expr $(,)?) => {{
+        let (left, right): (&$crate::color::Color, &$crate::color::Color) = (&$left, &$right);
+        assert!(
+            $crate::math::ApproxEq::approx_eq(left, right),
+            "assertion `left ≈ right` failed\n  left: {:?}\n right: {:?}",
+            left,
+            right
+        );
+    }};
+}
+
+/// Two concentric spheres lit from the upper left: a green-tinted unit
+/// sphere around one of half the size, with a white point light at
+/// (-10, 10, -10).
+pub fn default_world() -> World {
+    crate::world::default_world()
+}
+
+/// A canvas whose red rises from 0 to 1 across it and green from 0 to 1
+/// down it, so every pixel differs and the corners are easy to predict.
+pub fn gradient_canvas(width: usize, height: usize) -> Canvas {
+    let step = |i: usize, n: usize| {
+        if n > 1 {
+            i as f32 / (n - 1) as f32
+        } else {
+            0.0
+        }
+    };
+    Canvas::from_fn(width, height, |x, y| {
+        Color::new(step(x, width), step(y, height), 0.0)
+    })
+}
+
+/// A checkerboard of `size` × `size` squares, `a` at the top left
+/// alternating with `b`.
+///
+/// # Panics
+///
+/// Panics if `size` is zero.
+pub fn checker_canvas(width: usize, height: usize, size: usize, a: Color, b: Color) -> Canvas {
+    assert!(size > 0, "checker squares must not be empty");
+    Canvas::from_fn(width, height, |x, y| {
+        if (x / size + y / size).is_multiple_of(2) {
+            a
+        } else {
+            b
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vert4;
+    use crate::ray::Ray;
+
+    #[test]
+    fn assertions_allow_for_rounding() {
+        assert_vert_eq!(Vert4::point(1.0, 2.0, 3.0), Vert4::point(1.00001, 2.0, 3.0));
+        assert_color_eq!(Color::new(0.1, 0.2, 0.3), Color::new(0.1, 0.20001, 0.3));
+    }
+
+    #[test]
+    #[should_panic(expected = "left ≈ right")]
+    fn assertions_fail_beyond_epsilon() {
+        assert_color_eq!(Color::BLACK, Color::new(0.0, 0.001, 0.0));
+    }
+
+    #[test]
+    fn gradient_canvas_spans_its_corners() {
+        let c = gradient_canvas(5, 3);
+        assert_color_eq!(c.pixel_at(0, 0), Color::BLACK);
+        assert_color_eq!(c.pixel_at(4, 2), Color::new(1.0, 1.0, 0.0));
+        assert_color_eq!(c.pixel_at(2, 1), Color::new(0.5, 0.5, 0.0));
+    }
+
+    #[test]
+    fn checker_canvas_alternates_squares() {
+        let c = checker_canvas(4, 4, 2, Color::WHITE, Color::BLACK);
+        assert_eq!(c.pixel_at(1, 1), Color::WHITE);
+        assert_eq!(c.pixel_at(2, 1), Color::BLACK);
+        assert_eq!(c.pixel_at(3, 3), Color::WHITE);
+    }
+
+    #[test]
+    fn default_world_has_two_lit_spheres() {
+        let w = default_world();
+        assert_eq!((w.objects.len(), w.lights.len()), (2, 1));
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert_color_eq!(w.color_at(&r), Color::new(0.38066, 0.47583, 0.2855));
+    }
+}
@@ -0,0 +1,1486 @@
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::f32::consts::PI;
+use std::fmt::{self, Display};
+use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::integrator::{Caustics, IrradianceCache, PhotonMap};
+use crate::intersection::{Computations, Intersection, Intersections};
+use crate::light::{lighting, surface_color, Light};
+use crate::material::{Material, MaterialLibrary, SpecularModel};
+use crate::math::{Vert4, EPSILON};
+use crate::pattern::{Pattern, TextureMemory};
+use crate::ray::Ray;
+use crate::shape::{Group, ObjectId, RayKind, Shape, SmoothTriangle, Triangle};
+
+/// The default [`World::max_depth`].
+pub const DEFAULT_MAX_DEPTH: u32 = 5;
+
+/// Settings for darkening ambient light in creases and corners.
+///
+/// At each hit, `samples` rays are spread over the hemisphere around the
+/// normal, and the ambient term is scaled by the fraction that travel
+/// `radius` without striking anything. The rays follow a fixed cosine-weighted
+/// spiral, so renders are noise-free and repeatable.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AmbientOcclusion {
+    pub samples: u32,
+    pub radius: f32,
+}
+
+impl AmbientOcclusion {
+    pub const fn new(samples: u32, radius: f32) -> Self {
+        Self { samples, radius }
+    }
+
+    /// The `samples` unit directions around `normal`, denser towards it.
+    fn directions(&self, normal: Vert4) -> impl Iterator<Item = Vert4> {
+        hemisphere_spiral(normal, self.samples)
+    }
+}
+
+/// `samples` unit directions spread over the hemisphere around `normal` in a
+/// fixed spiral, cosine weighted so their plain average estimates light
+/// falling on a matte surface.
+fn hemisphere_spiral(normal: Vert4, samples: u32) -> impl Iterator<Item = Vert4> {
+    let helper = if normal.x.abs() > 0.9 {
+        Vert4::vector(0.0, 1.0, 0.0)
+    } else {
+        Vert4::vector(1.0, 0.0, 0.0)
+    };
+    let tangent = helper.cross(&normal).normalize();
+    let bitangent = normal.cross(&tangent);
+    let golden_angle = PI * (3.0 - 5f32.sqrt());
+    let n = samples as f32;
+    (0..samples).map(move |i| {
+        let i = i as f32;
+        let cos_theta = (1.0 - (i + 0.5) / n).sqrt();
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let phi = i * golden_angle;
+        tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + normal * cos_theta
+    })
+}
+
+/// The object [`World::pick`] finds under a pixel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pick {
+    /// The hit shape, or the [`Instance`](crate::shape::Instance) placing it;
+    /// look it up with [`World::object`].
+    pub object: ObjectId,
+    /// Where the pixel's ray struck it, in world space.
+    pub point: Vert4,
+}
+
+/// What a world holds and roughly how much memory rendering it takes, from
+/// [`World::report`], to see why a scene is slow or runs out of memory
+/// before rendering it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SceneReport {
+    /// How many shapes there are of each [kind](Shape::kind), groups
+    /// included. Geometry an [`Instance`](crate::shape::Instance) places is
+    /// counted once for each instance, as rays see it.
+    pub shapes: BTreeMap<&'static str, usize>,
+    /// Triangles and smooth triangles, usually the bulk of a mesh.
+    pub triangles: usize,
+    /// Groups, the nodes of the bounding-volume hierarchy.
+    pub bvh_nodes: usize,
+    /// The most groups nested inside one another.
+    pub bvh_depth: usize,
+    pub lights: usize,
+    /// Bytes held by texture images and caches, each counted once however
+    /// many materials share it.
+    pub texture_bytes: usize,
+    /// Bytes of image buffers a render at the camera's resolution needs:
+    /// the canvas returned, the buffer samples are summed in, and the canvas
+    /// each pass is traced into.
+    pub buffer_bytes: usize,
+}
+
+impl SceneReport {
+    pub fn shape_count(&self) -> usize {
+        self.shapes.values().sum()
+    }
+}
+
+impl Display for SceneReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "shapes:    {}", self.shape_count())?;
+        for (kind, count) in &self.shapes {
+            writeln!(f, "  {kind}: {count}")?;
+        }
+        writeln!(f, "triangles: {}", self.triangles)?;
+        writeln!(
+            f,
+            "bvh:       {} nodes, depth {}",
+            self.bvh_nodes, self.bvh_depth
+        )?;
+        writeln!(f, "lights:    {}", self.lights)?;
+        writeln!(f, "textures:  {}", Bytes(self.texture_bytes))?;
+        write!(f, "buffers:   {}", Bytes(self.buffer_bytes))
+    }
+}
+
+/// A byte count in the largest binary unit that keeps it at least 1.
+struct Bytes(usize);
+
+impl Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+        let mut size = self.0 as f64;
+        let mut unit = 0;
+        while size >= 1024.0 && unit + 1 < UNITS.len() {
+            size /= 1024.0;
+            unit += 1;
+        }
+        if unit == 0 {
+            write!(f, "{} B", self.0)
+        } else {
+            write!(f, "{size:.1} {}", UNITS[unit])
+        }
+    }
+}
+
+/// A scene: the objects to render and the lights illuminating them.
+#[derive(Debug)]
+pub struct World {
+    pub objects: Vec<Box<dyn Shape>>,
+    pub lights: Vec<Light>,
+    /// Named materials the objects can share; see
+    /// [`edit_material`](Self::edit_material).
+    pub materials: MaterialLibrary,
+    /// How many times a ray may be reflected or refracted before
+    /// [`color_at`](Self::color_at) stops following it, so facing mirrors
+    /// don't recurse forever.
+    pub max_depth: u32,
+    /// How far shading points are nudged off surfaces to keep secondary rays
+    /// from re-hitting them (acne); see
+    /// [`Intersection::prepare_computations_with_offset`](crate::intersection::Intersection::prepare_computations_with_offset).
+    pub surface_offset: f32,
+    /// What rays that escape the scene see, sampled at their direction (as a
+    /// point one unit from the origin); typically a
+    /// [`CubeMap`](crate::pattern::CubeMap). Black when unset.
+    pub background: Option<Arc<dyn Pattern>>,
+    /// Darkens the ambient term where nearby geometry blocks the sky. Off
+    /// when unset.
+    pub ambient_occlusion: Option<AmbientOcclusion>,
+    /// Whether transparent objects let light through into their shadows; see
+    /// [`light_fraction`](Self::light_fraction). Off by default, when every
+    /// object casts a solid shadow.
+    pub transparent_shadows: bool,
+    /// Light focused onto surfaces by mirrors and glass, which direct
+    /// lighting misses; see [`with_caustics`](Self::with_caustics). Only
+    /// [`shade_hit`](Self::shade_hit) uses it: a path tracer finds caustics
+    /// by itself. None when unset.
+    pub caustics: Option<PhotonMap>,
+    /// Indirect diffuse light, sampled and interpolated in place of the
+    /// ambient term, which it overrides along with
+    /// [`ambient_occlusion`](Self::ambient_occlusion). Off when unset.
+    pub irradiance_cache: Option<IrradianceCache>,
+    /// The names of the render layers, in the order of the bits in each
+    /// object's [`Visibility::layers`](crate::shape::Visibility::layers); see
+    /// [`Camera::render_layers`](crate::camera::Camera::render_layers).
+    pub layers: Vec<String>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self {
+            objects: Vec::new(),
+            lights: Vec::new(),
+            materials: MaterialLibrary::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            surface_offset: EPSILON,
+            background: None,
+            ambient_occlusion: None,
+            transparent_shadows: false,
+            caustics: None,
+            irradiance_cache: None,
+            layers: Vec::new(),
+        }
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_depth(mut self, max_depth: u32) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn with_surface_offset(mut self, surface_offset: f32) -> Self {
+        self.surface_offset = surface_offset;
+        self
+    }
+
+    pub fn with_ambient_occlusion(mut self, ambient_occlusion: AmbientOcclusion) -> Self {
+        self.ambient_occlusion = Some(ambient_occlusion);
+        self
+    }
+
+    pub fn with_transparent_shadows(mut self, transparent_shadows: bool) -> Self {
+        self.transparent_shadows = transparent_shadows;
+        self
+    }
+
+    /// Shoots photons for `caustics` through the world as it is now, so add
+    /// the objects and lights first, and call it again after changing them.
+    pub fn with_caustics(mut self, caustics: Caustics) -> Self {
+        self.caustics = Some(PhotonMap::caustics(&self, &caustics));
+        self
+    }
+
+    pub fn with_irradiance_cache(mut self, irradiance_cache: IrradianceCache) -> Self {
+        self.irradiance_cache = Some(irradiance_cache);
+        self
+    }
+
+    pub fn with_background(mut self, background: Arc<dyn Pattern>) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    /// Adds a render layer after any already in the world.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the world already has 64 layers, as many as
+    /// [`Visibility::layers`](crate::shape::Visibility::layers) has bits.
+    pub fn with_layer(mut self, name: impl Into<String>) -> Self {
+        assert!(
+            self.layers.len() < 64,
+            "a world has at most 64 render layers"
+        );
+        self.layers.push(name.into());
+        self
+    }
+
+    /// Adds a light alongside any already in the world.
+    pub fn with_light(mut self, light: impl Into<Light>) -> Self {
+        self.lights.push(light.into());
+        self
+    }
+
+    pub fn with_object(mut self, object: impl Shape) -> Self {
+        self.objects.push(Box::new(object));
+        self
+    }
+
+    /// Registers `material` in the world's library under `name`, returning the
+    /// handle to give shapes with
+    /// [`with_shared_material`](Shape::with_shared_material).
+    pub fn add_material(&mut self, name: impl Into<String>, material: Material) -> Arc<Material> {
+        self.materials.insert(name, material)
+    }
+
+    /// The library material called `name`.
+    pub fn material(&self, name: &str) -> Option<Arc<Material>> {
+        self.materials.get(name).cloned()
+    }
+
+    /// Changes the library material called `name` for every object sharing
+    /// it. Returns `false`, changing nothing, if there's no such material.
+    pub fn edit_material(&mut self, name: &str, edit: impl FnOnce(&mut Material)) -> bool {
+        let Some(old) = self.materials.get(name).cloned() else {
+            return false;
+        };
+        let mut material = Material::clone(&old);
+        edit(&mut material);
+        let new = self.materials.insert(name, material);
+        for object in &mut self.objects {
+            object.replace_material(&old, &new);
+        }
+        true
+    }
+
+    /// Every intersection of `ray` with the world's objects, sorted by `t`.
+    pub fn intersect(&self, ray: &Ray) -> Intersections<'_> {
+        let mut xs = Intersections::default();
+        self.intersect_into(ray, &mut xs);
+        xs
+    }
+
+    /// The intersections of `ray` with the objects a ray of `kind` can
+    /// strike, by their [`Visibility`](crate::shape::Visibility).
+    pub fn intersect_visible(&self, ray: &Ray, kind: RayKind) -> Intersections<'_> {
+        let mut xs = self.intersect(ray);
+        xs.retain(|x| x.shading_object().visibility().allows(kind));
+        xs
+    }
+
+    /// Like [`intersect`](Self::intersect), but adds the intersections to
+    /// `xs`, whose storage can be reused from ray to ray.
+    pub fn intersect_into<'a>(&'a self, ray: &Ray, xs: &mut Intersections<'a>) {
+        for object in &self.objects {
+            object.intersect_into(ray, xs);
+        }
+    }
+
+    /// The object seen through the centre of pixel (`x`, `y`) of `camera`,
+    /// ignoring depth of field, or `None` if the pixel shows the background.
+    pub fn pick(&self, camera: &Camera, x: usize, y: usize) -> Option<Pick> {
+        let ray = camera.ray_for_pixel(x, y);
+        let xs = self.intersect_visible(&ray, RayKind::Camera(None));
+        let hit = xs.hit()?;
+        Some(Pick {
+            object: hit.shading_object().id(),
+            point: ray.position(hit.t),
+        })
+    }
+
+    /// Counts what the world holds, and estimates the memory its textures
+    /// and a render through `camera` take.
+    pub fn report(&self, camera: &Camera) -> SceneReport {
+        fn walk(shape: &dyn Shape, depth: usize, report: &mut SceneReport) {
+            *report.shapes.entry(shape.kind()).or_default() += 1;
+            let any = shape as &dyn Any;
+            if any.is::<Triangle>() || any.is::<SmoothTriangle>() {
+                report.triangles += 1;
+            }
+            let depth = if any.is::<Group>() {
+                report.bvh_nodes += 1;
+                report.bvh_depth = report.bvh_depth.max(depth + 1);
+                depth + 1
+            } else {
+                depth
+            };
+            shape.for_each_child(&mut |child| walk(child, depth, report));
+        }
+
+        let mut report = SceneReport {
+            lights: self.lights.len(),
+            ..SceneReport::default()
+        };
+        for object in &self.objects {
+            walk(object.as_ref(), 0, &mut report);
+        }
+
+        let mut textures = HashMap::new();
+        let mut add = |texture: TextureMemory| {
+            textures.insert(texture.storage, texture.bytes);
+        };
+        fn materials(shape: &dyn Shape, add: &mut dyn FnMut(TextureMemory)) {
+            shape.material().textures(add);
+            shape.for_each_child(&mut |child| materials(child, add));
+        }
+        for object in &self.objects {
+            materials(object.as_ref(), &mut add);
+        }
+        for (_, material) in self.materials.iter() {
+            material.textures(&mut add);
+        }
+        if let Some(background) = &self.background {
+            background.textures(&mut add);
+        }
+        report.texture_bytes = textures.values().sum();
+
+        let pixels = camera.hsize() * camera.vsize();
+        let canvases = 3 * mem::size_of::<Color>();
+        report.buffer_bytes = pixels * (canvases + mem::size_of::<u32>());
+        report
+    }
+
+    /// The object, or descendant of one, with the given `id`.
+    pub fn object(&self, id: ObjectId) -> Option<&dyn Shape> {
+        fn find(shape: &dyn Shape, id: ObjectId) -> Option<&dyn Shape> {
+            if shape.id() == id {
+                return Some(shape);
+            }
+            let mut found = None;
+            shape.for_each_child(&mut |child| {
+                if found.is_none() {
+                    found = find(child, id);
+                }
+            });
+            found
+        }
+        self.objects
+            .iter()
+            .find_map(|object| find(object.as_ref(), id))
+    }
+
+    /// The color at a prepared intersection, including light reflected off and
+    /// transmitted through it. `remaining` bounds how many more rays may be spawned.
+    ///
+    /// Each light contributes its own [`lighting`] term, ambient included (and
+    /// darkened by [`ambient_occlusion`](Self::ambient_occlusion) if set), and
+    /// the material's emission is added once on top, so only emissive surfaces
+    /// show up in a world without lights. With an
+    /// [`irradiance_cache`](Self::irradiance_cache), the light the surface
+    /// diffuses from its surroundings replaces the ambient term.
+    ///
+    /// Surfaces that both reflect and refract split the light between the two
+    /// by their Fresnel reflectance ([`Computations::schlick`]).
+    ///
+    /// A [`shadow_catcher`](Material::shadow_catcher) shows whatever lies
+    /// behind it, darkened by its [`shadow_strength`](Self::shadow_strength).
+    /// Over an empty background that's black, ready to be laid over a
+    /// photograph with the shadow strength as alpha.
+    pub fn shade_hit(&self, comps: &Computations, remaining: u32) -> Color {
+        let material = comps.material;
+        if material.shadow_catcher {
+            if remaining == 0 {
+                return Color::BLACK;
+            }
+            let behind = Ray::new(comps.under_point, -comps.eyev);
+            return self.color_at_depth(&behind, remaining - 1)
+                * (1.0 - self.shadow_strength(comps));
+        }
+        let adjusted;
+        let material = match (&self.irradiance_cache, &self.ambient_occlusion) {
+            (Some(_), _) => {
+                adjusted = Material {
+                    ambient: 0.0,
+                    ..material.clone()
+                };
+                &adjusted
+            }
+            (None, Some(ao)) => {
+                adjusted = Material {
+                    ambient: material.ambient * self.ambient_visibility(comps, ao),
+                    ..material.clone()
+                };
+                &adjusted
+            }
+            (None, None) => material,
+        };
+        let surface = self.local_light(comps, material) + self.indirect_light(comps, material);
+        let reflected = self.reflected_color(comps, remaining);
+        let refracted = self.refracted_color(comps, remaining);
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            let reflectance = comps.schlick();
+            surface + reflected * reflectance + refracted * (1.0 - reflectance)
+        } else {
+            surface + reflected + refracted
+        }
+    }
+
+    /// The light leaving a hit without following reflections or
+    /// refractions: each light's [`lighting`], the emission, and any caustics.
+    fn local_light(&self, comps: &Computations, material: &Material) -> Color {
+        self.lights
+            .iter()
+            .map(|light| {
+                lighting(
+                    material,
+                    comps.object,
+                    light,
+                    comps.over_point,
+                    comps.eyev,
+                    comps.normalv,
+                    self.light_fraction(comps.over_point, light),
+                )
+            })
+            .sum::<Color>()
+            + material.emission
+            + self.caustic_light(comps, material)
+    }
+
+    /// The color mirrored by a reflective surface, tinted for metals (see
+    /// [`Material::reflection_tint`]); black for matte surfaces and once
+    /// `remaining` runs out.
+    pub fn reflected_color(&self, comps: &Computations, remaining: u32) -> Color {
+        let reflective = comps.material.reflective;
+        if remaining == 0 || reflective == 0.0 {
+            return Color::BLACK;
+        }
+        let material = comps.material;
+        let base_color = surface_color(material, comps.object, comps.point);
+        let tint = material.reflection_tint(base_color, comps.eyev.dot(&comps.normalv));
+        let ray = Ray::new(comps.over_point, comps.reflectv);
+        self.color_at_depth(&ray, remaining - 1) * tint * reflective
+    }
+
+    /// The color seen through a transparent surface, bent by Snell's law.
+    ///
+    /// Black for opaque surfaces, once `remaining` runs out, and under total
+    /// internal reflection.
+    pub fn refracted_color(&self, comps: &Computations, remaining: u32) -> Color {
+        let transparency = comps.material.transparency;
+        if remaining == 0 || transparency == 0.0 {
+            return Color::BLACK;
+        }
+        let Some(direction) = comps.refracted_direction() else {
+            return Color::BLACK;
+        };
+        let ray = Ray::new(comps.under_point, direction);
+        self.color_at_depth(&ray, remaining - 1) * transparency
+    }
+
+    /// The light the [`caustics`](Self::caustics) photon map has landing on a
+    /// hit, diffused by its surface.
+    fn caustic_light(&self, comps: &Computations, material: &Material) -> Color {
+        let Some(photons) = &self.caustics else {
+            return Color::BLACK;
+        };
+        photons.irradiance(comps.point, comps.normalv) * diffuse_color(comps, material)
+    }
+
+    /// The light diffused from the surroundings at a hit, from the
+    /// [`irradiance_cache`](Self::irradiance_cache).
+    fn indirect_light(&self, comps: &Computations, material: &Material) -> Color {
+        let Some(cache) = &self.irradiance_cache else {
+            return Color::BLACK;
+        };
+        let light = cache.light_at(comps.point, comps.normalv, || {
+            self.sample_surroundings(comps, cache.samples)
+        });
+        light * diffuse_color(comps, material)
+    }
+
+    /// The average light reaching a hit over `samples` rays, cosine weighted,
+    /// and the harmonic mean distance to what they struck. Only the
+    /// [`local_light`](Self::local_light) of each surface struck is counted,
+    /// ambient included, standing in for light that bounced further.
+    fn sample_surroundings(&self, comps: &Computations, samples: u32) -> (Color, f32) {
+        if samples == 0 {
+            return (Color::BLACK, f32::INFINITY);
+        }
+        let mut light = Color::BLACK;
+        let mut inverse_distances = 0.0;
+        for direction in hemisphere_spiral(comps.normalv, samples) {
+            let ray = Ray::new(comps.over_point, direction);
+            let xs = self.intersect(&ray);
+            match xs.hit() {
+                Some(hit) => {
+                    let comps =
+                        hit.prepare_computations_with_offset(&ray, &xs, self.surface_offset);
+                    light += self.local_light(&comps, comps.material);
+                    inverse_distances += 1.0 / hit.t;
+                }
+                None => light += self.background_color(&ray),
+            }
+        }
+        let samples = samples as f32;
+        (light / samples, samples / inverse_distances)
+    }
+
+    /// How much of the light that would fall on a hit is blocked, from 0 in
+    /// the open to 1 in full shadow.
+    ///
+    /// Each light counts by how brightly it would light the surface's
+    /// diffuse term, scaled by its [`light_fraction`](Self::light_fraction).
+    /// The ambient term counts too, darkened by
+    /// [`ambient_occlusion`](Self::ambient_occlusion) if set, so contact
+    /// shadows show under objects even where no light is blocked. Hits
+    /// from inside an object, such as on the far side of a shadow-catching
+    /// box, catch nothing.
+    pub fn shadow_strength(&self, comps: &Computations) -> f32 {
+        if comps.inside {
+            return 0.0;
+        }
+        let material = comps.material;
+        let brightness = |c: Color| (c.r + c.g + c.b) / 3.0;
+        let ambient_visibility = self
+            .ambient_occlusion
+            .map_or(1.0, |ao| self.ambient_visibility(comps, &ao));
+        let (mut lit, mut total) = (0.0, 0.0);
+        for light in &self.lights {
+            let intensity = brightness(light.intensity_at(comps.over_point));
+            let facing = light.direction_from(comps.over_point).dot(&comps.normalv);
+            let direct = intensity * material.diffuse * facing.max(0.0);
+            let ambient = intensity * material.ambient;
+            lit += direct * self.light_fraction(comps.over_point, light)
+                + ambient * ambient_visibility;
+            total += direct + ambient;
+        }
+        if total > 0.0 {
+            1.0 - lit / total
+        } else {
+            0.0
+        }
+    }
+
+    /// The fraction of `ao`'s sample rays from a hit that escape within its
+    /// radius.
+    fn ambient_visibility(&self, comps: &Computations, ao: &AmbientOcclusion) -> f32 {
+        if ao.samples == 0 {
+            return 1.0;
+        }
+        let open = ao
+            .directions(comps.normalv)
+            .filter(|&direction| !self.is_occluded(comps.over_point, direction, ao.radius))
+            .count();
+        open as f32 / ao.samples as f32
+    }
+
+    /// How much of `light` reaches `point`, from 0 to 1.
+    ///
+    /// Normally all or nothing, as by [`is_shadowed`](Self::is_shadowed). With
+    /// [`transparent_shadows`](Self::transparent_shadows) set, the light is
+    /// instead scaled by the transparency of every surface it crosses, so an
+    /// opaque surface still blocks it but a glass one only dims it.
+    pub fn light_fraction(&self, point: Vert4, light: &Light) -> f32 {
+        if !self.transparent_shadows {
+            return if self.is_shadowed(point, light) {
+                0.0
+            } else {
+                1.0
+            };
+        }
+        let distance = light.distance_from(point);
+        let ray = Ray::new(point, light.direction_from(point));
+        let mut fraction = 1.0;
+        for x in self.intersect_visible(&ray, RayKind::Shadow).iter() {
+            if x.t < 0.0 {
+                continue;
+            }
+            if x.t >= distance || fraction == 0.0 {
+                break;
+            }
+            fraction *= x.material().transparency;
+        }
+        fraction
+    }
+
+    /// Whether any object that casts
+    /// [shadows](crate::shape::Visibility::shadows) lies between `point` and
+    /// `light`. Directional lights are shadowed by anything in their
+    /// direction.
+    pub fn is_shadowed(&self, point: Vert4, light: &Light) -> bool {
+        let distance = light.distance_from(point);
+        self.is_occluded(point, light.direction_from(point), distance)
+    }
+
+    /// Whether any object that casts
+    /// [shadows](crate::shape::Visibility::shadows) lies along `direction`
+    /// from `origin`, before `max_t` times its length. It stops at the first
+    /// object found rather than sorting hits to find the nearest, so it's
+    /// the cheapest way to test shadows and lines of sight.
+    pub fn is_occluded(&self, origin: Vert4, direction: Vert4, max_t: f32) -> bool {
+        let ray = Ray::new(origin, direction);
+        self.objects.iter().any(|object| {
+            object
+                .any_hit(&ray, &(0.0..max_t), RayKind::Shadow)
+                .is_some()
+        })
+    }
+
+    /// Some intersection with `ray` with `t` in `t_range`, among objects
+    /// seen by camera rays, or `None` if there isn't one. Like
+    /// [`is_occluded`](Self::is_occluded) it stops at the first found,
+    /// which isn't necessarily the nearest; [`intersect`](Self::intersect)
+    /// finds that.
+    pub fn raycast(&self, ray: &Ray, t_range: Range<f32>) -> Option<Intersection<'_>> {
+        self.objects
+            .iter()
+            .find_map(|object| object.any_hit(ray, &t_range, RayKind::Camera(None)))
+    }
+
+    /// The color seen along the camera ray `ray`, the
+    /// [`background`](Self::background) where it hits nothing, following at
+    /// most [`max_depth`](Self::max_depth) reflections and refractions.
+    pub fn color_at(&self, ray: &Ray) -> Color {
+        self.color_seen(ray, RayKind::Camera(None))
+    }
+
+    /// [`color_at`](Self::color_at) for a ray of any `kind`, such as a camera
+    /// ray that only sees one render layer.
+    pub fn color_seen(&self, ray: &Ray, kind: RayKind) -> Color {
+        self.trace(ray, self.max_depth, kind)
+    }
+
+    /// The color seen along a reflected or refracted `ray`, allowing
+    /// `remaining` further rays to be spawned.
+    pub fn color_at_depth(&self, ray: &Ray, remaining: u32) -> Color {
+        self.trace(ray, remaining, RayKind::Reflection)
+    }
+
+    fn trace(&self, ray: &Ray, remaining: u32, kind: RayKind) -> Color {
+        let xs = self.intersect_visible(ray, kind);
+        match xs.hit() {
+            Some(hit) => {
+                let comps = hit.prepare_computations_with_offset(ray, &xs, self.surface_offset);
+                self.shade_hit(&comps, remaining)
+            }
+            None => self.background_color(ray),
+        }
+    }
+
+    /// What `ray` sees if it escapes the scene; see
+    /// [`background`](Self::background).
+    pub fn background_color(&self, ray: &Ray) -> Color {
+        match &self.background {
+            Some(background) => {
+                let d = ray.direction.normalize();
+                background.pattern_at_object(Vert4::point(d.x, d.y, d.z))
+            }
+            None => Color::BLACK,
+        }
+    }
+}
+
+/// The fraction of the light arriving at a hit that its surface diffuses, as
+/// [`lighting`] diffuses light straight from a lamp.
+fn diffuse_color(comps: &Computations, material: &Material) -> Color {
+    let diffuse = match material.specular_model {
+        SpecularModel::Phong => material.diffuse,
+        SpecularModel::Ggx { metalness, .. } => material.diffuse * (1.0 - metalness),
+    };
+    surface_color(material, comps.object, comps.point) * diffuse
+}
+
+/// The two concentric spheres lit from the upper left used throughout the
+/// tests, and offered to other crates as `testing::default_world`.
+#[cfg(any(test, feature = "testing"))]
+pub(crate) fn default_world() -> World {
+    use crate::material::Material;
+    use crate::math::Matrix4;
+    use crate::shape::Sphere;
+
+    let outer = Sphere::new().with_material(Material {
+        color: Color::new(0.8, 1.0, 0.6),
+        diffuse: 0.7,
+        specular: 0.2,
+        ..Material::default()
+    });
+    let inner = Sphere::new().with_transform(Matrix4::scaling(0.5, 0.5, 0.5));
+    World::new()
+        .with_light(Light::point(Vert4::point(-10.0, 10.0, -10.0), Color::WHITE))
+        .with_object(outer)
+        .with_object(inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::*;
+    use crate::camera::view_transform;
+    use crate::canvas::Canvas;
+    use crate::intersection::Intersection;
+    use crate::material::{Material, SpecularModel};
+    use crate::math::Matrix4;
+    use crate::pattern::{CubeMap, Gradient, TestPattern, TexturePattern};
+    use crate::shape::{Cube, Group, Sphere, Visibility};
+
+    fn make_glass(shape: &mut dyn Shape) {
+        let material = shape.material_mut();
+        material.transparency = 1.0;
+        material.refractive_index = 1.5;
+    }
+
+    #[test]
+    fn empty_world() {
+        let w = World::new();
+        assert!(w.objects.is_empty());
+        assert!(w.lights.is_empty());
+    }
+
+    #[test]
+    fn intersect_default_world() {
+        let w = default_world();
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let ts: Vec<f32> = w.intersect(&r).iter().map(|x| x.t).collect();
+        assert_eq!(ts, [4.0, 4.5, 5.5, 6.0]);
+    }
+
+    #[test]
+    fn a_cleared_buffer_can_be_reused_for_the_next_ray() {
+        let w = default_world();
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let mut xs = Intersections::default();
+        w.intersect_into(&r, &mut xs);
+        w.intersect_into(&r, &mut xs);
+        assert_eq!(xs.len(), 8);
+        xs.clear();
+        w.intersect_into(&r, &mut xs);
+        let ts: Vec<f32> = xs.iter().map(|x| x.t).collect();
+        assert_eq!(ts, [4.0, 4.5, 5.5, 6.0]);
+    }
+
+    #[test]
+    fn picking_the_object_under_a_pixel() {
+        let w = default_world();
+        let c = Camera::new(11, 11, PI / 2.0).with_transform(view_transform(
+            Vert4::point(0.0, 0.0, -5.0),
+            Vert4::point(0.0, 0.0, 0.0),
+            Vert4::vector(0.0, 1.0, 0.0),
+        ));
+        let pick = w.pick(&c, 5, 5).unwrap();
+        assert_eq!(pick.object, w.objects[0].id());
+        assert_approx_eq!(pick.point, Vert4::point(0.0, 0.0, -1.0));
+        assert!(w.pick(&c, 0, 0).is_none());
+    }
+
+    #[test]
+    fn reporting_what_a_world_holds() {
+        let image = Arc::new(Canvas::new(4, 2));
+        let textured = || {
+            Material::builder()
+                .pattern(Arc::new(TexturePattern::new(Arc::clone(&image))))
+                .build()
+        };
+        let p = |x| Vert4::point(x, 0.0, 0.0);
+        let mesh = Group::new()
+            .with_child(Triangle::new(p(0.0), p(1.0), Vert4::point(0.0, 1.0, 0.0)))
+            .with_child(Triangle::new(p(1.0), p(2.0), Vert4::point(1.0, 1.0, 0.0)))
+            .with_child(Sphere::new().with_material(textured()));
+        let mut w = World::new()
+            .with_object(Group::new().with_child(mesh).with_child(Cube::new()))
+            .with_object(Sphere::new().with_material(textured()))
+            .with_light(Light::point(Vert4::point(0.0, 5.0, 0.0), Color::WHITE));
+        w.background = Some(Arc::new(CubeMap::from_cross(image.clone())));
+
+        let report = w.report(&Camera::new(10, 5, PI / 2.0));
+        assert_eq!(report.shape_count(), 7);
+        assert_eq!(report.shapes["Sphere"], 2);
+        assert_eq!(report.shapes["Group"], 2);
+        assert_eq!(report.triangles, 2);
+        assert_eq!((report.bvh_nodes, report.bvh_depth), (2, 2));
+        assert_eq!(report.lights, 1);
+        assert_eq!(report.texture_bytes, 4 * 2 * 12);
+        assert_eq!(report.buffer_bytes, 10 * 5 * (3 * 12 + 4));
+        let text = report.to_string();
+        assert!(text.contains("triangles: 2\n"));
+        assert!(text.contains("textures:  96 B\n"));
+        assert!(text.ends_with("buffers:   2.0 KiB"));
+    }
+
+    #[test]
+    fn objects_are_found_by_id_inside_groups() {
+        let group = Group::new()
+            .with_child(Sphere::new())
+            .with_child(Cube::new());
+        let cube = group.children()[1].id();
+        let w = World::new().with_object(group);
+        let found = w.object(cube).unwrap() as &dyn std::any::Any;
+        assert!(found.is::<Cube>());
+        assert!(w.object(Sphere::new().id()).is_none());
+    }
+
+    #[test]
+    fn shading_an_intersection() {
+        let w = default_world();
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects[0].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_approx_eq!(
+            w.shade_hit(&comps, DEFAULT_MAX_DEPTH),
+            Color::new(0.38066, 0.47583, 0.2855)
+        );
+    }
+
+    #[test]
+    fn shading_from_inside() {
+        let mut w = default_world();
+        w.lights = vec![Light::point(Vert4::point(0.0, 0.25, 0.0), Color::WHITE)];
+        let r = Ray::new(Vert4::point(0.0, 0.0, 0.0), Vert4::vector(0.0, 0.0, 1.0));
+        let i = Intersection::new(0.5, w.objects[1].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_approx_eq!(
+            w.shade_hit(&comps, DEFAULT_MAX_DEPTH),
+            Color::new(0.90498, 0.90498, 0.90498)
+        );
+    }
+
+    #[test]
+    fn color_when_ray_misses() {
+        let w = default_world();
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 1.0, 0.0));
+        assert_eq!(w.color_at(&r), Color::BLACK);
+    }
+
+    #[test]
+    fn each_kind_of_ray_only_strikes_the_objects_visible_to_it() {
+        let mut w = default_world();
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let ts = |w: &World, kind| -> Vec<f32> {
+            w.intersect_visible(&r, kind).iter().map(|x| x.t).collect()
+        };
+        w.objects[0].set_visibility(Visibility {
+            camera: false,
+            ..Visibility::in_layers([1])
+        });
+        assert_eq!(ts(&w, RayKind::Camera(None)), [4.5, 5.5]);
+        assert_eq!(ts(&w, RayKind::Reflection), [4.0, 4.5, 5.5, 6.0]);
+
+        w.objects[0].set_visibility(Visibility::in_layers([1]));
+        assert_eq!(ts(&w, RayKind::Camera(None)), [4.0, 4.5, 5.5, 6.0]);
+        assert_eq!(ts(&w, RayKind::Camera(Some(0))), [4.5, 5.5]);
+        assert_eq!(ts(&w, RayKind::Camera(Some(1))), [4.0, 4.5, 5.5, 6.0]);
+
+        let p = Vert4::point(10.0, -10.0, 10.0);
+        assert!(w.is_shadowed(p, &w.lights[0]));
+        for object in &mut w.objects {
+            object.set_visibility(Visibility {
+                shadows: false,
+                ..Visibility::default()
+            });
+        }
+        assert!(!w.is_shadowed(p, &w.lights[0]));
+    }
+
+    /// A shadow-catching floor at y = 0 under a sphere, lit from above.
+    fn shadow_catcher_world() -> World {
+        World::new()
+            .with_light(Light::point(Vert4::point(0.0, 10.0, 0.0), Color::WHITE))
+            .with_object(
+                Cube::new()
+                    .with_transform(
+                        Matrix4::translation(0.0, -1.0, 0.0) * Matrix4::scaling(10.0, 1.0, 10.0),
+                    )
+                    .with_material(Material::builder().shadow_catcher(true).build()),
+            )
+            .with_object(Sphere::new().with_transform(Matrix4::translation(0.0, 2.0, 0.0)))
+            .with_background(Arc::new(Gradient::new(Color::WHITE, Color::WHITE)))
+    }
+
+    #[test]
+    fn shadow_catchers_only_show_the_shadows_on_them() {
+        let w = shadow_catcher_world();
+        let down = Vert4::vector(0.0, -1.0, 0.0);
+        let open = Ray::new(Vert4::point(0.0, 1.0, -3.0), down);
+        let shadowed = Ray::new(Vert4::point(0.0, 0.5, 0.0), down);
+        let comps = |r: &Ray| {
+            let xs = w.intersect(r);
+            let hit = xs.hit().unwrap();
+            assert!(hit.material().shadow_catcher);
+            hit.prepare_computations(r, &xs)
+        };
+        // The sphere blocks the diffuse light but not the ambient.
+        assert_approx_eq!(w.shadow_strength(&comps(&open)), 0.0);
+        assert_approx_eq!(w.shadow_strength(&comps(&shadowed)), 0.9);
+        assert_approx_eq!(w.color_at(&open), Color::WHITE);
+        assert_approx_eq!(w.color_at(&shadowed), Color::WHITE * 0.1);
+    }
+
+    #[test]
+    fn missed_rays_see_the_background() {
+        let gradient = Gradient::new(Color::BLACK, Color::WHITE);
+        let w = default_world().with_background(Arc::new(gradient));
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(2.0, 2.0, 0.0));
+        assert_approx_eq!(w.color_at(&r), Color::WHITE * (2f32.sqrt() / 2.0));
+    }
+
+    #[test]
+    fn gold_reflects_gold() {
+        let gold = Color::new(1.0, 0.78, 0.34);
+        let w = World::new()
+            .with_object(Cube::new().with_material(Material {
+                color: gold,
+                ambient: 0.0,
+                diffuse: 0.0,
+                specular: 0.0,
+                reflective: 1.0,
+                specular_model: SpecularModel::Ggx {
+                    roughness: 0.2,
+                    metalness: 1.0,
+                },
+                ..Material::default()
+            }))
+            .with_background(Arc::new(Gradient::new(Color::WHITE, Color::WHITE)));
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert_approx_eq!(w.color_at(&r), gold);
+    }
+
+    #[test]
+    fn mirrors_reflect_the_background() {
+        let w = World::new()
+            .with_object(Cube::new().with_material(Material {
+                color: Color::BLACK,
+                ambient: 0.0,
+                diffuse: 0.0,
+                specular: 0.0,
+                reflective: 1.0,
+                ..Material::default()
+            }))
+            .with_background(Arc::new(TestPattern));
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert_approx_eq!(w.color_at(&r), Color::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn color_when_ray_hits() {
+        let w = default_world();
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert_approx_eq!(w.color_at(&r), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn color_with_intersection_behind_ray() {
+        let mut w = default_world();
+        w.objects[0].material_mut().ambient = 1.0;
+        w.objects[1].material_mut().ambient = 1.0;
+        let r = Ray::new(Vert4::point(0.0, 0.0, 0.75), Vert4::vector(0.0, 0.0, -1.0));
+        assert_approx_eq!(w.color_at(&r), w.objects[1].material().color);
+    }
+
+    #[test]
+    fn emissive_surfaces_glow_without_light() {
+        let mut w = default_world();
+        w.lights.clear();
+        w.objects[0].material_mut().emission = Color::new(0.5, 0.25, 0.0);
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert_eq!(w.color_at(&r), Color::new(0.5, 0.25, 0.0));
+    }
+
+    #[test]
+    fn emission_is_added_to_lit_color() {
+        let w = default_world();
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let lit = w.color_at(&r);
+        let mut w = w;
+        w.objects[0].material_mut().emission = Color::new(0.0, 0.0, 1.0);
+        assert_approx_eq!(w.color_at(&r), lit + Color::new(0.0, 0.0, 1.0));
+    }
+
+    /// A matte, ambient-only unit sphere inside a sphere of radius 3, lit
+    /// from inside the enclosure.
+    fn enclosed_sphere() -> World {
+        World::new()
+            .with_object(Sphere::new().with_material(Material {
+                ambient: 1.0,
+                diffuse: 0.0,
+                specular: 0.0,
+                ..Material::default()
+            }))
+            .with_object(Sphere::new().with_transform(Matrix4::scaling(3.0, 3.0, 3.0)))
+            .with_light(Light::point(Vert4::point(0.0, 0.0, -2.5), Color::WHITE))
+    }
+
+    #[test]
+    fn ambient_occlusion_darkens_enclosed_surfaces() {
+        let r = Ray::new(Vert4::point(0.0, 0.0, -2.0), Vert4::vector(0.0, 0.0, 1.0));
+        let open = enclosed_sphere();
+        assert_approx_eq!(open.color_at(&r), Color::WHITE);
+        let short = enclosed_sphere().with_ambient_occlusion(AmbientOcclusion::new(16, 0.5));
+        assert_approx_eq!(short.color_at(&r), Color::WHITE);
+        let long = enclosed_sphere().with_ambient_occlusion(AmbientOcclusion::new(16, 10.0));
+        assert_approx_eq!(long.color_at(&r), Color::BLACK);
+    }
+
+    #[test]
+    fn irradiance_cache_replaces_ambient_with_surrounding_light() {
+        // A matte sphere inside a glowing shell, with no lights: every sample
+        // ray sees the glow, and the whole sphere shares one sample.
+        let shell = Sphere::new()
+            .with_transform(Matrix4::scaling(3.0, 3.0, 3.0))
+            .with_material(Material {
+                color: Color::BLACK,
+                ambient: 0.0,
+                emission: Color::new(0.5, 0.5, 0.5),
+                ..Material::default()
+            });
+        let w = World::new()
+            .with_object(Sphere::new().with_material(Material {
+                color: Color::new(1.0, 0.5, 0.0),
+                diffuse: 0.8,
+                ..Material::default()
+            }))
+            .with_object(shell);
+        let r = Ray::new(Vert4::point(0.0, 0.0, -2.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert_eq!(w.color_at(&r), Color::BLACK);
+
+        let w = w.with_irradiance_cache(IrradianceCache::new(16, 0.5));
+        assert_approx_eq!(w.color_at(&r), Color::new(0.4, 0.2, 0.0));
+        let r = Ray::new(Vert4::point(0.0, 0.1, -2.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert_approx_eq!(w.color_at(&r), Color::new(0.4, 0.2, 0.0));
+        assert_eq!(w.irradiance_cache.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn ambient_occlusion_samples_cover_hemisphere() {
+        let ao = AmbientOcclusion::new(64, 1.0);
+        let normal = Vert4::vector(0.0, 1.0, 0.0);
+        let dirs: Vec<Vert4> = ao.directions(normal).collect();
+        assert_eq!(dirs.len(), 64);
+        assert!(dirs.iter().all(|d| d.dot(&normal) > 0.0));
+        assert!(dirs.iter().all(|d| (d.magnitude() - 1.0).abs() < EPSILON));
+        // Roughly balanced around the normal.
+        let mean = dirs
+            .iter()
+            .fold(Vert4::vector(0.0, 0.0, 0.0), |a, d| a + *d);
+        assert!(mean.x.abs() < 2.0 && mean.z.abs() < 2.0);
+    }
+
+    #[test]
+    fn transparent_shadows_let_light_through() {
+        let light = Light::point(Vert4::point(0.0, 0.0, -10.0), Color::WHITE);
+        let pane = |transparency: f32| {
+            Sphere::new().with_material(Material {
+                transparency,
+                ..Material::default()
+            })
+        };
+        let behind = Vert4::point(0.0, 0.0, 10.0);
+
+        let solid = World::new().with_object(pane(0.9)).with_light(light);
+        assert_eq!(solid.light_fraction(behind, &light), 0.0);
+
+        let w = World::new()
+            .with_object(pane(0.9))
+            .with_light(light)
+            .with_transparent_shadows(true);
+        // Dimmed once entering the sphere and again leaving it.
+        assert_approx_eq!(w.light_fraction(behind, &light), 0.81);
+        assert_eq!(w.light_fraction(Vert4::point(0.0, 0.0, -5.0), &light), 1.0);
+
+        let opaque = World::new()
+            .with_object(pane(0.0))
+            .with_light(light)
+            .with_transparent_shadows(true);
+        assert_eq!(opaque.light_fraction(behind, &light), 0.0);
+    }
+
+    #[test]
+    fn caustics_brighten_the_floor_under_glass() {
+        let glass = Sphere::new()
+            .with_transform(Matrix4::translation(0.0, 1.0, 0.0))
+            .with_material(Material {
+                diffuse: 0.0,
+                transparency: 1.0,
+                refractive_index: 1.5,
+                ..Material::default()
+            });
+        let w = World::new()
+            .with_object(reflective_floor(0.0))
+            .with_object(glass)
+            .with_light(Light::point(Vert4::point(0.0, 10.0, 0.0), Color::WHITE));
+        // Looking down at the middle of the sphere's shadow.
+        let eye = Vert4::point(0.0, 5.0, -5.0);
+        let r = Ray::new(eye, (Vert4::point(0.0, -1.0, 0.0) - eye).normalize());
+        assert_approx_eq!(w.color_at(&r), Color::new(0.1, 0.1, 0.1));
+        let w = w.with_caustics(Caustics::new(20_000, 50, 0.5));
+        assert!(w.color_at(&r).r > 1.0);
+    }
+
+    #[test]
+    fn editing_a_library_material_updates_every_user() {
+        let mut w = World::new();
+        let red = w.add_material(
+            "red",
+            Material {
+                color: Color::new(1.0, 0.0, 0.0),
+                ..Material::default()
+            },
+        );
+        w.objects.push(Box::new(
+            Sphere::new().with_shared_material(Arc::clone(&red)),
+        ));
+        w.objects.push(Box::new(
+            Group::new().with_child(Cube::new().with_shared_material(Arc::clone(&red))),
+        ));
+        w.objects.push(Box::new(Sphere::new()));
+
+        assert!(w.edit_material("red", |m| m.color = Color::new(0.0, 0.0, 1.0)));
+        let blue = Color::new(0.0, 0.0, 1.0);
+        assert_eq!(w.objects[0].material().color, blue);
+        let group = w.objects[1].as_ref() as &dyn std::any::Any;
+        let group = group.downcast_ref::<Group>().unwrap();
+        assert_eq!(group.children()[0].material().color, blue);
+        assert_eq!(w.objects[2].material().color, Color::WHITE);
+        assert_eq!(w.material("red").unwrap().color, blue);
+        assert!(!w.edit_material("green", |_| {}));
+    }
+
+    #[test]
+    fn material_mut_detaches_from_shared_material() {
+        let shared = Arc::new(Material::default());
+        let mut a = Sphere::new().with_shared_material(Arc::clone(&shared));
+        let b = Sphere::new().with_shared_material(Arc::clone(&shared));
+        a.material_mut().ambient = 1.0;
+        assert_eq!(a.material().ambient, 1.0);
+        assert_eq!(b.material().ambient, 0.1);
+        assert!(Arc::ptr_eq(b.shared_material(), &shared));
+    }
+
+    #[test]
+    fn lights_add_up() {
+        let w = default_world();
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let single = w.color_at(&r);
+        let light = w.lights[0];
+        let w = w.with_light(light);
+        assert_approx_eq!(w.color_at(&r), single * 2.0);
+    }
+
+    #[test]
+    fn each_light_casts_its_own_shadows() {
+        // The point is shadowed from the default light but lit from above.
+        let w =
+            default_world().with_light(Light::point(Vert4::point(10.0, 10.0, 10.0), Color::WHITE));
+        let p = Vert4::point(10.0, -10.0, 10.0);
+        assert!(w.is_shadowed(p, &w.lights[0]));
+        assert!(!w.is_shadowed(p, &w.lights[1]));
+    }
+
+    #[test]
+    fn world_without_light_is_black() {
+        let mut w = default_world();
+        w.lights.clear();
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        assert_eq!(w.color_at(&r), Color::BLACK);
+    }
+
+    #[test]
+    fn no_shadow_when_nothing_collinear() {
+        let w = default_world();
+        let light = w.lights[0];
+        assert!(!w.is_shadowed(Vert4::point(0.0, 10.0, 0.0), &light));
+    }
+
+    #[test]
+    fn shadow_when_object_between_point_and_light() {
+        let w = default_world();
+        let light = w.lights[0];
+        assert!(w.is_shadowed(Vert4::point(10.0, -10.0, 10.0), &light));
+    }
+
+    #[test]
+    fn no_shadow_when_object_behind_light() {
+        let w = default_world();
+        let light = w.lights[0];
+        assert!(!w.is_shadowed(Vert4::point(-20.0, 20.0, -20.0), &light));
+    }
+
+    #[test]
+    fn no_shadow_when_object_behind_point() {
+        let w = default_world();
+        let light = w.lights[0];
+        assert!(!w.is_shadowed(Vert4::point(-2.0, 2.0, -2.0), &light));
+    }
+
+    #[test]
+    fn occlusion_is_checked_up_to_max_t() {
+        let w = default_world();
+        let origin = Vert4::point(0.0, 0.0, -5.0);
+        let towards = Vert4::vector(0.0, 0.0, 2.0);
+        assert!(w.is_occluded(origin, towards, 5.0));
+        assert!(!w.is_occluded(origin, towards, 1.5));
+        assert!(!w.is_occluded(origin, -towards, 5.0));
+
+        let see_through = Visibility {
+            shadows: false,
+            ..Visibility::default()
+        };
+        let w = World::new().with_object(Sphere::new().with_visibility(see_through));
+        assert!(!w.is_occluded(origin, towards, 5.0));
+    }
+
+    #[test]
+    fn raycasts_find_a_hit_within_the_range() {
+        let w = default_world();
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let hit = w.raycast(&r, 0.0..10.0).unwrap();
+        assert!(ptr::addr_eq(hit.object, w.objects[0].as_ref()));
+        assert_eq!(hit.t, 4.0);
+        assert_eq!(w.raycast(&r, 5.0..10.0).unwrap().t, 6.0);
+        assert!(w.raycast(&r, 0.0..3.0).is_none());
+
+        let grouped = World::new().with_object(
+            Group::new()
+                .with_child(Sphere::new().with_transform(Matrix4::translation(0.0, 0.0, 10.0)))
+                .with_child(Sphere::new()),
+        );
+        assert_eq!(grouped.raycast(&r, 0.0..10.0).unwrap().t, 4.0);
+        assert!(grouped.raycast(&r, 0.0..3.0).is_none());
+    }
+
+    #[test]
+    fn directional_light_is_blocked_at_any_distance() {
+        let w = default_world();
+        let sun = Light::directional(Vert4::vector(1.0, -1.0, 1.0), Color::WHITE);
+        // Far beyond where the default point light sits, along its line.
+        assert!(w.is_shadowed(Vert4::point(1000.0, -1000.0, 1000.0), &sun));
+        assert!(!w.is_shadowed(Vert4::point(-20.0, 20.0, -20.0), &sun));
+        assert!(!w.is_shadowed(Vert4::point(0.0, 10.0, 0.0), &sun));
+    }
+
+    #[test]
+    fn shade_hit_in_shadow() {
+        let w = World::new()
+            .with_light(Light::point(Vert4::point(0.0, 0.0, -10.0), Color::WHITE))
+            .with_object(Sphere::new())
+            .with_object(Sphere::new().with_transform(Matrix4::translation(0.0, 0.0, 10.0)));
+        let r = Ray::new(Vert4::point(0.0, 0.0, 5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects[1].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_approx_eq!(
+            w.shade_hit(&comps, DEFAULT_MAX_DEPTH),
+            Color::new(0.1, 0.1, 0.1)
+        );
+    }
+
+    #[test]
+    fn refracted_color_of_opaque_surface() {
+        let w = default_world();
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_eq!(w.refracted_color(&comps, DEFAULT_MAX_DEPTH), Color::BLACK);
+    }
+
+    #[test]
+    fn refracted_color_at_max_depth() {
+        let mut w = default_world();
+        make_glass(w.objects[0].as_mut());
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_eq!(w.refracted_color(&comps, 0), Color::BLACK);
+    }
+
+    #[test]
+    fn refracted_color_under_total_internal_reflection() {
+        let mut w = default_world();
+        make_glass(w.objects[0].as_mut());
+        let half = 2f32.sqrt() / 2.0;
+        let r = Ray::new(Vert4::point(0.0, 0.0, half), Vert4::vector(0.0, 1.0, 0.0));
+        let i1 = Intersection::new(-half, w.objects[0].as_ref());
+        let i2 = Intersection::new(half, w.objects[0].as_ref());
+        let comps = i2.prepare_computations(&r, &[i1, i2]);
+        assert_eq!(w.refracted_color(&comps, DEFAULT_MAX_DEPTH), Color::BLACK);
+    }
+
+    #[test]
+    fn shade_hit_sees_through_transparent_surface() {
+        let glass = Sphere::new().with_material(Material {
+            ambient: 0.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            transparency: 0.5,
+            refractive_index: 1.5,
+            ..Material::default()
+        });
+        let backdrop = Sphere::new()
+            .with_transform(Matrix4::translation(0.0, 0.0, 10.0))
+            .with_material(Material {
+                color: Color::new(1.0, 0.0, 0.0),
+                ambient: 1.0,
+                diffuse: 0.0,
+                specular: 0.0,
+                ..Material::default()
+            });
+        let w = World::new()
+            .with_light(Light::point(Vert4::point(0.0, 10.0, -10.0), Color::WHITE))
+            .with_object(glass)
+            .with_object(backdrop);
+        // Head-on, so the ray isn't bent; each glass surface passes half the light.
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_approx_eq!(
+            w.shade_hit(&comps, DEFAULT_MAX_DEPTH),
+            Color::new(0.25, 0.0, 0.0)
+        );
+    }
+
+    fn reflective_floor(reflective: f32) -> Cube {
+        // A slab whose top face is the plane y = -1.
+        Cube::new()
+            .with_transform(
+                Matrix4::translation(0.0, -2.0, 0.0) * Matrix4::scaling(10.0, 1.0, 10.0),
+            )
+            .with_material(Material {
+                reflective,
+                ..Material::default()
+            })
+    }
+
+    #[test]
+    fn reflected_color_of_nonreflective_surface() {
+        let mut w = default_world();
+        w.objects[1].material_mut().ambient = 1.0;
+        let r = Ray::new(Vert4::point(0.0, 0.0, 0.0), Vert4::vector(0.0, 0.0, 1.0));
+        let i = Intersection::new(1.0, w.objects[1].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_eq!(w.reflected_color(&comps, DEFAULT_MAX_DEPTH), Color::BLACK);
+    }
+
+    #[test]
+    fn reflected_color_of_reflective_surface() {
+        let w = default_world().with_object(reflective_floor(0.5));
+        let half = 2f32.sqrt() / 2.0;
+        let r = Ray::new(
+            Vert4::point(0.0, 0.0, -3.0),
+            Vert4::vector(0.0, -half, half),
+        );
+        let i = Intersection::new(2f32.sqrt(), w.objects[2].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_approx_eq!(
+            w.reflected_color(&comps, DEFAULT_MAX_DEPTH),
+            Color::new(0.19032, 0.2379, 0.14274)
+        );
+        assert_approx_eq!(
+            w.shade_hit(&comps, DEFAULT_MAX_DEPTH),
+            Color::new(0.87677, 0.92436, 0.82918)
+        );
+    }
+
+    #[test]
+    fn fresnel_splits_light_between_reflection_and_refraction() {
+        let glass = Sphere::new().with_material(Material {
+            ambient: 0.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            reflective: 1.0,
+            transparency: 1.0,
+            refractive_index: 1.5,
+            ..Material::default()
+        });
+        let backdrop = Sphere::new()
+            .with_transform(Matrix4::translation(0.0, 0.0, 10.0))
+            .with_material(Material {
+                color: Color::new(1.0, 0.0, 0.0),
+                ambient: 1.0,
+                diffuse: 0.0,
+                specular: 0.0,
+                ..Material::default()
+            });
+        let w = World::new()
+            .with_light(Light::point(Vert4::point(0.0, 10.0, -10.0), Color::WHITE))
+            .with_object(glass)
+            .with_object(backdrop);
+        // Head-on, each surface reflects 4% and transmits 96%. The light
+        // reaching the backdrop directly is 0.96², plus a second path that
+        // bounces once off each inner surface before the depth limit.
+        let r = Ray::new(Vert4::point(0.0, 0.0, -5.0), Vert4::vector(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_approx_eq!(
+            w.shade_hit(&comps, DEFAULT_MAX_DEPTH),
+            Color::new(0.92307, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn mutually_reflective_surfaces_terminate() {
+        let mirror = |y: f32| {
+            Cube::new()
+                .with_transform(
+                    Matrix4::translation(0.0, y, 0.0) * Matrix4::scaling(10.0, 1.0, 10.0),
+                )
+                .with_material(Material {
+                    reflective: 1.0,
+                    ..Material::default()
+                })
+        };
+        let w = World::new()
+            .with_light(Light::point(Vert4::point(0.0, 0.0, 0.0), Color::WHITE))
+            .with_object(mirror(-2.0))
+            .with_object(mirror(2.0));
+        let r = Ray::new(Vert4::point(0.0, 0.0, 0.0), Vert4::vector(0.0, 1.0, 0.0));
+        let c = w.color_at(&r);
+        assert!(c.r.is_finite() && c.r > 0.0);
+    }
+
+    #[test]
+    fn max_depth_limits_reflection() {
+        let r = Ray::new(Vert4::point(0.0, 0.0, -3.0), {
+            let half = 2f32.sqrt() / 2.0;
+            Vert4::vector(0.0, -half, half)
+        });
+        let w = default_world().with_object(reflective_floor(0.5));
+        let mirrored = w.color_at(&r);
+        let w = w.with_max_depth(0);
+        let matte = w.color_at(&r);
+        assert_approx_eq!(mirrored, matte + Color::new(0.19032, 0.2379, 0.14274));
+    }
+}